@@ -8,11 +8,20 @@
 
 mod package_id;
 mod report;
+mod schema;
 mod source;
 
 pub use package_id::PackageId;
 pub use report::{
-    Count, CounterBlock, DependencyKind, PackageInfo, QuickReportEntry,
-    QuickSafetyReport, ReportEntry, SafetyReport, UnsafeInfo,
+    ApiCounterBlock, ApproximateUnsafety, BuildFailure, Count, CounterBlock,
+    CountingStrategy, DependencyCounts, DependencyKind, DevOnlyPackages,
+    ExternalPathGroup, GeneratedUnsafety, InactivePackages, InconsistentForbid,
+    LockedPackage, MemberUnsafety, MergedSafetyReport, ModuleUnsafety,
+    NativeLinkage, NotScannedReason, OversizedFile, PackageInfo, PackageReport,
+    PhaseTiming, ProcMacroUse, PublicApiUnsafety, QuickReportEntry,
+    QuickSafetyReport, ReportEntry, ReportIndex, ReportIndexEntry,
+    ReportMetadata, SafetyReport, ScanStatus, ScanWarning, ScannedFile,
+    TargetKind, TreeLine, UnsafeInfo, WatchedCallCount, WorkspaceSafetyReport,
 };
+pub use schema::{safety_report_schema, SCHEMA_VERSION};
 pub use source::Source;
@@ -1,13 +1,15 @@
 use crate::PackageId;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     ops::{Add, AddAssign},
     path::PathBuf,
+    str::FromStr,
 };
 
 /// Package dependency information
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct PackageInfo {
     pub id: PackageId,
     #[serde(serialize_with = "set_serde::serialize")]
@@ -38,7 +40,7 @@ impl PackageInfo {
 }
 
 /// Entry of the report generated from scanning for packages that forbid the use of `unsafe`
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct QuickReportEntry {
     pub package: PackageInfo,
     /// Whether this package forbids the use of `unsafe`
@@ -46,37 +48,468 @@ pub struct QuickReportEntry {
 }
 
 /// Report generated from scanning for packages that forbid the use of `unsafe`
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
 pub struct QuickSafetyReport {
     /// Packages that were scanned successfully
     #[serde(with = "entry_serde")]
+    #[schemars(with = "Vec<QuickReportEntry>")]
     pub packages: HashMap<PackageId, QuickReportEntry>,
     /// Packages that were not scanned successfully
     #[serde(serialize_with = "set_serde::serialize")]
     pub packages_without_metrics: HashSet<PackageId>,
+    /// Groups of packages that depend on each other through
+    /// dev-dependencies
+    #[serde(default)]
+    pub dependency_cycles: Vec<Vec<PackageId>>,
+    #[serde(default)]
+    pub metadata: ReportMetadata,
+}
+
+/// Information about the workspace that a report was generated from
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct ReportMetadata {
+    /// The version of the format of the report, see `SCHEMA_VERSION`. `0` in
+    /// reports of older versions.
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Stable hash of the resolved package set, used to tell if a report
+    /// still corresponds to a `Cargo.lock`
+    pub lock_fingerprint: String,
+    /// The resolved packages that the fingerprint was computed from
+    pub locked_packages: Vec<LockedPackage>,
+    /// Which expressions the counters count, `Geiger` in reports of older
+    /// versions
+    #[serde(default)]
+    pub counting_strategy: CountingStrategy,
+    /// The time spent in each phase of the run before the report was
+    /// printed, with `--timings`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<PhaseTiming>,
+}
+
+/// The time spent in one phase of a run
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub seconds: f64,
+}
+
+/// A resolved package together with its checksum from `Cargo.lock`
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    JsonSchema,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub struct LockedPackage {
+    pub id: PackageId,
+    /// Checksum of the package contents, only known for registry packages
+    pub checksum: Option<String>,
 }
 
 /// Entry of the report generated from scanning for the use of `unsafe`
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct ReportEntry {
     pub package: PackageInfo,
     /// Unsafety scan results
     pub unsafety: UnsafeInfo,
+    /// The features of the package enabled by the resolve, unified across
+    /// the whole dependency graph, sorted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    /// The platform expressions of the target-specific dependencies that
+    /// pull in a package only used on other platforms, with
+    /// `--all-platforms`. Empty for packages used on the scanned platform.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<String>,
+    /// The native library the package links to, from the `links` key of its
+    /// manifest. The code of native libraries is never scanned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub native_linkage: Option<NativeLinkage>,
+    /// The number of dependencies of the package, with `--show-dep-counts`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependency_counts: Option<DependencyCounts>,
+    /// The minimum number of dependency edges between the root and the
+    /// package, `0` for the root. A package reached through several paths
+    /// takes the shortest one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u64>,
+    /// The `.rs` files scanned for the package, with `--report-file-list`,
+    /// sorted by path
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<ScannedFile>,
+    /// The package is a member of the scanned workspace, its code is ours
+    /// rather than a dependency
+    #[serde(default)]
+    pub is_workspace_member: bool,
+    /// The crate names of the proc-macro crates the build passed to the
+    /// package, the ones that may have generated code into it. Their output
+    /// isn't scanned, sorted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proc_macros_applied: Vec<String>,
+}
+
+/// The number of dependencies of a package in the dependency graph
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    JsonSchema,
+    PartialEq,
+    Serialize,
+)]
+pub struct DependencyCounts {
+    /// The packages depended on directly
+    pub direct: u64,
+    /// All packages reachable through the dependencies, each counted once
+    pub transitive: u64,
+}
+
+/// A `.rs` file scanned for a package
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub struct ScannedFile {
+    /// The path relative to the package root, with `/` as the separator on
+    /// all platforms. Absolute for files outside of the package root.
+    pub path: String,
+    /// Whether the build used the file
+    pub used: bool,
+}
+
+/// The native library of a package with a `links` key in its manifest
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct NativeLinkage {
+    /// The name of the native library, the value of the `links` key
+    pub links: String,
+    /// Whether the package has a build script, which builds or finds the
+    /// native library
+    pub build_script: bool,
 }
 
 /// Report generated from scanning for the use of `unsafe`
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
 pub struct SafetyReport {
     #[serde(with = "entry_serde")]
+    #[schemars(with = "Vec<ReportEntry>")]
     pub packages: HashMap<PackageId, ReportEntry>,
     #[serde(serialize_with = "set_serde::serialize")]
     pub packages_without_metrics: HashSet<PackageId>,
     #[serde(serialize_with = "set_serde::serialize")]
     pub used_but_not_scanned_files: HashSet<PathBuf>,
+    /// Groups of packages that depend on each other through
+    /// dev-dependencies
+    #[serde(default)]
+    pub dependency_cycles: Vec<Vec<PackageId>>,
+    #[serde(default)]
+    pub metadata: ReportMetadata,
+    /// Packages whose entries were copied from a previous report instead of
+    /// being scanned again
+    #[serde(default, serialize_with = "set_serde::serialize")]
+    pub reused_packages: HashSet<PackageId>,
+    /// Packages that failed to build with `--keep-going`, their files are
+    /// counted as not used by the build
+    #[serde(default, with = "entry_serde")]
+    #[schemars(with = "Vec<BuildFailure>")]
+    pub build_failures: HashMap<PackageId, BuildFailure>,
+    /// Problems that didn't stop the scan but make parts of the report less
+    /// reliable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ScanWarning>,
+    /// The unsafe usage of each workspace member in the dependency graph,
+    /// with `--members-only`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<MemberUnsafety>,
+    /// The path dependencies with roots outside of the workspace, like the
+    /// crates of sibling repositories, grouped by the directory they are in,
+    /// with `--split-local-paths`. Sorted by directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_path_groups: Vec<ExternalPathGroup>,
+    /// Packages that forbid unsafe code but still have unsafe usage counted,
+    /// sorted by package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inconsistent_forbid: Vec<InconsistentForbid>,
+    /// The packages nothing of was built for the target, counted apart from
+    /// the others
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inactive: Option<InactivePackages>,
+    /// The packages only reached through dev-dependencies of dependencies,
+    /// left out of the totals
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dev_only: Option<DevOnlyPackages>,
+    /// The proc-macro crates of the build with the packages they were
+    /// applied to, sorted by crate name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proc_macros: Vec<ProcMacroUse>,
+}
+
+/// Packages in the dependency graph without a single file used by the build
+/// for the target, like packages only used on other platforms. All of their
+/// unsafe usage is counted as not used, it is not part of the build.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct InactivePackages {
+    /// The target triple that was built for
+    pub target: String,
+    /// Sorted
+    pub packages: Vec<PackageId>,
+}
+
+/// Packages in the dependency graph that can only be reached from the root
+/// through a dev-dependency of another package, like the test helpers of a
+/// workspace member the root depends on. They are never compiled into
+/// anything the root ships. Their entries are kept, but their unsafe usage
+/// is not part of the totals, it is added up here instead.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct DevOnlyPackages {
+    /// Sorted
+    pub packages: Vec<PackageId>,
+    /// Unsafe usage of these packages, used by the build
+    pub used: CounterBlock,
+    /// Unsafe usage of these packages, not used by the build
+    pub unused: CounterBlock,
+}
+
+/// A proc-macro crate and the packages it may have generated code into, see
+/// `ReportEntry::proc_macros_applied`
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct ProcMacroUse {
+    pub crate_name: String,
+    /// Sorted
+    pub applied_to: Vec<PackageId>,
+}
+
+/// A package with an entry point declaring `#![forbid(unsafe_code)]` that
+/// has unsafe usage counted anyway. The unsafe usage is in files the forbid
+/// doesn't cover, like a build script, examples or tests without the
+/// attribute, or it was attributed to the package by mistake.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct InconsistentForbid {
+    pub package: PackageId,
+    /// The files with unsafe usage, sorted
+    pub files: Vec<PathBuf>,
+}
+
+/// The unsafe usage of a workspace member and of everything it depends on
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct MemberUnsafety {
+    pub id: PackageId,
+    /// Unsafe usage of the member itself, used by the build
+    pub used: CounterBlock,
+    /// Unsafe usage of the member itself, not used by the build
+    pub unused: CounterBlock,
+    /// Unsafe usage of the member and all packages it depends on, directly or
+    /// not, used by the build. A package shared by several members is counted
+    /// for each of them.
+    pub transitive_used: CounterBlock,
+    /// Like `transitive_used`, not used by the build
+    pub transitive_unused: CounterBlock,
+}
+
+/// Path dependencies outside of the workspace that are in the same directory,
+/// with their unsafe usage added up
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct ExternalPathGroup {
+    /// The top-most directory of the packages not shared with the workspace,
+    /// the child of their deepest common ancestor with the workspace root.
+    /// For a workspace in `src/product`, the packages in `src/repo_a` and
+    /// its subdirectories form the group `src/repo_a`.
+    pub directory: PathBuf,
+    /// Sorted
+    pub packages: Vec<PackageId>,
+    /// Unsafe usage of the packages of the group, used by the build
+    pub used: CounterBlock,
+    /// Unsafe usage of the packages of the group, not used by the build
+    pub unused: CounterBlock,
+}
+
+/// The reports of several workspaces scanned together, with the unsafe usage
+/// of all their packages added up
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct MergedSafetyReport {
+    /// The report of each workspace, in the order their manifests were given
+    pub workspaces: Vec<WorkspaceSafetyReport>,
+    /// Unsafe usage used by the builds, a package in more than one workspace
+    /// is counted once, the way the first of them counts it
+    pub used: CounterBlock,
+    /// Unsafe usage not used by the builds, counted like `used`
+    pub unused: CounterBlock,
+}
+
+/// The report of one of the workspaces of a merged report
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct WorkspaceSafetyReport {
+    pub manifest_path: PathBuf,
+    pub report: SafetyReport,
+}
+
+/// The file of a package written to the directory given with
+/// `--output-dir`, named after the package
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct PackageReport {
+    /// The metadata of the whole report, without the timings, which change
+    /// with every run
+    pub metadata: ReportMetadata,
+    pub entry: ReportEntry,
+}
+
+/// `index.json` of the directory given with `--output-dir`, listing the
+/// files of all packages of the report
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct ReportIndex {
+    pub metadata: ReportMetadata,
+    /// Sorted by package
+    pub packages: Vec<ReportIndexEntry>,
+    /// Unsafe usage used by the build, of all packages
+    pub used: CounterBlock,
+    /// Unsafe usage not used by the build, of all packages
+    pub unused: CounterBlock,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ScanWarning>,
+}
+
+/// A package listed in the index of `--output-dir`
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct ReportIndexEntry {
+    pub id: PackageId,
+    /// The name of the file holding the package's `PackageReport`, relative
+    /// to the directory
+    pub file: String,
+    pub used: CounterBlock,
+    pub unused: CounterBlock,
+}
+
+/// A problem found during a scan that didn't stop it
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    JsonSchema,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+#[serde(tag = "kind")]
+pub enum ScanWarning {
+    /// A `.rs` file that failed to parse, it has no metrics
+    ParseFailed { path: PathBuf, error: String },
+    /// A `.rs` file that could not be read, like one without read permission,
+    /// it has no metrics
+    Unreadable { path: PathBuf, error: String },
+    /// A `.rs` file larger than `--max-file-size` with its size in bytes, it
+    /// was not scanned
+    Oversized { path: PathBuf, size: u64 },
+    /// A path that could not be canonicalized, files below it may not match
+    /// the ones used by the build
+    NotCanonicalizable { path: PathBuf },
+    /// A file listed in a dep-info file that was not found, it is counted as
+    /// not used by the build
+    MissingDepInfoPath { path: PathBuf },
+    /// A package without a single file used by the build, all its unsafe
+    /// usage is counted as not used
+    NoBuildData { package: PackageId },
+    /// A package that was not scanned
+    NoMetrics { package: PackageId },
+    /// A file used by the build that was not scanned, with the likely reason
+    /// guessed from its path. `None` in reports of older versions.
+    NotScanned {
+        path: PathBuf,
+        #[serde(default)]
+        reason: Option<NotScannedReason>,
+    },
+    /// A package that failed to build with `--keep-going`
+    BuildFailed { package: PackageId },
+    /// A package whose sources couldn't be downloaded or read, it was not
+    /// scanned
+    Unavailable { package: PackageId, reason: String },
+}
+
+/// Why a file used by the build was likely not scanned, guessed from its path
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    JsonSchema,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub enum NotScannedReason {
+    /// Not a `.rs` file, like one pulled in with `include_str!`
+    NotRust,
+    /// Written by a build script below its `OUT_DIR`
+    OutDir,
+    /// The file no longer exists
+    Missing,
+    /// Outside the roots of all packages, like a file included through a
+    /// relative path leaving the package
+    OutsidePackageRoots,
+    /// Below the root of a package, yet not found by the scan of it
+    InsidePackageRoot,
+}
+
+/// A package that failed to build
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct BuildFailure {
+    pub id: PackageId,
+    /// The errors reported by the compiler
+    pub error: String,
+}
+
+/// A line of the dependency tree, in the order the table output prints them
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub enum TreeLine {
+    /// A package with the unsafe usage the table shows for it, `None` if no
+    /// metrics were found. Boxed, it is much larger than the other variants.
+    Package {
+        id: PackageId,
+        depth: usize,
+        tree_vines: String,
+        unsafety: Option<Box<UnsafeInfo>>,
+    },
+    /// The header of a group of build or dev dependencies, at the depth of the
+    /// dependencies below it
+    ExtraDepsGroup {
+        kind: DependencyKind,
+        depth: usize,
+        tree_vines: String,
+    },
+    /// A package that is already on the path from the root, its dependencies
+    /// are not listed again
+    Cycle {
+        id: PackageId,
+        depth: usize,
+        tree_vines: String,
+    },
 }
 
 /// Unsafety usage in a package
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
 pub struct UnsafeInfo {
     /// Unsafe usage statistics for code used by the project
     pub used: CounterBlock,
@@ -84,10 +517,252 @@ pub struct UnsafeInfo {
     pub unused: CounterBlock,
     /// Whether this package forbids the use of `unsafe`
     pub forbids_unsafe: bool,
+    /// How far the counters above can be trusted
+    #[serde(default)]
+    pub status: ScanStatus,
+    /// Unsafe usage per module, most unsafe first. Only reported with
+    /// `--report-modules`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<ModuleUnsafety>,
+    /// Unsafe usage of the code used by the build, split by the kind of
+    /// target that used it. Files used by several kinds of targets are
+    /// counted for each of them.
+    #[serde(default)]
+    pub targets: BTreeMap<TargetKind, CounterBlock>,
+    /// Unsafe usage of all categories combined, split by the cargo feature
+    /// gating it, or `(unconditional)` for code outside of feature gates.
+    /// Read from the `cfg` attributes, regardless of the enabled features.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_feature: BTreeMap<String, u64>,
+    /// The part of `used` and `unused` found in files that look machine
+    /// generated, like `bindgen` output. Only present for packages with such
+    /// files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated: Option<GeneratedUnsafety>,
+    /// The part of the functions, methods and traits in `used` and `unused`
+    /// that is declared `pub`
+    #[serde(default)]
+    pub public_api: PublicApiUnsafety,
+    /// Calls matching the patterns given with `--watch-calls`, keyed by the
+    /// pattern. Patterns without matching calls are left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub watched_calls: BTreeMap<String, WatchedCallCount>,
+    /// Usage statistics for the code gated by the cfgs of verification
+    /// harnesses, like `#[cfg(fuzzing)]` or `#[cfg(kani)]`, see
+    /// `--harness-cfgs`. Left out of `used` and `unused`, like tests. Only
+    /// present for packages with such code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_harness: Option<CounterBlock>,
+    /// The `unsafe` keywords of the files that failed to parse, counted from
+    /// their tokens. Left out of all other counters, nothing else is known
+    /// about these files. Only present for packages with such files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approximate: Option<ApproximateUnsafety>,
+    /// The files larger than `--max-file-size`, they were not scanned and are
+    /// missing from all counters. Sorted by path.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_oversized: Vec<OversizedFile>,
+}
+
+/// Number of calls matching a watched call pattern in a package
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct WatchedCallCount {
+    /// Matching calls in code used by the project
+    pub used: Count,
+    /// Matching calls in code not used by the project
+    pub unused: Count,
+}
+
+/// Unsafe usage in the machine generated files of a package
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct GeneratedUnsafety {
+    /// Unsafe usage statistics for generated code used by the project
+    pub used: CounterBlock,
+    /// Unsafe usage statistics for generated code not used by the project
+    pub unused: CounterBlock,
+}
+
+/// The `unsafe` keywords in the files of a package that failed to parse,
+/// counted from their tokens. Keywords in string literals and comments are
+/// not counted, keywords in tests are.
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct ApproximateUnsafety {
+    /// `unsafe` keywords in files used by the project
+    pub used: u64,
+    /// `unsafe` keywords in files not used by the project
+    pub unused: u64,
+}
+
+/// A file that was not scanned because of its size
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub struct OversizedFile {
+    pub path: PathBuf,
+    /// The size of the file in bytes
+    pub size: u64,
+}
+
+/// Unsafe usage in the public API of a package. Only plain `pub` counts as
+/// public, items with a restricted visibility like `pub(crate)` are internal.
+/// Visibility is read from the item alone, a `pub` item in a private module
+/// counts as public.
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct PublicApiUnsafety {
+    /// Public items in code used by the project
+    pub used: ApiCounterBlock,
+    /// Public items in code not used by the project
+    pub unused: ApiCounterBlock,
+}
+
+/// Unsafe usage metrics of the kinds of items that can be part of an API.
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
+pub struct ApiCounterBlock {
+    pub functions: Count,
+    pub methods: Count,
+    pub item_traits: Count,
+}
+
+impl ApiCounterBlock {
+    pub fn has_unsafe(&self) -> bool {
+        self.functions.unsafe_ > 0
+            || self.methods.unsafe_ > 0
+            || self.item_traits.unsafe_ > 0
+    }
+}
+
+impl Add for ApiCounterBlock {
+    type Output = ApiCounterBlock;
+
+    fn add(self, other: ApiCounterBlock) -> ApiCounterBlock {
+        ApiCounterBlock {
+            functions: self.functions + other.functions,
+            methods: self.methods + other.methods,
+            item_traits: self.item_traits + other.item_traits,
+        }
+    }
+}
+
+impl AddAssign for ApiCounterBlock {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = self.clone() + rhs;
+    }
+}
+
+/// Unsafe usage in a single module of a package
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub struct ModuleUnsafety {
+    /// The file of the module relative to the package root, followed by the
+    /// path of the module within that file for inline modules, e.g.
+    /// `src/raw/ffi.rs::sys`
+    pub module: String,
+    /// Unsafe usage of all categories combined
+    pub unsafe_: u64,
+}
+
+/// Completeness of the scan of a package
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    JsonSchema,
+    PartialEq,
+    Serialize,
+)]
+pub enum ScanStatus {
+    /// The package was built and all of its files were scanned
+    #[default]
+    Complete,
+    /// Nothing of the package was seen by the build, because it was skipped,
+    /// failed or filtered out. All of its files are counted as not used.
+    NoBuildData,
+    /// Some files of the package could not be read or parsed, or were larger
+    /// than `--max-file-size`, and are missing from the counters
+    ParseErrors,
+    /// The package is only used on other platforms than the one scanned, see
+    /// `--all-platforms`. It wasn't built, all of its files are counted as
+    /// not used.
+    OtherPlatform,
+}
+
+/// Which expressions are counted in `CounterBlock::exprs`
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    JsonSchema,
+    PartialEq,
+    Serialize,
+)]
+pub enum CountingStrategy {
+    /// Paths and literals are not counted, so that the call `f(x)` counts as
+    /// one expression rather than three
+    #[default]
+    Geiger,
+    /// Every expression is counted, like tools that count all nodes of the
+    /// syntax tree do
+    AllNodes,
+}
+
+impl FromStr for CountingStrategy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<CountingStrategy, &'static str> {
+        match s {
+            "geiger" => Ok(CountingStrategy::Geiger),
+            "all-nodes" => Ok(CountingStrategy::AllNodes),
+            _ => Err("invalid counting strategy"),
+        }
+    }
+}
+
+/// Kind of build target of a package
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    JsonSchema,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub enum TargetKind {
+    /// The library, what dependent packages use
+    Lib,
+    /// A binary
+    Bin,
+    /// An integration test
+    Test,
+    /// A benchmark
+    Bench,
+    /// An example
+    Example,
+    /// A build script
+    CustomBuild,
 }
 
 /// Kind of dependency for a package
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, Hash, JsonSchema, PartialEq, Serialize,
+)]
 pub enum DependencyKind {
     /// Dependency in the `[dependencies]` section of `Cargo.toml`
     Normal,
@@ -98,7 +773,9 @@ pub enum DependencyKind {
 }
 
 /// Statistics about the use of `unsafe`
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
 pub struct Count {
     /// Number of safe items
     pub safe: u64,
@@ -135,13 +812,30 @@ impl AddAssign for Count {
 }
 
 /// Unsafe usage metrics collection.
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(
+    Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize,
+)]
 pub struct CounterBlock {
     pub functions: Count,
     pub exprs: Count,
     pub item_impls: Count,
     pub item_traits: Count,
     pub methods: Count,
+    /// Functions and statics declared in `extern` blocks, these are always
+    /// unsafe to use
+    #[serde(default)]
+    pub foreign_items: Count,
+    /// Calls to `mem::transmute` and `mem::transmute_copy`, found by the
+    /// name of the called function alone. Any other function with one of
+    /// these names is counted too. The calls are also counted in `exprs`.
+    #[serde(default)]
+    pub transmutes: Count,
+    /// Expressions evaluated at compile time, in the initializers of consts
+    /// and statics and in the bodies of `const fn`s. These run on the machine
+    /// doing the build, a `const fn` may run again at runtime. The
+    /// expressions are also counted in `exprs`.
+    #[serde(default)]
+    pub const_unsafe: Count,
 }
 
 impl CounterBlock {
@@ -151,6 +845,7 @@ impl CounterBlock {
             || self.item_impls.unsafe_ > 0
             || self.item_traits.unsafe_ > 0
             || self.methods.unsafe_ > 0
+            || self.foreign_items.unsafe_ > 0
     }
 }
 
@@ -164,6 +859,9 @@ impl Add for CounterBlock {
             item_impls: self.item_impls + other.item_impls,
             item_traits: self.item_traits + other.item_traits,
             methods: self.methods + other.methods,
+            foreign_items: self.foreign_items + other.foreign_items,
+            transmutes: self.transmutes + other.transmutes,
+            const_unsafe: self.const_unsafe + other.const_unsafe,
         }
     }
 }
@@ -190,6 +888,12 @@ impl Entry for QuickReportEntry {
     }
 }
 
+impl Entry for BuildFailure {
+    fn package_id(&self) -> &PackageId {
+        &self.id
+    }
+}
+
 mod entry_serde {
     use crate::PackageId;
     use serde::{
@@ -0,0 +1,18 @@
+use crate::SafetyReport;
+use schemars::{schema::RootSchema, schema_for};
+
+/// The version of the format of the JSON reports, stored in their metadata.
+/// Increased with every change that a reader of the reports could trip over,
+/// like a renamed or removed field.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON Schema of `SafetyReport`, the report printed with `--json`. The
+/// `schema_version` keyword of the root holds `SCHEMA_VERSION`.
+pub fn safety_report_schema() -> RootSchema {
+    let mut schema = schema_for!(SafetyReport);
+    schema
+        .schema
+        .extensions
+        .insert("schema_version".to_owned(), SCHEMA_VERSION.into());
+    schema
+}
@@ -1,12 +1,30 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 /// Source of a package (where it is fetched from)
 #[derive(
-    Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+    Clone,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    JsonSchema,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
 )]
 pub enum Source {
-    Git { url: Url, rev: String },
-    Registry { name: String, url: Url },
-    Path(Url),
+    Git {
+        #[schemars(with = "String")]
+        url: Url,
+        rev: String,
+    },
+    Registry {
+        name: String,
+        #[schemars(with = "String")]
+        url: Url,
+    },
+    Path(#[schemars(with = "String")] Url),
 }
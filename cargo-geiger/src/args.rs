@@ -1,7 +1,12 @@
-use crate::format::print_config::OutputFormat;
-use crate::format::Charset;
+use crate::format::print_config::{OutputFormat, SortKey};
+use crate::format::{Charset, MarkerPosition};
+use crate::graph::GraphBackend;
+use crate::rs_file::Resolver;
+use crate::scan::UnsafeCriteria;
 
+use cargo_geiger_serde::CountingStrategy;
 use pico_args::Arguments;
+use std::ffi::OsString;
 use std::path::PathBuf;
 
 pub const HELP: &str =
@@ -9,48 +14,211 @@ pub const HELP: &str =
 
 USAGE:
     cargo geiger [OPTIONS]
+    cargo-geiger [OPTIONS]
 
 OPTIONS:
     -p, --package <SPEC>          Package to be used as the root of the tree.
-        --features <FEATURES>     Space-separated list of features to activate.
+        --exclude <SPEC>          Workspace member to leave out, can be given
+                                  multiple times. Without `--package` the
+                                  default members of the workspace are
+                                  scanned.
+        --features <FEATURES>     Space or comma separated list of features to
+                                  activate, `package/feature` activates a
+                                  feature of a dependency.
         --all-features            Activate all available features.
         --no-default-features     Do not activate the `default` feature.
         --target <TARGET>         Set the target triple.
         --all-targets             Return dependencies for all targets. By
                                   default only the host target is matched.
-        --manifest-path <PATH>    Path to Cargo.toml.
+        --all-platforms           Scan the dependencies of all platforms, the
+                                  ones only used on other platforms than the
+                                  target are shown with their platform and
+                                  without used counts.
+        --manifest-path <PATH>    Path to Cargo.toml, can be given multiple
+                                  times to scan several workspaces and add up
+                                  their unsafe usage.
     -i, --invert                  Invert the tree direction.
         --no-indent               Display the dependencies as a list (rather
                                   than a tree).
         --prefix-depth            Display the dependencies as a list (rather
                                   than a tree), but prefixed with the depth.
+                                  Can't be combined with --no-indent.
+        --sort <KEY>              Order the list of --prefix-depth or
+                                  --no-indent by KEY instead of the tree:
+                                  depth. depth lists each package once, at
+                                  the minimum depth it is found at.
     -a, --all                     Don't truncate dependencies that have already
                                   been displayed.
         --charset <CHARSET>       Character set to use in output: utf8, ascii
                                   [default: utf8].
+        --marker-position <POS>   Where the detection status of each package
+                                  is shown: left, right, none. left puts the
+                                  symbol in a column before the tree, right
+                                  at the end of the row and none replaces it
+                                  with a word at the end of the row, for
+                                  screen readers [default: left].
     --format <FORMAT>             Format string used for printing dependencies
                                   [default: {p}].
     --json                        Output in JSON format.
+        --output-format <FORMAT>  Output format: json, tree-json. json is the
+                                  same as --json, tree-json lists the lines
+                                  of the tree with the unsafe usage of each
+                                  package.
+        --output <PATH>           Write the JSON report to PATH and print the
+                                  table to stdout, both from the same scan.
+                                  Requires --json.
+        --output-dir <DIR>        Write the JSON report to DIR split by
+                                  package, a <name>-<version>.json per
+                                  package and an index.json listing them,
+                                  and print the table to stdout. Requires
+                                  --json.
+        --prune-output            Remove the other .json files in the
+                                  --output-dir, like the ones of packages no
+                                  longer in the graph.
+        --report-file-list        Include the .rs files scanned for each
+                                  package in the JSON report, and whether the
+                                  build used them.
+        --report-modules          Include the unsafe usage per module in the
+                                  JSON report.
     -v, --verbose                 Use verbose output (-vv very verbose/build.rs
-                                  output).
+                                  output). Lists the modules with the most
+                                  unsafe usage, the unsafe items in the
+                                  public API and the transmute calls of each
+                                  package, -vv also the enabled features of
+                                  each package.
     -q, --quiet                   No output printed to stdout other than the
                                   tree.
+        --progress-json           Print the progress of the run on stderr as
+                                  lines of JSON, ending with the totals of
+                                  the scan.
+        --timings                 Print the time spent resolving, cleaning,
+                                  building, reading dep-info files, scanning
+                                  and printing to stderr, also in the
+                                  metadata of the JSON report.
         --color <WHEN>            Coloring: auto, always, never.
+        --hyperlinks              Link dependency names to their repository or
+                                  crates.io page even if the terminal isn't
+                                  detected to support hyperlinks.
         --frozen                  Require Cargo.lock and cache are up to date.
         --locked                  Require Cargo.lock is up to date.
         --offline                 Run without accessing the network.
     -Z \"<FLAG>...\"                Unstable (nightly-only) flags to Cargo.
+        --config <KEY=VALUE>      Override a Cargo configuration value, can be
+                                  given multiple times. Requires
+                                  -Z unstable-options.
         --include-tests           Count unsafe usage in tests..
         --build-dependencies      Also analyze build dependencies.
         --dev-dependencies        Also analyze dev dependencies.
         --all-dependencies        Analyze all dependencies, including build and
                                   dev.
+        --no-dev-deps             Don't resolve, build or scan dev
+                                  dependencies, also with
+                                  --all-dependencies.
+        --keep-going              Continue scanning when packages fail to
+                                  build, their files are counted as not used
+                                  by the build.
+        --reuse-build             Don't clean and rebuild everything, read
+                                  the files used by up-to-date packages from
+                                  their existing dep-info files. Faster, but
+                                  trusts the build artifacts in the target
+                                  directory.
+        --backend <BACKEND>       How to build the dependency graph: resolve,
+                                  metadata [default: resolve]. metadata
+                                  reads it from `cargo metadata` of the
+                                  installed cargo.
+        --resolver <RESOLVER>     How to find the .rs files used by the
+                                  build: executor, unit-graph
+                                  [default: executor]. unit-graph follows the
+                                  module declarations of each build target
+                                  instead of building, unless build scripts
+                                  need to run.
+        --consider <CATEGORIES>   Comma-separated unsafe usage categories that
+                                  make a crate count as using unsafe:
+                                  functions, exprs, impls, traits, methods,
+                                  ffi. Prefix a category with used- to only
+                                  count it in code used by the build
+                                  [default: all categories].
+        --ignore-ffi              Don't count functions and statics declared
+                                  in extern blocks, their counts are still
+                                  shown, dimmed.
+        --counting-strategy <STRATEGY>
+                                  Which expressions to count: geiger leaves
+                                  out paths and literals, so that f(x) is one
+                                  expression, all-nodes counts every one like
+                                  other tools do. Recorded in the metadata of
+                                  the JSON report [default: geiger].
+        --all-target-kinds        Count the unsafe usage of all kinds of
+                                  targets in the tree as used, like binaries
+                                  and tests. By default only the library and
+                                  build script of a package count as used.
+        --split-generated         Show the unsafe usage of machine generated
+                                  files, like bindgen output, in a row of its
+                                  own below each package.
+        --show-totals             Show the total number of items of each
+                                  category, safe and unsafe, in the table
+                                  cells as x/y (of N).
+        --no-legend               Don't explain the counts and symbols in two
+                                  lines below the table header.
+        --normalize-output        Print paths under the workspace root as
+                                  [ROOT]/..., leave out the timings and sort
+                                  the warnings, so the text output of a scan
+                                  can be compared byte for byte, like in
+                                  snapshot tests.
+        --show-dep-counts         Show the number of direct and transitive
+                                  dependencies of each package in a column of
+                                  the table and in the JSON report.
+        --members-only            Only show the workspace members in the
+                                  table, each with its own unsafe usage and
+                                  the one of all packages it depends on.
+                                  Adds a members section to the JSON report.
+        --split-local-paths       Group the path dependencies outside of the
+                                  workspace by the directory they are in,
+                                  like sibling repositories, with the
+                                  subtotals of each group below the table and
+                                  in the JSON report.
+        --generated-marker <TEXT> Also count files as machine generated when
+                                  TEXT is found in their leading comments,
+                                  can be given multiple times.
+        --watch-calls <PATTERNS>  Comma-separated paths of calls to count per
+                                  package, like ptr::copy_nonoverlapping. A
+                                  pattern matches calls whose path ends with
+                                  it, method calls match its last segment.
+                                  Reported in the JSON report and with -v,
+                                  can be given multiple times.
+        --harness-cfgs <NAMES>    Comma-separated cfg names of verification
+                                  harnesses, fuzzing,kani,loom,miri by
+                                  default. Code gated by them is reported
+                                  apart in the JSON report and left out of
+                                  the table. An empty list turns this off.
+        --max-file-size <BYTES>   Don't scan .rs files larger than BYTES,
+                                  like huge generated files, list them apart
+                                  in the table and the JSON report instead
+                                  [default: 16777216].
         --forbid-only             Don't build or clean anything, only scan
                                   entry point .rs source files for.
                                   forbid(unsafe_code) flags. This is
                                   significantly faster than the default
                                   scanning mode. TODO: Add ability to combine
                                   this with a whitelist for use in CI.
+        --check-lock <PATH>       Compare the Cargo.lock fingerprint stored in
+                                  a JSON report with the current workspace,
+                                  list the changed packages and exit with an
+                                  error if they differ.
+        --explain <SPEC>          Instead of the tree, list the .rs files of
+                                  the packages matching SPEC with their unsafe
+                                  usage and crate root lint levels.
+        --crate <NAME[@VERSION]>  Instead of the current project, scan the
+                                  crates.io crate NAME, by default its latest
+                                  version that isn't yanked. Nothing is
+                                  built, all its .rs files are scanned. Use
+                                  --offline to only use the local cache.
+        --reuse-report <PATH>     Copy the entries of unchanged registry and
+                                  git packages from a previous JSON report
+                                  instead of scanning them again. Requires
+                                  --json.
+        --print-schema            Print the JSON Schema of the --json report,
+                                  with its schema_version, and exit. No
+                                  workspace is needed.
     -h, --help                    Prints help information.
     -V, --version                 Prints version information.
 ";
@@ -59,34 +227,89 @@ pub struct Args {
     pub all: bool,
     pub all_deps: bool,
     pub all_features: bool,
+    pub all_platforms: bool,
+    pub all_target_kinds: bool,
     pub all_targets: bool,
+    pub backend: GraphBackend,
     pub build_deps: bool,
     pub charset: Charset,
+    pub check_lock: Option<PathBuf>,
+    pub cli_config: Vec<String>,
     pub color: Option<String>,
+    pub counting_strategy: CountingStrategy,
     pub dev_deps: bool,
-    pub features: Option<String>,
+    pub exclude: Vec<String>,
+    pub explain: Option<String>,
+    pub features: Vec<String>,
     pub forbid_only: bool,
     pub format: String,
     pub frozen: bool,
+    pub generated_markers: Vec<String>,
+    /// The `cfg` names given with `--harness-cfgs`, `None` for the defaults
+    pub harness_cfgs: Option<Vec<String>>,
     pub help: bool,
+    pub hyperlinks: bool,
     pub include_tests: bool,
     pub invert: bool,
+    pub keep_going: bool,
     pub locked: bool,
-    pub manifest_path: Option<PathBuf>,
+    pub manifest_paths: Vec<PathBuf>,
+    pub marker_position: MarkerPosition,
+    /// The limit given with `--max-file-size`, `None` for the default
+    pub max_file_size: Option<u64>,
+    pub members_only: bool,
     pub no_default_features: bool,
+    pub no_dev_deps: bool,
     pub no_indent: bool,
+    pub no_legend: bool,
+    pub normalize_output: bool,
     pub offline: bool,
+    pub output: Option<PathBuf>,
+    pub output_dir: Option<PathBuf>,
     pub package: Option<String>,
     pub prefix_depth: bool,
+    pub print_schema: bool,
+    pub progress_json: bool,
+    pub prune_output: bool,
+    pub published_crate: Option<String>,
     pub quiet: bool,
+    pub report_file_list: bool,
+    pub report_modules: bool,
+    pub resolver: Resolver,
+    pub reuse_build: bool,
+    pub reuse_report: Option<PathBuf>,
+    pub show_dep_counts: bool,
+    pub show_totals: bool,
+    pub sort: Option<SortKey>,
+    pub split_generated: bool,
+    pub split_local_paths: bool,
     pub target: Option<String>,
+    pub timings: bool,
+    pub unsafe_criteria: UnsafeCriteria,
     pub unstable_flags: Vec<String>,
     pub verbose: u32,
     pub version: bool,
     pub output_format: Option<OutputFormat>,
+    pub watched_calls: Vec<String>,
 }
 
 impl Args {
+    /// Parses the arguments of the process, including the program name. Run
+    /// as `cargo geiger`, cargo passes `geiger` on as the first argument, it
+    /// is skipped. Run directly as `cargo-geiger` there is none to skip.
+    pub fn parse_args_from_argument_vector(
+        argument_vector: Vec<OsString>,
+    ) -> Result<Args, Box<dyn std::error::Error>> {
+        let mut arguments = argument_vector.into_iter().skip(1).peekable();
+        if arguments
+            .peek()
+            .map_or(false, |argument| argument == "geiger")
+        {
+            arguments.next();
+        }
+        Args::parse_args(Arguments::from_vec(arguments.collect()))
+    }
+
     pub fn parse_args(
         mut raw_args: Arguments,
     ) -> Result<Args, Box<dyn std::error::Error>> {
@@ -94,31 +317,102 @@ impl Args {
             all: raw_args.contains(["-a", "--all"]),
             all_deps: raw_args.contains("--all-dependencies"),
             all_features: raw_args.contains("--all-features"),
+            all_platforms: raw_args.contains("--all-platforms"),
+            all_target_kinds: raw_args.contains("--all-target-kinds"),
             all_targets: raw_args.contains("--all-targets"),
+            backend: raw_args
+                .opt_value_from_str("--backend")?
+                .unwrap_or(GraphBackend::Resolve),
             build_deps: raw_args.contains("--build-dependencies"),
             charset: raw_args
                 .opt_value_from_str("--charset")?
                 .unwrap_or(Charset::Utf8),
+            check_lock: raw_args.opt_value_from_str("--check-lock")?,
+            cli_config: raw_args.values_from_str("--config")?,
             color: raw_args.opt_value_from_str("--color")?,
+            counting_strategy: raw_args
+                .opt_value_from_str("--counting-strategy")?
+                .unwrap_or(CountingStrategy::Geiger),
             dev_deps: raw_args.contains("--dev-dependencies"),
-            features: raw_args.opt_value_from_str("--features")?,
+            exclude: raw_args.values_from_str("--exclude")?,
+            explain: raw_args.opt_value_from_str("--explain")?,
+            features: raw_args
+                .values_from_str("--features")?
+                .iter()
+                .flat_map(|features: &String| {
+                    features.split(|c: char| c == ',' || c.is_whitespace())
+                })
+                .filter(|feature| !feature.is_empty())
+                .map(String::from)
+                .collect(),
             forbid_only: raw_args.contains(["-f", "--forbid-only"]),
             format: raw_args
                 .opt_value_from_str("--format")?
                 .unwrap_or_else(|| "{p}".to_string()),
             frozen: raw_args.contains("--frozen"),
+            generated_markers: raw_args
+                .values_from_str("--generated-marker")?,
+            harness_cfgs: raw_args.opt_value_from_str("--harness-cfgs")?.map(
+                |harness_cfgs: String| {
+                    harness_cfgs
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|harness_cfg| !harness_cfg.is_empty())
+                        .map(String::from)
+                        .collect()
+                },
+            ),
             help: raw_args.contains(["-h", "--help"]),
+            hyperlinks: raw_args.contains("--hyperlinks"),
             include_tests: raw_args.contains("--include-tests"),
             invert: raw_args.contains(["-i", "--invert"]),
+            keep_going: raw_args.contains("--keep-going"),
             locked: raw_args.contains("--locked"),
-            manifest_path: raw_args.opt_value_from_str("--manifest-path")?,
+            manifest_paths: raw_args.values_from_str("--manifest-path")?,
+            marker_position: raw_args
+                .opt_value_from_str("--marker-position")?
+                .unwrap_or(MarkerPosition::Left),
+            max_file_size: raw_args.opt_value_from_str("--max-file-size")?,
+            members_only: raw_args.contains("--members-only"),
             no_default_features: raw_args.contains("--no-default-features"),
+            no_dev_deps: raw_args.contains("--no-dev-deps"),
             no_indent: raw_args.contains("--no-indent"),
+            no_legend: raw_args.contains("--no-legend"),
+            normalize_output: raw_args.contains("--normalize-output"),
             offline: raw_args.contains("--offline"),
-            package: raw_args.opt_value_from_str("--manifest-path")?,
+            output: raw_args.opt_value_from_str("--output")?,
+            output_dir: raw_args.opt_value_from_str("--output-dir")?,
+            package: raw_args.opt_value_from_str(["-p", "--package"])?,
             prefix_depth: raw_args.contains("--prefix-depth"),
+            print_schema: raw_args.contains("--print-schema"),
+            progress_json: raw_args.contains("--progress-json"),
+            prune_output: raw_args.contains("--prune-output"),
+            published_crate: raw_args.opt_value_from_str("--crate")?,
             quiet: raw_args.contains(["-q", "--quiet"]),
+            report_file_list: raw_args.contains("--report-file-list"),
+            report_modules: raw_args.contains("--report-modules"),
+            resolver: raw_args
+                .opt_value_from_str("--resolver")?
+                .unwrap_or(Resolver::Executor),
+            reuse_build: raw_args.contains("--reuse-build"),
+            reuse_report: raw_args.opt_value_from_str("--reuse-report")?,
+            show_dep_counts: raw_args.contains("--show-dep-counts"),
+            show_totals: raw_args.contains("--show-totals"),
+            sort: raw_args.opt_value_from_str("--sort")?,
+            split_generated: raw_args.contains("--split-generated"),
+            split_local_paths: raw_args.contains("--split-local-paths"),
             target: raw_args.opt_value_from_str("--target")?,
+            timings: raw_args.contains("--timings"),
+            unsafe_criteria: {
+                let unsafe_criteria: UnsafeCriteria = raw_args
+                    .opt_value_from_str("--consider")?
+                    .unwrap_or_default();
+                if raw_args.contains("--ignore-ffi") {
+                    unsafe_criteria.without_foreign_items()
+                } else {
+                    unsafe_criteria
+                }
+            },
             unstable_flags: raw_args
                 .opt_value_from_str("-Z")?
                 .map(|s: String| s.split(' ').map(|s| s.to_owned()).collect())
@@ -135,8 +429,16 @@ impl Args {
             output_format: if raw_args.contains("--json") {
                 Some(OutputFormat::Json)
             } else {
-                None
+                raw_args.opt_value_from_str("--output-format")?
             },
+            watched_calls: raw_args
+                .values_from_str("--watch-calls")?
+                .iter()
+                .flat_map(|patterns: &String| patterns.split(','))
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .map(String::from)
+                .collect(),
         };
         Ok(args)
     }
@@ -147,7 +449,6 @@ pub mod args_tests {
     use super::*;
 
     use rstest::*;
-    use std::ffi::OsString;
 
     #[rstest(
         input_argument_vector,
@@ -202,4 +503,367 @@ pub mod args_tests {
         assert_eq!(args.charset, expected_charset);
         assert_eq!(args.verbose, expected_verbose)
     }
+
+    #[rstest(
+        input_argument_vector,
+        expected_all,
+        expected_version,
+        case(vec!["cargo-geiger", "--all"], true, false),
+        case(vec!["cargo", "geiger", "--all"], true, false),
+        case(vec!["cargo-geiger", "--version"], false, true),
+        case(vec!["cargo", "geiger", "-V"], false, true)
+    )]
+    fn parse_args_from_argument_vector_test(
+        input_argument_vector: Vec<&str>,
+        expected_all: bool,
+        expected_version: bool,
+    ) {
+        let args = Args::parse_args_from_argument_vector(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        )
+        .unwrap();
+
+        assert_eq!(args.all, expected_all);
+        assert_eq!(args.version, expected_version);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_features,
+        case(vec![], vec![]),
+        case(vec!["--features", "simd avx"], vec!["simd", "avx"]),
+        case(vec!["--features", "simd,avx"], vec!["simd", "avx"]),
+        case(vec!["--features", " simd, avx ,"], vec!["simd", "avx"]),
+        case(
+            vec!["--features", "dep/simd", "--features", "dep?/avx"],
+            vec!["dep/simd", "dep?/avx"]
+        )
+    )]
+    fn parse_args_test_features(
+        input_argument_vector: Vec<&str>,
+        expected_features: Vec<&str>,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert!(args_result.is_ok());
+        assert_eq!(args_result.unwrap().features, expected_features);
+    }
+
+    #[rstest]
+    fn parse_args_test_cli_config() {
+        let args_result = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("--config"),
+            OsString::from("net.offline=true"),
+            OsString::from("--config"),
+            OsString::from("build.jobs=1"),
+        ]));
+
+        assert!(args_result.is_ok());
+        assert_eq!(
+            args_result.unwrap().cli_config,
+            vec![
+                String::from("net.offline=true"),
+                String::from("build.jobs=1")
+            ]
+        );
+    }
+
+    #[rstest]
+    fn parse_args_test_generated_files() {
+        let args_result = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("--split-generated"),
+            OsString::from("--generated-marker"),
+            OsString::from("Generated by protoc"),
+            OsString::from("--generated-marker"),
+            OsString::from("DO NOT EDIT"),
+        ]));
+
+        assert!(args_result.is_ok());
+        let args = args_result.unwrap();
+        assert!(args.split_generated);
+        assert_eq!(
+            args.generated_markers,
+            vec![
+                String::from("Generated by protoc"),
+                String::from("DO NOT EDIT")
+            ]
+        );
+    }
+
+    #[rstest]
+    fn parse_args_test_watch_calls() {
+        let args_result = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("--watch-calls"),
+            OsString::from("ptr::copy_nonoverlapping, slice::from_raw_parts"),
+            OsString::from("--watch-calls"),
+            OsString::from("MaybeUninit::assume_init"),
+        ]));
+
+        assert!(args_result.is_ok());
+        assert_eq!(
+            args_result.unwrap().watched_calls,
+            vec![
+                String::from("ptr::copy_nonoverlapping"),
+                String::from("slice::from_raw_parts"),
+                String::from("MaybeUninit::assume_init")
+            ]
+        );
+    }
+
+    #[rstest(
+        input_args,
+        expected_harness_cfgs,
+        case(vec![], None),
+        case(
+            vec!["--harness-cfgs", "fuzzing, kani"],
+            Some(vec![String::from("fuzzing"), String::from("kani")])
+        ),
+        case(vec!["--harness-cfgs", ""], Some(vec![]))
+    )]
+    fn parse_args_test_harness_cfgs(
+        input_args: Vec<&str>,
+        expected_harness_cfgs: Option<Vec<String>>,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_args.into_iter().map(OsString::from).collect(),
+        ));
+
+        assert!(args_result.is_ok());
+        assert_eq!(args_result.unwrap().harness_cfgs, expected_harness_cfgs);
+    }
+
+    #[rstest(
+        input_args,
+        expected_max_file_size,
+        case(vec![], Some(None)),
+        case(vec!["--max-file-size", "1024"], Some(Some(1024))),
+        case(vec!["--max-file-size", "1k"], None)
+    )]
+    fn parse_args_test_max_file_size(
+        input_args: Vec<&str>,
+        expected_max_file_size: Option<Option<u64>>,
+    ) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_args.into_iter().map(OsString::from).collect(),
+        ));
+
+        assert_eq!(
+            args_result.ok().map(|a| a.max_file_size),
+            expected_max_file_size
+        );
+    }
+
+    #[rstest]
+    fn parse_args_test_manifest_paths() {
+        let args_result = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("--manifest-path"),
+            OsString::from("frontend/Cargo.toml"),
+            OsString::from("--manifest-path"),
+            OsString::from("backend/Cargo.toml"),
+        ]));
+
+        assert!(args_result.is_ok());
+        assert_eq!(
+            args_result.unwrap().manifest_paths,
+            vec![
+                PathBuf::from("frontend/Cargo.toml"),
+                PathBuf::from("backend/Cargo.toml")
+            ]
+        );
+    }
+
+    #[rstest(
+        input_args,
+        expected_package,
+        case(vec![], None),
+        case(vec!["-p", "member1"], Some("member1")),
+        case(vec!["--package", "member1:0.1.0"], Some("member1:0.1.0")),
+        case(vec!["--manifest-path", "ws/Cargo.toml"], None)
+    )]
+    fn parse_args_test_package(
+        input_args: Vec<&str>,
+        expected_package: Option<&str>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_args.into_iter().map(OsString::from).collect(),
+        ))
+        .unwrap();
+
+        assert_eq!(args.package.as_deref(), expected_package);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_resolver,
+        case(vec![], Some(Resolver::Executor)),
+        case(
+            vec![OsString::from("--resolver"), OsString::from("unit-graph")],
+            Some(Resolver::UnitGraph)
+        ),
+        case(
+            vec![OsString::from("--resolver"), OsString::from("executor")],
+            Some(Resolver::Executor)
+        ),
+        case(
+            vec![OsString::from("--resolver"), OsString::from("build-plan")],
+            None
+        )
+    )]
+    fn parse_args_test_resolver(
+        input_argument_vector: Vec<OsString>,
+        expected_resolver: Option<Resolver>,
+    ) {
+        let args_result =
+            Args::parse_args(Arguments::from_vec(input_argument_vector));
+
+        assert_eq!(args_result.ok().map(|a| a.resolver), expected_resolver);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_backend,
+        case(vec![], Some(GraphBackend::Resolve)),
+        case(
+            vec![OsString::from("--backend"), OsString::from("metadata")],
+            Some(GraphBackend::Metadata)
+        ),
+        case(
+            vec![OsString::from("--backend"), OsString::from("resolve")],
+            Some(GraphBackend::Resolve)
+        ),
+        case(
+            vec![OsString::from("--backend"), OsString::from("krates")],
+            None
+        )
+    )]
+    fn parse_args_test_backend(
+        input_argument_vector: Vec<OsString>,
+        expected_backend: Option<GraphBackend>,
+    ) {
+        let args_result =
+            Args::parse_args(Arguments::from_vec(input_argument_vector));
+
+        assert_eq!(args_result.ok().map(|a| a.backend), expected_backend);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_counting_strategy,
+        case(vec![], Some(CountingStrategy::Geiger)),
+        case(
+            vec![
+                OsString::from("--counting-strategy"),
+                OsString::from("all-nodes")
+            ],
+            Some(CountingStrategy::AllNodes)
+        ),
+        case(
+            vec![
+                OsString::from("--counting-strategy"),
+                OsString::from("all")
+            ],
+            None
+        )
+    )]
+    fn parse_args_test_counting_strategy(
+        input_argument_vector: Vec<OsString>,
+        expected_counting_strategy: Option<CountingStrategy>,
+    ) {
+        let args_result =
+            Args::parse_args(Arguments::from_vec(input_argument_vector));
+
+        assert_eq!(
+            args_result.ok().map(|a| a.counting_strategy),
+            expected_counting_strategy
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_sort,
+        case(vec![], Some(None)),
+        case(
+            vec![OsString::from("--sort"), OsString::from("depth")],
+            Some(Some(SortKey::Depth))
+        ),
+        case(vec![OsString::from("--sort"), OsString::from("name")], None)
+    )]
+    fn parse_args_test_sort(
+        input_argument_vector: Vec<OsString>,
+        expected_sort: Option<Option<SortKey>>,
+    ) {
+        let args_result =
+            Args::parse_args(Arguments::from_vec(input_argument_vector));
+
+        assert_eq!(args_result.ok().map(|a| a.sort), expected_sort);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_counts_foreign_items,
+        case(vec![], true),
+        case(vec![OsString::from("--ignore-ffi")], false),
+        case(
+            vec![
+                OsString::from("--consider"),
+                OsString::from("exprs,ffi"),
+                OsString::from("--ignore-ffi")
+            ],
+            false
+        )
+    )]
+    fn parse_args_test_ignore_ffi(
+        input_argument_vector: Vec<OsString>,
+        expected_counts_foreign_items: bool,
+    ) {
+        let args_result =
+            Args::parse_args(Arguments::from_vec(input_argument_vector));
+
+        assert_eq!(
+            args_result.unwrap().unsafe_criteria.counts_foreign_items(),
+            expected_counts_foreign_items
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_output_format,
+        case(vec![], Some(None)),
+        case(vec![OsString::from("--json")], Some(Some(OutputFormat::Json))),
+        case(
+            vec![OsString::from("--output-format"), OsString::from("json")],
+            Some(Some(OutputFormat::Json))
+        ),
+        case(
+            vec![
+                OsString::from("--output-format"),
+                OsString::from("tree-json")
+            ],
+            Some(Some(OutputFormat::TreeJson))
+        ),
+        case(
+            vec![OsString::from("--output-format"), OsString::from("yaml")],
+            None
+        )
+    )]
+    fn parse_args_test_output_format(
+        input_argument_vector: Vec<OsString>,
+        expected_output_format: Option<Option<OutputFormat>>,
+    ) {
+        let args_result =
+            Args::parse_args(Arguments::from_vec(input_argument_vector));
+
+        assert_eq!(
+            args_result.ok().map(|a| a.output_format),
+            expected_output_format
+        );
+    }
 }
@@ -12,24 +12,95 @@ use crate::Args;
 
 // TODO: Consider making this a lib.rs (again) and expose a full API, excluding
 // only the terminal output..? That API would be dependent on cargo.
+use anyhow::Context;
 use cargo::core::package::PackageSet;
 use cargo::core::registry::PackageRegistry;
 use cargo::core::resolver::ResolveOpts;
-use cargo::core::{Package, PackageId, PackageIdSpec, Resolve, Workspace};
+use cargo::core::{
+    enable_nightly_features, Package, PackageId, PackageIdSpec, Resolve,
+    Workspace,
+};
 use cargo::ops;
 use cargo::util::{self, important_paths, CargoResult};
 use cargo::Config;
 use cargo_metadata::{CargoOpt, Metadata, MetadataCommand};
 use cargo_platform::Cfg;
 use krates::{Builder, Krates};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::{self, FromStr};
 
+/// The manifest to scan can't be loaded as a package or workspace, caught
+/// before cargo loads it.
+#[derive(Debug)]
+pub enum ManifestError {
+    /// `--manifest-path` is a directory without a `Cargo.toml` in it.
+    DirectoryWithoutManifest(PathBuf),
+
+    /// The manifest is not valid TOML, with the line of the error when it is
+    /// known.
+    InvalidToml {
+        path: PathBuf,
+        line: Option<usize>,
+        message: String,
+    },
+
+    /// The manifest is the virtual manifest of a workspace, it has no package
+    /// to look up `--package` in.
+    Virtual(PathBuf),
+}
+
+impl Error for ManifestError {}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManifestError::DirectoryWithoutManifest(path) => write!(
+                f,
+                "manifest path `{}` is a directory without a `Cargo.toml`",
+                path.display()
+            ),
+            ManifestError::InvalidToml {
+                path,
+                line: Some(line),
+                message,
+            } => write!(
+                f,
+                "the manifest `{}` is not valid TOML, line {}: {}",
+                path.display(),
+                line,
+                message
+            ),
+            ManifestError::InvalidToml {
+                path,
+                line: None,
+                message,
+            } => write!(
+                f,
+                "the manifest `{}` is not valid TOML: {}",
+                path.display(),
+                message
+            ),
+            ManifestError::Virtual(path) => write!(
+                f,
+                "`{}` is the virtual manifest of a workspace, it has no \
+                 package of its own to look up `--package` in",
+                path.display()
+            ),
+        }
+    }
+}
+
 pub fn get_cargo_metadata(
     args: &Args,
     config: &Config,
+    manifest_path: Option<PathBuf>,
 ) -> CargoResult<Metadata> {
-    let root_manifest_path = match args.manifest_path.clone() {
+    let root_manifest_path = match manifest_path {
         Some(path) => path,
         None => important_paths::find_root_manifest_for_wd(config.cwd())?,
     };
@@ -45,22 +116,61 @@ pub fn get_cargo_metadata(
         metadata_command.features(CargoOpt::NoDefaultFeatures);
     }
 
-    if args.features.is_some() {
-        let features = args
-            .features
-            .as_ref()
-            .cloned()
-            .unwrap_or_else(String::new)
-            .split(' ')
-            .map(str::to_owned)
-            .collect::<Vec<String>>();
-
-        metadata_command.features(CargoOpt::SomeFeatures(features));
+    if !args.features.is_empty() {
+        metadata_command
+            .features(CargoOpt::SomeFeatures(args.features.clone()));
+    }
+
+    // The same unstable flags as for the resolve and the check build, these
+    // can change which packages end up in the metadata.
+    let mut other_options = args
+        .unstable_flags
+        .iter()
+        .map(|flag| format!("-Z{}", flag))
+        .chain(
+            args.cli_config
+                .iter()
+                .map(|value| format!("--config={}", value)),
+        )
+        .collect::<Vec<_>>();
+    if args.locked {
+        other_options.push("--locked".to_string());
+    }
+    if args.frozen {
+        other_options.push("--frozen".to_string());
+    }
+    if !other_options.is_empty() {
+        metadata_command.other_options(other_options);
     }
 
     Ok(metadata_command.exec()?)
 }
 
+/// Cargo accepts `-Z` flags depending on the channel cargo-geiger was built
+/// for, which says nothing about the toolchain that does the build. Accept
+/// them when the configured rustc is a nightly, like plain `cargo` does, so
+/// that they apply to the resolve and the check build alike.
+pub fn allow_unstable_flags(
+    config: &Config,
+    unstable_flags: &[String],
+) -> CargoResult<()> {
+    let rustc = config.load_global_rustc(None)?;
+    let rustc_version = rustc.version.to_string();
+    let rustc_bootstrap = env::var("RUSTC_BOOTSTRAP").ok();
+    if rustc_accepts_unstable_flags(&rustc_version, rustc_bootstrap.as_deref())
+    {
+        enable_nightly_features();
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "`-Z {}` requires a nightly toolchain, but the configured rustc \
+             is {}",
+            unstable_flags.join(" "),
+            rustc_version
+        ))
+    }
+}
+
 /// TODO: Write proper documentation for this.
 /// This function seems to be looking up the active flags for conditional
 /// compilation (cargo_platform::Cfg instances).
@@ -92,11 +202,34 @@ pub fn get_krates(cargo_metadata: &Metadata) -> CargoResult<Krates> {
     Ok(Builder::new().build_with_metadata(cargo_metadata.clone(), |_| ())?)
 }
 
+/// Path overrides (the `paths` list) from `.cargo/config`, paired with a
+/// description of where each override was defined.
+pub fn get_path_overrides(
+    config: &Config,
+) -> CargoResult<Vec<(PathBuf, String)>> {
+    let paths = match config.get_list("paths")? {
+        Some(list) => list,
+        None => return Ok(vec![]),
+    };
+    Ok(paths
+        .val
+        .iter()
+        .map(|(path, definition)| {
+            (definition.root(config).join(path), definition.to_string())
+        })
+        .collect())
+}
+
+/// The registry honors source replacement through the `Config` and has the
+/// path overrides from `.cargo/config` registered, to make the resolved
+/// packages match what the build compiles.
 pub fn get_registry<'a>(
     config: &'a Config,
     package: &Package,
+    workspace: &Workspace<'a>,
 ) -> CargoResult<PackageRegistry<'a>> {
     let mut registry = PackageRegistry::new(config)?;
+    ops::add_overrides(&mut registry, workspace)?;
     registry.add_sources(Some(package.package_id().source_id()))?;
     Ok(registry)
 }
@@ -109,9 +242,156 @@ pub fn get_workspace(
         Some(path) => path,
         None => important_paths::find_root_manifest_for_wd(config.cwd())?,
     };
+    check_manifest_syntax(&root)?;
     Workspace::new(&root, config)
 }
 
+/// The package of the manifest the workspace was loaded from. A virtual
+/// manifest has none, its members are scanned unless `--package` is given.
+pub fn current_package<'a>(
+    workspace: &'a Workspace,
+) -> CargoResult<&'a Package> {
+    if workspace.is_virtual() {
+        return Err(ManifestError::Virtual(
+            workspace.root().join("Cargo.toml"),
+        )
+        .into());
+    }
+    workspace.current()
+}
+
+/// Reads the manifest as TOML before cargo loads the workspace, for an error
+/// naming the file and line of a syntax error.
+fn check_manifest_syntax(manifest_path: &Path) -> CargoResult<()> {
+    let contents = fs::read_to_string(manifest_path).with_context(|| {
+        format!("failed to read the manifest `{}`", manifest_path.display())
+    })?;
+    match contents.parse::<toml::Value>() {
+        Ok(_) => Ok(()),
+        Err(error) => Err(ManifestError::InvalidToml {
+            path: manifest_path.to_path_buf(),
+            line: error.line_col().map(|(line, _)| line + 1),
+            message: error.to_string(),
+        }
+        .into()),
+    }
+}
+
+/// `manifest_path` made absolute against the directory cargo-geiger was
+/// invoked from, with symlinks resolved. The scanned files are canonicalized,
+/// paths are only shown relative to the workspace root if it is too. A
+/// directory stands for the `Cargo.toml` in it.
+pub fn canonical_manifest_path(
+    config: &Config,
+    manifest_path: &Path,
+) -> CargoResult<PathBuf> {
+    let path = config.cwd().join(manifest_path);
+    let path = if path.is_dir() {
+        let path = path.join("Cargo.toml");
+        if !path.is_file() {
+            return Err(ManifestError::DirectoryWithoutManifest(
+                manifest_path.to_path_buf(),
+            )
+            .into());
+        }
+        path
+    } else {
+        path
+    };
+    path.canonicalize().with_context(|| {
+        format!("manifest path `{}` does not exist", manifest_path.display())
+    })
+}
+
+/// The manifests of the workspace members to scan, for each `--manifest-path`
+/// or for the workspace of the current directory. Like cargo, these are the
+/// default members of the workspace, which are all members of a virtual
+/// manifest without `default-members`, less the `--exclude`d members. With
+/// `--package` the manifest paths are used as given, only made canonical, the
+/// package is looked up in the resolve of the current package.
+pub fn get_member_manifest_paths(
+    args: &Args,
+    config: &Config,
+) -> CargoResult<Vec<PathBuf>> {
+    if args.package.is_some() {
+        if !args.exclude.is_empty() {
+            return Err(anyhow::anyhow!(
+                "`--exclude` can't be combined with `--package`"
+            ));
+        }
+        return args
+            .manifest_paths
+            .iter()
+            .map(|path| canonical_manifest_path(config, path))
+            .collect();
+    }
+
+    let manifest_paths = if args.manifest_paths.is_empty() {
+        vec![None]
+    } else {
+        args.manifest_paths
+            .iter()
+            .map(|path| canonical_manifest_path(config, path).map(Some))
+            .collect::<CargoResult<Vec<_>>>()?
+    };
+    let exclude_specs = args
+        .exclude
+        .iter()
+        .map(|spec| Ok((spec.as_str(), PackageIdSpec::parse(spec)?)))
+        .collect::<CargoResult<Vec<_>>>()?;
+    let is_excluded = |package: &Package| {
+        exclude_specs
+            .iter()
+            .any(|(_, spec)| spec.matches(package.package_id()))
+    };
+
+    let mut matched_exclude_specs = HashSet::new();
+    let mut member_manifest_paths = Vec::new();
+    for manifest_path in manifest_paths {
+        let workspace = get_workspace(config, manifest_path)?;
+        for member in workspace.members() {
+            matched_exclude_specs.extend(
+                exclude_specs
+                    .iter()
+                    .filter(|(_, spec)| spec.matches(member.package_id()))
+                    .map(|(name, _)| *name),
+            );
+        }
+        let default_members = workspace
+            .default_members()
+            .filter(|member| !is_excluded(member))
+            .map(|member| member.manifest_path().to_path_buf())
+            .collect::<Vec<_>>();
+        if default_members.is_empty() {
+            return Err(anyhow::anyhow!(
+                "all default members of the workspace at `{}` are excluded",
+                workspace.root().display()
+            ));
+        }
+        // The same workspace can be given through more than one of its
+        // members.
+        for default_member in default_members {
+            if !member_manifest_paths.contains(&default_member) {
+                member_manifest_paths.push(default_member);
+            }
+        }
+    }
+
+    let unknown_exclude_specs = exclude_specs
+        .iter()
+        .filter(|(name, _)| !matched_exclude_specs.contains(name))
+        .map(|(name, _)| format!("`{}`", name))
+        .collect::<Vec<_>>();
+    if !unknown_exclude_specs.is_empty() {
+        return Err(anyhow::anyhow!(
+            "`--exclude` doesn't match any workspace member: {}",
+            unknown_exclude_specs.join(", ")
+        ));
+    }
+
+    Ok(member_manifest_paths)
+}
+
 pub fn resolve<'a, 'cfg>(
     package_id: PackageId,
     registry: &mut PackageRegistry<'cfg>,
@@ -119,8 +399,8 @@ pub fn resolve<'a, 'cfg>(
     features: &[String],
     all_features: bool,
     no_default_features: bool,
+    dev_deps: bool,
 ) -> CargoResult<(PackageSet<'a>, Resolve)> {
-    let dev_deps = true; // TODO: Review this.
     let uses_default_features = !no_default_features;
     let opts = ResolveOpts::new(
         dev_deps,
@@ -138,27 +418,40 @@ pub fn resolve<'a, 'cfg>(
         &[PackageIdSpec::from_package_id(package_id)],
         true,
     )?;
-    let packages = ops::get_resolved_packages(
-        &resolve,
-        PackageRegistry::new(workspace.config())?,
-    )?;
+    let mut package_registry = PackageRegistry::new(workspace.config())?;
+    ops::add_overrides(&mut package_registry, workspace)?;
+    let packages = ops::get_resolved_packages(&resolve, package_registry)?;
     Ok((packages, resolve))
 }
 
+fn rustc_accepts_unstable_flags(
+    rustc_version: &str,
+    rustc_bootstrap: Option<&str>,
+) -> bool {
+    rustc_bootstrap == Some("1")
+        || rustc_version.ends_with("-nightly")
+        || rustc_version.ends_with("-dev")
+}
+
 // TODO: Make a wrapper type for canonical paths and hide all mutable access.
 
 #[cfg(test)]
 mod cli_tests {
     use super::*;
-    use crate::format::Charset;
+    use crate::format::{Charset, MarkerPosition};
+    use crate::graph::GraphBackend;
+    use crate::rs_file::Resolver;
+    use crate::scan::UnsafeCriteria;
+    use cargo_geiger_serde::CountingStrategy;
     use rstest::*;
+    use std::ffi::OsString;
 
     #[rstest]
     fn get_cargo_metadata_test() {
         let args = create_args();
         let config = Config::default().unwrap();
 
-        let cargo_metadata_result = get_cargo_metadata(&args, &config);
+        let cargo_metadata_result = get_cargo_metadata(&args, &config, None);
 
         assert!(cargo_metadata_result.is_ok());
     }
@@ -196,7 +489,7 @@ mod cli_tests {
     fn get_krates_test() {
         let args = create_args();
         let config = Config::default().unwrap();
-        let cargo_metadata = get_cargo_metadata(&args, &config).unwrap();
+        let cargo_metadata = get_cargo_metadata(&args, &config, None).unwrap();
 
         let krates_result = get_krates(&cargo_metadata);
         assert!(krates_result.is_ok());
@@ -212,7 +505,7 @@ mod cli_tests {
         .unwrap();
         let package = workspace.current().unwrap();
 
-        let registry_result = get_registry(&config, &package);
+        let registry_result = get_registry(&config, &package, &workspace);
 
         assert!(registry_result.is_ok());
         let registry = registry_result.unwrap();
@@ -241,17 +534,153 @@ mod cli_tests {
         assert_eq!(package.package_id().name(), "cargo-geiger");
     }
 
+    #[rstest]
+    fn canonical_manifest_path_test_directory() {
+        let config = Config::default().unwrap();
+
+        assert_eq!(
+            canonical_manifest_path(&config, Path::new(".")).unwrap(),
+            config.cwd().join("Cargo.toml").canonicalize().unwrap()
+        );
+
+        let error =
+            canonical_manifest_path(&config, Path::new("src")).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ManifestError>(),
+            Some(ManifestError::DirectoryWithoutManifest(path))
+                if path == Path::new("src")
+        ));
+    }
+
+    #[rstest(
+        input_manifest,
+        expected_line,
+        case("[package]\nname = \"broken\"\nversion 0.1.0\n", Some(3)),
+        case("[package\n", Some(1))
+    )]
+    fn check_manifest_syntax_test(
+        input_manifest: &str,
+        expected_line: Option<usize>,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        fs::write(&manifest_path, input_manifest).unwrap();
+
+        let error = check_manifest_syntax(&manifest_path).unwrap_err();
+
+        match error.downcast_ref::<ManifestError>() {
+            Some(ManifestError::InvalidToml { path, line, .. }) => {
+                assert_eq!(path, &manifest_path);
+                assert_eq!(line, &expected_line);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        fs::write(&manifest_path, "[package]\nname = \"fixed\"\n").unwrap();
+        assert!(check_manifest_syntax(&manifest_path).is_ok());
+    }
+
+    #[rstest]
+    fn current_package_test_virtual_manifest() {
+        let config = Config::default().unwrap();
+        let manifest_path = config
+            .cwd()
+            .parent()
+            .unwrap()
+            .join("test_crates/test5_workspace_with_virtual_manifest")
+            .join("Cargo.toml");
+        let workspace = Workspace::new(&manifest_path, &config).unwrap();
+
+        let error = current_package(&workspace).unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<ManifestError>(),
+            Some(ManifestError::Virtual(_))
+        ));
+    }
+
+    #[rstest]
+    fn get_member_manifest_paths_test() {
+        let config = Config::default().unwrap();
+        let workspace_root = config.cwd().parent().unwrap().to_path_buf();
+        let mut args = create_args();
+        args.manifest_paths = vec![workspace_root.join("Cargo.toml")];
+        args.exclude = vec![String::from("geiger")];
+
+        let member_manifest_paths =
+            get_member_manifest_paths(&args, &config).unwrap();
+        assert_eq!(
+            member_manifest_paths,
+            vec![
+                workspace_root.join("cargo-geiger").join("Cargo.toml"),
+                workspace_root.join("cargo-geiger-serde").join("Cargo.toml"),
+            ]
+        );
+
+        args.exclude.push(String::from("not-a-member"));
+        let error = get_member_manifest_paths(&args, &config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`--exclude` doesn't match any workspace member: `not-a-member`"
+        );
+
+        args.package = Some(String::from("geiger"));
+        let error = get_member_manifest_paths(&args, &config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`--exclude` can't be combined with `--package`"
+        );
+    }
+
+    #[rstest]
+    fn get_member_manifest_paths_test_package_of_virtual_manifest() {
+        let config = Config::default().unwrap();
+        let manifest_path = config
+            .cwd()
+            .parent()
+            .unwrap()
+            .join("test_crates/test5_workspace_with_virtual_manifest")
+            .join("Cargo.toml");
+        let args = Args::parse_args_from_argument_vector(vec![
+            OsString::from("cargo-geiger"),
+            OsString::from("-p"),
+            OsString::from("member1"),
+            OsString::from("--manifest-path"),
+            manifest_path.clone().into_os_string(),
+        ])
+        .unwrap();
+
+        // With `--package` the virtual manifest is kept, rather than being
+        // replaced by the manifests of its members.
+        let member_manifest_paths =
+            get_member_manifest_paths(&args, &config).unwrap();
+        assert_eq!(
+            member_manifest_paths,
+            vec![manifest_path.canonicalize().unwrap()]
+        );
+
+        let workspace =
+            get_workspace(&config, member_manifest_paths.first().cloned())
+                .unwrap();
+        let error = current_package(&workspace).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ManifestError>(),
+            Some(ManifestError::Virtual(_))
+        ));
+    }
+
     #[rstest]
     fn resolve_test() {
         let config = Config::default().unwrap();
         let manifest_path: Option<PathBuf> = None;
         let workspace = get_workspace(&config, manifest_path).unwrap();
         let package = workspace.current().unwrap();
-        let mut registry = get_registry(&config, &package).unwrap();
+        let mut registry = get_registry(&config, &package, &workspace).unwrap();
 
         let features: Vec<String> = vec![];
         let all_features = false;
         let no_default_features = false;
+        let dev_deps = true;
 
         let resolve_cargo_result = resolve(
             package.package_id(),
@@ -260,41 +689,104 @@ mod cli_tests {
             &features,
             all_features,
             no_default_features,
+            dev_deps,
         );
 
         assert!(resolve_cargo_result.is_ok());
     }
 
+    #[rstest(
+        input_rustc_version,
+        input_rustc_bootstrap,
+        expected_accepts_unstable_flags,
+        case("1.48.0-nightly", None, true),
+        case("1.49.0-dev", None, true),
+        case("1.47.0-beta.6", None, false),
+        case("1.46.0", None, false),
+        case("1.46.0", Some("1"), true),
+        case("1.46.0", Some("0"), false)
+    )]
+    fn rustc_accepts_unstable_flags_test(
+        input_rustc_version: &str,
+        input_rustc_bootstrap: Option<&str>,
+        expected_accepts_unstable_flags: bool,
+    ) {
+        assert_eq!(
+            rustc_accepts_unstable_flags(
+                input_rustc_version,
+                input_rustc_bootstrap
+            ),
+            expected_accepts_unstable_flags
+        );
+    }
+
     fn create_args() -> Args {
         Args {
             all: false,
             all_deps: false,
             all_features: false,
+            all_platforms: false,
+            all_target_kinds: false,
             all_targets: false,
+            backend: GraphBackend::Resolve,
             build_deps: false,
             charset: Charset::Ascii,
+            check_lock: None,
+            cli_config: vec![],
             color: None,
+            counting_strategy: CountingStrategy::Geiger,
             dev_deps: false,
-            features: None,
+            exclude: Vec::new(),
+            explain: None,
+            features: Vec::new(),
             forbid_only: false,
             format: "".to_string(),
             frozen: false,
+            generated_markers: vec![],
+            harness_cfgs: None,
             help: false,
+            hyperlinks: false,
             include_tests: false,
             invert: false,
+            keep_going: false,
             locked: false,
-            manifest_path: None,
+            manifest_paths: Vec::new(),
+            marker_position: MarkerPosition::Left,
+            max_file_size: None,
+            members_only: false,
             no_default_features: false,
+            no_dev_deps: false,
             no_indent: false,
+            no_legend: false,
+            normalize_output: false,
             offline: false,
+            output: None,
+            output_dir: None,
             package: None,
             prefix_depth: false,
+            print_schema: false,
+            progress_json: false,
+            prune_output: false,
+            published_crate: None,
             quiet: false,
+            report_file_list: false,
+            report_modules: false,
+            resolver: Resolver::Executor,
+            reuse_build: false,
+            reuse_report: None,
+            show_dep_counts: false,
+            show_totals: false,
+            sort: None,
+            split_generated: false,
+            split_local_paths: false,
             target: None,
+            timings: false,
+            unsafe_criteria: UnsafeCriteria::default(),
             unstable_flags: vec![],
             verbose: 0,
             version: false,
             output_format: None,
+            watched_calls: vec![],
         }
     }
 }
@@ -0,0 +1,362 @@
+//! The messages shown for the errors of a failed run. The errors of the scan
+//! stay structured until here, this is where they are turned into messages,
+//! with a hint on what to do about them where there is one.
+
+use crate::cli::ManifestError;
+use crate::lockfile::{LockMismatchError, LockfileError, UnlockedResolveError};
+use crate::rs_file::{CustomExecutorError, RsResolveError};
+
+use cargo::CliError;
+use geiger::ScanFileError;
+
+const BUG_HINT: &str = "this is a bug in cargo-geiger, please report it at \
+                        https://github.com/rust-secure-code/cargo-geiger/issues";
+
+/// Replaces the error of `cli_error` with its rendered message, if it is one
+/// of the errors of the scan. All other errors are left to cargo, which
+/// prints them with their causes.
+pub fn render_cli_error(cli_error: CliError) -> CliError {
+    match cli_error.error.as_ref().and_then(render_error) {
+        Some(message) => {
+            CliError::new(anyhow::anyhow!(message), cli_error.exit_code)
+        }
+        None => cli_error,
+    }
+}
+
+/// The message of one of the errors of the scan, `None` for other errors.
+pub fn render_error(error: &anyhow::Error) -> Option<String> {
+    if let Some(error) = error.downcast_ref::<RsResolveError>() {
+        return Some(render_rs_resolve_error(error));
+    }
+    if let Some(error) = error.downcast_ref::<CustomExecutorError>() {
+        return Some(render_custom_executor_error(error));
+    }
+    if let Some(error) = error.downcast_ref::<ScanFileError>() {
+        return Some(render_scan_file_error(error));
+    }
+    if let Some(error) = error.downcast_ref::<LockfileError>() {
+        return Some(render_lockfile_error(error));
+    }
+    if let Some(error) = error.downcast_ref::<LockMismatchError>() {
+        return Some(with_hint(
+            error,
+            "the changed packages are listed above, `-` as in the report and \
+             `+` as resolved now, scan again to get a report of the current \
+             Cargo.lock",
+        ));
+    }
+    if let Some(error) = error.downcast_ref::<UnlockedResolveError>() {
+        return Some(with_hint(
+            error,
+            "`-` is locked and `+` is resolved now, run a build without \
+             `--locked` to update the lock file, then check it in to scan the \
+             locked dependencies",
+        ));
+    }
+    if let Some(error) = error.downcast_ref::<ManifestError>() {
+        return Some(render_manifest_error(error));
+    }
+    None
+}
+
+fn render_manifest_error(error: &ManifestError) -> String {
+    match error {
+        ManifestError::DirectoryWithoutManifest(_) => with_hint(
+            error,
+            "pass the directory of a package or workspace, or the path of its \
+             `Cargo.toml`",
+        ),
+        ManifestError::InvalidToml { .. } => error.to_string(),
+        ManifestError::Virtual(_) => with_hint(
+            error,
+            "leave out `--package` to scan all default members of the \
+             workspace, or pass the `--manifest-path` of the member depending \
+             on the package",
+        ),
+    }
+}
+
+fn render_lockfile_error(error: &LockfileError) -> String {
+    match error {
+        LockfileError::Missing(_) => with_hint(
+            error,
+            "run `cargo generate-lockfile` to create it, then check it in to \
+             scan the locked dependencies",
+        ),
+        LockfileError::Unreadable(_, _) => error.to_string(),
+    }
+}
+
+fn render_rs_resolve_error(error: &RsResolveError) -> String {
+    match error {
+        RsResolveError::ArcUnwrap() | RsResolveError::InnerContextMutex(_) => {
+            with_hint(error, BUG_HINT)
+        }
+        RsResolveError::Cargo(message)
+            if message.contains("could not compile") =>
+        {
+            with_hint(
+                error,
+                "a dependency failed to compile, try `--keep-going` to scan \
+                 the other packages and count its files as not used by the \
+                 build",
+            )
+        }
+        RsResolveError::Cargo(_) => with_hint(
+            error,
+            "the build failed, `cargo check --all-targets` shows whether it \
+             fails without cargo-geiger too",
+        ),
+        RsResolveError::DepParse(_, _) => with_hint(
+            error,
+            "the dep-info files may have been written by another version of \
+             cargo, try `cargo clean`",
+        ),
+        RsResolveError::Executor(error) => render_custom_executor_error(error),
+        RsResolveError::Io(_, _) | RsResolveError::Walkdir(_) => {
+            error.to_string()
+        }
+        RsResolveError::ModuleFiles(error) => render_scan_file_error(error),
+    }
+}
+
+fn render_custom_executor_error(error: &CustomExecutorError) -> String {
+    match error {
+        CustomExecutorError::InnerContextMutex(_) => with_hint(error, BUG_HINT),
+        CustomExecutorError::Io(_, _) => error.to_string(),
+        CustomExecutorError::OutDirKeyMissing(_)
+        | CustomExecutorError::OutDirValueMissing(_) => with_hint(
+            error,
+            "the rustc arguments were changed, a `RUSTC_WRAPPER` or \
+             `RUSTC_WORKSPACE_WRAPPER` has to pass them on unchanged",
+        ),
+    }
+}
+
+fn render_scan_file_error(error: &ScanFileError) -> String {
+    match error {
+        ScanFileError::Io(error, path) => {
+            format!("failed to read `{}`: {}", path.display(), error)
+        }
+        ScanFileError::Utf8(_, path) => format!(
+            "file `{}` is not valid UTF-8\n\nhint: only UTF-8 encoded .rs \
+             files can be scanned",
+            path.display()
+        ),
+        ScanFileError::Syn(error, path) => format!(
+            "failed to parse `{}`: {}\n\nhint: the file may use syntax that \
+             cargo-geiger doesn't support yet",
+            path.display(),
+            error
+        ),
+        ScanFileError::TooLarge(size, path) => format!(
+            "file `{}` is larger than the maximum file size, {} bytes\n\n\
+             hint: raise the limit with `--max-file-size`",
+            path.display(),
+            size
+        ),
+    }
+}
+
+fn with_hint(error: &dyn std::fmt::Display, hint: &str) -> String {
+    format!("{}\n\nhint: {}", error, hint)
+}
+
+#[cfg(test)]
+mod errors_tests {
+    use super::*;
+
+    use geiger::{find_unsafe_in_string_with_options, ScanOptions};
+    use rstest::*;
+    use std::io;
+    use std::path::PathBuf;
+
+    #[rstest(
+        input_error,
+        expected_hint,
+        case(RsResolveError::ArcUnwrap(), "this is a bug in cargo-geiger"),
+        case(
+            RsResolveError::Cargo(String::from(
+                "could not compile `broken_dep`."
+            )),
+            "a dependency failed to compile, try `--keep-going`"
+        ),
+        case(
+            RsResolveError::Cargo(String::from("failed to run `rustc`")),
+            "`cargo check --all-targets` shows whether it fails"
+        ),
+        case(
+            RsResolveError::DepParse(
+                String::from("malformed dep-info format, trailing \\"),
+                PathBuf::from("target/debug/deps/lib.d")
+            ),
+            "try `cargo clean`"
+        ),
+        case(
+            RsResolveError::Executor(CustomExecutorError::OutDirKeyMissing(
+                String::from("rustc --crate-name lib src/lib.rs")
+            )),
+            "a `RUSTC_WRAPPER` or `RUSTC_WORKSPACE_WRAPPER` has to pass them \
+             on unchanged"
+        ),
+        case(
+            RsResolveError::InnerContextMutex(String::from("poisoned")),
+            "this is a bug in cargo-geiger"
+        ),
+        case(
+            RsResolveError::ModuleFiles(ScanFileError::Utf8(
+                String::from_utf8(vec![0xff]).unwrap_err(),
+                PathBuf::from("src/lib.rs")
+            )),
+            "only UTF-8 encoded .rs files can be scanned"
+        ),
+        case(
+            RsResolveError::ModuleFiles(ScanFileError::Syn(
+                find_unsafe_in_string_with_options(
+                    "fn",
+                    &ScanOptions::default()
+                )
+                .unwrap_err(),
+                PathBuf::from("src/lib.rs")
+            )),
+            "the file may use syntax that cargo-geiger doesn't support yet"
+        ),
+        case(
+            RsResolveError::ModuleFiles(ScanFileError::TooLarge(
+                2048,
+                PathBuf::from("src/generated.rs")
+            )),
+            "raise the limit with `--max-file-size`"
+        )
+    )]
+    fn render_error_hint_test(
+        input_error: RsResolveError,
+        expected_hint: &str,
+    ) {
+        let message = render_error(&anyhow::Error::new(input_error)).unwrap();
+        let hint = message.split("\n\nhint: ").nth(1).unwrap();
+        assert!(hint.contains(expected_hint), "unexpected hint: {}", hint);
+    }
+
+    #[rstest]
+    fn render_error_names_the_file_that_is_not_utf8() {
+        let error = ScanFileError::Utf8(
+            String::from_utf8(vec![0xff]).unwrap_err(),
+            PathBuf::from("src/lib.rs"),
+        );
+
+        let message = render_error(&anyhow::Error::new(error)).unwrap();
+
+        assert!(message.starts_with("file `src/lib.rs` is not valid UTF-8"));
+    }
+
+    #[rstest]
+    fn render_error_without_hint_test() {
+        let error = RsResolveError::Io(
+            io::Error::new(io::ErrorKind::NotFound, "not found"),
+            PathBuf::from("src/lib.rs"),
+        );
+
+        let message = render_error(&anyhow::Error::new(error)).unwrap();
+
+        assert_eq!(message, "failed to read `src/lib.rs`: not found");
+    }
+
+    #[rstest]
+    fn render_error_hints_at_creating_a_missing_lock_file() {
+        let error = LockfileError::Missing(PathBuf::from("ws/Cargo.lock"));
+
+        let message = render_error(&anyhow::Error::new(error)).unwrap();
+
+        assert!(message.starts_with("the lock file `ws/Cargo.lock` does not"));
+        assert!(message.contains("\n\nhint: run `cargo generate-lockfile`"));
+    }
+
+    #[rstest]
+    fn render_error_lists_the_unlocked_packages() {
+        let error = UnlockedResolveError {
+            differences: vec![
+                String::from("- a 1.0.0 path -"),
+                String::from("+ a 1.1.0 path -"),
+            ],
+        };
+
+        let message = render_error(&anyhow::Error::new(error)).unwrap();
+
+        assert_eq!(
+            message,
+            "the resolved dependencies differ from the lock file, but \
+             --locked or --frozen was passed:\n    - a 1.0.0 path -\n    \
+             + a 1.1.0 path -\n\nhint: `-` is locked and `+` is resolved now, \
+             run a build without `--locked` to update the lock file, then \
+             check it in to scan the locked dependencies"
+        );
+    }
+
+    #[rstest(
+        input_error,
+        expected_message,
+        case(
+            ManifestError::DirectoryWithoutManifest(PathBuf::from("ws")),
+            "manifest path `ws` is a directory without a `Cargo.toml`\n\n\
+             hint: pass the directory of a package or workspace"
+        ),
+        case(
+            ManifestError::InvalidToml {
+                path: PathBuf::from("ws/Cargo.toml"),
+                line: Some(3),
+                message: String::from("expected an equals"),
+            },
+            "the manifest `ws/Cargo.toml` is not valid TOML, line 3: expected \
+             an equals"
+        ),
+        case(
+            ManifestError::Virtual(PathBuf::from("ws/Cargo.toml")),
+            "`ws/Cargo.toml` is the virtual manifest of a workspace, it has no \
+             package of its own to look up `--package` in\n\n\
+             hint: leave out `--package` to scan all default members"
+        )
+    )]
+    fn render_error_explains_manifest_errors(
+        input_error: ManifestError,
+        expected_message: &str,
+    ) {
+        let message = render_error(&anyhow::Error::new(input_error)).unwrap();
+
+        assert!(
+            message.starts_with(expected_message),
+            "unexpected message: {}",
+            message
+        );
+    }
+
+    #[rstest]
+    fn render_error_explains_a_lock_mismatch() {
+        let error = LockMismatchError {
+            changed_package_count: 2,
+        };
+
+        let message = render_error(&anyhow::Error::new(error)).unwrap();
+
+        assert_eq!(
+            message,
+            "the report was made from a different Cargo.lock, 2 package lines \
+             changed\n\nhint: the changed packages are listed above, `-` as \
+             in the report and `+` as resolved now, scan again to get a report \
+             of the current Cargo.lock"
+        );
+    }
+
+    #[rstest]
+    fn render_error_leaves_other_errors_to_cargo() {
+        assert_eq!(render_error(&anyhow::anyhow!("other error")), None);
+
+        let cli_error = render_cli_error(CliError::new(
+            anyhow::anyhow!("other error"),
+            101,
+        ));
+        assert_eq!(cli_error.exit_code, 101);
+        assert_eq!(cli_error.error.unwrap().to_string(), "other error");
+    }
+}
@@ -1,15 +1,22 @@
 pub mod emoji_symbols;
+pub mod hyperlink;
 pub mod pattern;
 pub mod print_config;
+pub mod rendered_line;
 pub mod table;
+pub mod tree_json;
 
 mod display;
 mod parse;
 
+use hyperlink::strip_hyperlinks;
+
 use cargo::core::dependency::DepKind;
+use cargo::core::PackageId;
 use std::fmt;
 use std::str::{self, FromStr};
 use strum_macros::EnumIter;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Charset {
@@ -41,9 +48,36 @@ impl FromStr for Charset {
 pub enum CrateDetectionStatus {
     NoneDetectedForbidsUnsafe,
     NoneDetectedAllowsUnsafe,
+    UnusedUnsafeDetected,
     UnsafeDetected,
 }
 
+/// Where the detection status of each package is shown in the table, see
+/// `--marker-position`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MarkerPosition {
+    /// A symbol in a column of its own between the counters and the tree
+    Left,
+    /// A word at the end of the row instead of the symbol, read out by
+    /// screen readers
+    None,
+    /// A symbol at the end of the row
+    Right,
+}
+
+impl FromStr for MarkerPosition {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<MarkerPosition, &'static str> {
+        match s {
+            "left" => Ok(MarkerPosition::Left),
+            "none" => Ok(MarkerPosition::None),
+            "right" => Ok(MarkerPosition::Right),
+            _ => Err("invalid marker position"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum RawChunk<'a> {
     Argument(&'a str),
@@ -56,6 +90,8 @@ pub enum SymbolKind {
     Lock = 0,
     QuestionMark = 1,
     Rads = 2,
+    HollowRads = 3,
+    Gear = 4,
 }
 
 #[derive(Debug)]
@@ -73,6 +109,31 @@ impl fmt::Display for FormatError {
     }
 }
 
+/// The number of terminal columns taken up by `text`. ANSI escape codes,
+/// hyperlinks and combining marks take up none, most CJK characters and emoji
+/// take up two.
+pub fn display_width(text: &str) -> usize {
+    let text = strip_hyperlinks(text);
+    UnicodeWidthStr::width(console::strip_ansi_codes(&text).as_ref())
+}
+
+/// Pads `text` with spaces to `width` terminal columns. Use this instead of
+/// the `{: <N}` format specifier, which counts chars rather than columns.
+pub fn pad_to_display_width(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// The name of a package whose sources couldn't be downloaded or read, in the
+/// place of the formatted name that needs its manifest.
+pub fn unavailable_package_name(package_id: PackageId) -> String {
+    format!(
+        "{} {} (unavailable)",
+        package_id.name(),
+        package_id.version()
+    )
+}
+
 pub fn get_kind_group_name(dep_kind: DepKind) -> Option<&'static str> {
     match dep_kind {
         DepKind::Build => Some("[build-dependencies]"),
@@ -85,6 +146,7 @@ pub fn get_kind_group_name(dep_kind: DepKind) -> Option<&'static str> {
 mod format_tests {
     use super::*;
 
+    use insta::assert_snapshot;
     use rstest::*;
 
     #[rstest]
@@ -94,6 +156,20 @@ mod format_tests {
         assert_eq!(Charset::from_str("invalid_str"), Err("invalid charset"));
     }
 
+    #[rstest]
+    fn marker_position_from_str_test() {
+        assert_eq!(MarkerPosition::from_str("left"), Ok(MarkerPosition::Left));
+        assert_eq!(MarkerPosition::from_str("none"), Ok(MarkerPosition::None));
+        assert_eq!(
+            MarkerPosition::from_str("right"),
+            Ok(MarkerPosition::Right)
+        );
+        assert_eq!(
+            MarkerPosition::from_str("invalid_str"),
+            Err("invalid marker position")
+        );
+    }
+
     #[rstest]
     fn get_kind_group_name_test() {
         assert_eq!(
@@ -108,4 +184,37 @@ mod format_tests {
 
         assert_eq!(get_kind_group_name(DepKind::Normal), None);
     }
+
+    #[rstest(
+        input_text,
+        expected_display_width,
+        case("ascii", 5),
+        case("日本語", 6),
+        case("e\u{301}", 1),
+        case("\x1B[31m日本\x1B[0m", 4),
+        case("\x1B]8;;https://crates.io\x1B\\日本\x1B]8;;\x1B\\", 4),
+        case("", 0)
+    )]
+    fn display_width_test(input_text: &str, expected_display_width: usize) {
+        assert_eq!(display_width(input_text), expected_display_width);
+    }
+
+    #[rstest]
+    fn pad_to_display_width_test() {
+        let lines = [
+            "ascii",
+            "日本語",
+            "e\u{301}te\u{301}",
+            "a\u{308}o\u{308}u\u{308}",
+            "🦀 crab",
+            "ｆｕｌｌ",
+            "longer than width",
+        ]
+        .iter()
+        .map(|text| format!("{}|", pad_to_display_width(text, 12)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        assert_snapshot!("pad_to_display_width", lines);
+    }
 }
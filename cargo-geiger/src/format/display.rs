@@ -1,3 +1,4 @@
+use crate::format::hyperlink::{hyperlink, package_url};
 use crate::format::pattern::Pattern;
 use crate::format::Chunk;
 
@@ -9,6 +10,8 @@ pub struct Display<'a> {
     pub pattern: &'a Pattern,
     pub package: &'a PackageId,
     pub metadata: &'a ManifestMetadata,
+    /// Link the package name to its repository or crates.io page.
+    pub hyperlinks: bool,
 }
 
 impl<'a> fmt::Display for Display<'a> {
@@ -21,12 +24,20 @@ impl<'a> fmt::Display for Display<'a> {
                     }
                 }
                 Chunk::Package => {
-                    (write!(
-                        fmt,
+                    let name = format!(
                         "{} {}",
                         self.package.name(),
                         self.package.version()
-                    ))?
+                    );
+                    let url = if self.hyperlinks {
+                        package_url(self.package, self.metadata)
+                    } else {
+                        None
+                    };
+                    match url {
+                        Some(url) => (fmt.write_str(&hyperlink(&name, &url)))?,
+                        None => (fmt.write_str(&name))?,
+                    }
                 }
                 Chunk::Raw(ref s) => (fmt.write_str(s))?,
                 Chunk::Repository => {
@@ -105,8 +116,52 @@ pub mod display_tests {
             pattern: &input_pattern,
             package: &package_id,
             metadata: &manifest_metadata,
+            hyperlinks: false,
+        };
+
+        assert_eq!(format!("{}", display), expected_formatted_string);
+    }
+
+    #[rstest(
+        input_repository,
+        expected_formatted_string,
+        case(
+            Some("https://github.com/rust-secure-code/cargo-geiger"),
+            "[\x1B]8;;https://github.com/rust-secure-code/cargo-geiger\x1B\\package_name 1.2.3\x1B]8;;\x1B\\] MIT"
+        ),
+        case(None, "[package_name 1.2.3] MIT")
+    )]
+    fn display_format_fmt_hyperlinks_test(
+        input_repository: Option<&str>,
+        expected_formatted_string: &str,
+    ) {
+        let package_id = PackageId::new(
+            "package_name",
+            "1.2.3".to_semver().unwrap(),
+            SourceId::from_url(
+                "git+https://github.com/rust-secure-code/cargo-geiger",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let pattern = Pattern::try_build("[{p}] {l}").unwrap();
+        let manifest_metadata = ManifestMetadata {
+            authors: vec![],
+            keywords: vec![],
+            categories: vec![],
+            license: Some(String::from("MIT")),
+            license_file: None,
+            description: None,
+            readme: None,
+            homepage: None,
+            repository: input_repository.map(String::from),
+            documentation: None,
+            badges: Default::default(),
+            links: None,
         };
 
+        let display = pattern.display(&package_id, &manifest_metadata, true);
+
         assert_eq!(format!("{}", display), expected_formatted_string);
     }
 }
@@ -3,9 +3,9 @@ use crate::format::{Charset, SymbolKind};
 use colored::Colorize;
 
 pub struct EmojiSymbols {
-    charset: Charset,
-    emojis: [&'static str; 3],
-    fallbacks: [colored::ColoredString; 3],
+    output_emoji: bool,
+    emojis: [&'static str; 5],
+    fallbacks: [colored::ColoredString; 5],
 }
 
 impl EmojiSymbols {
@@ -18,15 +18,32 @@ impl EmojiSymbols {
         }
     }
     pub fn new(charset: Charset) -> EmojiSymbols {
+        let output_emoji = charset == Charset::Utf8
+            && console::Term::stdout().features().wants_emoji();
+        Self::with_output_emoji(output_emoji)
+    }
+
+    /// Like `new`, as if the terminal showed emoji.
+    #[cfg(test)]
+    pub fn for_emoji_terminal(charset: Charset) -> EmojiSymbols {
+        Self::with_output_emoji(charset == Charset::Utf8)
+    }
+
+    fn with_output_emoji(output_emoji: bool) -> EmojiSymbols {
         Self {
-            charset,
-            emojis: ["🔒", "❓", "☢️"],
-            fallbacks: [":)".green(), "?".normal(), "!".red().bold()],
+            output_emoji,
+            emojis: ["🔒", "❓", "☢️", "○", "⚙"],
+            fallbacks: [
+                ":)".green(),
+                "?".normal(),
+                "!".red().bold(),
+                "~".yellow(),
+                "L".cyan(),
+            ],
         }
     }
 
     pub fn will_output_emoji(&self) -> bool {
-        self.charset == Charset::Utf8
-            && console::Term::stdout().features().wants_emoji()
+        self.output_emoji
     }
 }
@@ -0,0 +1,198 @@
+use cargo::core::manifest::ManifestMetadata;
+use cargo::core::PackageId;
+use std::env;
+
+const OSC_8_START: &str = "\x1B]8;;";
+const STRING_TERMINATOR: &str = "\x1B\\";
+
+/// Wraps `text` in an OSC 8 hyperlink to `url`. Terminals without hyperlink
+/// support are expected to print `text` only, the escape sequences take up no
+/// columns.
+pub fn hyperlink(text: &str, url: &str) -> String {
+    format!(
+        "{0}{1}{2}{3}{0}{2}",
+        OSC_8_START, url, STRING_TERMINATOR, text
+    )
+}
+
+/// The page a package name links to: the repository from the manifest if it
+/// has one, otherwise the crates.io page for packages from crates.io.
+pub fn package_url(
+    package_id: &PackageId,
+    metadata: &ManifestMetadata,
+) -> Option<String> {
+    match &metadata.repository {
+        Some(repository) if repository.starts_with("http") => {
+            Some(repository.clone())
+        }
+        _ if package_id.source_id().is_default_registry() => Some(format!(
+            "https://crates.io/crates/{}/{}",
+            package_id.name(),
+            package_id.version()
+        )),
+        _ => None,
+    }
+}
+
+/// Removes the OSC 8 escape sequences added by `hyperlink`, keeping the link
+/// text.
+pub fn strip_hyperlinks(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(OSC_8_START) {
+        stripped.push_str(&rest[..start]);
+        rest = &rest[start + OSC_8_START.len()..];
+        match rest.find(STRING_TERMINATOR) {
+            Some(end) => rest = &rest[end + STRING_TERMINATOR.len()..],
+            None => rest = "",
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// There is no way to query a terminal for hyperlink support, this checks
+/// that stdout is a terminal and looks for the environment variables set by
+/// terminals known to support them.
+pub fn terminal_supports_hyperlinks() -> bool {
+    console::user_attended()
+        && env_supports_hyperlinks(|key| env::var(key).ok())
+}
+
+fn env_supports_hyperlinks(var: impl Fn(&str) -> Option<String>) -> bool {
+    if let Some(force_hyperlink) = var("FORCE_HYPERLINK") {
+        return force_hyperlink != "0";
+    }
+    if var("DOMTERM").is_some()
+        || var("WT_SESSION").is_some()
+        || var("KONSOLE_VERSION").is_some()
+    {
+        return true;
+    }
+    // VTE based terminals such as GNOME Terminal support them since 0.50.
+    if let Some(vte_version) = var("VTE_VERSION") {
+        if vte_version.parse::<u32>().map_or(false, |v| v >= 5000) {
+            return true;
+        }
+    }
+    match var("TERM_PROGRAM").as_deref() {
+        Some("Hyper") | Some("iTerm.app") | Some("terminology")
+        | Some("vscode") | Some("WezTerm") => return true,
+        _ => {}
+    }
+    var("TERM").as_deref() == Some("xterm-kitty")
+}
+
+#[cfg(test)]
+mod hyperlink_tests {
+    use super::*;
+
+    use crate::format::display_width;
+
+    use cargo::core::SourceId;
+    use cargo::Config;
+    use rstest::*;
+    use std::collections::HashMap;
+
+    #[rstest]
+    fn hyperlink_test() {
+        let linked =
+            hyperlink("serde 1.0.0", "https://crates.io/crates/serde/1.0.0");
+
+        assert_eq!(
+            linked,
+            "\x1B]8;;https://crates.io/crates/serde/1.0.0\x1B\\serde 1.0.0\x1B]8;;\x1B\\"
+        );
+        assert_eq!(display_width(&linked), display_width("serde 1.0.0"));
+    }
+
+    #[rstest(
+        input_repository,
+        input_is_crates_io,
+        expected_url,
+        case(
+            Some("https://github.com/serde-rs/serde"),
+            true,
+            Some("https://github.com/serde-rs/serde")
+        ),
+        case(None, true, Some("https://crates.io/crates/serde/1.0.0")),
+        case(
+            Some("not a url"),
+            true,
+            Some("https://crates.io/crates/serde/1.0.0")
+        ),
+        case(None, false, None)
+    )]
+    fn package_url_test(
+        input_repository: Option<&str>,
+        input_is_crates_io: bool,
+        expected_url: Option<&str>,
+    ) {
+        let config = Config::default().unwrap();
+        let source_id = if input_is_crates_io {
+            SourceId::crates_io(&config).unwrap()
+        } else {
+            SourceId::for_path(&env::current_dir().unwrap()).unwrap()
+        };
+        let package_id = PackageId::new("serde", "1.0.0", source_id).unwrap();
+        let metadata = ManifestMetadata {
+            authors: vec![],
+            keywords: vec![],
+            categories: vec![],
+            license: None,
+            license_file: None,
+            description: None,
+            readme: None,
+            homepage: None,
+            repository: input_repository.map(String::from),
+            documentation: None,
+            badges: Default::default(),
+            links: None,
+        };
+
+        assert_eq!(
+            package_url(&package_id, &metadata),
+            expected_url.map(String::from)
+        );
+    }
+
+    #[rstest(
+        input_text,
+        expected_stripped_text,
+        case("plain", "plain"),
+        case("\x1B]8;;https://a\x1B\\a\x1B]8;;\x1B\\ b", "a b"),
+        case(
+            "\x1B[31m\x1B]8;;u\x1B\\red\x1B]8;;\x1B\\\x1B[0m",
+            "\x1B[31mred\x1B[0m"
+        ),
+        case("\x1B]8;;unterminated", "")
+    )]
+    fn strip_hyperlinks_test(input_text: &str, expected_stripped_text: &str) {
+        assert_eq!(strip_hyperlinks(input_text), expected_stripped_text);
+    }
+
+    #[rstest(
+        input_vars,
+        expected_supports_hyperlinks,
+        case(vec![], false),
+        case(vec![("TERM_PROGRAM", "iTerm.app")], true),
+        case(vec![("TERM_PROGRAM", "Apple_Terminal")], false),
+        case(vec![("VTE_VERSION", "5402")], true),
+        case(vec![("VTE_VERSION", "4601")], false),
+        case(vec![("WT_SESSION", "1")], true),
+        case(vec![("TERM", "xterm-kitty")], true),
+        case(vec![("FORCE_HYPERLINK", "0"), ("WT_SESSION", "1")], false),
+        case(vec![("FORCE_HYPERLINK", "1")], true)
+    )]
+    fn env_supports_hyperlinks_test(
+        input_vars: Vec<(&str, &str)>,
+        expected_supports_hyperlinks: bool,
+    ) {
+        let vars = input_vars.into_iter().collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            env_supports_hyperlinks(|key| vars.get(key).map(|v| v.to_string())),
+            expected_supports_hyperlinks
+        );
+    }
+}
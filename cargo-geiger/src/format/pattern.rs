@@ -15,11 +15,13 @@ impl Pattern {
         &'a self,
         package: &'a PackageId,
         metadata: &'a ManifestMetadata,
+        hyperlinks: bool,
     ) -> Display<'a> {
         Display {
             pattern: self,
             package,
             metadata,
+            hyperlinks,
         }
     }
 
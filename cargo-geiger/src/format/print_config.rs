@@ -1,12 +1,18 @@
 use crate::args::Args;
+use crate::format::hyperlink::terminal_supports_hyperlinks;
 use crate::format::pattern::Pattern;
 use crate::format::{Charset, CrateDetectionStatus, FormatError};
+use crate::rs_file::DEFAULT_GENERATED_MARKERS;
 
-use cargo::core::shell::Verbosity;
+use cargo::core::shell::{ColorChoice, Verbosity};
 use cargo::util::errors::CliError;
+use cargo_geiger_serde::CountingStrategy;
 use colored::Colorize;
-use geiger::IncludeTests;
+use geiger::{
+    IncludeTests, ScanOptions, DEFAULT_HARNESS_CFGS, DEFAULT_MAX_FILE_SIZE,
+};
 use petgraph::EdgeDirection;
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Prefix {
@@ -18,6 +24,39 @@ pub enum Prefix {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum OutputFormat {
     Json,
+    /// The lines of the tree as printed by the table, with the unsafe usage
+    /// of each package
+    TreeJson,
+}
+
+/// The orders of the flat output other than the one of the tree walk.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortKey {
+    /// The minimum depth at which a package appears below the root
+    Depth,
+}
+
+impl FromStr for SortKey {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<SortKey, &'static str> {
+        match s {
+            "depth" => Ok(SortKey::Depth),
+            _ => Err("invalid sort key"),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<OutputFormat, &'static str> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "tree-json" => Ok(OutputFormat::TreeJson),
+            _ => Err("invalid output format"),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -27,19 +66,55 @@ pub struct PrintConfig {
 
     pub allow_partial_results: bool,
     pub charset: Charset,
+
+    /// Which expressions are counted, `--counting-strategy`.
+    pub counting_strategy: CountingStrategy,
+
     pub direction: EdgeDirection,
 
     // Is anyone using this? This is a carry-over from cargo-tree.
     // TODO: Open a github issue to discuss deprecation.
     pub format: Pattern,
 
+    /// The markers of machine generated files, the defaults followed by the
+    /// ones given with `--generated-marker`.
+    pub generated_markers: Vec<String>,
+
+    /// The `cfg` names of verification harnesses, the defaults unless
+    /// `--harness-cfgs` is given.
+    pub harness_cfgs: Vec<String>,
+
+    /// Wrap package names in OSC 8 terminal hyperlinks.
+    pub hyperlinks: bool,
+
     pub include_tests: IncludeTests,
+
+    /// Files larger than this many bytes are not scanned, `--max-file-size`.
+    pub max_file_size: u64,
+
+    /// Byte stable text output for snapshot tests, `--normalize-output`.
+    pub normalize_output: bool,
+
     pub prefix: Prefix,
     pub output_format: Option<OutputFormat>,
+
+    /// Print `--progress-json` events on stderr.
+    pub progress_json: bool,
+
+    /// The order of the packages given with `--sort`, only for the flat
+    /// output of `--prefix-depth` and `--no-indent`.
+    pub sort: Option<SortKey>,
+
     pub verbosity: Verbosity,
+
+    /// The call patterns given with `--watch-calls`.
+    pub watched_calls: Vec<String>,
 }
 
 impl PrintConfig {
+    /// All decisions about the output that follow from the command line, made
+    /// once for the whole run. Conflicting flags are an error instead of one
+    /// of them winning.
     pub fn new(args: &Args) -> Result<Self, CliError> {
         // TODO: Add command line flag for this and make it default to false?
         let allow_partial_results = true;
@@ -66,13 +141,37 @@ impl PrintConfig {
             IncludeTests::No
         };
 
-        let prefix = if args.prefix_depth {
-            Prefix::Depth
-        } else if args.no_indent {
-            Prefix::None
-        } else {
-            Prefix::Indent
+        let prefix = match (args.prefix_depth, args.no_indent) {
+            (true, true) => {
+                return Err(CliError::new(
+                    anyhow::anyhow!(
+                        "`--prefix-depth` can't be combined with `--no-indent`"
+                    ),
+                    1,
+                ))
+            }
+            (true, false) => Prefix::Depth,
+            (false, true) => Prefix::None,
+            (false, false) => Prefix::Indent,
         };
+        if args.sort.is_some() && prefix == Prefix::Indent {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "`--sort` requires `--prefix-depth` or `--no-indent`"
+                ),
+                1,
+            ));
+        }
+
+        if args.normalize_output && args.output_format.is_some() {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "`--normalize-output` only applies to the text output and \
+                     can't be combined with `--json` or `--output-format`"
+                ),
+                1,
+            ));
+        }
 
         let verbosity = if args.verbose == 0 {
             Verbosity::Normal
@@ -84,14 +183,53 @@ impl PrintConfig {
             all: args.all,
             allow_partial_results,
             charset: args.charset,
+            counting_strategy: args.counting_strategy,
             direction,
             format,
+            generated_markers: DEFAULT_GENERATED_MARKERS
+                .iter()
+                .map(|marker| marker.to_string())
+                .chain(args.generated_markers.iter().cloned())
+                .collect(),
+            harness_cfgs: args.harness_cfgs.clone().unwrap_or_else(|| {
+                DEFAULT_HARNESS_CFGS
+                    .iter()
+                    .map(|harness_cfg| harness_cfg.to_string())
+                    .collect()
+            }),
+            hyperlinks: args.hyperlinks
+                || (!args.normalize_output && terminal_supports_hyperlinks()),
             include_tests,
+            max_file_size: args.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+            normalize_output: args.normalize_output,
             output_format: args.output_format,
             prefix,
+            progress_json: args.progress_json,
+            sort: args.sort,
             verbosity,
+            watched_calls: args.watched_calls.clone(),
         })
     }
+
+    /// The options of the scan of each file.
+    pub fn scan_options(&self) -> ScanOptions {
+        ScanOptions::default()
+            .include_tests(self.include_tests)
+            .watched_calls(self.watched_calls.clone())
+            .harness_cfgs(self.harness_cfgs.clone())
+            .counting_strategy(self.counting_strategy)
+            .max_file_size(self.max_file_size)
+    }
+}
+
+/// The colors of the tables follow `--color` like the output of cargo, the
+/// terminal is only detected with `auto`.
+pub fn override_colors(color_choice: ColorChoice) {
+    match color_choice {
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+        ColorChoice::CargoAuto => {}
+    }
 }
 
 pub fn colorize(
@@ -101,6 +239,7 @@ pub fn colorize(
     match crate_detection_status {
         CrateDetectionStatus::NoneDetectedForbidsUnsafe => string.green(),
         CrateDetectionStatus::NoneDetectedAllowsUnsafe => string.normal(),
+        CrateDetectionStatus::UnusedUnsafeDetected => string.yellow(),
         CrateDetectionStatus::UnsafeDetected => string.red().bold(),
     }
 }
@@ -109,6 +248,11 @@ pub fn colorize(
 mod print_config_tests {
     use super::*;
 
+    use crate::format::MarkerPosition;
+    use crate::graph::GraphBackend;
+    use crate::rs_file::Resolver;
+    use crate::scan::UnsafeCriteria;
+
     use colored::ColoredString;
     use rstest::*;
 
@@ -134,6 +278,40 @@ mod print_config_tests {
         );
     }
 
+    #[rstest]
+    fn print_config_new_test_hyperlinks() {
+        let mut args = create_args();
+        args.hyperlinks = true;
+
+        let print_config_result = PrintConfig::new(&args);
+
+        assert!(print_config_result.is_ok());
+        assert!(print_config_result.unwrap().hyperlinks);
+    }
+
+    #[rstest(
+        input_harness_cfgs,
+        expected_harness_cfgs,
+        case(None, vec!["fuzzing", "kani", "loom", "miri"]),
+        case(Some(vec![String::from("loom")]), vec!["loom"]),
+        case(Some(vec![]), vec![])
+    )]
+    fn print_config_new_test_harness_cfgs(
+        input_harness_cfgs: Option<Vec<String>>,
+        expected_harness_cfgs: Vec<&str>,
+    ) {
+        let mut args = create_args();
+        args.harness_cfgs = input_harness_cfgs;
+
+        let print_config_result = PrintConfig::new(&args);
+
+        assert!(print_config_result.is_ok());
+        assert_eq!(
+            print_config_result.unwrap().harness_cfgs,
+            expected_harness_cfgs
+        );
+    }
+
     #[rstest(
         input_include_tests_bool,
         expected_include_tests,
@@ -161,7 +339,6 @@ mod print_config_tests {
         input_no_indent_bool,
         expected_output_prefix,
         case(true, false, Prefix::Depth,),
-        case(true, false, Prefix::Depth,),
         case(false, true, Prefix::None,),
         case(false, false, Prefix::Indent,)
     )]
@@ -180,6 +357,102 @@ mod print_config_tests {
         assert_eq!(print_config_result.unwrap().prefix, expected_output_prefix);
     }
 
+    #[rstest]
+    fn print_config_new_test_conflicting_prefixes() {
+        let mut args = create_args();
+        args.prefix_depth = true;
+        args.no_indent = true;
+
+        let print_config_result = PrintConfig::new(&args);
+
+        assert_eq!(
+            print_config_result.unwrap_err().error.unwrap().to_string(),
+            "`--prefix-depth` can't be combined with `--no-indent`"
+        );
+    }
+
+    #[rstest(
+        input_output_format,
+        expected_normalize_output,
+        case(None, Some(true)),
+        case(Some(OutputFormat::Json), None),
+        case(Some(OutputFormat::TreeJson), None)
+    )]
+    fn print_config_new_test_normalize_output(
+        input_output_format: Option<OutputFormat>,
+        expected_normalize_output: Option<bool>,
+    ) {
+        let mut args = create_args();
+        args.normalize_output = true;
+        args.output_format = input_output_format;
+
+        let print_config_result = PrintConfig::new(&args);
+
+        assert_eq!(
+            print_config_result
+                .ok()
+                .map(|print_config| print_config.normalize_output),
+            expected_normalize_output
+        );
+    }
+
+    #[rstest(
+        input_prefix_depth_bool,
+        input_no_indent_bool,
+        expected_sort,
+        case(true, false, Some(Some(SortKey::Depth))),
+        case(false, true, Some(Some(SortKey::Depth))),
+        case(false, false, None)
+    )]
+    fn print_config_new_test_sort(
+        input_prefix_depth_bool: bool,
+        input_no_indent_bool: bool,
+        expected_sort: Option<Option<SortKey>>,
+    ) {
+        let mut args = create_args();
+        args.prefix_depth = input_prefix_depth_bool;
+        args.no_indent = input_no_indent_bool;
+        args.sort = Some(SortKey::Depth);
+
+        let print_config_result = PrintConfig::new(&args);
+
+        assert_eq!(
+            print_config_result
+                .ok()
+                .map(|print_config| print_config.sort),
+            expected_sort
+        );
+    }
+
+    #[rstest(
+        input_charset,
+        input_output_format,
+        case(Charset::Ascii, None),
+        case(Charset::Utf8, Some(OutputFormat::Json)),
+        case(Charset::Utf8, Some(OutputFormat::TreeJson))
+    )]
+    fn print_config_new_test_passes_through_charset_and_output_format(
+        input_charset: Charset,
+        input_output_format: Option<OutputFormat>,
+    ) {
+        let mut args = create_args();
+        args.charset = input_charset;
+        args.output_format = input_output_format;
+
+        let print_config = PrintConfig::new(&args).unwrap();
+
+        assert_eq!(print_config.charset, input_charset);
+        assert_eq!(print_config.output_format, input_output_format);
+    }
+
+    #[rstest]
+    fn print_config_new_test_format_error() {
+        let mut args = create_args();
+        args.format = String::from("{unknown}");
+
+        assert!(PrintConfig::new(&args).is_err());
+    }
+
     #[rstest(
         input_verbosity_u32,
         expected_verbosity,
@@ -200,6 +473,25 @@ mod print_config_tests {
         assert_eq!(print_config_result.unwrap().verbosity, expected_verbosity);
     }
 
+    #[rstest]
+    fn sort_key_from_str_test() {
+        assert_eq!(SortKey::from_str("depth"), Ok(SortKey::Depth));
+        assert_eq!(SortKey::from_str("name"), Err("invalid sort key"));
+    }
+
+    #[rstest]
+    fn output_format_from_str_test() {
+        assert_eq!(OutputFormat::from_str("json"), Ok(OutputFormat::Json));
+        assert_eq!(
+            OutputFormat::from_str("tree-json"),
+            Ok(OutputFormat::TreeJson)
+        );
+        assert_eq!(
+            OutputFormat::from_str("invalid_str"),
+            Err("invalid output format")
+        );
+    }
+
     #[rstest(
         input_crate_detection_status,
         expected_colorized_string,
@@ -211,6 +503,10 @@ mod print_config_tests {
             CrateDetectionStatus::NoneDetectedAllowsUnsafe,
             String::from("string_value").normal()
         ),
+        case(
+            CrateDetectionStatus::UnusedUnsafeDetected,
+            String::from("string_value").yellow()
+        ),
         case(
             CrateDetectionStatus::UnsafeDetected,
             String::from("string_value").red().bold()
@@ -233,31 +529,68 @@ mod print_config_tests {
             all: false,
             all_deps: false,
             all_features: false,
+            all_platforms: false,
+            all_target_kinds: false,
             all_targets: false,
+            backend: GraphBackend::Resolve,
             build_deps: false,
             charset: Charset::Ascii,
+            check_lock: None,
+            cli_config: vec![],
             color: None,
+            counting_strategy: CountingStrategy::Geiger,
             dev_deps: false,
-            features: None,
+            exclude: Vec::new(),
+            explain: None,
+            features: Vec::new(),
             forbid_only: false,
             format: "".to_string(),
             frozen: false,
+            generated_markers: vec![],
+            harness_cfgs: None,
             help: false,
+            hyperlinks: false,
             include_tests: false,
             invert: false,
+            keep_going: false,
             locked: false,
-            manifest_path: None,
+            manifest_paths: Vec::new(),
+            marker_position: MarkerPosition::Left,
+            max_file_size: None,
+            members_only: false,
             no_default_features: false,
+            no_dev_deps: false,
             no_indent: false,
+            no_legend: false,
+            normalize_output: false,
             offline: false,
+            output: None,
+            output_dir: None,
             package: None,
             prefix_depth: false,
+            print_schema: false,
+            progress_json: false,
+            prune_output: false,
+            published_crate: None,
             quiet: false,
+            report_file_list: false,
+            report_modules: false,
+            resolver: Resolver::Executor,
+            reuse_build: false,
+            reuse_report: None,
+            show_dep_counts: false,
+            show_totals: false,
+            sort: None,
+            split_generated: false,
+            split_local_paths: false,
             target: None,
+            timings: false,
+            unsafe_criteria: UnsafeCriteria::default(),
             unstable_flags: vec![],
             verbose: 0,
             version: false,
             output_format: None,
+            watched_calls: vec![],
         }
     }
 }
@@ -0,0 +1,527 @@
+use crate::format::table::TableParameters;
+use crate::format::CrateDetectionStatus;
+use crate::scan::{
+    hand_written_metrics, library_metrics, unsafe_stats, PackageMetrics,
+    UnsafeCriteria,
+};
+use crate::tree::TextTreeLine;
+
+use cargo::core::PackageId;
+use cargo_geiger_serde::{ScanStatus, UnsafeInfo};
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+/// A line of the dependency tree with the unsafe usage of its package looked
+/// up and its detection status decided. The renderers of the tree only format
+/// these, the terminal table and the JSON tree count the same way.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderedLine {
+    /// The package of the line, `None` for the header of a group of extra
+    /// dependencies
+    pub package_id: Option<PackageId>,
+    pub depth: usize,
+    pub vines: String,
+    /// The unsafe usage of the package, `None` for lines without a package,
+    /// for cycles and for packages without metrics
+    pub counters: Option<PackageCounters>,
+    pub markers: LineMarkers,
+}
+
+/// The unsafe usage of the package of a line, as counted in its table row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackageCounters {
+    /// The unsafe usage of the whole package. Unless `--all-target-kinds` is
+    /// given or the package has no library, only the files of the library
+    /// and build script count as used, the files of the other targets count
+    /// as not used. The total stays the one of the package.
+    pub unsafety: UnsafeInfo,
+    /// The unsafe usage of the library and build script only, the detection
+    /// status is decided on it. `None` with `--all-target-kinds` and for
+    /// packages without a library.
+    pub library: Option<UnsafeInfo>,
+    /// The unsafe usage of the files not detected as machine generated,
+    /// `None` for packages without generated files
+    pub hand_written: Option<UnsafeInfo>,
+    /// The files with unsafe usage of a package that forbids unsafe code,
+    /// of all its targets
+    pub inconsistent_forbid_files: Vec<PathBuf>,
+}
+
+/// What the renderers mark a line with, next to its counters.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineMarkers {
+    /// The detection status of the package, `None` without counters and for
+    /// packages without build data
+    pub status: Option<CrateDetectionStatus>,
+    /// The package is already on the path from the root
+    pub cycle: bool,
+    /// The package forbids unsafe code, yet unsafe usage was counted
+    pub inconsistent_forbid: bool,
+    /// Some files of the package failed to parse, only their `unsafe`
+    /// keywords were counted, from the tokens
+    pub approximate: bool,
+    /// Some files of the package were larger than `--max-file-size` and were
+    /// not scanned
+    pub oversized: bool,
+}
+
+/// Looks up the unsafe usage of the package of a line and decides its
+/// detection status. Pure, the totals and warnings are left to the renderers.
+pub fn rendered_line(
+    text_tree_line: &TextTreeLine,
+    table_parameters: &TableParameters,
+) -> RenderedLine {
+    match text_tree_line {
+        TextTreeLine::Package {
+            id,
+            depth,
+            tree_vines,
+        } => {
+            let counters = package_counters(*id, table_parameters);
+            let markers = match &counters {
+                Some(counters) => LineMarkers {
+                    status: detection_status(
+                        counters.library.as_ref().unwrap_or(&counters.unsafety),
+                        table_parameters.unsafe_criteria,
+                    ),
+                    cycle: false,
+                    inconsistent_forbid: !counters
+                        .inconsistent_forbid_files
+                        .is_empty(),
+                    approximate: counters.unsafety.approximate.is_some(),
+                    oversized: !counters.unsafety.skipped_oversized.is_empty(),
+                },
+                None => LineMarkers::default(),
+            };
+            RenderedLine {
+                package_id: Some(*id),
+                depth: *depth,
+                vines: tree_vines.clone(),
+                counters,
+                markers,
+            }
+        }
+        TextTreeLine::ExtraDepsGroup {
+            depth, tree_vines, ..
+        } => RenderedLine {
+            package_id: None,
+            depth: *depth,
+            vines: tree_vines.clone(),
+            counters: None,
+            markers: LineMarkers::default(),
+        },
+        TextTreeLine::Cycle {
+            id,
+            depth,
+            tree_vines,
+        } => RenderedLine {
+            package_id: Some(*id),
+            depth: *depth,
+            vines: tree_vines.clone(),
+            counters: None,
+            markers: LineMarkers {
+                cycle: true,
+                ..Default::default()
+            },
+        },
+    }
+}
+
+/// The detection status of a package from what was found in its code.
+pub fn crate_detection_status(
+    crate_forbids_unsafe: bool,
+    used_unsafe_found: bool,
+    only_unused_unsafe_found: bool,
+) -> CrateDetectionStatus {
+    match (
+        crate_forbids_unsafe,
+        used_unsafe_found,
+        only_unused_unsafe_found,
+    ) {
+        (_, true, _) => CrateDetectionStatus::UnsafeDetected,
+        (_, false, true) => CrateDetectionStatus::UnusedUnsafeDetected,
+        (true, false, false) => CrateDetectionStatus::NoneDetectedForbidsUnsafe,
+        (false, false, false) => CrateDetectionStatus::NoneDetectedAllowsUnsafe,
+    }
+}
+
+fn package_counters(
+    package_id: PackageId,
+    table_parameters: &TableParameters,
+) -> Option<PackageCounters> {
+    let package_metrics = table_parameters
+        .geiger_context
+        .package_id_to_metrics
+        .get(&package_id)?;
+    // Checked on all files, the forbid of the library doesn't cover the
+    // other targets.
+    let inconsistent_forbid_files = package_metrics.inconsistent_forbid.clone();
+    let library_metrics = if table_parameters.all_target_kinds {
+        None
+    } else {
+        library_metrics(package_metrics, table_parameters.target_kinds)
+    };
+    let rs_files_used = match &library_metrics {
+        Some(library_metrics) => Cow::Owned(
+            table_parameters
+                .rs_files_used
+                .iter()
+                .filter(|path_buf| library_metrics.rs_paths.contains(*path_buf))
+                .cloned()
+                .collect(),
+        ),
+        None => Cow::Borrowed(table_parameters.rs_files_used),
+    };
+    let is_other_platform =
+        table_parameters.platforms.contains_key(&package_id);
+    let stats = |package_metrics: &PackageMetrics| {
+        let mut unsafety = unsafe_stats(package_metrics, &rs_files_used);
+        if is_other_platform {
+            unsafety.status = ScanStatus::OtherPlatform;
+        }
+        unsafety
+    };
+    let unsafety = stats(package_metrics);
+    let library = library_metrics.as_ref().map(&stats);
+    let hand_written = unsafety
+        .generated
+        .as_ref()
+        .map(|_| stats(&hand_written_metrics(package_metrics)));
+    Some(PackageCounters {
+        unsafety,
+        library,
+        hand_written,
+        inconsistent_forbid_files,
+    })
+}
+
+/// Zeros for the used counts of a package without build data would read as
+/// safe at a glance, it is left out of the detection status.
+fn detection_status(
+    unsafety: &UnsafeInfo,
+    unsafe_criteria: &UnsafeCriteria,
+) -> Option<CrateDetectionStatus> {
+    if matches!(
+        unsafety.status,
+        ScanStatus::NoBuildData | ScanStatus::OtherPlatform
+    ) {
+        return None;
+    }
+    Some(crate_detection_status(
+        unsafety.forbids_unsafe,
+        unsafe_criteria.has_used_unsafe(unsafety),
+        unsafe_criteria.has_only_unused_unsafe(unsafety),
+    ))
+}
+
+#[cfg(test)]
+mod rendered_line_tests {
+    use super::*;
+
+    use crate::format::pattern::Pattern;
+    use crate::format::print_config::{OutputFormat, Prefix, PrintConfig};
+    use crate::format::table::CounterColumns;
+    use crate::format::Charset;
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::{DetailLevel, FileUsage, GeigerContext};
+
+    use cargo::core::dependency::DepKind;
+    use cargo::core::{SourceId, Verbosity};
+    use cargo_geiger_serde::{Count, CounterBlock, CountingStrategy};
+    use geiger::{IncludeTests, RsFileMetrics, DEFAULT_MAX_FILE_SIZE};
+    use petgraph::EdgeDirection;
+    use rstest::*;
+    use std::collections::{HashMap, HashSet};
+
+    #[rstest(
+        input_crate_forbids_unsafe,
+        input_used_unsafe_found,
+        input_only_unused_unsafe_found,
+        expected_crate_detection_status,
+        case(
+            true,
+            false,
+            false,
+            CrateDetectionStatus::NoneDetectedForbidsUnsafe
+        ),
+        case(
+            false,
+            false,
+            false,
+            CrateDetectionStatus::NoneDetectedAllowsUnsafe
+        ),
+        case(true, false, true, CrateDetectionStatus::UnusedUnsafeDetected),
+        case(false, false, true, CrateDetectionStatus::UnusedUnsafeDetected),
+        case(true, true, false, CrateDetectionStatus::UnsafeDetected),
+        case(false, true, false, CrateDetectionStatus::UnsafeDetected)
+    )]
+    fn crate_detection_status_test(
+        input_crate_forbids_unsafe: bool,
+        input_used_unsafe_found: bool,
+        input_only_unused_unsafe_found: bool,
+        expected_crate_detection_status: CrateDetectionStatus,
+    ) {
+        assert_eq!(
+            crate_detection_status(
+                input_crate_forbids_unsafe,
+                input_used_unsafe_found,
+                input_only_unused_unsafe_found,
+            ),
+            expected_crate_detection_status
+        );
+    }
+
+    #[rstest(
+        input_unsafe_,
+        input_is_used,
+        input_forbids_unsafe,
+        expected_status,
+        expected_inconsistent_forbid,
+        case(
+            1,
+            true,
+            false,
+            Some(CrateDetectionStatus::UnsafeDetected),
+            false
+        ),
+        case(1, true, true, Some(CrateDetectionStatus::UnsafeDetected), true),
+        case(
+            0,
+            true,
+            true,
+            Some(CrateDetectionStatus::NoneDetectedForbidsUnsafe),
+            false
+        ),
+        case(
+            0,
+            true,
+            false,
+            Some(CrateDetectionStatus::NoneDetectedAllowsUnsafe),
+            false
+        ),
+        case(1, false, false, None, false)
+    )]
+    fn rendered_line_package_test(
+        input_unsafe_: u64,
+        input_is_used: bool,
+        input_forbids_unsafe: bool,
+        expected_status: Option<CrateDetectionStatus>,
+        expected_inconsistent_forbid: bool,
+    ) {
+        let package_id = create_package_id("package");
+        let rs_files_used = if input_is_used {
+            vec![PathBuf::from("src/lib.rs")].into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+        let geiger_context = create_geiger_context(
+            package_id,
+            input_unsafe_,
+            input_forbids_unsafe,
+            &rs_files_used,
+        );
+        let rendered_line = with_table_parameters(
+            &geiger_context,
+            &rs_files_used,
+            |table_parameters| {
+                rendered_line(
+                    &TextTreeLine::Package {
+                        id: package_id,
+                        depth: 1,
+                        tree_vines: String::from("|-- "),
+                    },
+                    table_parameters,
+                )
+            },
+        );
+
+        assert_eq!(rendered_line.package_id, Some(package_id));
+        assert_eq!(rendered_line.depth, 1);
+        assert_eq!(rendered_line.vines, "|-- ");
+        let counters = rendered_line.counters.unwrap();
+        let expected_count = Count {
+            safe: 0,
+            unsafe_: input_unsafe_,
+        };
+        if input_is_used {
+            assert_eq!(counters.unsafety.used.exprs, expected_count);
+        } else {
+            assert_eq!(counters.unsafety.unused.exprs, expected_count);
+        }
+        assert_eq!(counters.hand_written, None);
+        assert_eq!(rendered_line.markers.status, expected_status);
+        assert!(!rendered_line.markers.cycle);
+        assert_eq!(
+            rendered_line.markers.inconsistent_forbid,
+            expected_inconsistent_forbid
+        );
+    }
+
+    #[rstest]
+    fn rendered_line_package_without_counters_test() {
+        let geiger_context = GeigerContext {
+            package_id_to_metrics: HashMap::new(),
+            warnings: Vec::new(),
+        };
+        let package_id = create_package_id("unscanned");
+        let rendered_line = with_table_parameters(
+            &geiger_context,
+            &HashSet::new(),
+            |table_parameters| {
+                rendered_line(
+                    &TextTreeLine::Package {
+                        id: package_id,
+                        depth: 0,
+                        tree_vines: String::new(),
+                    },
+                    table_parameters,
+                )
+            },
+        );
+
+        assert_eq!(rendered_line.package_id, Some(package_id));
+        assert_eq!(rendered_line.counters, None);
+        assert_eq!(rendered_line.markers, LineMarkers::default());
+    }
+
+    #[rstest]
+    fn rendered_line_cycle_and_extra_deps_group_test() {
+        let package_id = create_package_id("package");
+        let geiger_context =
+            create_geiger_context(package_id, 1, false, &HashSet::new());
+        let (cycle, extra_deps_group) = with_table_parameters(
+            &geiger_context,
+            &HashSet::new(),
+            |table_parameters| {
+                (
+                    rendered_line(
+                        &TextTreeLine::Cycle {
+                            id: package_id,
+                            depth: 2,
+                            tree_vines: String::from("    `-- "),
+                        },
+                        table_parameters,
+                    ),
+                    rendered_line(
+                        &TextTreeLine::ExtraDepsGroup {
+                            kind: DepKind::Build,
+                            depth: 1,
+                            tree_vines: String::from("|-- "),
+                        },
+                        table_parameters,
+                    ),
+                )
+            },
+        );
+
+        assert_eq!(cycle.package_id, Some(package_id));
+        assert_eq!(cycle.counters, None);
+        assert_eq!(
+            cycle.markers,
+            LineMarkers {
+                cycle: true,
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(extra_deps_group.package_id, None);
+        assert_eq!(extra_deps_group.depth, 1);
+        assert_eq!(extra_deps_group.counters, None);
+        assert_eq!(extra_deps_group.markers, LineMarkers::default());
+    }
+
+    fn create_geiger_context(
+        package_id: PackageId,
+        unsafe_: u64,
+        forbids_unsafe: bool,
+        rs_files_used: &HashSet<PathBuf>,
+    ) -> GeigerContext {
+        let rs_file_metrics_wrapper = RsFileMetricsWrapper {
+            metrics: RsFileMetrics {
+                counters: CounterBlock {
+                    exprs: Count { safe: 0, unsafe_ },
+                    ..Default::default()
+                },
+                forbids_unsafe,
+                ..Default::default()
+            },
+            is_crate_entry_point: true,
+            is_generated: false,
+        };
+        let mut package_metrics = PackageMetrics {
+            rs_path_to_metrics: vec![(
+                PathBuf::from("src/lib.rs"),
+                rs_file_metrics_wrapper,
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        package_metrics.fold_files(
+            &FileUsage {
+                rs_files_used,
+                target_kinds: &HashMap::new(),
+            },
+            DetailLevel::Summary,
+        );
+        GeigerContext {
+            package_id_to_metrics: vec![(package_id, package_metrics)]
+                .into_iter()
+                .collect(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn create_package_id(name: &str) -> PackageId {
+        let source_id =
+            SourceId::for_path(&std::env::current_dir().unwrap()).unwrap();
+        PackageId::new(name, "1.0.0", source_id).unwrap()
+    }
+
+    fn with_table_parameters<F, T>(
+        geiger_context: &GeigerContext,
+        rs_files_used: &HashSet<PathBuf>,
+        f: F,
+    ) -> T
+    where
+        F: FnOnce(&TableParameters) -> T,
+    {
+        let print_config = PrintConfig {
+            all: false,
+            allow_partial_results: false,
+            charset: Charset::Ascii,
+            counting_strategy: CountingStrategy::Geiger,
+            direction: EdgeDirection::Outgoing,
+            format: Pattern::try_build("{p}").unwrap(),
+            generated_markers: Vec::new(),
+            harness_cfgs: Vec::new(),
+            hyperlinks: false,
+            include_tests: IncludeTests::Yes,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            normalize_output: false,
+            prefix: Prefix::Depth,
+            output_format: Some(OutputFormat::Json),
+            progress_json: false,
+            sort: None,
+            verbosity: Verbosity::Normal,
+            watched_calls: Vec::new(),
+        };
+        f(&TableParameters {
+            geiger_context,
+            print_config: &print_config,
+            rs_files_used,
+            target_kinds: &HashMap::new(),
+            all_target_kinds: true,
+            unsafe_criteria: &UnsafeCriteria::default(),
+            feature_resolve: None,
+            platforms: &HashMap::new(),
+            split_generated: false,
+            counter_columns: CounterColumns::default(),
+            target: "x86_64-unknown-linux-gnu",
+            build_failures: &HashMap::new(),
+            dependency_counts: &HashMap::new(),
+            members: &HashSet::new(),
+            dev_only: &HashSet::new(),
+        })
+    }
+}
@@ -3,72 +3,244 @@ mod total_package_counts;
 
 use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::print_config::{colorize, PrintConfig};
-use crate::format::CrateDetectionStatus;
-use crate::scan::GeigerContext;
+use crate::format::rendered_line::rendered_line;
+use crate::format::{
+    pad_to_display_width, CrateDetectionStatus, MarkerPosition, SymbolKind,
+};
+use crate::scan::{GeigerContext, UnsafeCriteria};
 use crate::tree::TextTreeLine;
 
 use handle_text_tree_line::{
-    handle_text_tree_line_extra_deps_group, handle_text_tree_line_package,
-    HandlePackageParameters,
+    handle_text_tree_line_cycle, handle_text_tree_line_extra_deps_group,
+    handle_text_tree_line_package, HandlePackageParameters,
 };
 use total_package_counts::TotalPackageCounts;
 
 use cargo::core::package::PackageSet;
-use cargo_geiger_serde::{Count, CounterBlock};
-use std::collections::HashSet;
+use cargo::core::{PackageId, Resolve};
+use cargo_geiger_serde::{
+    ApproximateUnsafety, Count, CounterBlock, DependencyCounts, OversizedFile,
+    ScanWarning, TargetKind,
+};
+use colored::Colorize;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 // TODO: use a table library, or factor the tableness out in a smarter way. This
 // is probably easier now when the tree formatting is separated from the tree
 // traversal.
-pub const UNSAFE_COUNTERS_HEADER: [&str; 6] = [
+pub const UNSAFE_COUNTERS_HEADER: [&str; 7] = [
     "Functions ",
     "Expressions ",
     "Impls ",
     "Traits ",
     "Methods ",
+    "FFI ",
     "Dependency",
 ];
 
+/// The header and the width of the dependency counts column, see
+/// `--show-dep-counts`.
+const DEP_COUNTS_HEADER: &str = "Deps";
+const DEP_COUNTS_WIDTH: usize = 9;
+
+/// The layout of the counter columns of the table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CounterColumns {
+    /// Append the number of items of each category, safe and unsafe, to the
+    /// cells, see `--show-totals`
+    pub show_totals: bool,
+    /// The width of each counter column, without the space after it
+    pub widths: [usize; 6],
+    /// Add a column with the dependency counts of each package after the
+    /// counter columns, see `--show-dep-counts`
+    pub show_dep_counts: bool,
+    /// Where the detection status of each package is shown, see
+    /// `--marker-position`
+    pub marker_position: MarkerPosition,
+}
+
+impl Default for CounterColumns {
+    fn default() -> Self {
+        CounterColumns {
+            show_totals: false,
+            widths: [10, 12, 6, 7, 8, 3],
+            show_dep_counts: false,
+            marker_position: MarkerPosition::Left,
+        }
+    }
+}
+
+impl CounterColumns {
+    /// Columns for `--show-totals`, wide enough for every cell of the table.
+    /// No row counts more than all scanned files together, so the cells of
+    /// those counts are the widest possible.
+    pub fn with_totals(geiger_context: &GeigerContext) -> Self {
+        let mut all_counters = CounterBlock::default();
+        for package_metrics in geiger_context.package_id_to_metrics.values() {
+            for metrics in package_metrics.file_groups.values() {
+                all_counters += metrics.counters.clone();
+            }
+        }
+        CounterColumns::fitting(&all_counters)
+    }
+
+    /// Columns for `--show-totals`, wide enough for every cell that counts no
+    /// more than `all_counters`.
+    pub fn fitting(all_counters: &CounterBlock) -> Self {
+        let cell_width = |count: &Count| {
+            format!(
+                "{}/{} (of {})",
+                count.unsafe_,
+                count.unsafe_,
+                count.safe + count.unsafe_
+            )
+            .len()
+        };
+        let counts = [
+            &all_counters.functions,
+            &all_counters.exprs,
+            &all_counters.item_impls,
+            &all_counters.item_traits,
+            &all_counters.methods,
+            &all_counters.foreign_items,
+        ];
+        let mut widths = CounterColumns::default().widths;
+        for (width, count) in widths.iter_mut().zip(counts.iter()) {
+            *width = (*width).max(cell_width(count));
+        }
+        CounterColumns {
+            show_totals: true,
+            widths,
+            ..Default::default()
+        }
+    }
+
+    /// The header line of the table, the counter headers padded to the width
+    /// of their columns.
+    pub fn header(&self) -> String {
+        let dependency_header = UNSAFE_COUNTERS_HEADER.last().unwrap();
+        format!(
+            "{}{}  {}",
+            self.counter_header(),
+            self.dep_counts_cell(DEP_COUNTS_HEADER),
+            dependency_header
+        )
+    }
+
+    /// The dependency counts of a package as `direct/transitive`, in the
+    /// column after the counters. Blank for rows without a package and empty
+    /// without the column.
+    pub fn dep_counts(
+        &self,
+        dependency_counts: Option<&DependencyCounts>,
+    ) -> String {
+        let cell = dependency_counts.map_or_else(String::new, |counts| {
+            format!("{}/{}", counts.direct, counts.transitive)
+        });
+        self.dep_counts_cell(&cell)
+    }
+
+    fn dep_counts_cell(&self, cell: &str) -> String {
+        if self.show_dep_counts {
+            format!(" {: <1$}", cell, DEP_COUNTS_WIDTH)
+        } else {
+            String::new()
+        }
+    }
+
+    /// The column of the detection status symbol between the counters and the
+    /// tree, blank for rows without a status. Empty unless the symbol is shown
+    /// on the left, the tree then starts below the `Dependency` header.
+    pub fn marker_cell(&self, symbol: &str) -> String {
+        match self.marker_position {
+            MarkerPosition::Left => {
+                format!("{} ", pad_to_display_width(symbol, 2))
+            }
+            MarkerPosition::None | MarkerPosition::Right => String::new(),
+        }
+    }
+
+    /// The headers of the counter columns only, padded to the width of their
+    /// columns.
+    pub fn counter_header(&self) -> String {
+        UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+            .iter()
+            .zip(self.widths.iter())
+            .map(|(header, width)| format!("{: <1$}", header.trim_end(), width))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn cell(&self, cell: String, used: &Count, not_used: &Count) -> String {
+        if self.show_totals {
+            let total =
+                used.safe + used.unsafe_ + not_used.safe + not_used.unsafe_;
+            format!("{} (of {})", cell, total)
+        } else {
+            cell
+        }
+    }
+}
+
 pub fn create_table_from_text_tree_lines(
     package_set: &PackageSet,
     table_parameters: &TableParameters,
     text_tree_lines: Vec<TextTreeLine>,
-) -> (Vec<String>, u64) {
+) -> (Vec<String>, Vec<ScanWarning>) {
     let mut table_lines = Vec::<String>::new();
     let mut total_package_counts = TotalPackageCounts::new();
-    let mut warning_count = 0;
+    let mut warnings = Vec::new();
+    let mut inconsistent_forbid = Vec::new();
+    let mut approximate = Vec::new();
+    let mut oversized = Vec::new();
     let mut visited_package_ids = HashSet::new();
     let emoji_symbols =
         EmojiSymbols::new(table_parameters.print_config.charset);
     let mut handle_package_parameters = HandlePackageParameters {
         total_package_counts: &mut total_package_counts,
         visited_package_ids: &mut visited_package_ids,
-        warning_count: &mut warning_count,
+        warnings: &mut warnings,
+        inconsistent_forbid: &mut inconsistent_forbid,
+        approximate: &mut approximate,
+        oversized: &mut oversized,
     };
 
     for text_tree_line in text_tree_lines {
+        let rendered_line = rendered_line(&text_tree_line, table_parameters);
         match text_tree_line {
-            TextTreeLine::ExtraDepsGroup {
-                kind: dep_kind,
-                tree_vines,
-            } => handle_text_tree_line_extra_deps_group(
-                dep_kind,
-                &mut table_lines,
-                tree_vines,
-            ),
-            TextTreeLine::Package {
+            TextTreeLine::Cycle {
                 id: package_id,
                 tree_vines,
-            } => handle_text_tree_line_package(
-                &emoji_symbols,
-                &mut handle_package_parameters,
+                ..
+            } => handle_text_tree_line_cycle(
                 package_id,
                 package_set,
                 &mut table_lines,
                 table_parameters,
                 tree_vines,
             ),
+            TextTreeLine::ExtraDepsGroup {
+                kind: dep_kind,
+                tree_vines,
+                ..
+            } => handle_text_tree_line_extra_deps_group(
+                dep_kind,
+                &mut table_lines,
+                &table_parameters.counter_columns,
+                tree_vines,
+            ),
+            TextTreeLine::Package { id: package_id, .. } => {
+                handle_text_tree_line_package(
+                    &emoji_symbols,
+                    &mut handle_package_parameters,
+                    package_id,
+                    package_set,
+                    &mut table_lines,
+                    table_parameters,
+                    rendered_line,
+                )
+            }
         }
     }
 
@@ -79,66 +251,325 @@ pub fn create_table_from_text_tree_lines(
     table_lines.push(format!(
         "{}",
         table_footer(
-            total_package_counts.total_counter_block,
-            total_package_counts.total_unused_counter_block,
-            total_detection_status
+            total_package_counts.total_counter_block.clone(),
+            total_package_counts.total_unused_counter_block.clone(),
+            total_detection_status,
+            table_parameters.unsafe_criteria,
+            &table_parameters.counter_columns,
         )
     ));
 
+    if table_parameters.counter_columns.show_totals {
+        table_lines.extend(member_totals_lines(
+            &total_package_counts,
+            table_parameters,
+        ));
+    }
+
     table_lines.push(String::new());
+    if total_package_counts.native_linkage > 0 {
+        table_lines.push(native_linkage_footnote(
+            &emoji_symbols,
+            total_package_counts.native_linkage,
+        ));
+        table_lines.push(String::new());
+    }
+    if total_package_counts.inactive > 0 {
+        table_lines.push(inactive_footnote(
+            table_parameters.target,
+            total_package_counts.inactive,
+        ));
+        table_lines.push(String::new());
+    }
+    if !inconsistent_forbid.is_empty() {
+        table_lines.extend(inconsistent_forbid_footnote(&inconsistent_forbid));
+        table_lines.push(String::new());
+    }
+    if !approximate.is_empty() {
+        table_lines.extend(approximate_footnote(&approximate));
+        table_lines.push(String::new());
+    }
+    if !oversized.is_empty() {
+        table_lines.extend(oversized_footnote(&oversized));
+        table_lines.push(String::new());
+    }
 
-    (table_lines, warning_count)
+    (table_lines, warnings)
+}
+
+/// The totals of the footer split into the code of the workspace members and
+/// the code of their dependencies, in rows of their own below the footer.
+fn member_totals_lines(
+    total_package_counts: &TotalPackageCounts,
+    table_parameters: &TableParameters,
+) -> Vec<String> {
+    let counter_columns = &table_parameters.counter_columns;
+    let row = |used: &CounterBlock, not_used: &CounterBlock, label: &str| {
+        // The icon column is left blank.
+        format!(
+            "{}{}  {}{}",
+            table_row(
+                used,
+                not_used,
+                table_parameters.unsafe_criteria,
+                counter_columns,
+            ),
+            counter_columns.dep_counts(None),
+            counter_columns.marker_cell(""),
+            label
+        )
+    };
+    vec![
+        row(
+            &total_package_counts.member_counter_block,
+            &total_package_counts.member_unused_counter_block,
+            "our code",
+        ),
+        row(
+            &total_package_counts.dependency_counter_block,
+            &total_package_counts.dependency_unused_counter_block,
+            "dependencies",
+        ),
+    ]
+}
+
+/// The packages linking to a native library are marked in the tree, their
+/// native code can't be scanned and isn't part of any count.
+fn native_linkage_footnote(
+    emoji_symbols: &EmojiSymbols,
+    native_linkage: i32,
+) -> String {
+    format!(
+        "{} = links to a native library whose code isn't scanned: {} {}",
+        emoji_symbols.emoji(SymbolKind::Gear),
+        native_linkage,
+        if native_linkage == 1 {
+            "package"
+        } else {
+            "packages"
+        }
+    )
+}
+
+/// The packages without a single file used by the build are shown dimmed,
+/// they are left out of the detection status.
+fn inactive_footnote(target: &str, inactive: i32) -> String {
+    format!(
+        "Inactive on {}, nothing of them was built: {} {}",
+        target,
+        inactive,
+        if inactive == 1 { "package" } else { "packages" }
+    )
+}
+
+/// The packages marked with `(!)` in the tree forbid unsafe code, yet unsafe
+/// usage was counted in some of their files. The files are listed per
+/// package, relative to the package root.
+fn inconsistent_forbid_footnote(
+    inconsistent_forbid: &[(PackageId, Vec<PathBuf>)],
+) -> Vec<String> {
+    let mut footnote_lines = vec![String::from(
+        "(!) = forbids unsafe code, yet unsafe usage was counted in:",
+    )];
+    for (package_id, files) in inconsistent_forbid {
+        footnote_lines.push(format!(
+            "    {} v{}: {}",
+            package_id.name(),
+            package_id.version(),
+            files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    footnote_lines
+}
+
+/// The packages marked with `(~)` in the tree have files that failed to
+/// parse. The `unsafe` keywords of these files were counted from the tokens,
+/// they are not part of the counters of the row.
+fn approximate_footnote(
+    approximate: &[(PackageId, ApproximateUnsafety)],
+) -> Vec<String> {
+    let mut footnote_lines = vec![String::from(
+        "(~) = files failed to parse, `unsafe` keywords counted from their \
+         tokens, used/unused:",
+    )];
+    for (package_id, approximate) in approximate {
+        footnote_lines.push(format!(
+            "    {} v{}: {}/{}",
+            package_id.name(),
+            package_id.version(),
+            approximate.used,
+            approximate.unused
+        ));
+    }
+    footnote_lines
+}
+
+/// The packages marked with `(>)` in the tree have files larger than
+/// `--max-file-size`. These files were not scanned, their unsafe usage is
+/// unknown.
+fn oversized_footnote(
+    oversized: &[(PackageId, Vec<OversizedFile>)],
+) -> Vec<String> {
+    let mut footnote_lines = vec![String::from(
+        "(>) = files larger than --max-file-size were not scanned:",
+    )];
+    for (package_id, files) in oversized {
+        footnote_lines.push(format!(
+            "    {} v{}: {}",
+            package_id.name(),
+            package_id.version(),
+            files
+                .iter()
+                .map(|file| format!(
+                    "{} ({} bytes)",
+                    file.path.display(),
+                    file.size
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    footnote_lines
 }
 
 pub struct TableParameters<'a> {
     pub geiger_context: &'a GeigerContext,
     pub print_config: &'a PrintConfig,
     pub rs_files_used: &'a HashSet<PathBuf>,
+    /// The kinds of the targets that used each file in `rs_files_used`
+    pub target_kinds: &'a HashMap<PathBuf, HashSet<TargetKind>>,
+    /// Show the unsafe usage of all targets instead of the library only
+    pub all_target_kinds: bool,
+    pub unsafe_criteria: &'a UnsafeCriteria,
+    /// List the enabled features after each package, with `-vv`
+    pub feature_resolve: Option<&'a Resolve>,
+    /// The packages only used on other platforms, with `--all-platforms`
+    pub platforms: &'a HashMap<PackageId, Vec<String>>,
+    /// Show the unsafe usage of generated files in a row of its own
+    pub split_generated: bool,
+    pub counter_columns: CounterColumns,
+    /// The target triple the packages were built for
+    pub target: &'a str,
+    /// The packages that failed to build with `--keep-going`, with their
+    /// errors
+    pub build_failures: &'a HashMap<PackageId, String>,
+    /// The dependency counts of each package, empty without
+    /// `--show-dep-counts`
+    pub dependency_counts: &'a HashMap<PackageId, DependencyCounts>,
+    /// The workspace members, underlined in the tree and totaled apart from
+    /// the dependencies with `--show-totals`
+    pub members: &'a HashSet<PackageId>,
+    /// The packages only reached through dev-dependencies of dependencies,
+    /// left out of the totals
+    pub dev_only: &'a HashSet<PackageId>,
 }
 
 fn table_footer(
     used: CounterBlock,
     not_used: CounterBlock,
     status: CrateDetectionStatus,
+    unsafe_criteria: &UnsafeCriteria,
+    counter_columns: &CounterColumns,
 ) -> colored::ColoredString {
+    colorize(
+        table_row(&used, &not_used, unsafe_criteria, counter_columns),
+        &status,
+    )
+}
+
+pub fn table_row(
+    used: &CounterBlock,
+    not_used: &CounterBlock,
+    unsafe_criteria: &UnsafeCriteria,
+    counter_columns: &CounterColumns,
+) -> String {
     let fmt = |used: &Count, not_used: &Count| {
-        format!("{}/{}", used.unsafe_, used.unsafe_ + not_used.unsafe_)
+        counter_columns.cell(
+            format!("{}/{}", used.unsafe_, used.unsafe_ + not_used.unsafe_),
+            used,
+            not_used,
+        )
     };
-    let output = format!(
-        "{: <10} {: <12} {: <6} {: <7} {: <7}",
-        fmt(&used.functions, &not_used.functions),
-        fmt(&used.exprs, &not_used.exprs),
-        fmt(&used.item_impls, &not_used.item_impls),
-        fmt(&used.item_traits, &not_used.item_traits),
-        fmt(&used.methods, &not_used.methods),
-    );
-    colorize(output, &status)
+    table_row_cells(
+        [
+            fmt(&used.functions, &not_used.functions),
+            fmt(&used.exprs, &not_used.exprs),
+            fmt(&used.item_impls, &not_used.item_impls),
+            fmt(&used.item_traits, &not_used.item_traits),
+            fmt(&used.methods, &not_used.methods),
+            fmt(&used.foreign_items, &not_used.foreign_items),
+        ],
+        unsafe_criteria,
+        counter_columns,
+    )
 }
 
-fn table_row(used: &CounterBlock, not_used: &CounterBlock) -> String {
-    let fmt = |used: &Count, not_used: &Count| {
-        format!("{}/{}", used.unsafe_, used.unsafe_ + not_used.unsafe_)
+/// A row for a package without build data, the used counts are unknown.
+pub fn table_row_without_build_data(
+    not_used: &CounterBlock,
+    unsafe_criteria: &UnsafeCriteria,
+    counter_columns: &CounterColumns,
+) -> String {
+    let fmt = |not_used: &Count| {
+        counter_columns.cell(
+            format!("?/{}", not_used.unsafe_),
+            &Count::default(),
+            not_used,
+        )
+    };
+    table_row_cells(
+        [
+            fmt(&not_used.functions),
+            fmt(&not_used.exprs),
+            fmt(&not_used.item_impls),
+            fmt(&not_used.item_traits),
+            fmt(&not_used.methods),
+            fmt(&not_used.foreign_items),
+        ],
+        unsafe_criteria,
+        counter_columns,
+    )
+}
+
+/// The FFI column is dimmed when it doesn't count, see `--ignore-ffi`.
+fn table_row_cells(
+    cells: [String; 6],
+    unsafe_criteria: &UnsafeCriteria,
+    counter_columns: &CounterColumns,
+) -> String {
+    let widths = counter_columns.widths;
+    let foreign_items = format!("{: <1$}", cells[5], widths[5]);
+    let foreign_items = if unsafe_criteria.counts_foreign_items() {
+        foreign_items
+    } else {
+        foreign_items.dimmed().to_string()
     };
     format!(
-        "{: <10} {: <12} {: <6} {: <7} {: <7}",
-        fmt(&used.functions, &not_used.functions),
-        fmt(&used.exprs, &not_used.exprs),
-        fmt(&used.item_impls, &not_used.item_impls),
-        fmt(&used.item_traits, &not_used.item_traits),
-        fmt(&used.methods, &not_used.methods),
+        "{: <f$} {: <e$} {: <i$} {: <t$} {: <m$} {}",
+        cells[0],
+        cells[1],
+        cells[2],
+        cells[3],
+        cells[4],
+        foreign_items,
+        f = widths[0],
+        e = widths[1],
+        i = widths[2],
+        t = widths[3],
+        m = widths[4],
     )
 }
 
-fn table_row_empty() -> String {
-    let headers_but_last =
-        &UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1];
-    let n = headers_but_last
-        .iter()
-        .map(|s| s.len())
-        .sum::<usize>()
-        + headers_but_last.len() // Space after each column
-        + 2 // Unsafety symbol width
-        + 1; // Space after symbol
+fn table_row_empty(counter_columns: &CounterColumns) -> String {
+    let n = counter_columns.widths.iter().sum::<usize>()
+        + counter_columns.widths.len() - 1 // Space between the columns
+        + counter_columns.dep_counts(None).len()
+        + 2 // Space before the unsafety symbol
+        + counter_columns.marker_cell("").len();
     " ".repeat(n)
 }
 
@@ -147,27 +578,117 @@ mod table_tests {
     use super::*;
 
     use crate::rs_file::RsFileMetricsWrapper;
-    use crate::scan::{unsafe_stats, PackageMetrics};
+    use crate::scan::{unsafe_stats, DetailLevel, FileUsage, PackageMetrics};
 
+    use cargo::core::SourceId;
     use geiger::RsFileMetrics;
     use rstest::*;
-    use std::collections::HashMap;
     use std::path::Path;
     use strum::IntoEnumIterator;
 
+    #[rstest(
+        input_inactive,
+        expected_footnote,
+        case(
+            1,
+            "Inactive on x86_64-pc-windows-msvc, nothing of them was built: \
+             1 package"
+        ),
+        case(
+            3,
+            "Inactive on x86_64-pc-windows-msvc, nothing of them was built: \
+             3 packages"
+        )
+    )]
+    fn inactive_footnote_test(input_inactive: i32, expected_footnote: &str) {
+        assert_eq!(
+            inactive_footnote("x86_64-pc-windows-msvc", input_inactive),
+            expected_footnote
+        );
+    }
+
+    #[rstest]
+    fn inconsistent_forbid_footnote_test() {
+        let source_id =
+            SourceId::for_path(&std::env::current_dir().unwrap()).unwrap();
+        let package_id = PackageId::new("forbids", "0.1.0", source_id).unwrap();
+
+        assert_eq!(
+            inconsistent_forbid_footnote(&[(
+                package_id,
+                vec![PathBuf::from("build.rs"), PathBuf::from("tests/raw.rs")]
+            )]),
+            vec![
+                "(!) = forbids unsafe code, yet unsafe usage was counted in:",
+                "    forbids v0.1.0: build.rs, tests/raw.rs",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn approximate_footnote_test() {
+        let source_id =
+            SourceId::for_path(&std::env::current_dir().unwrap()).unwrap();
+        let package_id = PackageId::new("broken", "0.1.0", source_id).unwrap();
+
+        assert_eq!(
+            approximate_footnote(&[(
+                package_id,
+                ApproximateUnsafety { used: 3, unused: 1 }
+            )]),
+            vec![
+                "(~) = files failed to parse, `unsafe` keywords counted from \
+                 their tokens, used/unused:",
+                "    broken v0.1.0: 3/1",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn oversized_footnote_test() {
+        let source_id =
+            SourceId::for_path(&std::env::current_dir().unwrap()).unwrap();
+        let package_id =
+            PackageId::new("generated", "0.1.0", source_id).unwrap();
+
+        assert_eq!(
+            oversized_footnote(&[(
+                package_id,
+                vec![
+                    OversizedFile {
+                        path: PathBuf::from("src/a.rs"),
+                        size: 2048
+                    },
+                    OversizedFile {
+                        path: PathBuf::from("src/b.rs"),
+                        size: 4096
+                    },
+                ]
+            )]),
+            vec![
+                "(>) = files larger than --max-file-size were not scanned:",
+                "    generated v0.1.0: src/a.rs (2048 bytes), src/b.rs (4096 \
+                 bytes)",
+            ]
+        );
+    }
+
     #[rstest]
     fn table_footer_test() {
         let used_counter_block = create_counter_block();
         let not_used_counter_block = create_counter_block();
 
-        let expected_line =
-            String::from("2/4        4/8          6/12   8/16    10/20  ");
+        let expected_line = String::from(
+            "2/4        4/8          6/12   8/16    10/20    12/24",
+        );
 
         for crate_detection_status in CrateDetectionStatus::iter() {
             let table_footer = table_footer(
                 used_counter_block.clone(),
                 not_used_counter_block.clone(),
                 crate_detection_status.clone(),
+                &UnsafeCriteria::default(),
+                &CounterColumns::default(),
             );
 
             assert_eq!(
@@ -197,7 +718,10 @@ mod table_tests {
             create_rs_file_metrics_wrapper(false, false),
         );
 
-        let package_metrics = PackageMetrics { rs_path_to_metrics };
+        let mut package_metrics = PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        };
         let rs_files_used: HashSet<PathBuf> = [
             Path::new("package_1_path").to_path_buf(),
             Path::new("package_3_path").to_path_buf(),
@@ -205,39 +729,187 @@ mod table_tests {
         .iter()
         .cloned()
         .collect();
+        package_metrics.fold_files(
+            &FileUsage {
+                rs_files_used: &rs_files_used,
+                target_kinds: &HashMap::new(),
+            },
+            DetailLevel::Summary,
+        );
         let unsafety = unsafe_stats(&package_metrics, &rs_files_used);
 
-        let table_row = table_row(&unsafety.used, &unsafety.unused);
-        assert_eq!(table_row, "4/6        8/12         12/18  16/24   20/30  ");
+        let table_row = table_row(
+            &unsafety.used,
+            &unsafety.unused,
+            &UnsafeCriteria::default(),
+            &CounterColumns::default(),
+        );
+        assert_eq!(
+            table_row,
+            "4/6        8/12         12/18  16/24   20/30    24/36"
+        );
+    }
+
+    #[rstest]
+    fn table_row_without_build_data_test() {
+        let table_row = table_row_without_build_data(
+            &create_counter_block(),
+            &UnsafeCriteria::default(),
+            &CounterColumns::default(),
+        );
+        assert_eq!(
+            table_row,
+            "?/2        ?/4          ?/6    ?/8     ?/10     ?/12"
+        );
+    }
+
+    #[rstest]
+    fn table_row_ignoring_foreign_items_test() {
+        let table_row = table_row(
+            &create_counter_block(),
+            &CounterBlock::default(),
+            &UnsafeCriteria::default().without_foreign_items(),
+            &CounterColumns::default(),
+        );
+
+        // The count is still shown, only dimmed.
+        assert_eq!(
+            table_row,
+            format!(
+                "2/2        4/4          6/6    8/8     10/10    {}",
+                "12/12".dimmed()
+            )
+        );
     }
 
     #[rstest]
     fn table_row_empty_test() {
-        let empty_table_row = table_row_empty();
-        assert_eq!(empty_table_row.len(), 51);
+        let empty_table_row = table_row_empty(&CounterColumns::default());
+        assert_eq!(empty_table_row.len(), 56);
+    }
+
+    #[rstest]
+    fn counter_columns_header_test() {
+        assert_eq!(
+            CounterColumns::default().header(),
+            UNSAFE_COUNTERS_HEADER.join(" ")
+        );
+    }
+
+    #[rstest]
+    fn counter_columns_with_dep_counts_test() {
+        let counter_columns = CounterColumns {
+            show_dep_counts: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            counter_columns.header(),
+            format!(
+                "{} Deps       Dependency",
+                counter_columns.counter_header()
+            )
+        );
+        assert_eq!(
+            counter_columns.dep_counts(Some(&DependencyCounts {
+                direct: 2,
+                transitive: 7
+            })),
+            " 2/7      "
+        );
+        assert_eq!(counter_columns.dep_counts(None), " ".repeat(10));
+        assert_eq!(table_row_empty(&counter_columns).len(), 66);
+    }
+
+    #[rstest(
+        input_marker_position,
+        expected_marker_cell,
+        expected_table_row_empty_len,
+        case(MarkerPosition::Left, "!  ", 56),
+        case(MarkerPosition::None, "", 53),
+        case(MarkerPosition::Right, "", 53)
+    )]
+    fn counter_columns_marker_cell_test(
+        input_marker_position: MarkerPosition,
+        expected_marker_cell: &str,
+        expected_table_row_empty_len: usize,
+    ) {
+        let counter_columns = CounterColumns {
+            marker_position: input_marker_position,
+            ..Default::default()
+        };
+        assert_eq!(counter_columns.marker_cell("!"), expected_marker_cell);
+        assert_eq!(
+            table_row_empty(&counter_columns).len(),
+            expected_table_row_empty_len
+        );
+    }
+
+    #[rstest]
+    fn table_row_with_totals_test() {
+        let counter_columns = CounterColumns {
+            show_totals: true,
+            widths: [13, 14, 13, 13, 14, 13],
+            ..Default::default()
+        };
+        let table_row = table_row(
+            &create_counter_block(),
+            &create_counter_block(),
+            &UnsafeCriteria::default(),
+            &counter_columns,
+        );
+        assert_eq!(
+            table_row,
+            "2/4 (of 6)    4/8 (of 14)    6/12 (of 22)  8/16 (of 30)  \
+             10/20 (of 38)  12/24 (of 24)"
+        );
+        assert_eq!(
+            counter_columns.header(),
+            "Functions     Expressions    Impls         Traits        \
+             Methods        FFI            Dependency"
+        );
+        assert_eq!(
+            table_row_without_build_data(
+                &create_counter_block(),
+                &UnsafeCriteria::default(),
+                &counter_columns,
+            ),
+            "?/2 (of 3)    ?/4 (of 7)     ?/6 (of 11)   ?/8 (of 15)   \
+             ?/10 (of 19)   ?/12 (of 12) "
+        );
     }
 
     #[rstest(
         input_none_detected_forbids_unsafe,
         input_none_detected_allows_unsafe,
+        input_unused_unsafe_detected,
         input_unsafe_detected,
         expected_crate_detection_status,
-        case(0, 0, 1, CrateDetectionStatus::UnsafeDetected),
-        case(1, 0, 0, CrateDetectionStatus::NoneDetectedForbidsUnsafe),
-        case(4, 1, 0, CrateDetectionStatus::NoneDetectedAllowsUnsafe)
+        case(0, 0, 0, 1, CrateDetectionStatus::UnsafeDetected),
+        case(0, 0, 2, 1, CrateDetectionStatus::UnsafeDetected),
+        case(1, 0, 2, 0, CrateDetectionStatus::UnusedUnsafeDetected),
+        case(1, 0, 0, 0, CrateDetectionStatus::NoneDetectedForbidsUnsafe),
+        case(4, 1, 0, 0, CrateDetectionStatus::NoneDetectedAllowsUnsafe)
     )]
     fn total_package_counts_get_total_detection_status_tests(
         input_none_detected_forbids_unsafe: i32,
         input_none_detected_allows_unsafe: i32,
+        input_unused_unsafe_detected: i32,
         input_unsafe_detected: i32,
         expected_crate_detection_status: CrateDetectionStatus,
     ) {
         let total_detection_status = TotalPackageCounts {
             none_detected_forbids_unsafe: input_none_detected_forbids_unsafe,
             none_detected_allows_unsafe: input_none_detected_allows_unsafe,
+            unused_unsafe_detected: input_unused_unsafe_detected,
             unsafe_detected: input_unsafe_detected,
+            native_linkage: 0,
+            inactive: 0,
             total_counter_block: CounterBlock::default(),
             total_unused_counter_block: CounterBlock::default(),
+            member_counter_block: CounterBlock::default(),
+            member_unused_counter_block: CounterBlock::default(),
+            dependency_counter_block: CounterBlock::default(),
+            dependency_unused_counter_block: CounterBlock::default(),
         };
 
         assert_eq!(
@@ -246,6 +918,28 @@ mod table_tests {
         );
     }
 
+    #[rstest]
+    fn total_package_counts_add_package_test() {
+        let exprs = |unsafe_| CounterBlock {
+            exprs: Count { safe: 0, unsafe_ },
+            ..Default::default()
+        };
+        let mut total_package_counts = TotalPackageCounts::new();
+        total_package_counts.add_package(&exprs(1), &exprs(2), true);
+        total_package_counts.add_package(&exprs(4), &exprs(8), false);
+        total_package_counts.add_package(&exprs(16), &exprs(32), false);
+
+        assert_eq!(total_package_counts.total_counter_block, exprs(21));
+        assert_eq!(total_package_counts.total_unused_counter_block, exprs(42));
+        assert_eq!(total_package_counts.member_counter_block, exprs(1));
+        assert_eq!(total_package_counts.member_unused_counter_block, exprs(2));
+        assert_eq!(total_package_counts.dependency_counter_block, exprs(20));
+        assert_eq!(
+            total_package_counts.dependency_unused_counter_block,
+            exprs(40)
+        );
+    }
+
     fn create_rs_file_metrics_wrapper(
         forbids_unsafe: bool,
         is_crate_entry_point: bool,
@@ -254,8 +948,10 @@ mod table_tests {
             metrics: RsFileMetrics {
                 counters: create_counter_block(),
                 forbids_unsafe,
+                ..Default::default()
             },
             is_crate_entry_point,
+            is_generated: false,
         }
     }
 
@@ -281,6 +977,11 @@ mod table_tests {
                 safe: 9,
                 unsafe_: 10,
             },
+            foreign_items: Count {
+                safe: 0,
+                unsafe_: 12,
+            },
+            ..Default::default()
         }
     }
 }
@@ -1,26 +1,77 @@
 use crate::format::print_config::colorize;
-use crate::format::{get_kind_group_name, CrateDetectionStatus, SymbolKind};
-use crate::scan::unsafe_stats;
+use crate::format::rendered_line::RenderedLine;
+use crate::format::{
+    display_width, get_kind_group_name, unavailable_package_name,
+    CrateDetectionStatus, MarkerPosition, SymbolKind,
+};
+use crate::scan::{
+    canonical_package_root, from_cargo_package_id, is_inactive,
+    resolved_features,
+};
 
 use super::total_package_counts::TotalPackageCounts;
-use super::TableParameters;
-use super::{table_row, table_row_empty};
+use super::{table_row, table_row_empty, table_row_without_build_data};
+use super::{CounterColumns, TableParameters};
 
 use crate::format::emoji_symbols::EmojiSymbols;
 use cargo::core::dependency::DepKind;
 use cargo::core::package::PackageSet;
-use cargo::core::PackageId;
+use cargo::core::{Package, PackageId};
+use cargo_geiger_serde::{
+    ApproximateUnsafety, GeneratedUnsafety, OversizedFile, ScanStatus,
+    ScanWarning,
+};
+use colored::{ColoredString, Colorize};
 use std::collections::HashSet;
+use std::path::PathBuf;
 
 pub struct HandlePackageParameters<'a> {
     pub total_package_counts: &'a mut TotalPackageCounts,
     pub visited_package_ids: &'a mut HashSet<PackageId>,
-    pub warning_count: &'a mut u64,
+    pub warnings: &'a mut Vec<ScanWarning>,
+    /// The packages that forbid unsafe code but have unsafe usage counted,
+    /// with the files holding it relative to the package root
+    pub inconsistent_forbid: &'a mut Vec<(PackageId, Vec<PathBuf>)>,
+    /// The packages with files that failed to parse, with the `unsafe`
+    /// keywords counted in them from the tokens
+    pub approximate: &'a mut Vec<(PackageId, ApproximateUnsafety)>,
+    /// The packages with files larger than `--max-file-size`, with these
+    /// files relative to the package root
+    pub oversized: &'a mut Vec<(PackageId, Vec<OversizedFile>)>,
+}
+
+pub fn handle_text_tree_line_cycle(
+    package_id: PackageId,
+    package_set: &PackageSet,
+    table_lines: &mut Vec<String>,
+    table_parameters: &TableParameters,
+    tree_vines: String,
+) {
+    let package_name = match package_set.get_one(package_id) {
+        Ok(package) => format!(
+            "{}",
+            table_parameters.print_config.format.display(
+                &package_id,
+                package.manifest().metadata(),
+                table_parameters.print_config.hyperlinks,
+            )
+        ),
+        Err(_) => unavailable_package_name(package_id),
+    };
+
+    // The package is already counted further up the tree.
+    table_lines.push(format!(
+        "{}{}{} (cycle)",
+        table_row_empty(&table_parameters.counter_columns),
+        tree_vines,
+        package_name
+    ));
 }
 
 pub fn handle_text_tree_line_extra_deps_group(
     dep_kind: DepKind,
     table_lines: &mut Vec<String>,
+    counter_columns: &CounterColumns,
     tree_vines: String,
 ) {
     let name = get_kind_group_name(dep_kind);
@@ -30,7 +81,12 @@ pub fn handle_text_tree_line_extra_deps_group(
     let name = name.unwrap();
 
     // TODO: Fix the alignment on macOS (others too?)
-    table_lines.push(format!("{}{}{}", table_row_empty(), tree_vines, name));
+    table_lines.push(format!(
+        "{}{}{}",
+        table_row_empty(counter_columns),
+        tree_vines,
+        name
+    ));
 }
 
 pub fn handle_text_tree_line_package(
@@ -40,47 +96,207 @@ pub fn handle_text_tree_line_package(
     package_set: &PackageSet,
     table_lines: &mut Vec<String>,
     table_parameters: &TableParameters,
-    tree_vines: String,
+    rendered_line: RenderedLine,
 ) {
+    let tree_vines = rendered_line.vines;
     let package_is_new = handle_package_parameters
         .visited_package_ids
         .insert(package_id);
-    let package = package_set.get_one(package_id).unwrap_or_else(|_| {
-        // TODO: Avoid panic, return Result.
-        panic!("Expected to find package by id: {}", package_id);
-    });
-    let package_metrics = match table_parameters
-        .geiger_context
-        .package_id_to_metrics
-        .get(&package_id)
-    {
-        Some(m) => m,
-        None => {
-            *handle_package_parameters.warning_count += package_is_new as u64;
-            eprintln!("WARNING: No metrics found for package: {}", package_id);
+    let package = match package_set.get_one(package_id) {
+        Ok(package) => package,
+        Err(_) => {
+            // Not scanned, the scan warns about it.
+            table_lines.push(format!(
+                "{}{}{}",
+                table_row_empty(&table_parameters.counter_columns),
+                tree_vines,
+                unavailable_package_name(package_id)
+            ));
             return;
         }
     };
-    let unsafe_info =
-        unsafe_stats(package_metrics, table_parameters.rs_files_used);
-    if package_is_new {
+    if package_is_new && package.manifest().links().is_some() {
         handle_package_parameters
             .total_package_counts
-            .total_counter_block += unsafe_info.used.clone();
+            .native_linkage += 1;
+    }
+    let counters = match rendered_line.counters {
+        Some(counters) => counters,
+        None => {
+            if package_is_new {
+                handle_package_parameters.warnings.push(
+                    ScanWarning::NoMetrics {
+                        package: from_cargo_package_id(package_id),
+                    },
+                );
+            }
+            return;
+        }
+    };
+    if package_is_new && rendered_line.markers.inconsistent_forbid {
+        let package_root = canonical_package_root(package);
+        handle_package_parameters.inconsistent_forbid.push((
+            package_id,
+            counters
+                .inconsistent_forbid_files
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&package_root)
+                        .unwrap_or(path)
+                        .to_path_buf()
+                })
+                .collect(),
+        ));
+    }
+    if let (true, Some(approximate)) =
+        (package_is_new, &counters.unsafety.approximate)
+    {
         handle_package_parameters
-            .total_package_counts
-            .total_unused_counter_block += unsafe_info.unused.clone();
+            .approximate
+            .push((package_id, approximate.clone()));
     }
-    let unsafe_found = unsafe_info.used.has_unsafe();
-    let crate_forbids_unsafe = unsafe_info.forbids_unsafe;
-    let total_inc = package_is_new as i32;
-    let crate_detection_status =
-        get_crate_detection_status_and_update_package_counts(
-            crate_forbids_unsafe,
-            handle_package_parameters,
-            total_inc,
-            unsafe_found,
+    if package_is_new && rendered_line.markers.oversized {
+        let package_root = canonical_package_root(package);
+        handle_package_parameters.oversized.push((
+            package_id,
+            counters
+                .unsafety
+                .skipped_oversized
+                .iter()
+                .map(|oversized_file| OversizedFile {
+                    path: oversized_file
+                        .path
+                        .strip_prefix(&package_root)
+                        .unwrap_or(&oversized_file.path)
+                        .to_path_buf(),
+                    size: oversized_file.size,
+                })
+                .collect(),
+        ));
+    }
+    let forbid_suffix = if rendered_line.markers.inconsistent_forbid {
+        " (!)"
+    } else {
+        ""
+    };
+    let approximate_suffix = if rendered_line.markers.approximate {
+        " (~)"
+    } else {
+        ""
+    };
+    let oversized_suffix = if rendered_line.markers.oversized {
+        " (>)"
+    } else {
+        ""
+    };
+    // The packages only reached through dev-dependencies of dependencies
+    // are listed apart, below the table.
+    let is_dev_only = table_parameters.dev_only.contains(&package_id);
+    let dev_only_suffix = if is_dev_only { " (dev-only)" } else { "" };
+    let counts_toward_totals = package_is_new && !is_dev_only;
+    let unsafe_info = counters.unsafety;
+    let is_workspace_member = table_parameters.members.contains(&package_id);
+    if counts_toward_totals {
+        handle_package_parameters.total_package_counts.add_package(
+            &unsafe_info.used,
+            &unsafe_info.unused,
+            is_workspace_member,
         );
+    }
+
+    // With `--split-generated` the package row only counts the hand-written
+    // files, the status and the totals still count all of them.
+    let (row_info, generated_line) = match (
+        table_parameters.split_generated,
+        &unsafe_info.generated,
+        counters.hand_written,
+    ) {
+        (true, Some(generated), Some(hand_written)) => (
+            hand_written,
+            Some(generated_row(
+                generated,
+                unsafe_info.status,
+                &format!(
+                    "{}",
+                    table_parameters.print_config.format.display(
+                        &package_id,
+                        package.manifest().metadata(),
+                        table_parameters.print_config.hyperlinks,
+                    )
+                ),
+                table_parameters,
+                &tree_vines,
+            )),
+        ),
+        _ => (unsafe_info.clone(), None),
+    };
+
+    // Zeros for the used counts would read as safe at a glance, show them as
+    // unknown. The package has no detection status.
+    let crate_detection_status = match rendered_line.markers.status {
+        Some(crate_detection_status) => crate_detection_status,
+        None => {
+            let is_inactive = is_inactive(
+                unsafe_info.status,
+                table_parameters.build_failures.contains_key(&package_id),
+            );
+            if package_is_new && is_inactive {
+                handle_package_parameters.total_package_counts.inactive += 1;
+            }
+            let package_name = format!(
+                "{}",
+                table_parameters.print_config.format.display(
+                    &package_id,
+                    package.manifest().metadata(),
+                    table_parameters.print_config.hyperlinks,
+                )
+            );
+            let row = table_row_without_build_data(
+                &row_info.unused,
+                table_parameters.unsafe_criteria,
+                &table_parameters.counter_columns,
+            );
+            // Nothing of an inactive package is part of the build, its unsafe
+            // usage is dimmed instead of standing out.
+            let (row, package_name, inactive_suffix) = if is_inactive {
+                (
+                    row.dimmed(),
+                    package_name.dimmed(),
+                    format!(" (inactive on {})", table_parameters.target),
+                )
+            } else {
+                (row.normal(), package_name.normal(), String::new())
+            };
+            let package_name = member_style(package_name, is_workspace_member);
+            // The icon column is left blank.
+            table_lines.push(format!(
+                "{}{}  {}{}{}{}{}{}{}{}{}{}{}",
+                row,
+                table_parameters.counter_columns.dep_counts(
+                    table_parameters.dependency_counts.get(&package_id)
+                ),
+                table_parameters.counter_columns.marker_cell(""),
+                tree_vines,
+                package_name,
+                forbid_suffix,
+                approximate_suffix,
+                oversized_suffix,
+                dev_only_suffix,
+                links_suffix(package, emoji_symbols),
+                platforms_suffix(package_id, table_parameters),
+                inactive_suffix,
+                features_suffix(package_id, table_parameters)
+            ));
+            table_lines.extend(generated_line);
+            return;
+        }
+    };
+
+    update_package_counts(
+        &crate_detection_status,
+        handle_package_parameters,
+        counts_toward_totals as i32,
+    );
 
     let icon = match crate_detection_status {
         CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
@@ -89,30 +305,53 @@ pub fn handle_text_tree_line_package(
         CrateDetectionStatus::NoneDetectedAllowsUnsafe => {
             emoji_symbols.emoji(SymbolKind::QuestionMark)
         }
+        CrateDetectionStatus::UnusedUnsafeDetected => {
+            emoji_symbols.emoji(SymbolKind::HollowRads)
+        }
         CrateDetectionStatus::UnsafeDetected => {
             emoji_symbols.emoji(SymbolKind::Rads)
         }
     };
 
-    let package_name = colorize(
-        format!(
-            "{}",
-            table_parameters
-                .print_config
-                .format
-                .display(&package_id, package.manifest().metadata())
-        ),
-        &crate_detection_status,
+    let package_name = format!(
+        "{}",
+        table_parameters.print_config.format.display(
+            &package_id,
+            package.manifest().metadata(),
+            table_parameters.print_config.hyperlinks,
+        )
     );
-    let unsafe_info = colorize(
-        table_row(&unsafe_info.used, &unsafe_info.unused),
-        &crate_detection_status,
+    let package_name = if rendered_line.markers.inconsistent_forbid {
+        // Set apart from the colors of the detection status.
+        package_name.magenta().bold()
+    } else {
+        colorize(package_name, &crate_detection_status)
+    };
+    let package_name = member_style(package_name, is_workspace_member);
+    let unsafe_info = format!(
+        "{}{}",
+        colorize(
+            table_row(
+                &row_info.used,
+                &row_info.unused,
+                table_parameters.unsafe_criteria,
+                &table_parameters.counter_columns,
+            ),
+            &crate_detection_status,
+        ),
+        table_parameters
+            .counter_columns
+            .dep_counts(table_parameters.dependency_counts.get(&package_id))
     );
 
-    let shift_chars = unsafe_info.chars().count() + 4;
+    let icon = icon.to_string();
+    let marker_position = table_parameters.counter_columns.marker_position;
+    let marker_cell = table_parameters.counter_columns.marker_cell(&icon);
+    // The spaces before the icon, its two columns and the space after it.
+    let shift_chars = display_width(&unsafe_info) + 5;
 
     let mut line = String::new();
-    line.push_str(format!("{}  {: <2}", unsafe_info, icon).as_str());
+    line.push_str(format!("{}  {}", unsafe_info, marker_cell).as_str());
 
     // Here comes some special control characters to position the cursor
     // properly for printing the last column containing the tree vines, after
@@ -121,38 +360,167 @@ pub fn handle_text_tree_line_package(
     // count as a single character if using the column formatting provided by
     // Rust. This could be unrelated to Rust and a quirk of this particular
     // symbol or something in the Terminal app on macOS.
-    if emoji_symbols.will_output_emoji() {
+    if marker_position == MarkerPosition::Left
+        && emoji_symbols.will_output_emoji()
+    {
         line.push('\r'); // Return the cursor to the start of the line.
-        line.push_str(format!("\x1B[{}C", shift_chars).as_str()); // Move the cursor to the right so that it points to the icon character.
+        line.push_str(format!("\x1B[{}C", shift_chars).as_str()); // Move the cursor to the right so that it points to the tree vines.
+    }
+
+    table_lines.push(format!(
+        "{}{}{}{}{}{}{}{}{}{}",
+        line,
+        tree_vines,
+        package_name,
+        forbid_suffix,
+        approximate_suffix,
+        oversized_suffix,
+        dev_only_suffix,
+        links_suffix(package, emoji_symbols),
+        features_suffix(package_id, table_parameters),
+        marker_suffix(&crate_detection_status, &icon, marker_position)
+    ));
+    table_lines.extend(generated_line);
+}
+
+/// The row below a package with `--split-generated`, holding the unsafe usage
+/// of its machine generated files. The name is indented to below the package
+/// name and the icon column is left blank.
+fn generated_row(
+    generated: &GeneratedUnsafety,
+    status: ScanStatus,
+    package_name: &str,
+    table_parameters: &TableParameters,
+    tree_vines: &str,
+) -> String {
+    let row = if has_no_build_data(status) {
+        table_row_without_build_data(
+            &generated.unused,
+            table_parameters.unsafe_criteria,
+            &table_parameters.counter_columns,
+        )
+    } else {
+        table_row(
+            &generated.used,
+            &generated.unused,
+            table_parameters.unsafe_criteria,
+            &table_parameters.counter_columns,
+        )
+    };
+    format!(
+        "{}{}  {}{}{} (generated)",
+        row,
+        table_parameters.counter_columns.dep_counts(None),
+        table_parameters.counter_columns.marker_cell(""),
+        " ".repeat(display_width(tree_vines)),
+        package_name
+    )
+}
+
+/// Packages of other platforms weren't built, like packages without any file
+/// used by the build.
+fn has_no_build_data(status: ScanStatus) -> bool {
+    matches!(status, ScanStatus::NoBuildData | ScanStatus::OtherPlatform)
+}
+
+/// The names of the workspace members are underlined, setting our own code
+/// apart from the dependencies.
+fn member_style(
+    package_name: ColoredString,
+    is_workspace_member: bool,
+) -> ColoredString {
+    if is_workspace_member {
+        package_name.underline()
+    } else {
+        package_name
+    }
+}
+
+/// The detection status at the end of the row, the symbol with
+/// `--marker-position right` and a word with `--marker-position none`. Empty
+/// with the symbol in its column on the left.
+fn marker_suffix(
+    crate_detection_status: &CrateDetectionStatus,
+    icon: &str,
+    marker_position: MarkerPosition,
+) -> String {
+    match marker_position {
+        MarkerPosition::Left => String::new(),
+        MarkerPosition::None => {
+            let word = match crate_detection_status {
+                CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
+                    "safe, forbids unsafe"
+                }
+                CrateDetectionStatus::NoneDetectedAllowsUnsafe => "safe",
+                CrateDetectionStatus::UnusedUnsafeDetected => "unused unsafe",
+                CrateDetectionStatus::UnsafeDetected => "unsafe",
+            };
+            format!(" ({})", word)
+        }
+        MarkerPosition::Right => format!(" {}", icon),
+    }
+}
+
+/// The native library of a package with a `links` key in its manifest, marked
+/// with its own symbol. Empty for all other packages.
+fn links_suffix(package: &Package, emoji_symbols: &EmojiSymbols) -> String {
+    match package.manifest().links() {
+        Some(links) => format!(
+            " {} (links: {})",
+            emoji_symbols.emoji(SymbolKind::Gear),
+            links
+        ),
+        None => String::new(),
     }
+}
+
+/// The platforms of a package only used on other platforms, with
+/// `--all-platforms`. Empty for all other packages.
+fn platforms_suffix(
+    package_id: PackageId,
+    table_parameters: &TableParameters,
+) -> String {
+    match table_parameters.platforms.get(&package_id) {
+        Some(platforms) => format!(" (platform: {})", platforms.join(", ")),
+        None => String::new(),
+    }
+}
 
-    table_lines.push(format!("{} {}{}", line, tree_vines, package_name));
+/// The enabled features of a package, with `-vv`. Empty for packages without
+/// any.
+fn features_suffix(
+    package_id: PackageId,
+    table_parameters: &TableParameters,
+) -> String {
+    let features = match table_parameters.feature_resolve {
+        Some(resolve) => resolved_features(resolve, package_id),
+        None => return String::new(),
+    };
+    if features.is_empty() {
+        return String::new();
+    }
+    format!(" (features: {})", features.join(", "))
 }
 
-fn get_crate_detection_status_and_update_package_counts(
-    crate_forbids_unsafe: bool,
+fn update_package_counts(
+    crate_detection_status: &CrateDetectionStatus,
     handle_package_parameters: &mut HandlePackageParameters,
     total_inc: i32,
-    unsafe_found: bool,
-) -> CrateDetectionStatus {
-    match (crate_forbids_unsafe, unsafe_found) {
-        (true, false) => {
-            handle_package_parameters
-                .total_package_counts
-                .none_detected_forbids_unsafe += total_inc;
-            CrateDetectionStatus::NoneDetectedForbidsUnsafe
+) {
+    let total_package_counts =
+        &mut handle_package_parameters.total_package_counts;
+    match crate_detection_status {
+        CrateDetectionStatus::UnsafeDetected => {
+            total_package_counts.unsafe_detected += total_inc
+        }
+        CrateDetectionStatus::UnusedUnsafeDetected => {
+            total_package_counts.unused_unsafe_detected += total_inc
         }
-        (false, false) => {
-            handle_package_parameters
-                .total_package_counts
-                .none_detected_allows_unsafe += total_inc;
-            CrateDetectionStatus::NoneDetectedAllowsUnsafe
+        CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
+            total_package_counts.none_detected_forbids_unsafe += total_inc
         }
-        (_, true) => {
-            handle_package_parameters
-                .total_package_counts
-                .unsafe_detected += total_inc;
-            CrateDetectionStatus::UnsafeDetected
+        CrateDetectionStatus::NoneDetectedAllowsUnsafe => {
+            total_package_counts.none_detected_allows_unsafe += total_inc
         }
     }
 }
@@ -181,6 +549,7 @@ mod handle_text_tree_line_tests {
         handle_text_tree_line_extra_deps_group(
             input_dep_kind,
             &mut table_lines,
+            &CounterColumns::default(),
             tree_vines.clone(),
         );
 
@@ -190,7 +559,7 @@ mod handle_text_tree_line_tests {
                 table_lines.first().unwrap().as_str(),
                 format!(
                     "{}{}{}",
-                    table_row_empty(),
+                    table_row_empty(&CounterColumns::default()),
                     tree_vines,
                     expected_kind_group_name.unwrap(),
                 )
@@ -201,102 +570,113 @@ mod handle_text_tree_line_tests {
     }
 
     #[rstest(
-        input_crate_forbids_unsafe,
-        input_total_inc,
-        input_unsafe_found,
-        expected_crate_detection_status,
-        expected_none_detected_forbids_unsafe,
-        expected_none_detected_allows_unsafe,
-        expected_unsafe_detected,
+        input_crate_detection_status,
+        input_marker_position,
+        expected_marker_suffix,
+        case(CrateDetectionStatus::UnsafeDetected, MarkerPosition::Left, ""),
         case(
-            true,
-            1,
-            false,
-            CrateDetectionStatus::NoneDetectedForbidsUnsafe,
-            1,
-            0,
-            0
+            CrateDetectionStatus::UnsafeDetected,
+            MarkerPosition::Right,
+            " !"
         ),
         case(
-            true,
-            0,
-            false,
             CrateDetectionStatus::NoneDetectedForbidsUnsafe,
-            0,
-            0,
-            0
+            MarkerPosition::None,
+            " (safe, forbids unsafe)"
         ),
         case(
-            false,
-            1,
-            false,
             CrateDetectionStatus::NoneDetectedAllowsUnsafe,
-            0,
-            1,
-            0
+            MarkerPosition::None,
+            " (safe)"
         ),
         case(
-            false,
-            0,
-            false,
-            CrateDetectionStatus::NoneDetectedAllowsUnsafe,
-            0,
-            0,
-            0
+            CrateDetectionStatus::UnusedUnsafeDetected,
+            MarkerPosition::None,
+            " (unused unsafe)"
         ),
-        case(false, 1, true, CrateDetectionStatus::UnsafeDetected, 0, 0, 1),
-        case(false, 0, true, CrateDetectionStatus::UnsafeDetected, 0, 0, 0)
+        case(
+            CrateDetectionStatus::UnsafeDetected,
+            MarkerPosition::None,
+            " (unsafe)"
+        )
+    )]
+    fn marker_suffix_test(
+        input_crate_detection_status: CrateDetectionStatus,
+        input_marker_position: MarkerPosition,
+        expected_marker_suffix: &str,
+    ) {
+        assert_eq!(
+            marker_suffix(
+                &input_crate_detection_status,
+                "!",
+                input_marker_position
+            ),
+            expected_marker_suffix
+        );
+    }
+
+    #[rstest(
+        input_is_workspace_member,
+        expected_package_name,
+        case(true, String::from("member").red().underline()),
+        case(false, String::from("member").red())
+    )]
+    fn member_style_test(
+        input_is_workspace_member: bool,
+        expected_package_name: ColoredString,
+    ) {
+        assert_eq!(
+            member_style(
+                String::from("member").red(),
+                input_is_workspace_member
+            ),
+            expected_package_name
+        );
+    }
+
+    #[rstest(
+        input_crate_detection_status,
+        input_total_inc,
+        expected_total_package_counts,
+        case(CrateDetectionStatus::NoneDetectedForbidsUnsafe, 1, [1, 0, 0, 0]),
+        case(CrateDetectionStatus::NoneDetectedForbidsUnsafe, 0, [0, 0, 0, 0]),
+        case(CrateDetectionStatus::NoneDetectedAllowsUnsafe, 1, [0, 1, 0, 0]),
+        case(CrateDetectionStatus::NoneDetectedAllowsUnsafe, 0, [0, 0, 0, 0]),
+        case(CrateDetectionStatus::UnusedUnsafeDetected, 1, [0, 0, 1, 0]),
+        case(CrateDetectionStatus::UnusedUnsafeDetected, 0, [0, 0, 0, 0]),
+        case(CrateDetectionStatus::UnsafeDetected, 1, [0, 0, 0, 1]),
+        case(CrateDetectionStatus::UnsafeDetected, 0, [0, 0, 0, 0])
     )]
-    fn get_crate_detection_status_and_update_package_counts_test(
-        input_crate_forbids_unsafe: bool,
+    fn update_package_counts_test(
+        input_crate_detection_status: CrateDetectionStatus,
         input_total_inc: i32,
-        input_unsafe_found: bool,
-        expected_crate_detection_status: CrateDetectionStatus,
-        expected_none_detected_forbids_unsafe: i32,
-        expected_none_detected_allows_unsafe: i32,
-        expected_unsafe_detected: i32,
+        expected_total_package_counts: [i32; 4],
     ) {
         let mut handle_package_parameters = HandlePackageParameters {
-            total_package_counts: &mut TotalPackageCounts {
-                none_detected_forbids_unsafe: 0,
-                none_detected_allows_unsafe: 0,
-                unsafe_detected: 0,
-                total_counter_block: Default::default(),
-                total_unused_counter_block: Default::default(),
-            },
+            total_package_counts: &mut TotalPackageCounts::new(),
             visited_package_ids: &mut Default::default(),
-            warning_count: &mut 0,
+            warnings: &mut Vec::new(),
+            inconsistent_forbid: &mut Vec::new(),
+            approximate: &mut Vec::new(),
+            oversized: &mut Vec::new(),
         };
 
-        let crate_detection_status =
-            get_crate_detection_status_and_update_package_counts(
-                input_crate_forbids_unsafe,
-                &mut handle_package_parameters,
-                input_total_inc,
-                input_unsafe_found,
-            );
-
-        assert_eq!(crate_detection_status, expected_crate_detection_status);
-
-        assert_eq!(
-            handle_package_parameters
-                .total_package_counts
-                .none_detected_forbids_unsafe,
-            expected_none_detected_forbids_unsafe
-        );
-
-        assert_eq!(
-            handle_package_parameters
-                .total_package_counts
-                .none_detected_allows_unsafe,
-            expected_none_detected_allows_unsafe
+        update_package_counts(
+            &input_crate_detection_status,
+            &mut handle_package_parameters,
+            input_total_inc,
         );
 
+        let total_package_counts =
+            &handle_package_parameters.total_package_counts;
         assert_eq!(
-            handle_package_parameters
-                .total_package_counts
-                .unsafe_detected,
-            expected_unsafe_detected
+            [
+                total_package_counts.none_detected_forbids_unsafe,
+                total_package_counts.none_detected_allows_unsafe,
+                total_package_counts.unused_unsafe_detected,
+                total_package_counts.unsafe_detected,
+            ],
+            expected_total_package_counts
         );
     }
 }
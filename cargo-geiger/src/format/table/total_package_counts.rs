@@ -5,9 +5,20 @@ use cargo_geiger_serde::CounterBlock;
 pub struct TotalPackageCounts {
     pub none_detected_forbids_unsafe: i32,
     pub none_detected_allows_unsafe: i32,
+    pub unused_unsafe_detected: i32,
     pub unsafe_detected: i32,
+    /// The packages linking to a native library, see `NativeLinkage`
+    pub native_linkage: i32,
+    /// The packages nothing of was built for the target, see `is_inactive`
+    pub inactive: i32,
     pub total_counter_block: CounterBlock,
     pub total_unused_counter_block: CounterBlock,
+    /// The part of the totals counted in the workspace members
+    pub member_counter_block: CounterBlock,
+    pub member_unused_counter_block: CounterBlock,
+    /// The part of the totals counted in the dependencies
+    pub dependency_counter_block: CounterBlock,
+    pub dependency_unused_counter_block: CounterBlock,
 }
 
 impl TotalPackageCounts {
@@ -15,9 +26,16 @@ impl TotalPackageCounts {
         TotalPackageCounts {
             none_detected_forbids_unsafe: 0,
             none_detected_allows_unsafe: 0,
+            unused_unsafe_detected: 0,
             unsafe_detected: 0,
+            native_linkage: 0,
+            inactive: 0,
             total_counter_block: CounterBlock::default(),
             total_unused_counter_block: CounterBlock::default(),
+            member_counter_block: CounterBlock::default(),
+            member_unused_counter_block: CounterBlock::default(),
+            dependency_counter_block: CounterBlock::default(),
+            dependency_unused_counter_block: CounterBlock::default(),
         }
     }
 
@@ -25,13 +43,34 @@ impl TotalPackageCounts {
         match (
             self.none_detected_forbids_unsafe > 0,
             self.none_detected_allows_unsafe > 0,
+            self.unused_unsafe_detected > 0,
             self.unsafe_detected > 0,
         ) {
-            (_, _, true) => CrateDetectionStatus::UnsafeDetected,
-            (true, false, false) => {
+            (_, _, _, true) => CrateDetectionStatus::UnsafeDetected,
+            (_, _, true, false) => CrateDetectionStatus::UnusedUnsafeDetected,
+            (true, false, false, false) => {
                 CrateDetectionStatus::NoneDetectedForbidsUnsafe
             }
             _ => CrateDetectionStatus::NoneDetectedAllowsUnsafe,
         }
     }
+
+    /// Adds the counts of a package to the totals and to the part of them
+    /// of the members or of the dependencies.
+    pub fn add_package(
+        &mut self,
+        used: &CounterBlock,
+        unused: &CounterBlock,
+        is_workspace_member: bool,
+    ) {
+        self.total_counter_block += used.clone();
+        self.total_unused_counter_block += unused.clone();
+        if is_workspace_member {
+            self.member_counter_block += used.clone();
+            self.member_unused_counter_block += unused.clone();
+        } else {
+            self.dependency_counter_block += used.clone();
+            self.dependency_unused_counter_block += unused.clone();
+        }
+    }
 }
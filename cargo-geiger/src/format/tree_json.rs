@@ -0,0 +1,53 @@
+use crate::format::rendered_line::rendered_line;
+use crate::format::table::TableParameters;
+use crate::scan::{from_cargo_dependency_kind, from_cargo_package_id};
+use crate::tree::TextTreeLine;
+
+use cargo_geiger_serde::TreeLine;
+
+/// The lines of the table in the same order, with the unsafe usage counted
+/// the same way as in the table rows.
+pub fn create_tree_lines_from_text_tree_lines(
+    table_parameters: &TableParameters,
+    text_tree_lines: Vec<TextTreeLine>,
+) -> Vec<TreeLine> {
+    text_tree_lines
+        .into_iter()
+        .map(|text_tree_line| {
+            let rendered_line =
+                rendered_line(&text_tree_line, table_parameters);
+            match text_tree_line {
+                TextTreeLine::Package {
+                    id,
+                    depth,
+                    tree_vines,
+                } => TreeLine::Package {
+                    id: from_cargo_package_id(id),
+                    depth,
+                    tree_vines,
+                    unsafety: rendered_line
+                        .counters
+                        .map(|counters| Box::new(counters.unsafety)),
+                },
+                TextTreeLine::ExtraDepsGroup {
+                    kind,
+                    depth,
+                    tree_vines,
+                } => TreeLine::ExtraDepsGroup {
+                    kind: from_cargo_dependency_kind(kind),
+                    depth,
+                    tree_vines,
+                },
+                TextTreeLine::Cycle {
+                    id,
+                    depth,
+                    tree_vines,
+                } => TreeLine::Cycle {
+                    id: from_cargo_package_id(id),
+                    depth,
+                    tree_vines,
+                },
+            }
+        })
+        .collect()
+}
@@ -1,16 +1,24 @@
+mod metadata;
+
 use crate::args::Args;
 use crate::cli::get_cfgs;
 
+pub use metadata::build_graph_from_metadata;
+
 use cargo::core::dependency::DepKind;
 use cargo::core::package::PackageSet;
-use cargo::core::{Dependency, PackageId, Resolve, Workspace};
+use cargo::core::{PackageId, Resolve, Workspace};
 use cargo::util::interning::InternedString;
 use cargo::util::CargoResult;
 use cargo::Config;
-use cargo_platform::Cfg;
-use petgraph::graph::NodeIndex;
+use cargo_geiger_serde::DependencyCounts;
+use cargo_platform::{Cfg, Platform};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::{Dfs, EdgeRef};
+use petgraph::EdgeDirection;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 
 #[derive(Debug, PartialEq)]
 pub enum ExtraDeps {
@@ -32,10 +40,50 @@ impl ExtraDeps {
     }
 }
 
+/// Where the dependency graph is built from. Both backends build the same
+/// graph, the scan doesn't tell them apart.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphBackend {
+    /// The resolve of the cargo crate linked into cargo-geiger.
+    Resolve,
+
+    /// The output of `cargo metadata`, from the cargo installed alongside,
+    /// which may understand dependency declarations that the linked cargo
+    /// crate resolves differently.
+    Metadata,
+}
+
+impl FromStr for GraphBackend {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<GraphBackend, &'static str> {
+        match s {
+            "resolve" => Ok(GraphBackend::Resolve),
+            "metadata" => Ok(GraphBackend::Metadata),
+            _ => Err("invalid backend"),
+        }
+    }
+}
+
 /// Representation of the package dependency graph
 pub struct Graph {
     pub graph: petgraph::Graph<Node, DepKind>,
     pub nodes: HashMap<PackageId, NodeIndex>,
+    /// The packages only used on other platforms than the one built for, with
+    /// the platform expressions of the target-specific dependencies pulling
+    /// them in. Only filled with `--all-platforms`.
+    pub platforms: HashMap<PackageId, Vec<String>>,
+    /// The target triple the packages are built for, `--target` or the host
+    pub target: String,
+    /// The members of the workspace, known when the graph is built. The
+    /// other packages are dependencies from registries, git or paths outside
+    /// the workspace.
+    pub members: HashSet<PackageId>,
+    /// The packages that can't be reached from the root without going
+    /// through a dev-dependency of another package than the root. These are
+    /// the test helpers of the dependencies, never compiled into anything the
+    /// root ships. Only filled with dev-dependencies in the graph.
+    pub dev_only: HashSet<PackageId>,
 }
 
 /// Representation of a node within the package dependency graph
@@ -61,17 +109,8 @@ pub fn build_graph<'a>(
     let (extra_deps, target) = build_graph_prerequisites(args, &config_host)?;
     let cfgs = get_cfgs(config, &args.target, &workspace)?;
 
-    let mut graph = Graph {
-        graph: petgraph::Graph::new(),
-        nodes: HashMap::new(),
-    };
-    let node = Node {
-        id: root_package_id,
-        //pack: packages.get_one(root)?,
-    };
-    graph
-        .nodes
-        .insert(root_package_id, graph.graph.add_node(node));
+    let mut graph =
+        new_graph_with_root(args, &config_host, workspace, root_package_id);
 
     let mut pending_packages = vec![root_package_id];
 
@@ -79,7 +118,9 @@ pub fn build_graph<'a>(
         target,
         cfgs: cfgs.as_deref(),
         extra_deps,
+        all_platforms: args.all_platforms,
     };
+    let mut other_platform_edges = HashMap::new();
 
     while let Some(package_id) = pending_packages.pop() {
         add_package_dependencies_to_graph(
@@ -89,25 +130,240 @@ pub fn build_graph<'a>(
             &graph_configuration,
             &mut graph,
             &mut pending_packages,
-        )?;
+            &mut other_platform_edges,
+        );
     }
 
+    graph.platforms =
+        other_platform_packages(&graph, root_package_id, &other_platform_edges);
+    graph.dev_only = dev_only_packages(&graph, root_package_id);
+
     Ok(graph)
 }
 
+/// A graph holding just the root package, to add the dependencies to.
+fn new_graph_with_root(
+    args: &Args,
+    config_host: &InternedString,
+    workspace: &Workspace,
+    root_package_id: PackageId,
+) -> Graph {
+    let mut graph = Graph {
+        graph: petgraph::Graph::new(),
+        nodes: HashMap::new(),
+        platforms: HashMap::new(),
+        target: args
+            .target
+            .clone()
+            .unwrap_or_else(|| config_host.to_string()),
+        members: workspace
+            .members()
+            .map(|member| member.package_id())
+            .collect(),
+        dev_only: HashSet::new(),
+    };
+    let node = Node {
+        id: root_package_id,
+        //pack: packages.get_one(root)?,
+    };
+    graph
+        .nodes
+        .insert(root_package_id, graph.graph.add_node(node));
+    graph
+}
+
 struct GraphConfiguration<'a> {
     target: Option<&'a str>,
     cfgs: Option<&'a [Cfg]>,
     extra_deps: ExtraDeps,
+    /// Keep the dependencies of other platforms than `target` too
+    all_platforms: bool,
+}
+
+impl GraphConfiguration<'_> {
+    /// Whether a dependency with the given platform expression is used on
+    /// the platform built for. Without a target, with `--all-targets`, all
+    /// dependencies are.
+    fn matches_target(&self, platform: Option<&Platform>) -> bool {
+        platform
+            .and_then(|p| {
+                self.target.map(|t| match self.cfgs {
+                    None => false,
+                    Some(cfgs) => p.matches(t, cfgs),
+                })
+            })
+            .unwrap_or(true)
+    }
+}
+
+/// The packages that can't be reached from the root without going through
+/// a dependency of another platform, with the platform expressions of those
+/// dependencies. A package pulled in by a package of another platform gets
+/// the platforms of that package.
+fn other_platform_packages(
+    graph: &Graph,
+    root_package_id: PackageId,
+    other_platform_edges: &HashMap<EdgeIndex, String>,
+) -> HashMap<PackageId, Vec<String>> {
+    if other_platform_edges.is_empty() {
+        return HashMap::new();
+    }
+
+    let mut host_indices = HashSet::new();
+    let mut pending_indices = vec![graph.nodes[&root_package_id]];
+    while let Some(index) = pending_indices.pop() {
+        if !host_indices.insert(index) {
+            continue;
+        }
+        pending_indices.extend(
+            graph
+                .graph
+                .edges(index)
+                .filter(|edge| !other_platform_edges.contains_key(&edge.id()))
+                .map(|edge| edge.target()),
+        );
+    }
+
+    let mut platforms = HashMap::<NodeIndex, BTreeSet<String>>::new();
+    for (edge_index, platform) in other_platform_edges {
+        let (_, index) = graph.graph.edge_endpoints(*edge_index).unwrap();
+        if !host_indices.contains(&index) {
+            platforms.entry(index).or_default().insert(platform.clone());
+        }
+    }
+    // The dependencies of the packages of other platforms are of those
+    // platforms too, unless the platform built for uses them as well.
+    let mut pending_indices = platforms.keys().cloned().collect::<Vec<_>>();
+    while let Some(index) = pending_indices.pop() {
+        let package_platforms = platforms[&index].clone();
+        for dependency_index in graph.graph.neighbors(index) {
+            if host_indices.contains(&dependency_index) {
+                continue;
+            }
+            let dependency_platforms =
+                platforms.entry(dependency_index).or_default();
+            let count = dependency_platforms.len();
+            dependency_platforms.extend(package_platforms.iter().cloned());
+            if dependency_platforms.len() > count {
+                pending_indices.push(dependency_index);
+            }
+        }
+    }
+
+    platforms
+        .into_iter()
+        .map(|(index, platforms)| {
+            (graph.graph[index].id, platforms.into_iter().collect())
+        })
+        .collect()
+}
+
+/// The packages only reachable from the root through a dev-dependency of
+/// another package than the root. The dev-dependencies of the root are
+/// followed like the other edges, they are built for its tests.
+fn dev_only_packages(
+    graph: &Graph,
+    root_package_id: PackageId,
+) -> HashSet<PackageId> {
+    let root_index = match graph.nodes.get(&root_package_id) {
+        Some(&root_index) => root_index,
+        None => return HashSet::new(),
+    };
+    let mut shipped_indices = HashSet::new();
+    let mut pending_indices = vec![root_index];
+    while let Some(index) = pending_indices.pop() {
+        if !shipped_indices.insert(index) {
+            continue;
+        }
+        pending_indices.extend(
+            graph
+                .graph
+                .edges(index)
+                .filter(|edge| {
+                    *edge.weight() != DepKind::Development
+                        || index == root_index
+                })
+                .map(|edge| edge.target()),
+        );
+    }
+    graph
+        .graph
+        .node_indices()
+        .filter(|index| !shipped_indices.contains(index))
+        .map(|index| graph.graph[index].id)
+        .collect()
+}
+
+/// The number of dependencies of each package in the graph. The transitive
+/// count includes the direct dependencies, a package reachable through
+/// several paths or through a cycle is counted once.
+pub fn dependency_counts(
+    graph: &Graph,
+) -> HashMap<PackageId, DependencyCounts> {
+    graph
+        .nodes
+        .iter()
+        .map(|(package_id, &index)| {
+            let direct = graph
+                .graph
+                .neighbors(index)
+                .filter(|&dependency_index| dependency_index != index)
+                .collect::<HashSet<_>>()
+                .len();
+            let mut dfs = Dfs::new(&graph.graph, index);
+            let mut transitive = 0;
+            while let Some(dependency_index) = dfs.next(&graph.graph) {
+                if dependency_index != index {
+                    transitive += 1;
+                }
+            }
+            (
+                *package_id,
+                DependencyCounts {
+                    direct: direct as u64,
+                    transitive,
+                },
+            )
+        })
+        .collect()
+}
+
+/// The minimum number of edges between the root and each package reachable
+/// from it, following the edges in `direction`. A package reached through
+/// several paths gets the length of the shortest one, the root gets `0`.
+pub fn package_depths(
+    graph: &Graph,
+    root_package_id: PackageId,
+    direction: EdgeDirection,
+) -> HashMap<PackageId, u64> {
+    let mut depths = HashMap::new();
+    let root_index = match graph.nodes.get(&root_package_id) {
+        Some(&root_index) => root_index,
+        None => return depths,
+    };
+    depths.insert(root_package_id, 0);
+    let mut pending = VecDeque::from(vec![(root_index, 0)]);
+    while let Some((index, depth)) = pending.pop_front() {
+        for dependency_index in graph.graph.neighbors_directed(index, direction)
+        {
+            if let Entry::Vacant(e) =
+                depths.entry(graph.graph[dependency_index].id)
+            {
+                e.insert(depth + 1);
+                pending.push_back((dependency_index, depth + 1));
+            }
+        }
+    }
+    depths
 }
 
 fn add_graph_node_if_not_present_and_edge(
-    dependency: &Dependency,
+    kind: DepKind,
     dependency_package_id: PackageId,
     graph: &mut Graph,
     index: NodeIndex,
     pending_packages: &mut Vec<PackageId>,
-) {
+) -> EdgeIndex {
     let dependency_index = match graph.nodes.entry(dependency_package_id) {
         Entry::Occupied(e) => *e.get(),
         Entry::Vacant(e) => {
@@ -119,9 +375,7 @@ fn add_graph_node_if_not_present_and_edge(
             *e.insert(graph.graph.add_node(node))
         }
     };
-    graph
-        .graph
-        .add_edge(index, dependency_index, dependency.kind());
+    graph.graph.add_edge(index, dependency_index, kind)
 }
 
 fn add_package_dependencies_to_graph<'a>(
@@ -131,28 +385,32 @@ fn add_package_dependencies_to_graph<'a>(
     graph_configuration: &GraphConfiguration,
     graph: &mut Graph,
     pending_packages: &mut Vec<PackageId>,
-) -> CargoResult<()> {
+    other_platform_edges: &mut HashMap<EdgeIndex, String>,
+) {
     let index = graph.nodes[&package_id];
-    let package = package_set.get_one(package_id)?;
+    // The sources of a package can fail to download or read, its dependencies
+    // are then taken from the resolve alone. The scan warns about it.
+    let package = package_set.get_one(package_id).ok();
 
-    for (raw_dependency_package_id, _) in resolve.deps_not_replaced(package_id)
+    for (raw_dependency_package_id, resolved_dependencies) in
+        resolve.deps_not_replaced(package_id)
     {
-        let dependency_iterator = package
-            .dependencies()
-            .iter()
-            .filter(|d| d.matches_ignoring_source(raw_dependency_package_id))
+        let dependencies = match package {
+            Some(package) => package
+                .dependencies()
+                .iter()
+                .filter(|d| {
+                    d.matches_ignoring_source(raw_dependency_package_id)
+                })
+                .collect::<Vec<_>>(),
+            None => resolved_dependencies.iter().collect(),
+        };
+        let dependency_iterator = dependencies
+            .into_iter()
             .filter(|d| graph_configuration.extra_deps.allows(d.kind()))
-            .filter(|d| {
-                d.platform()
-                    .and_then(|p| {
-                        graph_configuration.target.map(|t| {
-                            match graph_configuration.cfgs {
-                                None => false,
-                                Some(cfgs) => p.matches(t, cfgs),
-                            }
-                        })
-                    })
-                    .unwrap_or(true)
+            .map(|d| (d, graph_configuration.matches_target(d.platform())))
+            .filter(|(_, matches_target)| {
+                *matches_target || graph_configuration.all_platforms
             });
 
         let dependency_package_id =
@@ -161,35 +419,42 @@ fn add_package_dependencies_to_graph<'a>(
                 None => raw_dependency_package_id,
             };
 
-        for dependency in dependency_iterator {
-            add_graph_node_if_not_present_and_edge(
-                dependency,
+        for (dependency, matches_target) in dependency_iterator {
+            let edge_index = add_graph_node_if_not_present_and_edge(
+                dependency.kind(),
                 dependency_package_id,
                 graph,
                 index,
                 pending_packages,
             );
+            if let (false, Some(platform)) =
+                (matches_target, dependency.platform())
+            {
+                other_platform_edges.insert(edge_index, platform.to_string());
+            }
         }
     }
-
-    Ok(())
 }
 
 fn build_graph_prerequisites<'a>(
     args: &'a Args,
     config_host: &'a InternedString,
 ) -> CargoResult<(ExtraDeps, Option<&'a str>)> {
-    let extra_deps = if args.all_deps {
+    // Dev-dependencies are not resolved with `--no-dev-deps`.
+    let dev_deps = !args.no_dev_deps;
+    let extra_deps = if args.all_deps && dev_deps {
         ExtraDeps::All
-    } else if args.build_deps {
+    } else if args.all_deps || args.build_deps {
         ExtraDeps::Build
-    } else if args.dev_deps {
+    } else if args.dev_deps && dev_deps {
         ExtraDeps::Dev
     } else {
         ExtraDeps::NoMore
     };
 
-    let target = if args.all_targets {
+    // With `--all-platforms` the target is still needed, to tell the
+    // dependencies of the platform built for from the others.
+    let target = if args.all_targets && !args.all_platforms {
         None
     } else {
         Some(args.target.as_deref().unwrap_or(&config_host))
@@ -198,11 +463,242 @@ fn build_graph_prerequisites<'a>(
     Ok((extra_deps, target))
 }
 
+/// Package ids with the `names`, at version 1.0.0 from a path source
+#[cfg(test)]
+pub fn create_package_ids(names: &[&str]) -> Vec<PackageId> {
+    let source_id =
+        cargo::core::SourceId::for_path(&std::env::current_dir().unwrap())
+            .unwrap();
+    names
+        .iter()
+        .map(|name| PackageId::new(*name, "1.0.0", source_id).unwrap())
+        .collect()
+}
+
+/// A graph of the `package_ids`, the `edges` go between the packages at the
+/// given indices
+#[cfg(test)]
+pub fn graph_from_edges(
+    package_ids: &[PackageId],
+    edges: &[(usize, usize, DepKind)],
+) -> Graph {
+    let mut graph = Graph {
+        graph: petgraph::Graph::new(),
+        nodes: HashMap::new(),
+        platforms: HashMap::new(),
+        target: String::new(),
+        members: HashSet::new(),
+        dev_only: HashSet::new(),
+    };
+    for package_id in package_ids {
+        graph.nodes.insert(
+            *package_id,
+            graph.graph.add_node(Node { id: *package_id }),
+        );
+    }
+    for (from, to, kind) in edges {
+        graph.graph.add_edge(
+            graph.nodes[&package_ids[*from]],
+            graph.nodes[&package_ids[*to]],
+            *kind,
+        );
+    }
+    graph
+}
+
 #[cfg(test)]
 mod graph_tests {
     use super::*;
-    use crate::format::Charset;
+    use crate::cli::{
+        get_cargo_metadata, get_registry, get_workspace, resolve,
+    };
+    use crate::format::{Charset, MarkerPosition};
+    use crate::rs_file::Resolver;
+    use crate::scan::UnsafeCriteria;
+    use cargo_geiger_serde::CountingStrategy;
     use rstest::*;
+    use std::env;
+
+    #[rstest]
+    fn dependency_counts_test() {
+        // A diamond below the root, with a dev-dependency cycle back to it.
+        let package_ids =
+            create_package_ids(&["root", "left", "right", "bottom"]);
+        let graph = graph_from_edges(
+            &package_ids,
+            &[
+                (0, 1, DepKind::Normal),
+                (0, 2, DepKind::Normal),
+                (0, 2, DepKind::Build),
+                (1, 3, DepKind::Normal),
+                (2, 3, DepKind::Normal),
+                (3, 0, DepKind::Development),
+            ],
+        );
+
+        let counts = dependency_counts(&graph);
+
+        let counts_of = |index: usize| {
+            let counts = counts[&package_ids[index]];
+            (counts.direct, counts.transitive)
+        };
+        assert_eq!(counts_of(0), (2, 3));
+        assert_eq!(counts_of(1), (1, 3));
+        assert_eq!(counts_of(2), (1, 3));
+        assert_eq!(counts_of(3), (1, 3));
+    }
+
+    #[rstest]
+    fn package_depths_test() {
+        // A diamond with a longer left side, and a shortcut from the root to
+        // the bottom.
+        let package_ids = create_package_ids(&[
+            "root",
+            "left",
+            "middle",
+            "right",
+            "bottom",
+            "unreachable",
+        ]);
+        let (root, left, middle, right, bottom, unreachable) = (
+            package_ids[0],
+            package_ids[1],
+            package_ids[2],
+            package_ids[3],
+            package_ids[4],
+            package_ids[5],
+        );
+        let graph = graph_from_edges(
+            &package_ids,
+            &[
+                (0, 1, DepKind::Normal),
+                (1, 2, DepKind::Normal),
+                (2, 4, DepKind::Normal),
+                (0, 3, DepKind::Normal),
+                (3, 4, DepKind::Normal),
+                (5, 4, DepKind::Normal),
+            ],
+        );
+
+        let depths = package_depths(&graph, root, EdgeDirection::Outgoing);
+        assert_eq!(depths.len(), 5);
+        assert_eq!(depths[&root], 0);
+        assert_eq!(depths[&left], 1);
+        assert_eq!(depths[&right], 1);
+        assert_eq!(depths[&middle], 2);
+        assert_eq!(depths[&bottom], 2);
+
+        let inverted_depths =
+            package_depths(&graph, bottom, EdgeDirection::Incoming);
+        assert_eq!(inverted_depths.len(), 6);
+        assert_eq!(inverted_depths[&bottom], 0);
+        assert_eq!(inverted_depths[&middle], 1);
+        assert_eq!(inverted_depths[&right], 1);
+        assert_eq!(inverted_depths[&unreachable], 1);
+        assert_eq!(inverted_depths[&left], 2);
+        assert_eq!(inverted_depths[&root], 2);
+    }
+
+    #[rstest]
+    fn dev_only_packages_test() {
+        let package_ids = create_package_ids(&[
+            "root",
+            "dependency",
+            "root_test_helper",
+            "test_helper",
+            "test_helper_dependency",
+            "shared",
+        ]);
+        let graph = graph_from_edges(
+            &package_ids,
+            &[
+                (0, 1, DepKind::Normal),
+                (0, 2, DepKind::Development),
+                (1, 3, DepKind::Development),
+                (1, 5, DepKind::Build),
+                (3, 4, DepKind::Normal),
+                (3, 5, DepKind::Normal),
+            ],
+        );
+
+        // The test helper of the dependency and what only it depends on.
+        assert_eq!(
+            dev_only_packages(&graph, package_ids[0]),
+            vec![package_ids[3], package_ids[4]].into_iter().collect()
+        );
+    }
+
+    #[rstest(
+        input_test_crate,
+        case("test22_package_with_platform_deps"),
+        case("test29_package_with_diamond_dependencies")
+    )]
+    fn build_graph_from_metadata_test(input_test_crate: &str) {
+        let mut args = create_args();
+        args.all_platforms = true;
+        let manifest_path = env::current_dir()
+            .unwrap()
+            .join("../test_crates")
+            .join(input_test_crate)
+            .join("Cargo.toml")
+            .canonicalize()
+            .unwrap();
+        let config = Config::default().unwrap();
+        let workspace =
+            get_workspace(&config, Some(manifest_path.clone())).unwrap();
+        let package = workspace.current().unwrap();
+        let mut registry = get_registry(&config, &package, &workspace).unwrap();
+        let (package_set, resolve) = resolve(
+            package.package_id(),
+            &mut registry,
+            &workspace,
+            &args.features,
+            args.all_features,
+            args.no_default_features,
+            !args.no_dev_deps,
+        )
+        .unwrap();
+        let metadata =
+            get_cargo_metadata(&args, &config, Some(manifest_path)).unwrap();
+
+        let resolve_graph = build_graph(
+            &args,
+            &config,
+            &resolve,
+            &package_set,
+            package.package_id(),
+            &workspace,
+        )
+        .unwrap();
+        let metadata_graph = build_graph_from_metadata(
+            &args,
+            &config,
+            &metadata,
+            package.package_id(),
+            &workspace,
+        )
+        .unwrap();
+
+        assert!(resolve_graph.nodes.len() > 1);
+        assert_eq!(node_set(&metadata_graph), node_set(&resolve_graph));
+        assert_eq!(edge_set(&metadata_graph), edge_set(&resolve_graph));
+        assert_eq!(metadata_graph.platforms, resolve_graph.platforms);
+        assert_eq!(metadata_graph.dev_only, resolve_graph.dev_only);
+    }
+
+    #[rstest(
+        input_backend,
+        expected_backend,
+        case("resolve", Ok(GraphBackend::Resolve)),
+        case("metadata", Ok(GraphBackend::Metadata)),
+        case("krates", Err("invalid backend"))
+    )]
+    fn graph_backend_from_str_test(
+        input_backend: &str,
+        expected_backend: Result<GraphBackend, &'static str>,
+    ) {
+        assert_eq!(GraphBackend::from_str(input_backend), expected_backend);
+    }
 
     #[rstest(
         input_extra_deps,
@@ -257,13 +753,47 @@ mod graph_tests {
         assert_eq!(extra_deps, expected_extra_deps);
     }
 
+    #[rstest(
+        input_all_deps,
+        input_build_deps,
+        input_dev_deps,
+        expected_extra_deps,
+        case(true, false, false, ExtraDeps::Build),
+        case(false, true, false, ExtraDeps::Build),
+        case(false, false, true, ExtraDeps::NoMore),
+        case(false, false, false, ExtraDeps::NoMore)
+    )]
+    fn build_graph_prerequisites_extra_deps_without_dev_deps_test(
+        input_all_deps: bool,
+        input_build_deps: bool,
+        input_dev_deps: bool,
+        expected_extra_deps: ExtraDeps,
+    ) {
+        let mut args = create_args();
+        args.all_deps = input_all_deps;
+        args.build_deps = input_build_deps;
+        args.dev_deps = input_dev_deps;
+        args.no_dev_deps = true;
+
+        let config_host = InternedString::new("config_host");
+
+        let result = build_graph_prerequisites(&args, &config_host);
+
+        assert!(result.is_ok());
+        let (extra_deps, _) = result.unwrap();
+        assert_eq!(extra_deps, expected_extra_deps);
+    }
+
     #[rstest(
         input_all_targets,
+        input_all_platforms,
         input_target,
         expected_target,
-        case(true, None, None),
-        case(false, None, Some("default_config_host")),
+        case(true, false, None, None),
+        case(true, true, None, Some("default_config_host")),
+        case(false, false, None, Some("default_config_host")),
         case(
+            false,
             false,
             Some(String::from("provided_config_host")),
             Some("provided_config_host")
@@ -271,12 +801,14 @@ mod graph_tests {
     )]
     fn build_graph_prerequisites_all_targets_test(
         input_all_targets: bool,
+        input_all_platforms: bool,
         input_target: Option<String>,
         expected_target: Option<&str>,
     ) {
         let mut args = create_args();
 
         args.all_targets = input_all_targets;
+        args.all_platforms = input_all_platforms;
         args.target = input_target;
 
         let config_host = InternedString::new("default_config_host");
@@ -290,36 +822,91 @@ mod graph_tests {
         assert_eq!(target, expected_target);
     }
 
+    fn node_set(graph: &Graph) -> HashSet<PackageId> {
+        graph.nodes.keys().cloned().collect()
+    }
+
+    fn edge_set(graph: &Graph) -> HashSet<(PackageId, PackageId, DepKind)> {
+        graph
+            .graph
+            .edge_references()
+            .map(|edge| {
+                (
+                    graph.graph[edge.source()].id,
+                    graph.graph[edge.target()].id,
+                    *edge.weight(),
+                )
+            })
+            .collect()
+    }
+
     fn create_args() -> Args {
         Args {
             all: false,
             all_deps: false,
             all_features: false,
+            all_platforms: false,
+            all_target_kinds: false,
             all_targets: false,
+            backend: GraphBackend::Resolve,
             build_deps: false,
             charset: Charset::Ascii,
+            check_lock: None,
+            cli_config: vec![],
             color: None,
+            counting_strategy: CountingStrategy::Geiger,
             dev_deps: false,
-            features: None,
+            exclude: Vec::new(),
+            explain: None,
+            features: Vec::new(),
             forbid_only: false,
             format: "".to_string(),
             frozen: false,
+            generated_markers: vec![],
+            harness_cfgs: None,
             help: false,
+            hyperlinks: false,
             include_tests: false,
             invert: false,
+            keep_going: false,
             locked: false,
-            manifest_path: None,
+            manifest_paths: Vec::new(),
+            marker_position: MarkerPosition::Left,
+            max_file_size: None,
+            members_only: false,
             no_default_features: false,
+            no_dev_deps: false,
             no_indent: false,
+            no_legend: false,
+            normalize_output: false,
             offline: false,
+            output: None,
+            output_dir: None,
             package: None,
             prefix_depth: false,
+            print_schema: false,
+            progress_json: false,
+            prune_output: false,
+            published_crate: None,
             quiet: false,
+            report_file_list: false,
+            report_modules: false,
+            resolver: Resolver::Executor,
+            reuse_build: false,
+            reuse_report: None,
+            show_dep_counts: false,
+            show_totals: false,
+            sort: None,
+            split_generated: false,
+            split_local_paths: false,
             target: None,
+            timings: false,
+            unsafe_criteria: UnsafeCriteria::default(),
             unstable_flags: vec![],
             verbose: 0,
             version: false,
             output_format: None,
+            watched_calls: vec![],
         }
     }
 }
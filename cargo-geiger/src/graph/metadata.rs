@@ -0,0 +1,129 @@
+use super::{
+    add_graph_node_if_not_present_and_edge, build_graph_prerequisites,
+    dev_only_packages, new_graph_with_root, other_platform_packages, Graph,
+    GraphConfiguration,
+};
+use crate::args::Args;
+use crate::cli::get_cfgs;
+
+use cargo::core::dependency::DepKind;
+use cargo::core::{PackageId, SourceId, Workspace};
+use cargo::util::CargoResult;
+use cargo::Config;
+use cargo_metadata::{DependencyKind, Metadata};
+use cargo_platform::Platform;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Builds the same graph as `build_graph` from the resolve printed by
+/// `cargo metadata`, without resolving anything in-process. The package ids
+/// are the ones cargo gives the packages, the rest of the scan works with
+/// either graph.
+pub fn build_graph_from_metadata(
+    args: &Args,
+    config: &Config,
+    metadata: &Metadata,
+    root_package_id: PackageId,
+    workspace: &Workspace,
+) -> CargoResult<Graph> {
+    let config_host = config.load_global_rustc(Some(&workspace))?.host;
+    let (extra_deps, target) = build_graph_prerequisites(args, &config_host)?;
+    let cfgs = get_cfgs(config, &args.target, &workspace)?;
+    let graph_configuration = GraphConfiguration {
+        target,
+        cfgs: cfgs.as_deref(),
+        extra_deps,
+        all_platforms: args.all_platforms,
+    };
+
+    let package_ids = metadata
+        .packages
+        .iter()
+        .map(|package| Ok((&package.id, to_package_id(package)?)))
+        .collect::<CargoResult<HashMap<_, _>>>()?;
+    let resolve = metadata.resolve.as_ref().ok_or_else(|| {
+        anyhow::anyhow!("`cargo metadata` printed no dependency graph")
+    })?;
+    let resolve_nodes = resolve
+        .nodes
+        .iter()
+        .map(|node| (package_ids[&node.id], node))
+        .collect::<HashMap<_, _>>();
+
+    let mut graph =
+        new_graph_with_root(args, &config_host, workspace, root_package_id);
+    let mut pending_packages = vec![root_package_id];
+    let mut other_platform_edges = HashMap::new();
+
+    while let Some(package_id) = pending_packages.pop() {
+        let index = graph.nodes[&package_id];
+        let resolve_node = match resolve_nodes.get(&package_id) {
+            Some(resolve_node) => resolve_node,
+            None => continue,
+        };
+        for dependency in &resolve_node.deps {
+            let dependency_package_id = package_ids[&dependency.pkg];
+            for dep_kind_info in &dependency.dep_kinds {
+                let kind = match to_dep_kind(dep_kind_info.kind) {
+                    Some(kind) => kind,
+                    None => continue,
+                };
+                if !graph_configuration.extra_deps.allows(kind) {
+                    continue;
+                }
+                let platform = dep_kind_info
+                    .target
+                    .as_ref()
+                    .map(|target| Platform::from_str(&target.repr))
+                    .transpose()?;
+                let matches_target =
+                    graph_configuration.matches_target(platform.as_ref());
+                if !matches_target && !graph_configuration.all_platforms {
+                    continue;
+                }
+                let edge_index = add_graph_node_if_not_present_and_edge(
+                    kind,
+                    dependency_package_id,
+                    &mut graph,
+                    index,
+                    &mut pending_packages,
+                );
+                if let (false, Some(platform)) = (matches_target, platform) {
+                    other_platform_edges
+                        .insert(edge_index, platform.to_string());
+                }
+            }
+        }
+    }
+
+    graph.platforms =
+        other_platform_packages(&graph, root_package_id, &other_platform_edges);
+    graph.dev_only = dev_only_packages(&graph, root_package_id);
+
+    Ok(graph)
+}
+
+/// The id cargo gives the package in its own resolve. Path packages have no
+/// source in the metadata, their source is the directory of the manifest.
+fn to_package_id(package: &cargo_metadata::Package) -> CargoResult<PackageId> {
+    let source_id = match &package.source {
+        Some(source) => SourceId::from_url(&source.repr)?,
+        None => SourceId::for_path(package.manifest_path.parent().unwrap())?,
+    };
+    PackageId::new(
+        package.name.as_str(),
+        package.version.to_string().as_str(),
+        source_id,
+    )
+}
+
+/// `None` for kinds of dependencies newer than the metadata format known to
+/// cargo-geiger.
+fn to_dep_kind(dependency_kind: DependencyKind) -> Option<DepKind> {
+    match dependency_kind {
+        DependencyKind::Normal => Some(DepKind::Normal),
+        DependencyKind::Development => Some(DepKind::Development),
+        DependencyKind::Build => Some(DepKind::Build),
+        _ => None,
+    }
+}
@@ -1,19 +1,12 @@
 use cargo::core::{Package, PackageId, PackageSet};
 use cargo_metadata::Metadata;
 use krates::Krates;
-use std::path::PathBuf;
 
 pub struct CargoMetadataParameters<'a> {
     pub krates: &'a Krates,
     pub metadata: &'a Metadata,
 }
 
-impl GetRoot for cargo_metadata::Package {
-    fn get_root(&self) -> PathBuf {
-        self.manifest_path.parent().unwrap().to_path_buf()
-    }
-}
-
 impl ToCargoMetadataPackage for Package {
     fn to_cargo_metadata_package(
         &self,
@@ -78,10 +71,6 @@ impl ToPackageId for cargo_metadata::PackageId {
     }
 }
 
-pub trait GetRoot {
-    fn get_root(&self) -> PathBuf;
-}
-
 pub trait ToCargoMetadataPackage {
     fn to_cargo_metadata_package(
         &self,
@@ -136,11 +125,12 @@ mod krates_utils_tests {
         let manifest_path: Option<PathBuf> = None;
         let workspace = get_workspace(&config, manifest_path).unwrap();
         let package = workspace.current().unwrap();
-        let mut registry = get_registry(&config, &package).unwrap();
+        let mut registry = get_registry(&config, &package, &workspace).unwrap();
 
         let features: Vec<String> = vec![];
         let all_features = false;
         let no_default_features = false;
+        let dev_deps = true;
 
         let (package_set, _) = resolve(
             package.package_id(),
@@ -149,6 +139,7 @@ mod krates_utils_tests {
             &features,
             all_features,
             no_default_features,
+            dev_deps,
         )
         .unwrap();
 
@@ -0,0 +1,404 @@
+//! A fingerprint of the resolved package set, used to tell if a report still
+//! corresponds to the `Cargo.lock` of a workspace.
+
+use crate::scan::from_cargo_package_id;
+
+use cargo::core::{Resolve, Workspace};
+use cargo::ops;
+use cargo::util::{paths, Sha256};
+use cargo::{CliError, CliResult};
+use cargo_geiger_serde::{
+    CountingStrategy, LockedPackage, ReportMetadata, Source, SCHEMA_VERSION,
+};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Both report kinds store the metadata under the same key, nothing else is
+/// needed to check the lock.
+#[derive(Deserialize)]
+struct ReportWithMetadata {
+    #[serde(default)]
+    metadata: ReportMetadata,
+}
+
+/// The resolved packages of the workspace differ from the ones of the report
+/// given with `--check-lock`. The changed packages are printed before.
+#[derive(Debug)]
+pub struct LockMismatchError {
+    pub changed_package_count: usize,
+}
+
+impl Error for LockMismatchError {}
+
+impl fmt::Display for LockMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the report was made from a different Cargo.lock, {} package {} \
+             changed",
+            self.changed_package_count,
+            if self.changed_package_count == 1 {
+                "line"
+            } else {
+                "lines"
+            }
+        )
+    }
+}
+
+/// With `--locked` or `--frozen`, the resolve differs from `Cargo.lock`. The
+/// differences are lines of `unlocked_diff`.
+#[derive(Debug)]
+pub struct UnlockedResolveError {
+    pub differences: Vec<String>,
+}
+
+impl Error for UnlockedResolveError {}
+
+impl fmt::Display for UnlockedResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the resolved dependencies differ from the lock file, but \
+             --locked or --frozen was passed:"
+        )?;
+        for difference in &self.differences {
+            write!(f, "\n    {}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `Cargo.lock` of the workspace can't be used with `--locked` or
+/// `--frozen`.
+#[derive(Debug)]
+pub enum LockfileError {
+    /// The workspace has no `Cargo.lock`.
+    Missing(PathBuf),
+
+    /// The `Cargo.lock` exists but can't be read.
+    Unreadable(io::Error, PathBuf),
+}
+
+impl Error for LockfileError {}
+
+impl fmt::Display for LockfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockfileError::Missing(path) => write!(
+                f,
+                "the lock file `{}` does not exist, but --locked or --frozen \
+                 was passed",
+                path.display()
+            ),
+            LockfileError::Unreadable(error, path) => write!(
+                f,
+                "failed to read the lock file `{}`: {}",
+                path.display(),
+                error
+            ),
+        }
+    }
+}
+
+/// With `--locked` or `--frozen` the `Cargo.lock` of the workspace is checked
+/// before anything is resolved. Without it cargo only fails once the
+/// registry has been loaded, with a less helpful error.
+pub fn check_lockfile_is_readable(workspace: &Workspace) -> CliResult {
+    let path = workspace.root().join("Cargo.lock");
+    let error = match fs::read(&path) {
+        Ok(_) => return Ok(()),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            LockfileError::Missing(path)
+        }
+        Err(error) => LockfileError::Unreadable(error, path),
+    };
+    Err(CliError::new(anyhow::Error::new(error), 1))
+}
+
+/// Compares the lock fingerprint stored in a JSON report with the one of the
+/// current resolve. Prints the changed packages and fails on a mismatch.
+pub fn check_lock(report_path: &Path, resolve: &Resolve) -> CliResult {
+    let report = paths::read(report_path)?;
+    let report = serde_json::from_str::<ReportWithMetadata>(&report)
+        .map_err(|e| CliError::new(anyhow::Error::new(e), 1))?;
+    let current = construct_report_metadata(resolve);
+    if report.metadata.lock_fingerprint == current.lock_fingerprint {
+        return Ok(());
+    }
+    let diff_lines =
+        lock_diff(&report.metadata.locked_packages, &current.locked_packages);
+    for diff_line in &diff_lines {
+        println!("{}", diff_line);
+    }
+    Err(CliError::new(
+        anyhow::Error::new(LockMismatchError {
+            changed_package_count: diff_lines.len(),
+        }),
+        1,
+    ))
+}
+
+/// With `--locked`, every resolved package must be in `Cargo.lock` as it is,
+/// a scan of anything else doesn't audit the locked dependencies. The resolve
+/// may be a subset of the lock file, for example when scanning a single
+/// workspace member.
+pub fn check_resolve_is_locked(
+    workspace: &Workspace,
+    resolve: &Resolve,
+) -> CliResult {
+    let locked = match ops::load_pkg_lockfile(workspace)? {
+        Some(previous_resolve) => {
+            construct_report_metadata(&previous_resolve).locked_packages
+        }
+        None => Vec::new(),
+    };
+    let resolved = construct_report_metadata(resolve).locked_packages;
+    let differences = unlocked_diff(&locked, &resolved);
+    if differences.is_empty() {
+        return Ok(());
+    }
+    Err(CliError::new(
+        anyhow::Error::new(UnlockedResolveError { differences }),
+        1,
+    ))
+}
+
+/// The metadata of a report of `resolve`, the scan sets the counting strategy
+/// it used.
+pub fn construct_report_metadata(resolve: &Resolve) -> ReportMetadata {
+    let mut locked_packages = resolve
+        .iter()
+        .map(|package_id| LockedPackage {
+            id: from_cargo_package_id(package_id),
+            checksum: resolve.checksums().get(&package_id).cloned().flatten(),
+        })
+        .collect::<Vec<_>>();
+    locked_packages.sort();
+    ReportMetadata {
+        schema_version: SCHEMA_VERSION,
+        lock_fingerprint: lock_fingerprint(&locked_packages),
+        locked_packages,
+        counting_strategy: CountingStrategy::Geiger,
+        timings: Vec::new(),
+    }
+}
+
+/// The lines are sorted before hashing, which makes the fingerprint
+/// independent of the iteration order of the `Resolve`.
+pub fn lock_fingerprint(locked_packages: &[LockedPackage]) -> String {
+    let mut lines = locked_packages
+        .iter()
+        .map(fingerprint_line)
+        .collect::<Vec<_>>();
+    lines.sort();
+    let mut sha256 = Sha256::new();
+    for line in lines {
+        sha256.update(line.as_bytes()).update(b"\n");
+    }
+    sha256.finish_hex()
+}
+
+/// Lists the packages that differ between two resolved package sets, `-` for
+/// packages only in `old` and `+` for packages only in `new`.
+pub fn lock_diff(old: &[LockedPackage], new: &[LockedPackage]) -> Vec<String> {
+    let old_lines = old.iter().map(fingerprint_line).collect::<BTreeSet<_>>();
+    let new_lines = new.iter().map(fingerprint_line).collect::<BTreeSet<_>>();
+    let mut diff = old_lines
+        .difference(&new_lines)
+        .map(|line| ("-", line))
+        .chain(new_lines.difference(&old_lines).map(|line| ("+", line)))
+        .collect::<Vec<_>>();
+    // Sorting on the line keeps the old and new version of a package together.
+    diff.sort_by(|(_, a), (_, b)| a.cmp(b));
+    diff.into_iter()
+        .map(|(sign, line)| format!("{} {}", sign, line))
+        .collect()
+}
+
+/// Lists the resolved packages that are not in the lock file with `+`, along
+/// with the locked packages of the same name with `-`. Locked packages that
+/// were not resolved at all are left out.
+fn unlocked_diff(
+    locked: &[LockedPackage],
+    resolved: &[LockedPackage],
+) -> Vec<String> {
+    let lines = |packages: &[LockedPackage]| {
+        packages
+            .iter()
+            .map(fingerprint_line)
+            .collect::<BTreeSet<_>>()
+    };
+    let locked_lines = lines(locked);
+    let resolved_lines = lines(resolved);
+    let unlocked = resolved
+        .iter()
+        .filter(|p| !locked_lines.contains(&fingerprint_line(p)))
+        .cloned()
+        .collect::<Vec<_>>();
+    let unlocked_names =
+        unlocked.iter().map(|p| &p.id.name).collect::<BTreeSet<_>>();
+    let replaced = locked
+        .iter()
+        .filter(|p| unlocked_names.contains(&p.id.name))
+        .filter(|p| !resolved_lines.contains(&fingerprint_line(p)))
+        .cloned()
+        .collect::<Vec<_>>();
+    lock_diff(&replaced, &unlocked)
+}
+
+/// The location of path dependencies is left out, to make the fingerprint
+/// independent of where the workspace is checked out.
+fn fingerprint_line(locked_package: &LockedPackage) -> String {
+    let id = &locked_package.id;
+    let source = match &id.source {
+        Source::Git { url, rev } => format!("git+{}#{}", url, rev),
+        Source::Registry { url, .. } => format!("registry+{}", url),
+        Source::Path(_) => String::from("path"),
+    };
+    format!(
+        "{} {} {} {}",
+        id.name,
+        id.version,
+        source,
+        locked_package.checksum.as_deref().unwrap_or("-")
+    )
+}
+
+#[cfg(test)]
+mod lockfile_tests {
+    use super::*;
+
+    use cargo_geiger_serde::PackageId;
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest]
+    fn lock_fingerprint_is_independent_of_order_test() {
+        let mut locked_packages = vec![
+            registry_package("a", "1.0.0", "aaaa"),
+            registry_package("b", "2.0.0", "bbbb"),
+            path_package("c", "/workspace/c"),
+        ];
+        let fingerprint = lock_fingerprint(&locked_packages);
+
+        locked_packages.reverse();
+
+        assert_eq!(lock_fingerprint(&locked_packages), fingerprint);
+    }
+
+    #[rstest]
+    fn lock_fingerprint_ignores_path_dependency_location_test() {
+        assert_eq!(
+            lock_fingerprint(&[path_package("c", "/workspace/c")]),
+            lock_fingerprint(&[path_package("c", "/elsewhere/c")])
+        );
+    }
+
+    #[rstest(
+        input_other,
+        case(registry_package("a", "1.0.1", "aaaa")),
+        case(registry_package("a", "1.0.0", "changed")),
+        case(path_package("a", "/workspace/a"))
+    )]
+    fn lock_fingerprint_changes_with_package_test(input_other: LockedPackage) {
+        assert_ne!(
+            lock_fingerprint(&[registry_package("a", "1.0.0", "aaaa")]),
+            lock_fingerprint(&[input_other])
+        );
+    }
+
+    #[rstest]
+    fn lock_diff_test() {
+        let old = vec![
+            registry_package("a", "1.0.0", "aaaa"),
+            registry_package("b", "2.0.0", "bbbb"),
+        ];
+        let new = vec![
+            registry_package("a", "1.1.0", "cccc"),
+            registry_package("b", "2.0.0", "bbbb"),
+            path_package("c", "/workspace/c"),
+        ];
+
+        assert_eq!(
+            lock_diff(&old, &new),
+            vec![
+                "- a 1.0.0 registry+https://github.com/rust-lang/crates.io-index aaaa",
+                "+ a 1.1.0 registry+https://github.com/rust-lang/crates.io-index cccc",
+                "+ c 0.1.0 path -",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn unlocked_diff_test() {
+        let locked = vec![
+            registry_package("a", "1.0.0", "aaaa"),
+            registry_package("b", "2.0.0", "bbbb"),
+            registry_package("d", "4.0.0", "dddd"),
+        ];
+        let resolved = vec![
+            registry_package("a", "1.1.0", "cccc"),
+            registry_package("b", "2.0.0", "bbbb"),
+            path_package("c", "/workspace/c"),
+        ];
+
+        assert_eq!(
+            unlocked_diff(&locked, &resolved),
+            vec![
+                "- a 1.0.0 registry+https://github.com/rust-lang/crates.io-index aaaa",
+                "+ a 1.1.0 registry+https://github.com/rust-lang/crates.io-index cccc",
+                "+ c 0.1.0 path -",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn unlocked_diff_of_subset_is_empty_test() {
+        let locked = vec![
+            registry_package("a", "1.0.0", "aaaa"),
+            path_package("b", "/workspace/b"),
+        ];
+        let resolved = vec![path_package("b", "/elsewhere/b")];
+
+        assert!(unlocked_diff(&locked, &resolved).is_empty());
+    }
+
+    fn registry_package(
+        name: &str,
+        version: &str,
+        checksum: &str,
+    ) -> LockedPackage {
+        LockedPackage {
+            id: PackageId {
+                name: name.into(),
+                version: Version::parse(version).unwrap(),
+                source: Source::Registry {
+                    name: "crates.io".into(),
+                    url: Url::parse(
+                        "https://github.com/rust-lang/crates.io-index",
+                    )
+                    .unwrap(),
+                },
+            },
+            checksum: Some(checksum.into()),
+        }
+    }
+
+    fn path_package(name: &str, path: &str) -> LockedPackage {
+        LockedPackage {
+            id: PackageId {
+                name: name.into(),
+                version: Version::new(0, 1, 0),
+                source: Source::Path(Url::from_file_path(path).unwrap()),
+            },
+            checksum: None,
+        }
+    }
+}
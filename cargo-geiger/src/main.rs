@@ -12,23 +12,39 @@ extern crate strum_macros;
 
 mod args;
 mod cli;
+mod errors;
 mod format;
 mod graph;
 mod krates_utils;
+mod lockfile;
+mod progress;
 mod rs_file;
 mod scan;
+mod timings;
 mod tree;
 
 use crate::args::{Args, HELP};
 use crate::cli::{
-    get_cargo_metadata, get_krates, get_registry, get_workspace, resolve,
+    allow_unstable_flags, current_package, get_cargo_metadata, get_krates,
+    get_member_manifest_paths, get_registry, get_workspace, resolve,
 };
-use crate::graph::build_graph;
-use crate::scan::scan;
+use crate::errors::render_cli_error;
+use crate::format::print_config::{override_colors, PrintConfig};
+use crate::graph::{build_graph, build_graph_from_metadata, GraphBackend};
+use crate::lockfile::{
+    check_lock, check_lockfile_is_readable, check_resolve_is_locked,
+};
+use crate::progress::ProgressEvent;
+use crate::scan::{
+    scan, scan_published_crate, MergedScan, WorkspaceParameters,
+};
+use crate::timings::{Phase, Timings};
 
 use crate::krates_utils::CargoMetadataParameters;
-use cargo::core::shell::{ColorChoice, Shell};
-use cargo::{CliResult, Config};
+use cargo::core::shell::Shell;
+use cargo::{CliError, CliResult, Config};
+use cargo_geiger_serde::safety_report_schema;
+use std::path::PathBuf;
 
 const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 
@@ -41,6 +57,15 @@ fn real_main(args: &Args, config: &mut Config) -> CliResult {
         println!("{}", HELP);
         return Ok(());
     }
+    if args.print_schema {
+        let schema = safety_report_schema();
+        println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+        return Ok(());
+    }
+
+    if !args.unstable_flags.is_empty() {
+        allow_unstable_flags(config, &args.unstable_flags)?;
+    }
 
     let target_dir = None; // Doesn't add any value for cargo-geiger.
     config.configure(
@@ -52,69 +77,158 @@ fn real_main(args: &Args, config: &mut Config) -> CliResult {
         args.offline,
         &target_dir,
         &args.unstable_flags,
-        &[], // Some cargo API change, TODO: Look closer at this later.
+        &args.cli_config,
     )?;
 
-    match config.shell().color_choice() {
-        ColorChoice::Always => colored::control::set_override(true),
-        ColorChoice::Never => colored::control::set_override(false),
-        ColorChoice::CargoAuto => {}
-    }
+    override_colors(config.shell().color_choice());
+    let print_config = PrintConfig::new(args)?;
 
-    let cargo_metadata = get_cargo_metadata(args, config)?;
-    let krates = get_krates(&cargo_metadata)?;
+    // A published crate is scanned on its own, without a workspace.
+    if let Some(crate_spec) = &args.published_crate {
+        return scan_published_crate(args, config, &print_config, crate_spec);
+    }
 
-    let cargo_metadata_parameters = CargoMetadataParameters {
-        metadata: &cargo_metadata,
-        krates: &krates,
+    // The durations differ from run to run, normalized output leaves them out.
+    let timings = Timings::new(args.timings && !print_config.normalize_output);
+    let manifest_paths = get_member_manifest_paths(args, config)?;
+    let result = if manifest_paths.len() > 1 {
+        scan_manifests(args, config, &print_config, &manifest_paths, &timings)
+    } else {
+        scan_manifest(
+            args,
+            config,
+            &print_config,
+            manifest_paths.first().cloned(),
+            &timings,
+            None,
+        )
     };
+    timings.end_phase(Phase::Render);
+    timings.print();
+    result
+}
 
-    let workspace = get_workspace(config, args.manifest_path.clone())?;
-    let package = workspace.current()?;
-    let mut registry = get_registry(config, &package)?;
-    let features = args
-        .features
-        .as_ref()
-        .cloned()
-        .unwrap_or_else(String::new)
-        .split(' ')
-        .map(str::to_owned)
-        .collect::<Vec<String>>();
+/// Scans the workspace members of all `--manifest-path`s, or of the workspace
+/// of the current directory, and merges their results.
+fn scan_manifests(
+    args: &Args,
+    config: &Config,
+    print_config: &PrintConfig,
+    manifest_paths: &[PathBuf],
+    timings: &Timings,
+) -> CliResult {
+    let mut merged_scan = MergedScan::new(args)?;
+    for manifest_path in manifest_paths {
+        scan_manifest(
+            args,
+            config,
+            print_config,
+            Some(manifest_path.clone()),
+            timings,
+            Some(&mut merged_scan),
+        )?;
+    }
+    merged_scan.finish(args)
+}
 
+/// Resolves and scans the workspace of `manifest_path`, or the one of the
+/// current directory without it. With `merged_scan` the results are added to
+/// the ones of the other workspaces instead.
+fn scan_manifest(
+    args: &Args,
+    config: &Config,
+    print_config: &PrintConfig,
+    manifest_path: Option<PathBuf>,
+    timings: &Timings,
+    merged_scan: Option<&mut MergedScan>,
+) -> CliResult {
+    let workspace = get_workspace(config, manifest_path.clone())?;
+    if args.locked || args.frozen {
+        check_lockfile_is_readable(&workspace)?;
+    }
+    let package = current_package(&workspace)?;
+    let mut registry = get_registry(config, &package, &workspace)?;
     let (package_set, resolve) = resolve(
         package.package_id(),
         &mut registry,
         &workspace,
-        &features,
+        &args.features,
         args.all_features,
         args.no_default_features,
+        !args.no_dev_deps,
     )?;
+    // Checked before anything is downloaded.
+    if args.locked || args.frozen {
+        check_resolve_is_locked(&workspace, &resolve)?;
+    }
+
     let package_ids = package_set.package_ids().collect::<Vec<_>>();
     let package_set = registry.get(&package_ids)?;
 
+    if let Some(report_path) = &args.check_lock {
+        return check_lock(report_path, &resolve);
+    }
+
+    // Fetched after the lock check, `cargo metadata` is passed the same
+    // `--locked` and `--frozen` flags but would fail without the differences.
+    let cargo_metadata =
+        get_cargo_metadata(args, config, manifest_path.clone())?;
+    let krates = get_krates(&cargo_metadata)?;
+
+    let cargo_metadata_parameters = CargoMetadataParameters {
+        metadata: &cargo_metadata,
+        krates: &krates,
+    };
+
     let root_package_id = match args.package {
         Some(ref pkg) => resolve.query(pkg)?,
         None => package.package_id(),
     };
 
-    let graph = build_graph(
-        args,
-        config,
-        &resolve,
-        &package_set,
-        package.package_id(),
-        &workspace,
-    )?;
+    let graph = match args.backend {
+        GraphBackend::Resolve => build_graph(
+            args,
+            config,
+            &resolve,
+            &package_set,
+            package.package_id(),
+            &workspace,
+        )?,
+        GraphBackend::Metadata => build_graph_from_metadata(
+            args,
+            config,
+            &cargo_metadata,
+            package.package_id(),
+            &workspace,
+        )?,
+    };
+    timings.end_phase(Phase::Resolve);
+    if args.progress_json {
+        ProgressEvent::ResolveDone {
+            packages: package_ids.len(),
+        }
+        .emit();
+    }
 
-    scan(
-        args,
-        &cargo_metadata_parameters,
-        config,
-        &graph,
-        &package_set,
+    let workspace_parameters = WorkspaceParameters {
+        cargo_metadata_parameters: &cargo_metadata_parameters,
+        graph: &graph,
+        package_set: &package_set,
+        resolve: &resolve,
         root_package_id,
-        &workspace,
-    )
+        workspace: &workspace,
+    };
+    match (merged_scan, manifest_path) {
+        (Some(merged_scan), Some(manifest_path)) => merged_scan.scan_workspace(
+            args,
+            config,
+            print_config,
+            manifest_path,
+            timings,
+            &workspace_parameters,
+        ),
+        _ => scan(args, config, print_config, timings, &workspace_parameters),
+    }
 }
 
 fn main() {
@@ -126,9 +240,20 @@ fn main() {
             cargo::exit_with_error(e.into(), &mut shell)
         }
     };
-    let args = Args::parse_args(pico_args::Arguments::from_env()).unwrap();
+    let args = match Args::parse_args_from_argument_vector(
+        std::env::args_os().collect(),
+    ) {
+        Ok(args) => args,
+        Err(e) => {
+            let mut shell = Shell::new();
+            cargo::exit_with_error(
+                CliError::new(anyhow::anyhow!(e.to_string()), 1),
+                &mut shell,
+            )
+        }
+    };
     if let Err(e) = real_main(&args, &mut config) {
         let mut shell = Shell::new();
-        cargo::exit_with_error(e, &mut shell)
+        cargo::exit_with_error(render_cli_error(e), &mut shell)
     }
 }
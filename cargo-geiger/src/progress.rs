@@ -0,0 +1,87 @@
+use cargo_geiger_serde::CounterBlock;
+use serde::Serialize;
+
+/// An event of `--progress-json`, printed as a line of JSON on stderr. The
+/// report and the table go to stdout, so the events never end up in them.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// The dependencies are resolved, `packages` are about to be built and
+    /// scanned.
+    ResolveDone { packages: usize },
+    /// All `.rs` files of a package were scanned.
+    PackageScanned { package: String, files: usize },
+    /// The last event of a workspace, with the totals of all its packages.
+    /// The totals are boxed, they are much larger than the other variants.
+    ScanDone {
+        packages: usize,
+        files: usize,
+        used: Box<CounterBlock>,
+        unused: Box<CounterBlock>,
+        warnings: usize,
+    },
+}
+
+impl ProgressEvent {
+    pub fn emit(&self) {
+        eprintln!("{}", serde_json::to_string(self).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_event,
+        expected_json,
+        case(
+            ProgressEvent::ResolveDone { packages: 3 },
+            r#"{"event":"resolve_done","packages":3}"#
+        ),
+        case(
+            ProgressEvent::PackageScanned {
+                package: String::from("foo 1.2.3"),
+                files: 4,
+            },
+            r#"{"event":"package_scanned","package":"foo 1.2.3","files":4}"#
+        ),
+        case(
+            ProgressEvent::ScanDone {
+                packages: 2,
+                files: 5,
+                used: Box::new(CounterBlock::default()),
+                unused: Box::new(CounterBlock::default()),
+                warnings: 1,
+            },
+            concat!(
+                r#"{"event":"scan_done","packages":2,"files":5,"#,
+                r#""used":{"functions":{"safe":0,"unsafe_":0},"#,
+                r#""exprs":{"safe":0,"unsafe_":0},"#,
+                r#""item_impls":{"safe":0,"unsafe_":0},"#,
+                r#""item_traits":{"safe":0,"unsafe_":0},"#,
+                r#""methods":{"safe":0,"unsafe_":0},"#,
+                r#""foreign_items":{"safe":0,"unsafe_":0},"#,
+                r#""transmutes":{"safe":0,"unsafe_":0},"#,
+                r#""const_unsafe":{"safe":0,"unsafe_":0}},"#,
+                r#""unused":{"functions":{"safe":0,"unsafe_":0},"#,
+                r#""exprs":{"safe":0,"unsafe_":0},"#,
+                r#""item_impls":{"safe":0,"unsafe_":0},"#,
+                r#""item_traits":{"safe":0,"unsafe_":0},"#,
+                r#""methods":{"safe":0,"unsafe_":0},"#,
+                r#""foreign_items":{"safe":0,"unsafe_":0},"#,
+                r#""transmutes":{"safe":0,"unsafe_":0},"#,
+                r#""const_unsafe":{"safe":0,"unsafe_":0}},"#,
+                r#""warnings":1}"#
+            )
+        )
+    )]
+    fn progress_event_json_test(
+        input_event: ProgressEvent,
+        expected_json: &str,
+    ) {
+        assert_eq!(serde_json::to_string(&input_event).unwrap(), expected_json);
+    }
+}
@@ -1,20 +1,28 @@
 mod custom_executor;
 
+pub use custom_executor::CustomExecutorError;
+
 use custom_executor::{CustomExecutor, CustomExecutorInnerContext};
 
-use cargo::core::compiler::Executor;
+use crate::timings::{Phase, Timings};
+
+use cargo::core::compiler::{BuildContext, Context, Executor, UnitInterner};
 use cargo::core::manifest::TargetKind;
-use cargo::core::Workspace;
+use cargo::core::{Package, PackageId, Workspace};
 use cargo::ops;
 use cargo::ops::{CleanOptions, CompileOptions};
 use cargo::util::{interning::InternedString, paths, CargoResult};
-use cargo::Config;
-use geiger::RsFileMetrics;
-use std::collections::HashSet;
+use cargo::{CliError, Config};
+use cargo_geiger_serde::ScanWarning;
+use geiger::{find_module_files, RsFileMetrics, ScanFileError};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
+use std::fs::File;
 use std::io;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex, PoisonError};
 use walkdir::{DirEntry, WalkDir};
 
@@ -36,6 +44,15 @@ pub enum RsFile {
     Other(PathBuf),
 }
 
+/// The `.rs` files used by the build, with the kinds of the targets that used
+/// them. The kinds are unknown, and left empty, for files listed in dep-info
+/// files that were not written by an intercepted rustc call.
+type UsedRsFiles = HashMap<PathBuf, HashSet<cargo_geiger_serde::TargetKind>>;
+
+/// A `--remap-path-prefix FROM=TO` pair given to rustc. The paths in the
+/// dep-info files of the call start with TO instead of FROM.
+type PathRemap = (PathBuf, PathBuf);
+
 #[derive(Clone, Debug, Default)]
 pub struct RsFileMetricsWrapper {
     /// The information returned by the `geiger` crate for a `.rs` file.
@@ -46,6 +63,57 @@ pub struct RsFileMetricsWrapper {
     /// and cannot know if a file is a crate entry point or not, so we add this
     /// information here.
     pub is_crate_entry_point: bool,
+
+    /// Machine generated files, like `bindgen` output, are counted in a
+    /// separate bucket so they don't drown out the hand-written unsafe usage.
+    pub is_generated: bool,
+}
+
+/// The outcome of the interception build.
+#[derive(Debug, Default)]
+pub struct RsFileDeps {
+    /// All `.rs` files used by the build, canonicalized.
+    pub rs_files_used: HashSet<PathBuf>,
+
+    /// The kinds of the targets that used each file in `rs_files_used`,
+    /// see `UsedRsFiles`.
+    pub target_kinds: UsedRsFiles,
+
+    /// The compiler errors of the packages that failed to build, only
+    /// populated when the build keeps going after errors.
+    pub build_failures: HashMap<PackageId, String>,
+
+    /// Files listed in dep-info files that were not found.
+    pub warnings: Vec<ScanWarning>,
+
+    /// The crate names of the proc-macro crates applied to each package.
+    /// Only known for the units that were built, not for up-to-date units
+    /// with `--reuse-build` or with the unit graph resolver.
+    pub proc_macros: HashMap<PackageId, BTreeSet<String>>,
+}
+
+/// How the `.rs` files used by the build are found.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolver {
+    /// Clean, build and intercept the rustc calls.
+    Executor,
+
+    /// Follow the module declarations of the units in cargo's unit graph,
+    /// without building anything. Falls back to `Executor` when build scripts
+    /// would have to run.
+    UnitGraph,
+}
+
+impl FromStr for Resolver {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Resolver, &'static str> {
+        match s {
+            "executor" => Ok(Resolver::Executor),
+            "unit-graph" => Ok(Resolver::UnitGraph),
+            _ => Err("invalid resolver"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -61,21 +129,79 @@ pub enum RsResolveError {
     /// Failed to parse a .dep file.
     DepParse(String, PathBuf),
 
+    /// The build executor failed, before or after running rustc.
+    Executor(CustomExecutorError),
+
     /// Failed to get the inner context out of the mutex.
     InnerContextMutex(String),
 
     /// Like io::Error but with the related path.
     Io(io::Error, PathBuf),
 
+    /// Failed to read the module files of a unit.
+    ModuleFiles(ScanFileError),
+
     Walkdir(walkdir::Error),
 }
 
 impl Error for RsResolveError {}
 
-/// Forward Display to Debug.
 impl fmt::Display for RsResolveError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        match self {
+            RsResolveError::ArcUnwrap() => write!(
+                f,
+                "failed to collect the files used by the build, the build \
+                 executor is still referenced"
+            ),
+            RsResolveError::Cargo(message) => write!(f, "{}", message),
+            RsResolveError::DepParse(message, path) => write!(
+                f,
+                "failed to parse the dep-info file `{}`: {}",
+                path.display(),
+                message
+            ),
+            RsResolveError::Executor(error) => write!(f, "{}", error),
+            RsResolveError::InnerContextMutex(message) => write!(
+                f,
+                "failed to collect the files used by the build: {}",
+                message
+            ),
+            RsResolveError::Io(error, path) => {
+                write!(f, "failed to read `{}`: {}", path.display(), error)
+            }
+            RsResolveError::ModuleFiles(error) => {
+                let (message, path) = match error {
+                    ScanFileError::Io(error, path) => (error.to_string(), path),
+                    ScanFileError::Utf8(error, path) => {
+                        (error.to_string(), path)
+                    }
+                    ScanFileError::Syn(error, path) => {
+                        (error.to_string(), path)
+                    }
+                    ScanFileError::TooLarge(size, path) => (
+                        format!(
+                            "larger than the maximum file size, {} bytes",
+                            size
+                        ),
+                        path,
+                    ),
+                };
+                write!(
+                    f,
+                    "failed to read the module files of `{}`: {}",
+                    path.display(),
+                    message
+                )
+            }
+            RsResolveError::Walkdir(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<RsResolveError> for CliError {
+    fn from(e: RsResolveError) -> Self {
+        CliError::new(anyhow::Error::new(e), 1)
     }
 }
 
@@ -106,6 +232,20 @@ pub fn into_rs_code_file(target_kind: &TargetKind, path: PathBuf) -> RsFile {
     }
 }
 
+pub fn into_report_target_kind(
+    target_kind: &TargetKind,
+) -> cargo_geiger_serde::TargetKind {
+    match target_kind {
+        TargetKind::Bench => cargo_geiger_serde::TargetKind::Bench,
+        TargetKind::Bin => cargo_geiger_serde::TargetKind::Bin,
+        TargetKind::CustomBuild => cargo_geiger_serde::TargetKind::CustomBuild,
+        TargetKind::ExampleBin => cargo_geiger_serde::TargetKind::Example,
+        TargetKind::ExampleLib(_) => cargo_geiger_serde::TargetKind::Example,
+        TargetKind::Lib(_) => cargo_geiger_serde::TargetKind::Lib,
+        TargetKind::Test => cargo_geiger_serde::TargetKind::Test,
+    }
+}
+
 pub fn into_target_kind(raw_target_kind: Vec<String>) -> TargetKind {
     let mut raw_target_kind_str = raw_target_kind
         .iter()
@@ -140,29 +280,122 @@ pub fn is_file_with_ext(entry: &DirEntry, file_ext: &str) -> bool {
     ext.to_string_lossy() == file_ext
 }
 
+/// Markers of machine generated files, looked for in the comments a `.rs`
+/// file starts with. More can be added with `--generated-marker`.
+pub const DEFAULT_GENERATED_MARKERS: &[&str] =
+    &["automatically generated by rust-bindgen", "@generated"];
+
+/// A `.rs` file counts as machine generated when one of the markers is found
+/// in its leading comments, or when it is a `bindings.rs` in the `OUT_DIR` of
+/// a build script, `build/<package>-<hash>/out`.
+pub fn is_generated_file(path: &Path, markers: &[String]) -> bool {
+    is_bindings_in_out_dir(path) || has_generated_marker(path, markers)
+}
+
+fn is_bindings_in_out_dir(path: &Path) -> bool {
+    path.file_name().map_or(false, |name| name == "bindings.rs")
+        && is_in_out_dir(path)
+}
+
+/// Below the `OUT_DIR` of a build script, `build/<package>-<hash>/out` in the
+/// target directory.
+pub fn is_in_out_dir(path: &Path) -> bool {
+    let components = path
+        .components()
+        .map(|component| component.as_os_str())
+        .collect::<Vec<_>>();
+    components
+        .windows(3)
+        .any(|window| window[0] == "build" && window[2] == "out")
+}
+
+/// Only the comments, blank lines and inner attributes at the start of the
+/// file are read, generated files can be huge.
+fn has_generated_marker(path: &Path, markers: &[String]) -> bool {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+    BufReader::new(file)
+        .lines()
+        .filter_map(Result::ok)
+        .take_while(|line| {
+            let line = line.trim_start();
+            line.is_empty()
+                || line.starts_with("//")
+                || line.starts_with("/*")
+                || line.starts_with('*')
+                || line.starts_with("#!")
+        })
+        .any(|line| markers.iter().any(|marker| line.contains(marker.as_str())))
+}
+
 /// Trigger a `cargo clean` + `cargo check` and listen to the cargo/rustc
 /// communication to figure out which source files were used by the build.
+///
+/// With `reuse_build` the clean is skipped and up-to-date units are not
+/// rebuilt. Their `.rs` files are read from the dep-info files left by an
+/// earlier build instead, which is only as accurate as the target directory.
 pub fn resolve_rs_file_deps(
     compile_options: &CompileOptions,
+    resolver: Resolver,
+    keep_going: bool,
+    reuse_build: bool,
+    timings: &Timings,
     workspace: &Workspace,
-) -> Result<HashSet<PathBuf>, RsResolveError> {
+) -> Result<RsFileDeps, RsResolveError> {
     let config = workspace.config();
-    // Need to run a cargo clean to identify all new .d deps files.
-    // TODO: Figure out how this can be avoided to improve performance, clean
-    // Rust builds are __slow__.
-    let clean_options = CleanOptions {
-        config: &config,
-        spec: vec![],
-        targets: vec![],
-        profile_specified: false,
-        // A temporary hack to get cargo 0.43 to build, TODO: look closer at the updated cargo API
-        // later.
-        requested_profile: InternedString::new("dev"),
-        doc: false,
-    };
+    if resolver == Resolver::UnitGraph {
+        let interner = UnitInterner::new();
+        let build_context =
+            ops::create_bcx(workspace, compile_options, &interner)
+                .map_err(|e| RsResolveError::Cargo(format!("{:#}", e)))?;
+        let mut build_script_package_ids = build_context
+            .unit_graph
+            .keys()
+            .filter(|unit| unit.mode.is_run_custom_build())
+            .map(|unit| unit.pkg.package_id())
+            .collect::<Vec<_>>();
+        if build_script_package_ids.is_empty() {
+            let rs_file_deps = unit_graph_rs_file_deps(&build_context);
+            timings.end_phase(Phase::DepInfo);
+            return rs_file_deps;
+        }
+        // Build scripts can generate code and set cfgs, only the build
+        // knows what they do.
+        build_script_package_ids.sort();
+        build_script_package_ids.dedup();
+        config
+            .shell()
+            .warn(format!(
+                "falling back to the executor resolver, build scripts need \
+                 to run for: {}",
+                build_script_package_ids
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .map_err(|e| RsResolveError::Cargo(format!("{:#}", e)))?;
+    }
+    if !reuse_build {
+        // Need to run a cargo clean to identify all new .d deps files.
+        // Clean Rust builds are __slow__, `reuse_build` avoids this.
+        let clean_options = CleanOptions {
+            config: &config,
+            spec: vec![],
+            targets: vec![],
+            profile_specified: false,
+            // A temporary hack to get cargo 0.43 to build, TODO: look closer
+            // at the updated cargo API later.
+            requested_profile: InternedString::new("dev"),
+            doc: false,
+        };
 
-    ops::clean(workspace, &clean_options)
-        .map_err(|e| RsResolveError::Cargo(e.to_string()))?;
+        ops::clean(workspace, &clean_options)
+            .map_err(|e| RsResolveError::Cargo(format!("{:#}", e)))?;
+        timings.end_phase(Phase::Clean);
+    }
 
     let inner_arc = Arc::new(Mutex::new(CustomExecutorInnerContext::default()));
     {
@@ -170,79 +403,308 @@ pub fn resolve_rs_file_deps(
             compile_options,
             config,
             inner_arc.clone(),
+            keep_going,
+            reuse_build,
             workspace,
         )?;
     }
+    timings.end_phase(Phase::Build);
 
     let workspace_root = workspace.root().to_path_buf();
     let inner_mutex =
         Arc::try_unwrap(inner_arc).map_err(|_| RsResolveError::ArcUnwrap())?;
-    let (rs_files, out_dir_args) = {
-        let ctx = inner_mutex.into_inner()?;
-        (ctx.rs_file_args, ctx.out_dir_args)
-    };
-    let mut path_buf_hash_set = HashSet::<PathBuf>::new();
-    for out_dir in out_dir_args {
-        // TODO: Figure out if the `.d` dep files are used by one or more rustc
-        // calls. It could be useful to know which `.d` dep files belong to
-        // which rustc call. That would allow associating each `.rs` file found
-        // in each dep file with a PackageId.
-        add_dir_entries_to_path_buf_hash_set(
-            out_dir,
-            &mut path_buf_hash_set,
-            workspace_root.clone(),
-        )?;
+    let ctx = inner_mutex.into_inner()?;
+    let mut used_rs_files = UsedRsFiles::new();
+    let mut unresolved_paths = Vec::new();
+    if reuse_build {
+        // The out dirs can hold stale dep-info files from other builds, only
+        // the ones of the units in this build are read.
+        let dep_info_paths = unit_dep_info_paths(compile_options, workspace)
+            .map_err(|e| RsResolveError::Cargo(format!("{:#}", e)))?;
+        // The remaps of up-to-date units are taken from their rustflags.
+        let mut dep_info_remaps = ctx.dep_info_remaps.clone();
+        for (dep_info_path, target_kind, rustc_cwd, remaps) in dep_info_paths {
+            // Units that failed to build may not have one.
+            if !dep_info_path.exists() {
+                continue;
+            }
+            dep_info_remaps
+                .entry(dep_info_path.clone())
+                .or_insert(remaps);
+            add_dep_info_entries_to_used_rs_files(
+                &ctx.dep_info_cwds,
+                &dep_info_remaps,
+                &dep_info_path,
+                Some(target_kind),
+                &mut used_rs_files,
+                &rustc_cwd,
+                &mut unresolved_paths,
+            )?;
+        }
+    } else {
+        for out_dir in ctx.out_dir_args {
+            // TODO: Figure out if the `.d` dep files are used by one or more
+            // rustc calls. It could be useful to know which `.d` dep files
+            // belong to which rustc call. That would allow associating each
+            // `.rs` file found in each dep file with a PackageId.
+            add_dir_entries_to_used_rs_files(
+                &ctx.dep_info_cwds,
+                &ctx.dep_info_remaps,
+                &ctx.dep_info_target_kinds,
+                out_dir,
+                &mut used_rs_files,
+                &workspace_root,
+                &mut unresolved_paths,
+            )?;
+        }
     }
-    for path_buf in rs_files {
-        // rs_files must already be canonicalized
-        path_buf_hash_set.insert(path_buf);
+    unresolved_paths.sort();
+    unresolved_paths.dedup();
+    for (path_buf, target_kinds) in ctx.rs_file_args {
+        // rs_file_args must already be canonicalized
+        used_rs_files
+            .entry(path_buf)
+            .or_insert_with(HashSet::new)
+            .extend(target_kinds);
     }
+    timings.end_phase(Phase::DepInfo);
 
-    Ok(path_buf_hash_set)
+    Ok(RsFileDeps {
+        rs_files_used: used_rs_files.keys().cloned().collect(),
+        target_kinds: used_rs_files,
+        build_failures: ctx.build_failures,
+        proc_macros: ctx.proc_macros,
+        warnings: unresolved_paths
+            .into_iter()
+            .map(|path| ScanWarning::MissingDepInfoPath { path })
+            .collect(),
+    })
+}
+
+/// The files of each unit are found by following the module declarations from
+/// the target source file, see `find_module_files` for what that misses.
+fn unit_graph_rs_file_deps(
+    build_context: &BuildContext,
+) -> Result<RsFileDeps, RsResolveError> {
+    let src_paths = build_context
+        .unit_graph
+        .keys()
+        .filter_map(|unit| {
+            let src_path = unit.target.src_path().path()?;
+            Some((src_path, into_report_target_kind(unit.target.kind())))
+        })
+        .collect::<HashSet<_>>();
+    let mut used_rs_files = UsedRsFiles::new();
+    for (src_path, target_kind) in src_paths {
+        let module_files =
+            find_module_files(src_path).map_err(RsResolveError::ModuleFiles)?;
+        for path_buf in module_files {
+            let canonical_path = path_buf
+                .canonicalize()
+                .map_err(|e| RsResolveError::Io(e, path_buf))?;
+            used_rs_files
+                .entry(canonical_path)
+                .or_insert_with(HashSet::new)
+                .insert(target_kind);
+        }
+    }
+    Ok(RsFileDeps {
+        rs_files_used: used_rs_files.keys().cloned().collect(),
+        target_kinds: used_rs_files,
+        ..Default::default()
+    })
 }
 
-fn add_dir_entries_to_path_buf_hash_set(
+/// Relative paths in a `.d` dep-info file are resolved against the working
+/// directory of the rustc call that wrote it, falling back to the workspace
+/// root for dep-info files that were not written by an intercepted call.
+fn add_dir_entries_to_used_rs_files(
+    dep_info_cwds: &HashMap<PathBuf, PathBuf>,
+    dep_info_remaps: &HashMap<PathBuf, Vec<PathRemap>>,
+    dep_info_target_kinds: &HashMap<PathBuf, cargo_geiger_serde::TargetKind>,
     out_dir: PathBuf,
-    path_buf_hash_set: &mut HashSet<PathBuf>,
-    workspace_root: PathBuf,
+    used_rs_files: &mut UsedRsFiles,
+    workspace_root: &Path,
+    unresolved_paths: &mut Vec<PathBuf>,
 ) -> Result<(), RsResolveError> {
     for entry in WalkDir::new(&out_dir) {
         let entry = entry.map_err(RsResolveError::Walkdir)?;
         if !is_file_with_ext(&entry, "d") {
             continue;
         }
-        let dependencies = parse_rustc_dep_info(entry.path()).map_err(|e| {
-            RsResolveError::DepParse(e.to_string(), entry.path().to_path_buf())
-        })?;
-        let canonical_paths = dependencies
-            .into_iter()
-            .flat_map(|t| t.1)
-            .map(PathBuf::from)
-            .map(|pb| workspace_root.join(pb))
-            .map(|pb| pb.canonicalize().map_err(|e| RsResolveError::Io(e, pb)));
-        for path_buf in canonical_paths {
-            path_buf_hash_set.insert(path_buf?);
+        add_dep_info_entries_to_used_rs_files(
+            dep_info_cwds,
+            dep_info_remaps,
+            entry.path(),
+            dep_info_target_kinds.get(entry.path()).copied(),
+            used_rs_files,
+            workspace_root,
+            unresolved_paths,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The paths of a dep-info file written with `--remap-path-prefix` are
+/// mapped back before they are resolved. Paths that still don't exist are
+/// added to `unresolved_paths` and left out.
+fn add_dep_info_entries_to_used_rs_files(
+    dep_info_cwds: &HashMap<PathBuf, PathBuf>,
+    dep_info_remaps: &HashMap<PathBuf, Vec<PathRemap>>,
+    dep_info_path: &Path,
+    target_kind: Option<cargo_geiger_serde::TargetKind>,
+    used_rs_files: &mut UsedRsFiles,
+    default_cwd: &Path,
+    unresolved_paths: &mut Vec<PathBuf>,
+) -> Result<(), RsResolveError> {
+    let dependencies = parse_rustc_dep_info(dep_info_path).map_err(|e| {
+        RsResolveError::DepParse(e.to_string(), dep_info_path.to_path_buf())
+    })?;
+    let base_dir = dep_info_cwds
+        .get(dep_info_path)
+        .map(PathBuf::as_path)
+        .unwrap_or(default_cwd);
+    let remaps = dep_info_remaps
+        .get(dep_info_path)
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    let paths = dependencies
+        .into_iter()
+        .flat_map(|t| t.1)
+        .map(|path| base_dir.join(unmap_path(Path::new(&path), remaps)));
+    for path_buf in paths {
+        match path_buf.canonicalize() {
+            Ok(canonical_path) => {
+                let target_kinds = used_rs_files
+                    .entry(canonical_path)
+                    .or_insert_with(HashSet::new);
+                target_kinds.extend(target_kind);
+            }
+            Err(_) => unresolved_paths.push(path_buf),
         }
     }
 
     Ok(())
 }
 
+/// The `--remap-path-prefix FROM=TO` pairs among the arguments of a rustc
+/// call, in the order they are given. Both the separate and the `=` form of
+/// the flag are accepted, FROM and TO are split at the last `=` like rustc
+/// does.
+fn remap_path_prefixes(args: &[String]) -> Vec<PathRemap> {
+    let flag = "--remap-path-prefix";
+    let mut remaps = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = if arg == flag {
+            args.next().map(String::as_str)
+        } else {
+            arg.strip_prefix(flag)
+                .and_then(|rest| rest.strip_prefix('='))
+        };
+        if let Some((from, to)) =
+            value.and_then(|value| value.rfind('=').map(|i| value.split_at(i)))
+        {
+            remaps.push((PathBuf::from(from), PathBuf::from(&to[1..])));
+        }
+    }
+    remaps
+}
+
+/// Maps a path written by rustc back to the path it was given, undoing
+/// `--remap-path-prefix`. Rustc applies the last matching pair, so they are
+/// tried in reverse.
+fn unmap_path(path: &Path, remaps: &[PathRemap]) -> PathBuf {
+    remaps
+        .iter()
+        .rev()
+        .find_map(|(from, to)| {
+            path.strip_prefix(to).ok().map(|rest| from.join(rest))
+        })
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// The paths of the rustc dep-info files of all units in the build, named the
+/// same way cargo names them, with the kinds of their targets and the working
+/// directories and path remaps of their rustc calls. Up-to-date units never
+/// reach the executor, so their dep-info files can't be found through the
+/// intercepted `--out-dir`.
+fn unit_dep_info_paths(
+    compile_options: &CompileOptions,
+    workspace: &Workspace,
+) -> CargoResult<
+    Vec<(
+        PathBuf,
+        cargo_geiger_serde::TargetKind,
+        PathBuf,
+        Vec<PathRemap>,
+    )>,
+> {
+    let interner = UnitInterner::new();
+    let build_context = ops::create_bcx(workspace, compile_options, &interner)?;
+    let mut context = Context::new(&build_context)?;
+    context.prepare_units()?;
+    let files = context.files();
+    Ok(build_context
+        .unit_graph
+        .keys()
+        .filter(|unit| !unit.mode.is_run_custom_build())
+        .map(|unit| {
+            let crate_name = unit.target.crate_name();
+            let dep_info_name = match files.metadata(unit) {
+                Some(metadata) => format!("{}-{}.d", crate_name, metadata),
+                None => format!("{}.d", crate_name),
+            };
+            (
+                files.out_dir(unit).join(dep_info_name),
+                into_report_target_kind(unit.target.kind()),
+                rustc_cwd(&unit.pkg, workspace),
+                remap_path_prefixes(build_context.rustflags_args(unit)),
+            )
+        })
+        .collect())
+}
+
+/// Cargo runs rustc from the workspace root for path packages and from the
+/// package root for everything else, like registry, git and vendored
+/// packages. The relative paths in a dep-info file are relative to it.
+fn rustc_cwd(package: &Package, workspace: &Workspace) -> PathBuf {
+    if package.package_id().source_id().is_path() {
+        workspace.root().to_path_buf()
+    } else {
+        package.root().to_path_buf()
+    }
+}
+
 fn compile_with_exec(
     compile_options: &CompileOptions,
     config: &Config,
     inner_arc: Arc<Mutex<CustomExecutorInnerContext>>,
+    keep_going: bool,
+    reuse_build: bool,
     workspace: &Workspace,
 ) -> Result<(), RsResolveError> {
     let custom_executor = CustomExecutor {
         cwd: config.cwd().to_path_buf(),
         inner_ctx: inner_arc,
+        keep_going,
+        force_rebuild: !reuse_build,
     };
 
     let custom_executor_arc: Arc<dyn Executor> = Arc::new(custom_executor);
 
-    ops::compile_with_exec(workspace, &compile_options, &custom_executor_arc)
-        .map_err(|e| RsResolveError::Cargo(e.to_string()))?;
+    // The errors of the executor are kept, cargo only adds the unit they
+    // happened in as context.
+    let result = ops::compile_with_exec(
+        workspace,
+        &compile_options,
+        &custom_executor_arc,
+    );
+    result.map_err(|e| match e.downcast::<CustomExecutorError>() {
+        Ok(executor_error) => RsResolveError::Executor(executor_error),
+        Err(e) => RsResolveError::Cargo(format!("{:#}", e)),
+    })?;
 
     Ok(())
 }
@@ -267,13 +729,11 @@ fn parse_rustc_dep_info(
                 while file.ends_with('\\') {
                     file.pop();
                     file.push(' ');
-                    //file.push_str(deps.next().ok_or_else(|| {
-                    //internal("malformed dep-info format, trailing \\".to_string())
-                    //})?);
-                    file.push_str(
-                        deps.next()
-                            .expect("malformed dep-info format, trailing \\"),
-                    );
+                    file.push_str(deps.next().ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "malformed dep-info format, trailing \\"
+                        )
+                    })?);
                 }
                 ret.push(file);
             }
@@ -286,6 +746,194 @@ fn parse_rustc_dep_info(
 mod rs_file_tests {
     use super::*;
     use rstest::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[rstest]
+    fn add_dir_entries_to_used_rs_files_resolves_against_rustc_cwd_test() {
+        // The target dir is redirected away from the package, like with
+        // CARGO_TARGET_DIR, so the dep-info paths are only valid relative to
+        // the working directory of the rustc call.
+        let package_dir = tempdir().unwrap();
+        let target_dir = tempdir().unwrap();
+        let lib_rs = package_dir.path().join("src").join("lib.rs");
+        fs::create_dir(package_dir.path().join("src")).unwrap();
+        fs::write(&lib_rs, "").unwrap();
+
+        let out_dir = target_dir.path().join("debug").join("deps");
+        fs::create_dir_all(&out_dir).unwrap();
+        let dep_info_path = out_dir.join("foo-0123abcd.d");
+        fs::write(
+            &dep_info_path,
+            format!("{}: src/lib.rs\n", out_dir.join("libfoo.rmeta").display()),
+        )
+        .unwrap();
+
+        let dep_info_cwds =
+            vec![(dep_info_path.clone(), package_dir.path().to_path_buf())]
+                .into_iter()
+                .collect::<HashMap<PathBuf, PathBuf>>();
+        let dep_info_target_kinds =
+            vec![(dep_info_path, cargo_geiger_serde::TargetKind::Lib)]
+                .into_iter()
+                .collect::<HashMap<_, _>>();
+        let mut used_rs_files = UsedRsFiles::new();
+
+        let mut unresolved_paths = Vec::new();
+
+        let result = add_dir_entries_to_used_rs_files(
+            &dep_info_cwds,
+            &HashMap::new(),
+            &dep_info_target_kinds,
+            out_dir,
+            &mut used_rs_files,
+            target_dir.path(),
+            &mut unresolved_paths,
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(
+            used_rs_files,
+            vec![(
+                lib_rs.canonicalize().unwrap(),
+                vec![cargo_geiger_serde::TargetKind::Lib]
+                    .into_iter()
+                    .collect()
+            )]
+            .into_iter()
+            .collect::<UsedRsFiles>()
+        );
+    }
+
+    #[rstest]
+    fn add_dir_entries_to_used_rs_files_falls_back_to_workspace_root_test() {
+        let workspace_root = tempdir().unwrap();
+        let target_dir = tempdir().unwrap();
+        let lib_rs = workspace_root.path().join("lib.rs");
+        fs::write(&lib_rs, "").unwrap();
+        fs::write(target_dir.path().join("foo.d"), "libfoo.rmeta: lib.rs\n")
+            .unwrap();
+
+        let mut used_rs_files = UsedRsFiles::new();
+        let mut unresolved_paths = Vec::new();
+
+        let result = add_dir_entries_to_used_rs_files(
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+            target_dir.path().to_path_buf(),
+            &mut used_rs_files,
+            workspace_root.path(),
+            &mut unresolved_paths,
+        );
+
+        assert!(result.is_ok());
+        // Not written by an intercepted call, the target kind is unknown.
+        assert_eq!(
+            used_rs_files,
+            vec![(lib_rs.canonicalize().unwrap(), HashSet::new())]
+                .into_iter()
+                .collect::<UsedRsFiles>()
+        );
+    }
+
+    #[rstest]
+    fn add_dep_info_entries_to_used_rs_files_undoes_remaps_test() {
+        let workspace_root = tempdir().unwrap();
+        let target_dir = tempdir().unwrap();
+        fs::create_dir(workspace_root.path().join("src")).unwrap();
+        let lib_rs = workspace_root.path().join("src").join("lib.rs");
+        fs::write(&lib_rs, "").unwrap();
+        let dep_info_path = target_dir.path().join("foo.d");
+        fs::write(
+            &dep_info_path,
+            "libfoo.rmeta: /remapped/src/lib.rs /remapped/src/missing.rs\n",
+        )
+        .unwrap();
+
+        let dep_info_remaps = vec![(
+            dep_info_path.clone(),
+            vec![(PathBuf::from("src"), PathBuf::from("/remapped/src"))],
+        )]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+        let mut used_rs_files = UsedRsFiles::new();
+        let mut unresolved_paths = Vec::new();
+
+        let result = add_dep_info_entries_to_used_rs_files(
+            &HashMap::new(),
+            &dep_info_remaps,
+            &dep_info_path,
+            None,
+            &mut used_rs_files,
+            workspace_root.path(),
+            &mut unresolved_paths,
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(
+            used_rs_files.keys().cloned().collect::<Vec<_>>(),
+            vec![lib_rs.canonicalize().unwrap()]
+        );
+        assert_eq!(
+            unresolved_paths,
+            vec![workspace_root.path().join("src").join("missing.rs")]
+        );
+    }
+
+    #[rstest(
+        input_args,
+        expected_remaps,
+        case(vec!["--crate-name", "foo"], vec![]),
+        case(
+            vec!["--remap-path-prefix", "/home/user/foo=/foo"],
+            vec![("/home/user/foo", "/foo")]
+        ),
+        case(
+            vec![
+                "--remap-path-prefix=src=/remapped",
+                "--remap-path-prefix",
+                "a=b=c"
+            ],
+            vec![("src", "/remapped"), ("a=b", "c")]
+        ),
+        case(vec!["--remap-path-prefix"], vec![])
+    )]
+    fn remap_path_prefixes_test(
+        input_args: Vec<&str>,
+        expected_remaps: Vec<(&str, &str)>,
+    ) {
+        let args = input_args.into_iter().map(String::from).collect::<Vec<_>>();
+        let expected_remaps = expected_remaps
+            .into_iter()
+            .map(|(from, to)| (PathBuf::from(from), PathBuf::from(to)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(remap_path_prefixes(&args), expected_remaps);
+    }
+
+    #[rstest(
+        input_path,
+        expected_path,
+        case("/remapped/src/lib.rs", "/home/user/foo/src/lib.rs"),
+        case("/remapped/registry/lib.rs", "/registry/lib.rs"),
+        case("/other/lib.rs", "/other/lib.rs")
+    )]
+    fn unmap_path_test(input_path: &str, expected_path: &str) {
+        // The last matching pair wins, like in rustc.
+        let remaps = vec![
+            (PathBuf::from("/home/user/foo"), PathBuf::from("/remapped")),
+            (
+                PathBuf::from("/registry"),
+                PathBuf::from("/remapped/registry"),
+            ),
+        ];
+
+        assert_eq!(
+            unmap_path(Path::new(input_path), &remaps),
+            PathBuf::from(expected_path)
+        );
+    }
 
     #[rstest(
         input_rs_file,
@@ -363,6 +1011,33 @@ mod rs_file_tests {
         );
     }
 
+    #[rstest(
+        input_target_kind,
+        expected_target_kind,
+        case(TargetKind::Lib(vec![]), cargo_geiger_serde::TargetKind::Lib),
+        case(TargetKind::Bin, cargo_geiger_serde::TargetKind::Bin),
+        case(TargetKind::Test, cargo_geiger_serde::TargetKind::Test),
+        case(TargetKind::Bench, cargo_geiger_serde::TargetKind::Bench),
+        case(
+            TargetKind::ExampleLib(vec![]),
+            cargo_geiger_serde::TargetKind::Example
+        ),
+        case(TargetKind::ExampleBin, cargo_geiger_serde::TargetKind::Example),
+        case(
+            TargetKind::CustomBuild,
+            cargo_geiger_serde::TargetKind::CustomBuild
+        )
+    )]
+    fn into_report_target_kind_test(
+        input_target_kind: TargetKind,
+        expected_target_kind: cargo_geiger_serde::TargetKind,
+    ) {
+        assert_eq!(
+            into_report_target_kind(&input_target_kind),
+            expected_target_kind
+        );
+    }
+
     #[rstest(
         input_raw_target_kind,
         expected_target_kind,
@@ -418,6 +1093,56 @@ mod rs_file_tests {
         );
     }
 
+    #[rstest(
+        input_file_name,
+        input_content,
+        expected_is_generated,
+        case(
+            "ffi.rs",
+            "/* automatically generated by rust-bindgen 0.55.1 */\n\n\
+             extern \"C\" {}\n",
+            true
+        ),
+        case(
+            "lib.rs",
+            "#![allow(dead_code)]\n// @generated\nfn f() {}\n",
+            true
+        ),
+        case("lib.rs", "fn f() {}\n// @generated\n", false),
+        case("lib.rs", "// Hand-written.\nfn f() {}\n", false)
+    )]
+    fn is_generated_file_reads_leading_comments_test(
+        input_file_name: &str,
+        input_content: &str,
+        expected_is_generated: bool,
+    ) {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(input_file_name);
+        fs::write(&path, input_content).unwrap();
+        let markers = DEFAULT_GENERATED_MARKERS
+            .iter()
+            .map(|marker| marker.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(is_generated_file(&path, &markers), expected_is_generated);
+    }
+
+    #[rstest(
+        input_path,
+        expected_is_generated,
+        case("target/debug/build/foo-0123abcd/out/bindings.rs", true),
+        case("target/debug/build/foo-0123abcd/out/other.rs", false),
+        case("src/bindings.rs", false)
+    )]
+    fn is_generated_file_matches_bindings_in_out_dir_test(
+        input_path: &str,
+        expected_is_generated: bool,
+    ) {
+        assert_eq!(
+            is_generated_file(Path::new(input_path), &[]),
+            expected_is_generated
+        );
+    }
+
     #[rstest]
     fn is_file_with_ext_test() {
         let config = Config::default().unwrap();
@@ -441,4 +1166,52 @@ mod rs_file_tests {
             assert_eq!(is_file_with_ext(&entry, "rs"), false);
         }
     }
+
+    #[rstest]
+    fn parse_rustc_dep_info_rejects_trailing_backslash_test() {
+        let dir = tempdir().unwrap();
+        let dep_info_path = dir.path().join("lib.d");
+        fs::write(&dep_info_path, "lib.rmeta: src/with\\ space.rs\n").unwrap();
+        assert_eq!(
+            parse_rustc_dep_info(&dep_info_path).unwrap(),
+            vec![(
+                String::from("lib.rmeta"),
+                vec![String::from("src/with space.rs")]
+            )]
+        );
+
+        fs::write(&dep_info_path, "lib.rmeta: src/lib.rs\\\n").unwrap();
+        let error = parse_rustc_dep_info(&dep_info_path).unwrap_err();
+        assert_eq!(error.to_string(), "malformed dep-info format, trailing \\");
+    }
+
+    #[rstest(
+        input_error,
+        expected_message,
+        case(
+            RsResolveError::DepParse(
+                String::from("malformed dep-info format, trailing \\"),
+                PathBuf::from("target/debug/deps/lib.d")
+            ),
+            "failed to parse the dep-info file `target/debug/deps/lib.d`: \
+             malformed dep-info format, trailing \\"
+        ),
+        case(
+            RsResolveError::Io(
+                io::Error::new(io::ErrorKind::NotFound, "not found"),
+                PathBuf::from("src/lib.rs")
+            ),
+            "failed to read `src/lib.rs`: not found"
+        ),
+        case(
+            RsResolveError::Cargo(String::from("could not compile `broken`")),
+            "could not compile `broken`"
+        )
+    )]
+    fn rs_resolve_error_display_test(
+        input_error: RsResolveError,
+        expected_message: &str,
+    ) {
+        assert_eq!(input_error.to_string(), expected_message);
+    }
 }
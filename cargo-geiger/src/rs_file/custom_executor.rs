@@ -1,12 +1,16 @@
+use super::{into_report_target_kind, remap_path_prefixes, PathRemap};
+
 use cargo::core::compiler::{CompileMode, Executor, Unit};
 use cargo::core::{PackageId, Target};
 use cargo::util::{CargoResult, ProcessBuilder};
-use std::collections::HashSet;
+use cargo_geiger_serde::TargetKind;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error::Error;
 use std::ffi::OsString;
 use std::fmt;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 /// A cargo Executor to intercept all build tasks and store all ".rs" file
@@ -22,13 +26,28 @@ pub struct CustomExecutor {
 
     /// Needed since multiple rustc calls can be in flight at the same time.
     pub inner_ctx: Arc<Mutex<CustomExecutorInnerContext>>,
+
+    /// Record failing rustc calls instead of stopping the build.
+    pub keep_going: bool,
+
+    /// Rebuild up-to-date units too, so that every unit passes through
+    /// `exec`.
+    pub force_rebuild: bool,
 }
 
 #[derive(Debug)]
-enum CustomExecutorError {
+pub enum CustomExecutorError {
+    /// Failed to get the inner context out of the mutex.
     InnerContextMutex(String),
+
+    /// Like io::Error but with the related path.
     Io(io::Error, PathBuf),
+
+    /// A rustc call without `--out-dir`, with the command line.
     OutDirKeyMissing(String),
+
+    /// A rustc call with `--out-dir` as its last argument, with the command
+    /// line.
     OutDirValueMissing(String),
 }
 
@@ -38,13 +57,13 @@ impl Executor for CustomExecutor {
     fn exec(
         &self,
         cmd: &ProcessBuilder,
-        _id: PackageId,
-        _target: &Target,
+        id: PackageId,
+        target: &Target,
         _mode: CompileMode,
         _on_stdout_line: &mut dyn FnMut(&str) -> CargoResult<()>,
         _on_stderr_line: &mut dyn FnMut(&str) -> CargoResult<()>,
     ) -> CargoResult<()> {
-        let args = cmd.get_args();
+        let args = rustc_args(cmd.get_args());
         let out_dir_key = OsString::from("--out-dir");
         let out_dir_key_idx =
             args.iter().position(|s| *s == out_dir_key).ok_or_else(|| {
@@ -65,39 +84,189 @@ impl Executor for CustomExecutor {
             .map(PathBuf::from)
             .unwrap_or_else(|| self.cwd.to_owned());
 
+        let target_kind = into_report_target_kind(target.kind());
         {
             // Scope to drop and release the mutex before calling rustc.
             let mut ctx = self.inner_ctx.lock().map_err(|e| {
                 CustomExecutorError::InnerContextMutex(e.to_string())
             })?;
-            for (arg_name, _) in args
-                .iter()
-                .map(|s| (s, s.to_string_lossy().to_lowercase()))
-                .filter(|(_, arg_value)| arg_value.ends_with(".rs"))
-            {
+            for arg_name in rs_file_args(args) {
                 let raw_path = cwd.join(arg_name);
                 let path = raw_path
                     .canonicalize()
                     .map_err(|e| CustomExecutorError::Io(e, raw_path))?;
-                ctx.rs_file_args.insert(path);
+                ctx.rs_file_args
+                    .entry(path)
+                    .or_insert_with(HashSet::new)
+                    .insert(target_kind);
+            }
+            if let Some(dep_info_path) = dep_info_path(args, &out_dir) {
+                let remaps = remap_path_prefixes(
+                    &args
+                        .iter()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .collect::<Vec<_>>(),
+                );
+                if !remaps.is_empty() {
+                    ctx.dep_info_remaps.insert(dep_info_path.clone(), remaps);
+                }
+                ctx.dep_info_cwds.insert(dep_info_path.clone(), cwd.clone());
+                ctx.dep_info_target_kinds.insert(dep_info_path, target_kind);
+            }
+            let proc_macros = proc_macro_externs(args).collect::<Vec<_>>();
+            if !proc_macros.is_empty() {
+                ctx.proc_macros
+                    .entry(id)
+                    .or_insert_with(BTreeSet::new)
+                    .extend(proc_macros);
             }
             ctx.out_dir_args.insert(out_dir);
         }
-        cmd.exec()?;
+        if !self.keep_going {
+            cmd.exec()?;
+            return Ok(());
+        }
+        let output = cmd.build_command().output().map_err(|e| {
+            CustomExecutorError::Io(e, PathBuf::from(cmd.get_program()))
+        })?;
+        if !output.status.success() {
+            // Returning Ok lets cargo carry on with the other units. Units
+            // depending on this one will fail as well and are recorded too.
+            let mut ctx = self.inner_ctx.lock().map_err(|e| {
+                CustomExecutorError::InnerContextMutex(e.to_string())
+            })?;
+            ctx.build_failures
+                .entry(id)
+                .or_insert_with(|| rustc_error_text(&output.stderr));
+        }
         Ok(())
     }
 
     /// Queried when queuing each unit of work. If it returns true, then the
     /// unit will always be rebuilt, independent of whether it needs to be.
     fn force_rebuild(&self, _unit: &Unit) -> bool {
-        true // Overriding the default to force all units to be processed.
+        self.force_rebuild
     }
 }
 
-/// Forward Display to Debug. See the crate root documentation.
+/// The arguments meant for rustc. With a `RUSTC_WRAPPER` like sccache, cargo
+/// runs the wrapper with the path to rustc as its first argument. Cargo always
+/// starts the rustc arguments with a flag, so any leading arguments that are
+/// neither flags nor `.rs` files belong to the wrapper and are skipped. The
+/// wrapper is still what `cmd.exec()` runs.
+fn rustc_args(args: &[OsString]) -> &[OsString] {
+    let wrapper_arg_count = args
+        .iter()
+        .take_while(|s| {
+            let s = s.to_string_lossy();
+            !s.starts_with('-') && !is_rs_file_arg(&s)
+        })
+        .count();
+    &args[wrapper_arg_count..]
+}
+
+/// The `.rs` input files among the rustc arguments, flags and their values
+/// are left out.
+fn rs_file_args(args: &[OsString]) -> impl Iterator<Item = &OsString> + '_ {
+    args.iter().filter(|s| {
+        let s = s.to_string_lossy();
+        !s.starts_with('-') && is_rs_file_arg(&s)
+    })
+}
+
+fn is_rs_file_arg(arg: &str) -> bool {
+    arg.to_lowercase().ends_with(".rs")
+}
+
+/// The dep-info file written by rustc is placed in the `--out-dir` and named
+/// after the crate name and the `extra-filename` codegen option.
+fn dep_info_path(args: &[OsString], out_dir: &Path) -> Option<PathBuf> {
+    let crate_name = args
+        .iter()
+        .position(|s| s == "--crate-name")
+        .and_then(|i| args.get(i + 1))?
+        .to_string_lossy();
+    let extra_filename = args
+        .iter()
+        .filter_map(|s| s.to_str())
+        .find_map(|s| s.strip_prefix("extra-filename="))
+        .unwrap_or("");
+    Some(out_dir.join(format!("{}{}.d", crate_name, extra_filename)))
+}
+
+/// The crate names of the proc-macro crates among the `--extern NAME=PATH`
+/// arguments. Proc-macros are built as dynamic libraries for the host, all
+/// other libraries are passed as `.rlib` or `.rmeta` files. The name is taken
+/// from the file, `NAME` can be a rename.
+fn proc_macro_externs(args: &[OsString]) -> impl Iterator<Item = String> + '_ {
+    args.windows(2)
+        .filter(|pair| pair[0] == "--extern")
+        .filter_map(|pair| {
+            let value = pair[1].to_string_lossy();
+            let path = value.splitn(2, '=').nth(1)?;
+            proc_macro_crate_name(Path::new(path))
+        })
+}
+
+/// `lib<crate>-<hash>.so` or `.dylib`, `<crate>-<hash>.dll` on Windows.
+fn proc_macro_crate_name(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?;
+    let stem = path.file_stem()?.to_str()?;
+    let file_name = match extension {
+        "dll" => stem,
+        "dylib" | "so" => stem.strip_prefix("lib")?,
+        _ => return None,
+    };
+    let crate_name = match file_name.rfind('-') {
+        Some(index) => &file_name[..index],
+        None => file_name,
+    };
+    Some(crate_name.to_owned())
+}
+
+/// Cargo asks rustc for JSON diagnostics, keep the rendered errors only.
+/// Output that isn't JSON is kept as is.
+fn rustc_error_text(stderr: &[u8]) -> String {
+    String::from_utf8_lossy(stderr)
+        .lines()
+        .filter_map(|line| match serde_json::from_str::<Value>(line) {
+            Ok(diagnostic) => {
+                if diagnostic["level"] == "error" {
+                    diagnostic["rendered"].as_str().map(|rendered| {
+                        console::strip_ansi_codes(rendered)
+                            .trim_end()
+                            .to_owned()
+                    })
+                } else {
+                    None
+                }
+            }
+            Err(_) => Some(line.to_owned()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 impl fmt::Display for CustomExecutorError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        match self {
+            CustomExecutorError::InnerContextMutex(message) => write!(
+                f,
+                "failed to record the files used by the build: {}",
+                message
+            ),
+            CustomExecutorError::Io(error, path) => {
+                write!(f, "failed to access `{}`: {}", path.display(), error)
+            }
+            CustomExecutorError::OutDirKeyMissing(command) => {
+                write!(f, "rustc was called without `--out-dir`: {}", command)
+            }
+            CustomExecutorError::OutDirValueMissing(command) => write!(
+                f,
+                "rustc was called with `--out-dir` but no directory: {}",
+                command
+            ),
+        }
     }
 }
 
@@ -105,10 +274,200 @@ impl Error for CustomExecutorError {}
 
 #[derive(Debug, Default)]
 pub struct CustomExecutorInnerContext {
-    /// Stores all lib.rs, main.rs etc. passed to rustc during the build.
-    pub rs_file_args: HashSet<PathBuf>,
+    /// Stores all lib.rs, main.rs etc. passed to rustc during the build,
+    /// with the kinds of the targets they were passed for.
+    pub rs_file_args: HashMap<PathBuf, HashSet<TargetKind>>,
 
     /// Investigate if this needs to be intercepted like this or if it can be
     /// looked up in a nicer way.
     pub out_dir_args: HashSet<PathBuf>,
+
+    /// The working directory of each rustc call, keyed by the path of the
+    /// `.d` dep-info file that the call writes. Relative paths inside a
+    /// dep-info file are relative to this directory, which is not necessarily
+    /// the workspace root.
+    pub dep_info_cwds: HashMap<PathBuf, PathBuf>,
+
+    /// The `--remap-path-prefix` pairs of each rustc call that was given
+    /// any, keyed like `dep_info_cwds`. The paths in the dep-info file are
+    /// remapped too.
+    pub dep_info_remaps: HashMap<PathBuf, Vec<PathRemap>>,
+
+    /// The kind of the target of each rustc call, keyed like
+    /// `dep_info_cwds`.
+    pub dep_info_target_kinds: HashMap<PathBuf, TargetKind>,
+
+    /// The compiler errors of the packages that failed to build, only
+    /// recorded when the build keeps going.
+    pub build_failures: HashMap<PackageId, String>,
+
+    /// The crate names of the proc-macro crates passed to the rustc calls of
+    /// each package, see `proc_macro_externs`.
+    pub proc_macros: HashMap<PackageId, BTreeSet<String>>,
+}
+
+#[cfg(test)]
+mod custom_executor_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_args,
+        expected_dep_info_path,
+        case(
+            vec!["--crate-name", "foo", "--out-dir", "/target/debug/deps"],
+            Some("/target/debug/deps/foo.d")
+        ),
+        case(
+            vec![
+                "--crate-name",
+                "foo",
+                "-C",
+                "extra-filename=-0123abcd",
+                "--out-dir",
+                "/target/debug/deps"
+            ],
+            Some("/target/debug/deps/foo-0123abcd.d")
+        ),
+        case(vec!["--out-dir", "/target/debug/deps"], None)
+    )]
+    fn dep_info_path_test(
+        input_args: Vec<&str>,
+        expected_dep_info_path: Option<&str>,
+    ) {
+        let args = input_args
+            .into_iter()
+            .map(OsString::from)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            dep_info_path(&args, Path::new("/target/debug/deps")),
+            expected_dep_info_path.map(PathBuf::from)
+        );
+    }
+
+    #[rstest(
+        input_args,
+        expected_rustc_args,
+        case(
+            vec!["--crate-name", "foo", "src/lib.rs", "--out-dir", "deps"],
+            vec!["--crate-name", "foo", "src/lib.rs", "--out-dir", "deps"]
+        ),
+        case(
+            vec!["/usr/bin/rustc", "--crate-name", "foo", "src/lib.rs"],
+            vec!["--crate-name", "foo", "src/lib.rs"]
+        ),
+        case(
+            vec![
+                "/home/user/.rustup/toolchains/stable/bin/rustc",
+                "--crate-name",
+                "foo"
+            ],
+            vec!["--crate-name", "foo"]
+        ),
+        case(
+            vec!["src/main.rs", "--edition=2018"],
+            vec!["src/main.rs", "--edition=2018"]
+        )
+    )]
+    fn rustc_args_skips_wrapper_arguments_test(
+        input_args: Vec<&str>,
+        expected_rustc_args: Vec<&str>,
+    ) {
+        let args = input_args
+            .into_iter()
+            .map(OsString::from)
+            .collect::<Vec<_>>();
+        let expected_rustc_args = expected_rustc_args
+            .into_iter()
+            .map(OsString::from)
+            .collect::<Vec<_>>();
+
+        assert_eq!(rustc_args(&args), expected_rustc_args.as_slice());
+    }
+
+    #[rstest]
+    fn rs_file_args_of_wrapped_rustc_test() {
+        let args = vec![
+            "/usr/local/bin/rustc",
+            "--crate-name",
+            "build_script_build",
+            "--edition=2018",
+            "build.rs",
+            "--error-format=json",
+            "-C",
+            "metadata=0123abcd",
+            "--out-dir",
+            "/target/debug/build/foo-0123abcd",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect::<Vec<_>>();
+
+        let rustc_args = rustc_args(&args);
+        assert_eq!(
+            rs_file_args(rustc_args).collect::<Vec<_>>(),
+            vec![&OsString::from("build.rs")]
+        );
+        assert_eq!(
+            dep_info_path(rustc_args, Path::new("/target/debug/deps")),
+            Some(PathBuf::from("/target/debug/deps/build_script_build.d"))
+        );
+    }
+
+    #[rstest(
+        input_extern_path,
+        expected_crate_name,
+        case(
+            "/target/debug/deps/libserde_derive-0123abcd.so",
+            Some("serde_derive")
+        ),
+        case(
+            "/target/debug/deps/libasync_trait-0123abcd.dylib",
+            Some("async_trait")
+        ),
+        case(
+            "/target/debug/deps/serde_derive-0123abcd.dll",
+            Some("serde_derive")
+        ),
+        case("/target/debug/deps/liblibc-0123abcd.rlib", None),
+        case("/target/debug/deps/libserde-0123abcd.rmeta", None)
+    )]
+    fn proc_macro_externs_test(
+        input_extern_path: &str,
+        expected_crate_name: Option<&str>,
+    ) {
+        let args = vec![
+            OsString::from("--crate-name"),
+            OsString::from("user"),
+            OsString::from("--extern"),
+            OsString::from(format!("renamed={}", input_extern_path)),
+            OsString::from("--extern"),
+            OsString::from("proc_macro"),
+        ];
+
+        assert_eq!(
+            proc_macro_externs(&args).collect::<Vec<_>>(),
+            expected_crate_name
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn rustc_error_text_test() {
+        let stderr = [
+            r#"{"level":"warning","rendered":"warning: unused variable\n"}"#,
+            r#"{"level":"error","rendered":"\u001b[1merror\u001b[0m: this crate doesn't build\n"}"#,
+            "error: aborting due to previous error",
+        ]
+        .join("\n");
+
+        assert_eq!(
+            rustc_error_text(stderr.as_bytes()),
+            "error: this crate doesn't build\nerror: aborting due to previous error"
+        );
+    }
 }
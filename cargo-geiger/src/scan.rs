@@ -1,37 +1,199 @@
 mod default;
 mod find;
 mod forbid;
+mod published;
+mod unsafe_criteria;
 
 use crate::args::Args;
-use crate::format::print_config::PrintConfig;
+use crate::format::print_config::{OutputFormat, PrintConfig};
 use crate::graph::Graph;
-use crate::rs_file::RsFileMetricsWrapper;
+use crate::rs_file::{is_in_out_dir, RsFileMetricsWrapper};
+use crate::timings::Timings;
 
 use default::scan_unsafe;
 use forbid::scan_forbid_unsafe;
 
+pub use default::MergedScan;
+pub use published::scan_published_crate;
+pub use unsafe_criteria::UnsafeCriteria;
+
 use crate::krates_utils::CargoMetadataParameters;
 use cargo::core::dependency::DepKind;
-use cargo::core::{PackageId, PackageSet, Workspace};
-use cargo::{CliResult, Config};
+use cargo::core::{Package, PackageId, PackageSet, Resolve, Workspace};
+use cargo::util::CargoResult;
+use cargo::{CliError, CliResult, Config};
 use cargo_geiger_serde::{
-    CounterBlock, DependencyKind, PackageInfo, UnsafeInfo,
+    ApproximateUnsafety, CounterBlock, DependencyKind, GeneratedUnsafety,
+    InconsistentForbid, ModuleUnsafety, NativeLinkage, NotScannedReason,
+    OversizedFile, PackageInfo, ProcMacroUse, PublicApiUnsafety, ReportEntry,
+    SafetyReport, ScanStatus, ScanWarning, ScannedFile, TargetKind, UnsafeInfo,
+    WatchedCallCount,
 };
+use geiger::{count_unsafe_tokens_in_file, RsFileMetrics, ScanFileError};
+use petgraph::algo::tarjan_scc;
 use petgraph::visit::EdgeRef;
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use url::Url;
 
 /// Provides a more terse and searchable name for the wrapped generic
 /// collection.
 pub struct GeigerContext {
     pub package_id_to_metrics: HashMap<PackageId, PackageMetrics>,
+    /// Files that failed to parse and paths that could not be
+    /// canonicalized.
+    pub warnings: Vec<ScanWarning>,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct PackageMetrics {
-    /// The key is the canonicalized path to the rs source file.
+    /// The key is the canonicalized path to the rs source file. Only kept at
+    /// `DetailLevel::Detailed`, see `fold_files`.
     pub rs_path_to_metrics: HashMap<PathBuf, RsFileMetricsWrapper>,
+    /// The metrics of the files added up, see `FileGroup`.
+    pub file_groups: HashMap<FileGroup, RsFileMetrics>,
+    /// The canonicalized paths of the files with metrics.
+    pub rs_paths: HashSet<PathBuf>,
+    /// The files with unsafe usage if an entry point forbids unsafe code,
+    /// sorted. A forbid in one entry point doesn't cover the others, like a
+    /// build script, nor the examples and tests.
+    pub inconsistent_forbid: Vec<PathBuf>,
+    /// Files that failed to parse, these have no metrics.
+    pub parse_failures: Vec<PathBuf>,
+    /// The number of `unsafe` keywords of each file in `parse_failures` that
+    /// could still be lexed, counted from its tokens.
+    pub approximated: HashMap<PathBuf, u64>,
+    /// Files larger than `--max-file-size` with their size in bytes, these
+    /// were not read.
+    pub oversized: HashMap<PathBuf, u64>,
+}
+
+impl PackageMetrics {
+    /// Records a file that failed to scan. A file that only failed to parse
+    /// still has its `unsafe` keywords counted from its tokens, a single
+    /// construct syn can't parse would hide all unsafe code of the file
+    /// otherwise. Files that were too large to scan are kept apart.
+    pub fn add_scan_failure(
+        &mut self,
+        error: &ScanFileError,
+        path_buf: PathBuf,
+    ) {
+        if let ScanFileError::TooLarge(size, _) = error {
+            self.oversized.insert(path_buf, *size);
+            return;
+        }
+        if let ScanFileError::Syn(..) = error {
+            if let Some(unsafe_tokens) = count_unsafe_tokens_in_file(&path_buf)
+            {
+                self.approximated.insert(path_buf.clone(), unsafe_tokens);
+            }
+        }
+        self.parse_failures.push(path_buf);
+    }
+
+    /// Adds up the metrics of the files in `rs_path_to_metrics` by their
+    /// `FileGroup`, `file_usage` tells how the build used each of them. The
+    /// metrics of each file are only kept at `DetailLevel::Detailed`.
+    pub fn fold_files(
+        &mut self,
+        file_usage: &FileUsage,
+        detail_level: DetailLevel,
+    ) {
+        let claims_forbid = self
+            .rs_path_to_metrics
+            .values()
+            .any(|v| v.is_crate_entry_point && v.metrics.forbids_unsafe);
+        let mut file_groups = HashMap::<FileGroup, RsFileMetrics>::new();
+        let mut inconsistent_forbid = Vec::new();
+        for (path_buf, rs_file_metrics_wrapper) in &self.rs_path_to_metrics {
+            let file_group = FileGroup {
+                is_used: file_usage.rs_files_used.contains(path_buf),
+                target_kinds: file_usage
+                    .target_kinds
+                    .get(path_buf)
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .collect(),
+                is_crate_entry_point: rs_file_metrics_wrapper
+                    .is_crate_entry_point,
+                is_generated: rs_file_metrics_wrapper.is_generated,
+                forbids_unsafe: rs_file_metrics_wrapper.metrics.forbids_unsafe,
+            };
+            add_file_metrics(
+                file_groups.entry(file_group).or_default(),
+                &rs_file_metrics_wrapper.metrics,
+            );
+            if claims_forbid
+                && rs_file_metrics_wrapper.metrics.counters.has_unsafe()
+            {
+                inconsistent_forbid.push(path_buf.clone());
+            }
+        }
+        inconsistent_forbid.sort();
+        self.file_groups = file_groups;
+        self.inconsistent_forbid = inconsistent_forbid;
+        self.rs_paths = self.rs_path_to_metrics.keys().cloned().collect();
+        if detail_level == DetailLevel::Summary {
+            self.rs_path_to_metrics = HashMap::new();
+        }
+    }
+
+    /// Counts all files as not used by any target, for a package that failed
+    /// to build.
+    pub fn count_as_unused(&mut self) {
+        let mut file_groups = HashMap::<FileGroup, RsFileMetrics>::new();
+        for (file_group, metrics) in self.file_groups.drain() {
+            let file_group = FileGroup {
+                is_used: false,
+                target_kinds: BTreeSet::new(),
+                ..file_group
+            };
+            add_file_metrics(
+                file_groups.entry(file_group).or_default(),
+                &metrics,
+            );
+        }
+        self.file_groups = file_groups;
+    }
+}
+
+/// What the metrics of the files of a package are told apart by when they
+/// are added up, all that the outputs need except the ones showing where in
+/// the files the unsafe usage is.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct FileGroup {
+    /// The files are used by the build.
+    pub is_used: bool,
+    /// The kinds of the targets that used the files.
+    pub target_kinds: BTreeSet<TargetKind>,
+    pub is_crate_entry_point: bool,
+    pub is_generated: bool,
+    /// The files are decorated with `#![forbid(unsafe_code)]`.
+    pub forbids_unsafe: bool,
+}
+
+/// What the build tells about the scanned files. Without a build, like for
+/// `--forbid-only`, no file is used.
+pub struct FileUsage<'a> {
+    pub rs_files_used: &'a HashSet<PathBuf>,
+    pub target_kinds: &'a HashMap<PathBuf, HashSet<TargetKind>>,
+}
+
+/// Adds the metrics of a file to `total`. The breakdowns by module, the lines
+/// of the `transmute` calls and the `#[path]` modules only make sense within
+/// a file, they are left out.
+fn add_file_metrics(total: &mut RsFileMetrics, metrics: &RsFileMetrics) {
+    total.counters += metrics.counters.clone();
+    total.public_api += metrics.public_api.clone();
+    for (feature, unsafe_) in &metrics.unsafe_by_feature {
+        *total.unsafe_by_feature.entry(feature.clone()).or_insert(0) += unsafe_;
+    }
+    for (pattern, count) in &metrics.watched_calls {
+        *total.watched_calls.entry(pattern.clone()).or_default() +=
+            count.clone();
+    }
+    total.verification_harness += metrics.verification_harness.clone();
 }
 
 pub enum ScanMode {
@@ -44,27 +206,185 @@ pub enum ScanMode {
     Full,
 }
 
+/// How much of the metrics of each scanned file is kept. The metrics of each
+/// file and the breakdowns of where in a file the unsafe usage is only matter
+/// to a few outputs, on large dependency graphs they would take up most of
+/// the memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DetailLevel {
+    /// The metrics of the files of each package added up, see `FileGroup`.
+    /// The default.
+    Summary,
+    /// The metrics of each file too, with the unsafe usage by module and the
+    /// lines of the `transmute` calls, for `--report-modules`, `--verbose`
+    /// and the packages of `--explain`.
+    Detailed,
+}
+
+impl DetailLevel {
+    /// `rs_file_metrics` without the parts not kept at this level.
+    pub fn retain(self, rs_file_metrics: RsFileMetrics) -> RsFileMetrics {
+        match self {
+            DetailLevel::Summary => RsFileMetrics {
+                unsafe_by_module: HashMap::new(),
+                transmute_lines: Vec::new(),
+                ..rs_file_metrics
+            },
+            DetailLevel::Detailed => rs_file_metrics,
+        }
+    }
+}
+
+/// The name unsafe usage outside of feature gates is reported under.
+pub const UNCONDITIONAL_FEATURE: &str = "(unconditional)";
+
 pub struct ScanParameters<'a> {
     pub args: &'a Args,
     pub config: &'a Config,
     pub print_config: &'a PrintConfig,
+    pub resolve: &'a Resolve,
+    pub timings: &'a Timings,
+    /// The canonical root of the workspace, paths are shown relative to it
+    pub workspace_root: &'a Path,
+}
+
+/// The resolved workspace to scan, with its dependency graph.
+pub struct WorkspaceParameters<'a, 'cfg> {
+    pub cargo_metadata_parameters: &'a CargoMetadataParameters<'a>,
+    pub graph: &'a Graph,
+    pub package_set: &'a PackageSet<'cfg>,
+    pub resolve: &'a Resolve,
+    pub root_package_id: PackageId,
+    pub workspace: &'a Workspace<'cfg>,
 }
 
 pub fn scan(
     args: &Args,
-    cargo_metadata_parameters: &CargoMetadataParameters,
     config: &Config,
-    graph: &Graph,
-    package_set: &PackageSet,
-    root_package_id: PackageId,
-    workspace: &Workspace,
+    print_config: &PrintConfig,
+    timings: &Timings,
+    workspace_parameters: &WorkspaceParameters,
 ) -> CliResult {
-    let print_config = PrintConfig::new(args)?;
+    let WorkspaceParameters {
+        cargo_metadata_parameters,
+        graph,
+        package_set,
+        resolve,
+        root_package_id,
+        workspace,
+    } = *workspace_parameters;
+    if args.reuse_report.is_some()
+        && (args.forbid_only || args.output_format != Some(OutputFormat::Json))
+    {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--reuse-report` requires `--json` and can't be combined \
+                 with `--forbid-only`"
+            ),
+            1,
+        ));
+    }
+
+    if args.output.is_some()
+        && (args.forbid_only
+            || args.output_format != Some(OutputFormat::Json)
+            || args.reuse_report.is_some())
+    {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--output` requires `--json` and can't be combined with \
+                 `--forbid-only` or `--reuse-report`"
+            ),
+            1,
+        ));
+    }
+
+    if args.output_dir.is_some()
+        && (args.forbid_only || args.output_format != Some(OutputFormat::Json))
+    {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--output-dir` requires `--json` and can't be combined with \
+                 `--forbid-only`"
+            ),
+            1,
+        ));
+    }
+
+    if args.prune_output && args.output_dir.is_none() {
+        return Err(CliError::new(
+            anyhow::anyhow!("`--prune-output` requires `--output-dir`"),
+            1,
+        ));
+    }
+
+    if args.output_format == Some(OutputFormat::TreeJson) && args.forbid_only {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--output-format tree-json` can't be combined with \
+                 `--forbid-only`"
+            ),
+            1,
+        ));
+    }
+
+    if args.progress_json && args.forbid_only {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--progress-json` can't be combined with `--forbid-only`"
+            ),
+            1,
+        ));
+    }
+
+    if args.members_only
+        && (args.forbid_only
+            || args.explain.is_some()
+            || args.output_format == Some(OutputFormat::TreeJson))
+    {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--members-only` can't be combined with `--forbid-only`, \
+                 `--explain` or `--output-format tree-json`"
+            ),
+            1,
+        ));
+    }
+
+    if args.split_local_paths
+        && (args.forbid_only
+            || args.explain.is_some()
+            || args.output_format == Some(OutputFormat::TreeJson))
+    {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--split-local-paths` can't be combined with \
+                 `--forbid-only`, `--explain` or `--output-format tree-json`"
+            ),
+            1,
+        ));
+    }
+
+    if args.explain.is_some() && args.forbid_only {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--explain` can't be combined with `--forbid-only`"
+            ),
+            1,
+        ));
+    }
 
+    let workspace_root = workspace
+        .root()
+        .canonicalize()
+        .map_err(anyhow::Error::from)?;
     let scan_parameters = ScanParameters {
         args: &args,
         config: &config,
-        print_config: &print_config,
+        print_config,
+        resolve,
+        timings,
+        workspace_root: &workspace_root,
     };
 
     if args.forbid_only {
@@ -98,37 +418,342 @@ pub fn unsafe_stats(
     // files must declare `forbid(unsafe_code)`. Either a crate
     // forbids all unsafe code or it allows it _to some degree_.
     let forbids_unsafe = pack_metrics
-        .rs_path_to_metrics
-        .iter()
-        .filter(|(_, v)| v.is_crate_entry_point)
-        .all(|(_, v)| v.metrics.forbids_unsafe);
+        .file_groups
+        .keys()
+        .filter(|file_group| file_group.is_crate_entry_point)
+        .all(|file_group| file_group.forbids_unsafe);
 
     let mut used = CounterBlock::default();
     let mut unused = CounterBlock::default();
+    let mut generated: Option<GeneratedUnsafety> = None;
+    let mut public_api = PublicApiUnsafety::default();
+    let mut watched_calls = BTreeMap::<String, WatchedCallCount>::new();
+    let mut verification_harness: Option<CounterBlock> = None;
+    let mut approximate: Option<ApproximateUnsafety> = None;
 
-    for (path_buf, rs_file_metrics_wrapper) in &pack_metrics.rs_path_to_metrics
-    {
-        let target = if rs_files_used.contains(path_buf) {
-            &mut used
+    for (file_group, metrics) in &pack_metrics.file_groups {
+        let is_used = file_group.is_used;
+        let harness = &metrics.verification_harness;
+        if *harness != CounterBlock::default() {
+            *verification_harness.get_or_insert_with(CounterBlock::default) +=
+                harness.clone();
+        }
+        let target = if is_used { &mut used } else { &mut unused };
+        *target += metrics.counters.clone();
+        let target = if is_used {
+            &mut public_api.used
         } else {
-            &mut unused
+            &mut public_api.unused
         };
-        *target += rs_file_metrics_wrapper.metrics.counters.clone();
+        *target += metrics.public_api.clone();
+        for (pattern, count) in &metrics.watched_calls {
+            let watched_call =
+                watched_calls.entry(pattern.clone()).or_default();
+            let target = if is_used {
+                &mut watched_call.used
+            } else {
+                &mut watched_call.unused
+            };
+            *target += count.clone();
+        }
+        if file_group.is_generated {
+            let generated =
+                generated.get_or_insert_with(GeneratedUnsafety::default);
+            let target = if is_used {
+                &mut generated.used
+            } else {
+                &mut generated.unused
+            };
+            *target += metrics.counters.clone();
+        }
+    }
+
+    for (path_buf, unsafe_tokens) in &pack_metrics.approximated {
+        let approximate =
+            approximate.get_or_insert_with(ApproximateUnsafety::default);
+        if rs_files_used.contains(path_buf) {
+            approximate.used += unsafe_tokens;
+        } else {
+            approximate.unused += unsafe_tokens;
+        }
     }
+
+    let mut skipped_oversized = pack_metrics
+        .oversized
+        .iter()
+        .map(|(path_buf, size)| OversizedFile {
+            path: path_buf.clone(),
+            size: *size,
+        })
+        .collect::<Vec<_>>();
+    skipped_oversized.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Without a single file used by the build there is no telling which
+    // files would have been used, all counters end up in `unused`.
+    let has_build_data = pack_metrics.rs_paths.is_empty()
+        || pack_metrics
+            .rs_paths
+            .iter()
+            .any(|path_buf| rs_files_used.contains(path_buf));
+    let status = if !has_build_data {
+        ScanStatus::NoBuildData
+    } else if !pack_metrics.parse_failures.is_empty()
+        || !pack_metrics.oversized.is_empty()
+    {
+        ScanStatus::ParseErrors
+    } else {
+        ScanStatus::Complete
+    };
+
     UnsafeInfo {
         used,
         unused,
         forbids_unsafe,
+        status,
+        modules: Vec::new(),
+        targets: BTreeMap::new(),
+        by_feature: BTreeMap::new(),
+        generated,
+        public_api,
+        watched_calls,
+        verification_harness,
+        approximate,
+        skipped_oversized,
+    }
+}
+
+/// Whether nothing of a package was built for the target, because it is only
+/// used on other platforms or none of its files made it past the `cfg` gates.
+/// A package that failed to build is not inactive, it would have been built.
+pub fn is_inactive(status: ScanStatus, build_failed: bool) -> bool {
+    !build_failed
+        && matches!(status, ScanStatus::NoBuildData | ScanStatus::OtherPlatform)
+}
+
+/// The metrics of the hand-written files of a package, the ones not detected
+/// as machine generated.
+pub fn hand_written_metrics(pack_metrics: &PackageMetrics) -> PackageMetrics {
+    PackageMetrics {
+        file_groups: pack_metrics
+            .file_groups
+            .iter()
+            .filter(|(file_group, _)| !file_group.is_generated)
+            .map(|(file_group, metrics)| (file_group.clone(), metrics.clone()))
+            .collect(),
+        // Generated files still tell whether the build used the package.
+        rs_paths: pack_metrics.rs_paths.clone(),
+        parse_failures: pack_metrics.parse_failures.clone(),
+        approximated: pack_metrics.approximated.clone(),
+        oversized: pack_metrics.oversized.clone(),
+        ..Default::default()
+    }
+}
+
+/// Unsafe usage of the files of a package used by the build, split by the
+/// kinds of the targets that used them.
+pub fn unsafe_by_target(
+    pack_metrics: &PackageMetrics,
+) -> BTreeMap<TargetKind, CounterBlock> {
+    let mut targets = BTreeMap::<TargetKind, CounterBlock>::new();
+    for (file_group, metrics) in &pack_metrics.file_groups {
+        for target_kind in &file_group.target_kinds {
+            *targets.entry(*target_kind).or_default() +=
+                metrics.counters.clone();
+        }
+    }
+    targets
+}
+
+/// The metrics of what depending on a package compiles: its library and
+/// build script. Files only used by other kinds of targets are left out, files
+/// that are not used at all are kept. `None` for packages without a library
+/// in the build, like binary crates, these are only meaningful as a whole.
+/// `target_kinds` are the kinds of the targets that used each file.
+pub fn library_metrics(
+    pack_metrics: &PackageMetrics,
+    target_kinds: &HashMap<PathBuf, HashSet<TargetKind>>,
+) -> Option<PackageMetrics> {
+    let has_library = pack_metrics
+        .file_groups
+        .keys()
+        .any(|file_group| file_group.target_kinds.contains(&TargetKind::Lib));
+    if !has_library {
+        return None;
+    }
+    Some(PackageMetrics {
+        file_groups: pack_metrics
+            .file_groups
+            .iter()
+            .filter(|(file_group, _)| is_library_file(&file_group.target_kinds))
+            .map(|(file_group, metrics)| (file_group.clone(), metrics.clone()))
+            .collect(),
+        rs_paths: pack_metrics
+            .rs_paths
+            .iter()
+            .filter(|path_buf| {
+                is_library_file(
+                    target_kinds.get(*path_buf).into_iter().flatten(),
+                )
+            })
+            .cloned()
+            .collect(),
+        parse_failures: pack_metrics.parse_failures.clone(),
+        approximated: pack_metrics.approximated.clone(),
+        oversized: pack_metrics.oversized.clone(),
+        ..Default::default()
+    })
+}
+
+/// Whether a file used by targets of these kinds is compiled by depending on
+/// its package. Files of unknown target kinds count as library files.
+fn is_library_file<'a>(
+    target_kinds: impl IntoIterator<Item = &'a TargetKind>,
+) -> bool {
+    let mut target_kinds = target_kinds.into_iter().peekable();
+    target_kinds.peek().is_none()
+        || target_kinds.any(|target_kind| {
+            matches!(target_kind, TargetKind::Lib | TargetKind::CustomBuild)
+        })
+}
+
+/// The `file:line` locations of the `transmute` calls of a package, relative
+/// to the package root and sorted by file and line. Calls in files not used by
+/// the build are marked as such.
+pub fn transmute_locations(
+    pack_metrics: &PackageMetrics,
+    package_root: &Path,
+    rs_files_used: &HashSet<PathBuf>,
+) -> Vec<String> {
+    let mut files = pack_metrics
+        .rs_path_to_metrics
+        .iter()
+        .filter(|(_, wrapper)| !wrapper.metrics.transmute_lines.is_empty())
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.0.cmp(b.0));
+    files
+        .into_iter()
+        .flat_map(|(path_buf, rs_file_metrics_wrapper)| {
+            let file = path_buf
+                .strip_prefix(package_root)
+                .unwrap_or(path_buf)
+                .display()
+                .to_string();
+            let suffix = if rs_files_used.contains(path_buf) {
+                ""
+            } else {
+                " (not used by the build)"
+            };
+            rs_file_metrics_wrapper
+                .metrics
+                .transmute_lines
+                .iter()
+                .map(move |line| format!("{}:{}{}", file, line, suffix))
+        })
+        .collect()
+}
+
+/// Merges the unsafe usage per feature of all files of a package. Unsafe usage
+/// outside of feature gates is keyed by `UNCONDITIONAL_FEATURE`.
+pub fn unsafe_by_feature(
+    pack_metrics: &PackageMetrics,
+) -> BTreeMap<String, u64> {
+    let mut by_feature = BTreeMap::new();
+    for metrics in pack_metrics.file_groups.values() {
+        for (feature, unsafe_) in &metrics.unsafe_by_feature {
+            let feature = if feature.is_empty() {
+                String::from(UNCONDITIONAL_FEATURE)
+            } else {
+                feature.clone()
+            };
+            *by_feature.entry(feature).or_insert(0) += unsafe_;
+        }
+    }
+    by_feature
+}
+
+/// Merges the unsafe usage per module of all files of a package, most unsafe
+/// first. Files are named relative to `package_root`.
+pub fn unsafe_by_module(
+    pack_metrics: &PackageMetrics,
+    package_root: &Path,
+) -> Vec<ModuleUnsafety> {
+    let mut modules = pack_metrics
+        .rs_path_to_metrics
+        .iter()
+        .flat_map(|(path_buf, rs_file_metrics_wrapper)| {
+            let file = path_buf
+                .strip_prefix(package_root)
+                .unwrap_or(path_buf)
+                .display()
+                .to_string();
+            rs_file_metrics_wrapper.metrics.unsafe_by_module.iter().map(
+                move |(module_path, unsafe_)| ModuleUnsafety {
+                    module: if module_path.is_empty() {
+                        file.clone()
+                    } else {
+                        format!("{}::{}", file, module_path)
+                    },
+                    unsafe_: *unsafe_,
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    modules.sort_by(|a, b| {
+        b.unsafe_
+            .cmp(&a.unsafe_)
+            .then_with(|| a.module.cmp(&b.module))
+    });
+    modules
+}
+
+/// The files scanned for a package, including the ones that failed to parse,
+/// sorted by path. The paths are relative to `package_root` and use `/` as
+/// the separator, so reports from different platforms can be compared.
+pub fn scanned_files(
+    pack_metrics: &PackageMetrics,
+    package_root: &Path,
+    rs_files_used: &HashSet<PathBuf>,
+) -> Vec<ScannedFile> {
+    let mut files = pack_metrics
+        .rs_paths
+        .iter()
+        .chain(&pack_metrics.parse_failures)
+        .map(|path_buf| ScannedFile {
+            path: with_forward_slashes(
+                path_buf.strip_prefix(package_root).unwrap_or(path_buf),
+            ),
+            used: rs_files_used.contains(path_buf),
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+fn with_forward_slashes(path: &Path) -> String {
+    let path = path.display().to_string();
+    if std::path::MAIN_SEPARATOR == '/' {
+        path
+    } else {
+        path.replace(std::path::MAIN_SEPARATOR, "/")
     }
 }
 
 struct ScanDetails {
     rs_files_used: HashSet<PathBuf>,
+    target_kinds: HashMap<PathBuf, HashSet<TargetKind>>,
     geiger_context: GeigerContext,
+    build_failures: HashMap<PackageId, String>,
+    /// The warnings of the scan and of the build, see `report_warnings` for
+    /// the ones that follow from the results.
+    warnings: Vec<ScanWarning>,
+    /// The crate names of the proc-macro crates applied to each package
+    proc_macros: HashMap<PackageId, BTreeSet<String>>,
 }
 
 fn construct_rs_files_used_lines(
     rs_files_used: &HashSet<PathBuf>,
+    workspace_root: &Path,
+    normalize_output: bool,
 ) -> Vec<String> {
     // Print all .rs files found through the .d files, in sorted order.
     let mut paths = rs_files_used
@@ -140,7 +765,12 @@ fn construct_rs_files_used_lines(
 
     paths
         .iter()
-        .map(|p| format!("Used by build (sorted): {}", p.display()))
+        .map(|p| {
+            format!(
+                "Used by build (sorted): {}",
+                display_path(p, workspace_root, normalize_output)
+            )
+        })
         .collect::<Vec<String>>()
 }
 
@@ -151,7 +781,7 @@ fn list_files_used_but_not_scanned(
     let scanned_files = geiger_context
         .package_id_to_metrics
         .iter()
-        .flat_map(|(_, v)| v.rs_path_to_metrics.keys())
+        .flat_map(|(_, v)| &v.rs_paths)
         .collect::<HashSet<&PathBuf>>();
     rs_files_used
         .iter()
@@ -160,11 +790,414 @@ fn list_files_used_but_not_scanned(
         .collect()
 }
 
-fn package_metrics<'a>(
-    geiger_context: &'a GeigerContext,
-    graph: &'a Graph,
+/// A warning for each file used by the build that was not scanned, with the
+/// reason guessed from its path.
+pub fn not_scanned_warnings<'a>(
+    paths: impl IntoIterator<Item = &'a PathBuf>,
+    package_roots: &[PathBuf],
+) -> Vec<ScanWarning> {
+    paths
+        .into_iter()
+        .map(|path| ScanWarning::NotScanned {
+            path: path.clone(),
+            reason: Some(not_scanned_reason(path, package_roots, |path| {
+                path.exists()
+            })),
+        })
+        .collect()
+}
+
+/// The most specific reason first, a file in an `OUT_DIR` is outside all
+/// package roots as well.
+fn not_scanned_reason(
+    path: &Path,
+    package_roots: &[PathBuf],
+    exists: impl Fn(&Path) -> bool,
+) -> NotScannedReason {
+    if path.extension().map_or(true, |extension| extension != "rs") {
+        NotScannedReason::NotRust
+    } else if is_in_out_dir(path) {
+        NotScannedReason::OutDir
+    } else if !exists(path) {
+        NotScannedReason::Missing
+    } else if !package_roots.iter().any(|root| path.starts_with(root)) {
+        NotScannedReason::OutsidePackageRoots
+    } else {
+        NotScannedReason::InsidePackageRoot
+    }
+}
+
+/// The roots of all packages with sources, canonicalized like the scanned
+/// paths.
+pub fn all_package_roots(package_set: &PackageSet) -> Vec<PathBuf> {
+    package_set
+        .package_ids()
+        .filter_map(|id| package_root(package_set, id).ok())
+        .collect()
+}
+
+/// The warnings of a finished report, `scan_warnings` followed by the ones
+/// that follow from the report itself: packages without build data or
+/// metrics, files used by the build that were not scanned and packages that
+/// failed to build. The unscanned files are classified against
+/// `package_roots`.
+pub fn report_warnings(
+    report: &SafetyReport,
+    scan_warnings: &[ScanWarning],
+    package_roots: &[PathBuf],
+) -> Vec<ScanWarning> {
+    let unavailable = unavailable_packages(scan_warnings);
+    let mut warnings = report
+        .packages
+        .values()
+        .filter(|entry| entry.unsafety.status == ScanStatus::NoBuildData)
+        .map(|entry| ScanWarning::NoBuildData {
+            package: entry.package.id.clone(),
+        })
+        .chain(
+            report
+                .packages_without_metrics
+                .iter()
+                .filter(|id| !unavailable.contains(id))
+                .map(|id| ScanWarning::NoMetrics {
+                    package: id.clone(),
+                }),
+        )
+        .chain(not_scanned_warnings(
+            &report.used_but_not_scanned_files,
+            package_roots,
+        ))
+        .chain(report.build_failures.keys().map(|id| {
+            ScanWarning::BuildFailed {
+                package: id.clone(),
+            }
+        }))
+        .collect::<Vec<_>>();
+    warnings.sort();
+    let mut all_warnings = scan_warnings.to_vec();
+    all_warnings.append(&mut warnings);
+    all_warnings
+}
+
+/// The packages the scan warned about as unavailable. They have no metrics
+/// either, that is not warned about again.
+pub fn unavailable_packages(
+    scan_warnings: &[ScanWarning],
+) -> HashSet<&cargo_geiger_serde::PackageId> {
+    scan_warnings
+        .iter()
+        .filter_map(|warning| match warning {
+            ScanWarning::Unavailable { package, .. } => Some(package),
+            _ => None,
+        })
+        .collect()
+}
+
+/// What the workspace root is printed as with `--normalize-output`.
+const NORMALIZED_ROOT: &str = "[ROOT]";
+
+/// `path` relative to the workspace root, or as it is if it's outside of the
+/// workspace, like the files of registry dependencies. With
+/// `normalize_output` paths in the workspace start with `[ROOT]` and use `/`
+/// as the separator instead, the same wherever the workspace is.
+pub fn display_path(
+    path: &Path,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> String {
+    match path.strip_prefix(workspace_root) {
+        // Joined by components, the root itself has no trailing separator.
+        Ok(relative_path) if normalize_output => with_forward_slashes(
+            &Path::new(NORMALIZED_ROOT)
+                .components()
+                .chain(relative_path.components())
+                .collect::<PathBuf>(),
+        ),
+        Ok(relative_path) => relative_path.display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// The package id as cargo prints it, with the path of path dependencies
+/// shown like `display_path` with `normalize_output`.
+pub fn display_package_id(
+    package_id: PackageId,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> String {
+    if !normalize_output || !package_id.source_id().is_path() {
+        return package_id.to_string();
+    }
+    match package_id.source_id().url().to_file_path() {
+        Ok(path) => format!(
+            "{} v{} ({})",
+            package_id.name(),
+            package_id.version(),
+            display_path(
+                &path.canonicalize().unwrap_or(path),
+                workspace_root,
+                normalize_output
+            )
+        ),
+        Err(()) => package_id.to_string(),
+    }
+}
+
+/// Prints each warning on stderr, paths relative to the workspace root. The
+/// warnings are sorted by their message with `normalize_output`.
+pub fn print_warnings(
+    warnings: &[ScanWarning],
+    workspace_root: &Path,
+    normalize_output: bool,
+) {
+    for warning_line in
+        construct_warning_lines(warnings, workspace_root, normalize_output)
+    {
+        eprintln!("{}", warning_line);
+    }
+}
+
+fn construct_warning_lines(
+    warnings: &[ScanWarning],
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> Vec<String> {
+    let mut warning_lines = warnings
+        .iter()
+        .map(|warning| {
+            format!(
+                "WARNING: {}",
+                warning_message(warning, workspace_root, normalize_output)
+            )
+        })
+        .collect::<Vec<_>>();
+    if normalize_output {
+        warning_lines.sort();
+    }
+    warning_lines
+}
+
+fn warning_message(
+    warning: &ScanWarning,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> String {
+    let display_path =
+        |path: &Path| display_path(path, workspace_root, normalize_output);
+    let package_name = |id: &cargo_geiger_serde::PackageId| {
+        format!("{} v{}", id.name, id.version)
+    };
+    match warning {
+        ScanWarning::ParseFailed { path, error } => {
+            format!("Failed to parse file: {}, {}", display_path(path), error)
+        }
+        ScanWarning::Unreadable { path, error } => {
+            format!("Failed to read file: {}, {}", display_path(path), error)
+        }
+        ScanWarning::Oversized { path, size } => format!(
+            "File larger than --max-file-size was not scanned: {}, {} bytes",
+            display_path(path),
+            size
+        ),
+        ScanWarning::NotCanonicalizable { path } => {
+            format!("Path could not be canonicalized: {}", display_path(path))
+        }
+        ScanWarning::MissingDepInfoPath { path } => format!(
+            "File listed in a dep-info file was not found, it is counted as \
+             not used by the build: {}",
+            display_path(path)
+        ),
+        ScanWarning::NoBuildData { package } => format!(
+            "No files used by the build, all unsafe usage is counted as not \
+             used: {}",
+            package_name(package)
+        ),
+        ScanWarning::NoMetrics { package } => {
+            format!("No metrics found for package: {}", package_name(package))
+        }
+        ScanWarning::NotScanned { path, reason } => format!(
+            "Dependency file was never scanned: {}{}",
+            display_path(path),
+            reason.map_or_else(String::new, |reason| format!(
+                ", {}",
+                not_scanned_hint(reason)
+            ))
+        ),
+        ScanWarning::BuildFailed { package } => format!(
+            "Package failed to build, its files are counted as not used: {}",
+            package_name(package)
+        ),
+        ScanWarning::Unavailable { package, reason } => format!(
+            "Package sources could not be downloaded or read, it was not \
+             scanned: {}, {}",
+            package_name(package),
+            reason
+        ),
+    }
+}
+
+/// What a file used by the build but not scanned likely is, and what to do
+/// about it if anything.
+fn not_scanned_hint(reason: NotScannedReason) -> &'static str {
+    match reason {
+        NotScannedReason::NotRust => {
+            "not a .rs file, like one pulled in with `include_str!`, only Rust \
+             code is scanned"
+        }
+        NotScannedReason::OutDir => {
+            "written by a build script to its OUT_DIR, generated code outside \
+             of the package directories is not scanned yet"
+        }
+        NotScannedReason::Missing => {
+            "the file no longer exists, scan again after a fresh build"
+        }
+        NotScannedReason::OutsidePackageRoots => {
+            "outside of all package directories, like a file included through \
+             a path leaving its package or a registry cache out of sync with \
+             the sources, the files used by the build are listed with \
+             `--verbose`"
+        }
+        NotScannedReason::InsidePackageRoot => {
+            "below a package directory but not found by the scan of it, like \
+             a file behind a symlink"
+        }
+    }
+}
+
+/// A footer with the number of warnings of each kind, empty without
+/// warnings.
+fn construct_warning_summary_lines(warnings: &[ScanWarning]) -> Vec<String> {
+    let mut counts = BTreeMap::<&str, usize>::new();
+    for warning in warnings {
+        let kind = match warning {
+            ScanWarning::ParseFailed { .. } => "files failed to parse",
+            ScanWarning::Unreadable { .. } => "files could not be read",
+            ScanWarning::Oversized { .. } => "files were too large to scan",
+            ScanWarning::NotCanonicalizable { .. } => {
+                "paths could not be canonicalized"
+            }
+            ScanWarning::MissingDepInfoPath { .. } => {
+                "files listed in dep-info files were not found"
+            }
+            ScanWarning::NoBuildData { .. } => {
+                "packages have no files used by the build"
+            }
+            ScanWarning::NoMetrics { .. } => "packages have no metrics",
+            ScanWarning::NotScanned { .. } => {
+                "files used by the build were not scanned"
+            }
+            ScanWarning::BuildFailed { .. } => "packages failed to build",
+            ScanWarning::Unavailable { .. } => "packages were unavailable",
+        };
+        *counts.entry(kind).or_insert(0) += 1;
+    }
+    if counts.is_empty() {
+        return Vec::new();
+    }
+    let mut summary_lines = vec![String::from("Warnings, listed on stderr:")];
+    summary_lines.extend(
+        counts
+            .into_iter()
+            .map(|(kind, count)| format!("    {} {}", count, kind)),
+    );
+    summary_lines.push(String::new());
+    summary_lines
+}
+
+/// Builds the report of a finished scan of the dependency graph below
+/// `root_package_id`. Only what the scan itself tells is filled in, the
+/// report metadata, build failures and per module or target breakdowns are
+/// left empty.
+pub fn build_safety_report(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
     root_package_id: PackageId,
-) -> impl Iterator<Item = (PackageInfo, Option<&'a PackageMetrics>)> {
+    rs_files_used: &HashSet<PathBuf>,
+) -> SafetyReport {
+    let mut report = SafetyReport {
+        used_but_not_scanned_files: list_files_used_but_not_scanned(
+            geiger_context,
+            rs_files_used,
+        )
+        .into_iter()
+        .collect(),
+        dependency_cycles: dependency_cycles(graph),
+        ..Default::default()
+    };
+    for (id, package) in package_infos(graph, root_package_id) {
+        match geiger_context.package_id_to_metrics.get(&id) {
+            Some(package_metrics) => {
+                let mut unsafety = unsafe_stats(package_metrics, rs_files_used);
+                let platforms =
+                    graph.platforms.get(&id).cloned().unwrap_or_default();
+                if !platforms.is_empty() {
+                    unsafety.status = ScanStatus::OtherPlatform;
+                }
+                if !package_metrics.inconsistent_forbid.is_empty() {
+                    report.inconsistent_forbid.push(InconsistentForbid {
+                        package: package.id.clone(),
+                        files: package_metrics.inconsistent_forbid.clone(),
+                    });
+                }
+                let entry = ReportEntry {
+                    package,
+                    unsafety,
+                    features: Vec::new(),
+                    platforms,
+                    native_linkage: None,
+                    dependency_counts: None,
+                    depth: None,
+                    files: Vec::new(),
+                    is_workspace_member: graph.members.contains(&id),
+                    proc_macros_applied: Vec::new(),
+                };
+                report.packages.insert(entry.package.id.clone(), entry);
+            }
+            None => {
+                report.packages_without_metrics.insert(package.id);
+            }
+        }
+    }
+    report
+        .inconsistent_forbid
+        .sort_by(|a, b| a.package.cmp(&b.package));
+    report
+}
+
+/// The features enabled for a package by the resolve. Cargo unifies them, a
+/// feature enabled by any dependent is enabled for all of them.
+pub fn resolved_features(
+    resolve: &Resolve,
+    package_id: PackageId,
+) -> Vec<String> {
+    let mut features = resolve
+        .features(package_id)
+        .iter()
+        .map(|feature| feature.to_string())
+        .collect::<Vec<_>>();
+    features.sort();
+    features
+}
+
+/// The native library a package links to, from the `links` key of its
+/// manifest. `None` for packages without the key.
+pub fn native_linkage(package: &Package) -> Option<NativeLinkage> {
+    let links = package.manifest().links()?;
+    Some(NativeLinkage {
+        links: links.to_string(),
+        build_script: package
+            .targets()
+            .iter()
+            .any(|target| target.is_custom_build()),
+    })
+}
+
+/// The packages of the dependency graph below `root_package_id`, with their
+/// dependencies.
+fn package_infos(
+    graph: &Graph,
+    root_package_id: PackageId,
+) -> impl Iterator<Item = (PackageId, PackageInfo)> + '_ {
     let root_index = graph.nodes[&root_package_id];
     let mut indices = vec![root_index];
     let mut visited = HashSet::new();
@@ -183,17 +1216,90 @@ fn package_metrics<'a>(
                 from_cargo_dependency_kind(*edge.weight()),
             );
         }
+        Some((id, package))
+    })
+}
+
+fn package_metrics<'a>(
+    geiger_context: &'a GeigerContext,
+    graph: &'a Graph,
+    root_package_id: PackageId,
+) -> impl Iterator<Item = (PackageInfo, Option<&'a PackageMetrics>)> {
+    package_infos(graph, root_package_id).map(move |(id, package)| {
         match geiger_context.package_id_to_metrics.get(&id) {
-            Some(m) => Some((package, Some(m))),
+            Some(m) => (package, Some(m)),
             None => {
                 eprintln!("WARNING: No metrics found for package: {}", id);
-                Some((package, None))
+                (package, None)
             }
         }
     })
 }
 
-fn from_cargo_package_id(id: PackageId) -> cargo_geiger_serde::PackageId {
+/// The root directory of a package, canonicalized like the scanned paths, see
+/// `find_rs_files_in_dir`.
+fn package_root(
+    package_set: &PackageSet,
+    package_id: PackageId,
+) -> CargoResult<PathBuf> {
+    Ok(canonical_package_root(package_set.get_one(package_id)?))
+}
+
+/// The scan walks this directory, so a vendored or otherwise symlinked
+/// package root is resolved the same way as the paths rustc reports.
+pub fn canonical_package_root(package: &Package) -> PathBuf {
+    let root = package.root();
+    root.canonicalize().unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// The proc-macro crates applied to the packages of the report, each with the
+/// packages it was applied to.
+fn proc_macro_uses(report: &SafetyReport) -> Vec<ProcMacroUse> {
+    let mut applied_to =
+        BTreeMap::<&str, Vec<cargo_geiger_serde::PackageId>>::new();
+    for entry in report.packages.values() {
+        for crate_name in &entry.proc_macros_applied {
+            applied_to
+                .entry(crate_name)
+                .or_insert_with(Vec::new)
+                .push(entry.package.id.clone());
+        }
+    }
+    applied_to
+        .into_iter()
+        .map(|(crate_name, mut applied_to)| {
+            applied_to.sort();
+            ProcMacroUse {
+                crate_name: crate_name.to_owned(),
+                applied_to,
+            }
+        })
+        .collect()
+}
+
+/// Groups of packages that depend on each other, which is possible through
+/// dev-dependencies. Both the groups and the packages in them are sorted.
+fn dependency_cycles(graph: &Graph) -> Vec<Vec<cargo_geiger_serde::PackageId>> {
+    let mut dependency_cycles = tarjan_scc(&graph.graph)
+        .into_iter()
+        .filter(|component| {
+            component.len() > 1
+                || graph.graph.contains_edge(component[0], component[0])
+        })
+        .map(|component| {
+            let mut package_ids = component
+                .into_iter()
+                .map(|index| from_cargo_package_id(graph.graph[index].id))
+                .collect::<Vec<_>>();
+            package_ids.sort();
+            package_ids
+        })
+        .collect::<Vec<_>>();
+    dependency_cycles.sort();
+    dependency_cycles
+}
+
+pub fn from_cargo_package_id(id: PackageId) -> cargo_geiger_serde::PackageId {
     let source = id.source_id();
     let source_url = source.url();
     // Canonicalize paths as cargo does not seem to do so on all platforms.
@@ -236,7 +1342,7 @@ fn from_cargo_package_id(id: PackageId) -> cargo_geiger_serde::PackageId {
     }
 }
 
-fn from_cargo_dependency_kind(kind: DepKind) -> DependencyKind {
+pub fn from_cargo_dependency_kind(kind: DepKind) -> DependencyKind {
     match kind {
         DepKind::Normal => DependencyKind::Normal,
         DepKind::Development => DependencyKind::Development,
@@ -248,44 +1354,137 @@ fn from_cargo_dependency_kind(kind: DepKind) -> DependencyKind {
 mod scan_tests {
     use super::*;
 
+    use crate::graph::{create_package_ids, graph_from_edges};
     use crate::{rs_file::RsFileMetricsWrapper, scan::PackageMetrics};
 
+    use cargo::core::SourceId;
     use cargo_geiger_serde::{Count, UnsafeInfo};
     use rstest::*;
-    use std::{collections::HashSet, path::PathBuf};
+    use std::env;
+    use std::{
+        collections::HashSet,
+        path::{Path, PathBuf},
+    };
 
     #[rstest]
     fn construct_rs_files_used_lines_test() {
         let mut rs_files_used = HashSet::<PathBuf>::new();
 
-        rs_files_used.insert(PathBuf::from("b/path.rs"));
-        rs_files_used.insert(PathBuf::from("a/path.rs"));
-        rs_files_used.insert(PathBuf::from("c/path.rs"));
+        rs_files_used.insert(PathBuf::from("/workspace/b/path.rs"));
+        rs_files_used.insert(PathBuf::from("/workspace/a/path.rs"));
+        rs_files_used.insert(PathBuf::from("/registry/c/path.rs"));
 
-        let rs_files_used_lines = construct_rs_files_used_lines(&rs_files_used);
+        let rs_files_used_lines = construct_rs_files_used_lines(
+            &rs_files_used,
+            Path::new("/workspace"),
+            false,
+        );
 
         assert_eq!(
             rs_files_used_lines,
             vec![
+                String::from("Used by build (sorted): /registry/c/path.rs"),
                 String::from("Used by build (sorted): a/path.rs"),
                 String::from("Used by build (sorted): b/path.rs"),
-                String::from("Used by build (sorted): c/path.rs"),
             ]
         );
     }
 
-    #[rstest]
-    fn unsafe_stats_from_nothing_are_empty() {
-        let stats = unsafe_stats(&Default::default(), &Default::default());
-        let expected = UnsafeInfo {
-            forbids_unsafe: true,
-            ..Default::default()
-        };
-        assert_eq!(stats, expected);
+    #[rstest(
+        input_path,
+        input_normalize_output,
+        expected_display_path,
+        case("/workspace/member/src/lib.rs", false, "member/src/lib.rs"),
+        case(
+            "/workspace-other/src/lib.rs",
+            false,
+            "/workspace-other/src/lib.rs"
+        ),
+        case(
+            "/registry/rand-0.8.5/src/lib.rs",
+            false,
+            "/registry/rand-0.8.5/src/lib.rs"
+        ),
+        case("/workspace/member/src/lib.rs", true, "[ROOT]/member/src/lib.rs"),
+        case("/workspace", true, "[ROOT]"),
+        case(
+            "/registry/rand-0.8.5/src/lib.rs",
+            true,
+            "/registry/rand-0.8.5/src/lib.rs"
+        )
+    )]
+    fn display_path_test(
+        input_path: &str,
+        input_normalize_output: bool,
+        expected_display_path: &str,
+    ) {
+        assert_eq!(
+            display_path(
+                Path::new(input_path),
+                Path::new("/workspace"),
+                input_normalize_output
+            ),
+            expected_display_path
+        );
     }
 
     #[rstest]
-    fn unsafe_stats_report_forbid_unsafe_as_true_if_all_entry_points_forbid_unsafe(
+    fn display_package_id_test() {
+        let workspace_root =
+            env::current_dir().unwrap().canonicalize().unwrap();
+        let source_id = SourceId::for_path(&workspace_root).unwrap();
+        let package_id = PackageId::new("member", "0.1.0", source_id).unwrap();
+
+        assert_eq!(
+            display_package_id(package_id, &workspace_root, false),
+            package_id.to_string()
+        );
+        assert_eq!(
+            display_package_id(package_id, &workspace_root, true),
+            "member v0.1.0 ([ROOT])"
+        );
+    }
+
+    #[rstest]
+    fn construct_warning_lines_test() {
+        let workspace_root = Path::new("/workspace");
+        let warnings = vec![
+            ScanWarning::NotCanonicalizable {
+                path: PathBuf::from("/workspace/src/b.rs"),
+            },
+            ScanWarning::NotCanonicalizable {
+                path: PathBuf::from("/workspace/src/a.rs"),
+            },
+        ];
+
+        assert_eq!(
+            construct_warning_lines(&warnings, workspace_root, false),
+            vec![
+                "WARNING: Path could not be canonicalized: src/b.rs",
+                "WARNING: Path could not be canonicalized: src/a.rs",
+            ]
+        );
+        assert_eq!(
+            construct_warning_lines(&warnings, workspace_root, true),
+            vec![
+                "WARNING: Path could not be canonicalized: [ROOT]/src/a.rs",
+                "WARNING: Path could not be canonicalized: [ROOT]/src/b.rs",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn unsafe_stats_from_nothing_are_empty() {
+        let stats = unsafe_stats(&Default::default(), &Default::default());
+        let expected = UnsafeInfo {
+            forbids_unsafe: true,
+            ..Default::default()
+        };
+        assert_eq!(stats, expected);
+    }
+
+    #[rstest]
+    fn unsafe_stats_report_forbid_unsafe_as_true_if_all_entry_points_forbid_unsafe(
     ) {
         let metrics = metrics_from_iter(vec![(
             "foo.rs",
@@ -294,7 +1493,7 @@ mod scan_tests {
                 .set_is_crate_entry_point(true)
                 .build(),
         )]);
-        let stats = unsafe_stats(&metrics, &set_of_paths(&["foo.rs"]));
+        let stats = unsafe_stats_of_files(&metrics, &set_of_paths(&["foo.rs"]));
         assert!(stats.forbids_unsafe)
     }
 
@@ -317,8 +1516,10 @@ mod scan_tests {
                     .build(),
             ),
         ]);
-        let stats =
-            unsafe_stats(&metrics, &set_of_paths(&["foo.rs", "bar.rs"]));
+        let stats = unsafe_stats_of_files(
+            &metrics,
+            &set_of_paths(&["foo.rs", "bar.rs"]),
+        );
         assert!(!stats.forbids_unsafe)
     }
 
@@ -336,12 +1537,956 @@ mod scan_tests {
                 MetricsBuilder::default().functions(200, 100).build(),
             ),
         ]);
-        let stats =
-            unsafe_stats(&metrics, &set_of_paths(&["foo.rs", "bar.rs"]));
+        let stats = unsafe_stats_of_files(
+            &metrics,
+            &set_of_paths(&["foo.rs", "bar.rs"]),
+        );
         assert_eq!(stats.used.functions.safe, 7);
         assert_eq!(stats.used.functions.unsafe_, 4);
         assert_eq!(stats.unused.functions.safe, 220);
         assert_eq!(stats.unused.functions.unsafe_, 110);
+        assert_eq!(stats.status, ScanStatus::Complete);
+    }
+
+    #[rstest]
+    fn unsafe_stats_accumulate_public_api_counters() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .functions(2, 3)
+                    .public_functions(1, 1)
+                    .build(),
+            ),
+            (
+                "raw.rs",
+                MetricsBuilder::default()
+                    .functions(0, 4)
+                    .public_functions(0, 2)
+                    .build(),
+            ),
+            (
+                "unused.rs",
+                MetricsBuilder::default()
+                    .functions(0, 1)
+                    .public_functions(0, 1)
+                    .build(),
+            ),
+        ]);
+        let stats = unsafe_stats_of_files(
+            &metrics,
+            &set_of_paths(&["lib.rs", "raw.rs"]),
+        );
+        assert_eq!(stats.used.functions.unsafe_, 7);
+        assert_eq!(
+            stats.public_api.used.functions,
+            Count {
+                safe: 1,
+                unsafe_: 3
+            }
+        );
+        assert_eq!(stats.public_api.unused.functions.unsafe_, 1);
+    }
+
+    #[rstest]
+    fn unsafe_stats_count_verification_harnesses_separately() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .functions(2, 1)
+                    .harness_functions(1, 2)
+                    .build(),
+            ),
+            (
+                "fuzz.rs",
+                MetricsBuilder::default().harness_functions(0, 3).build(),
+            ),
+            ("safe.rs", MetricsBuilder::default().functions(1, 0).build()),
+        ]);
+        let used = set_of_paths(&["lib.rs", "safe.rs"]);
+        let stats = unsafe_stats_of_files(&metrics, &used);
+        assert_eq!(
+            stats.used.functions,
+            Count {
+                safe: 3,
+                unsafe_: 1
+            }
+        );
+        assert_eq!(stats.unused.functions, Count::default());
+        assert_eq!(
+            stats.verification_harness.unwrap().functions,
+            Count {
+                safe: 1,
+                unsafe_: 5
+            }
+        );
+
+        let stats = unsafe_stats_of_files(
+            &metrics_from_iter(vec![(
+                "lib.rs",
+                MetricsBuilder::default().functions(2, 1).build(),
+            )]),
+            &set_of_paths(&["lib.rs"]),
+        );
+        assert_eq!(stats.verification_harness, None);
+    }
+
+    #[rstest]
+    fn unsafe_stats_count_generated_files_separately() {
+        let metrics = metrics_from_iter(vec![
+            ("lib.rs", MetricsBuilder::default().functions(2, 1).build()),
+            (
+                "bindings.rs",
+                MetricsBuilder::default()
+                    .functions(0, 30)
+                    .set_is_generated(true)
+                    .build(),
+            ),
+            (
+                "old_bindings.rs",
+                MetricsBuilder::default()
+                    .functions(0, 5)
+                    .set_is_generated(true)
+                    .build(),
+            ),
+        ]);
+        let used = set_of_paths(&["lib.rs", "bindings.rs"]);
+        let stats = unsafe_stats_of_files(&metrics, &used);
+        assert_eq!(stats.used.functions.unsafe_, 31);
+        assert_eq!(stats.unused.functions.unsafe_, 5);
+        let generated = stats.generated.unwrap();
+        assert_eq!(generated.used.functions.unsafe_, 30);
+        assert_eq!(generated.unused.functions.unsafe_, 5);
+
+        let stats = unsafe_stats(
+            &hand_written_metrics(&folded(&metrics, &used, &HashMap::new())),
+            &used,
+        );
+        assert_eq!(stats.used.functions.unsafe_, 1);
+        assert_eq!(stats.unused.functions.unsafe_, 0);
+        assert_eq!(stats.generated, None);
+    }
+
+    #[rstest]
+    fn unsafe_stats_accumulate_watched_calls() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .watched_calls("ptr::copy", 0, 2)
+                    .build(),
+            ),
+            (
+                "raw.rs",
+                MetricsBuilder::default()
+                    .watched_calls("ptr::copy", 1, 1)
+                    .build(),
+            ),
+            (
+                "unused.rs",
+                MetricsBuilder::default()
+                    .watched_calls("assume_init", 0, 4)
+                    .build(),
+            ),
+        ]);
+        let stats = unsafe_stats_of_files(
+            &metrics,
+            &set_of_paths(&["lib.rs", "raw.rs"]),
+        );
+        assert_eq!(stats.watched_calls.len(), 2);
+        assert_eq!(
+            stats.watched_calls["ptr::copy"].used,
+            Count {
+                safe: 1,
+                unsafe_: 3
+            }
+        );
+        assert_eq!(stats.watched_calls["ptr::copy"].unused, Count::default());
+        assert_eq!(stats.watched_calls["assume_init"].unused.unsafe_, 4);
+    }
+
+    #[rstest]
+    fn unsafe_stats_without_used_files_have_no_build_data() {
+        let metrics = metrics_from_iter(vec![
+            ("foo.rs", MetricsBuilder::default().functions(2, 1).build()),
+            ("bar.rs", MetricsBuilder::default().functions(5, 3).build()),
+        ]);
+        let stats = unsafe_stats_of_files(&metrics, &set_of_paths(&["baz.rs"]));
+        assert_eq!(stats.status, ScanStatus::NoBuildData);
+        assert_eq!(stats.unused.functions.unsafe_, 4);
+    }
+
+    #[rstest]
+    fn unsafe_stats_with_parse_failures_have_parse_errors() {
+        let mut metrics = metrics_from_iter(vec![(
+            "foo.rs",
+            MetricsBuilder::default().functions(2, 1).build(),
+        )]);
+        metrics.parse_failures.push(PathBuf::from("bar.rs"));
+        let stats = unsafe_stats_of_files(&metrics, &set_of_paths(&["foo.rs"]));
+        assert_eq!(stats.status, ScanStatus::ParseErrors);
+    }
+
+    #[rstest]
+    fn unsafe_stats_count_approximated_files_apart() {
+        let mut metrics = metrics_from_iter(vec![(
+            "foo.rs",
+            MetricsBuilder::default().functions(2, 1).build(),
+        )]);
+        let rs_files_used = set_of_paths(&["foo.rs", "bar.rs"]);
+        let stats_without_approximations =
+            unsafe_stats_of_files(&metrics, &rs_files_used);
+        for (path, unsafe_tokens) in &[("bar.rs", 3), ("baz.rs", 1)] {
+            metrics.parse_failures.push(PathBuf::from(path));
+            metrics
+                .approximated
+                .insert(PathBuf::from(path), *unsafe_tokens);
+        }
+
+        let stats = unsafe_stats_of_files(&metrics, &rs_files_used);
+
+        assert_eq!(stats_without_approximations.approximate, None);
+        assert_eq!(
+            stats.approximate,
+            Some(ApproximateUnsafety { used: 3, unused: 1 })
+        );
+        assert_eq!(stats.used, stats_without_approximations.used);
+        assert_eq!(stats.unused, stats_without_approximations.unused);
+    }
+
+    #[rstest]
+    fn unsafe_stats_list_oversized_files_apart() {
+        let mut metrics = metrics_from_iter(vec![(
+            "foo.rs",
+            MetricsBuilder::default().functions(2, 1).build(),
+        )]);
+        for (path, size) in &[("big.rs", 2048), ("bar.rs", 4096)] {
+            metrics.add_scan_failure(
+                &ScanFileError::TooLarge(*size, PathBuf::from(path)),
+                PathBuf::from(path),
+            );
+        }
+
+        let stats = unsafe_stats_of_files(&metrics, &set_of_paths(&["foo.rs"]));
+
+        assert!(metrics.parse_failures.is_empty());
+        assert_eq!(stats.status, ScanStatus::ParseErrors);
+        assert_eq!(
+            stats.skipped_oversized,
+            vec![
+                OversizedFile {
+                    path: PathBuf::from("bar.rs"),
+                    size: 4096
+                },
+                OversizedFile {
+                    path: PathBuf::from("big.rs"),
+                    size: 2048
+                },
+            ]
+        );
+        assert_eq!(stats.used.functions.unsafe_, 1);
+    }
+
+    #[rstest]
+    fn transmute_locations_test() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "/package/src/lib.rs",
+                MetricsBuilder::default()
+                    .transmutes_on_lines(&[3, 12])
+                    .build(),
+            ),
+            ("/package/src/safe.rs", MetricsBuilder::default().build()),
+            (
+                "/package/src/old.rs",
+                MetricsBuilder::default().transmutes_on_lines(&[7]).build(),
+            ),
+        ]);
+        let locations = transmute_locations(
+            &metrics,
+            Path::new("/package"),
+            &set_of_paths(&["/package/src/lib.rs"]),
+        );
+        assert_eq!(
+            locations,
+            vec![
+                "src/lib.rs:3",
+                "src/lib.rs:12",
+                "src/old.rs:7 (not used by the build)"
+            ]
+        );
+    }
+
+    #[rstest]
+    fn unsafe_by_module_test() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "/package/src/lib.rs",
+                MetricsBuilder::default()
+                    .unsafe_in_module("", 2)
+                    .unsafe_in_module("raw::sys", 5)
+                    .build(),
+            ),
+            (
+                "/package/src/ffi.rs",
+                MetricsBuilder::default().unsafe_in_module("", 2).build(),
+            ),
+        ]);
+        let modules = unsafe_by_module(&metrics, Path::new("/package"));
+        assert_eq!(
+            modules,
+            vec![
+                ModuleUnsafety {
+                    module: String::from("src/lib.rs::raw::sys"),
+                    unsafe_: 5,
+                },
+                ModuleUnsafety {
+                    module: String::from("src/ffi.rs"),
+                    unsafe_: 2,
+                },
+                ModuleUnsafety {
+                    module: String::from("src/lib.rs"),
+                    unsafe_: 2,
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn scanned_files_test() {
+        let mut metrics = metrics_from_iter(vec![
+            ("/package/src/lib.rs", MetricsBuilder::default().build()),
+            ("/package/src/old.rs", MetricsBuilder::default().build()),
+            ("/shared/generated.rs", MetricsBuilder::default().build()),
+        ]);
+        metrics
+            .parse_failures
+            .push(PathBuf::from("/package/src/broken.rs"));
+        let rs_files_used =
+            set_of_paths(&["/package/src/lib.rs", "/shared/generated.rs"]);
+
+        let files = scanned_files(
+            &folded(&metrics, &rs_files_used, &HashMap::new()),
+            Path::new("/package"),
+            &rs_files_used,
+        );
+
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| (file.path.as_str(), file.used))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/shared/generated.rs", true),
+                ("src/broken.rs", false),
+                ("src/lib.rs", true),
+                ("src/old.rs", false),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn unsafe_by_target_test() {
+        let metrics = metrics_from_iter(vec![
+            ("lib.rs", MetricsBuilder::default().functions(2, 1).build()),
+            ("main.rs", MetricsBuilder::default().functions(1, 3).build()),
+            (
+                "unused.rs",
+                MetricsBuilder::default().functions(1, 5).build(),
+            ),
+        ]);
+        let target_kinds = target_kinds_from_iter(vec![
+            ("lib.rs", vec![TargetKind::Lib, TargetKind::Bin]),
+            ("main.rs", vec![TargetKind::Bin]),
+        ]);
+        let targets = unsafe_by_target(&folded(
+            &metrics,
+            &set_of_paths(&["lib.rs", "main.rs"]),
+            &target_kinds,
+        ));
+        assert_eq!(
+            targets.keys().collect::<Vec<_>>(),
+            vec![&TargetKind::Lib, &TargetKind::Bin]
+        );
+        assert_eq!(targets[&TargetKind::Lib].functions.unsafe_, 1);
+        assert_eq!(targets[&TargetKind::Bin].functions.unsafe_, 4);
+    }
+
+    #[rstest]
+    fn library_metrics_leaves_out_files_of_other_targets() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .forbids_unsafe(true)
+                    .set_is_crate_entry_point(true)
+                    .build(),
+            ),
+            (
+                "main.rs",
+                MetricsBuilder::default()
+                    .functions(1, 3)
+                    .set_is_crate_entry_point(true)
+                    .build(),
+            ),
+            (
+                "build.rs",
+                MetricsBuilder::default().functions(0, 1).build(),
+            ),
+            (
+                "unused.rs",
+                MetricsBuilder::default().functions(0, 2).build(),
+            ),
+        ]);
+        let target_kinds = target_kinds_from_iter(vec![
+            ("lib.rs", vec![TargetKind::Lib]),
+            ("main.rs", vec![TargetKind::Bin]),
+            ("build.rs", vec![TargetKind::CustomBuild]),
+        ]);
+        let rs_files_used = set_of_paths(&["lib.rs", "main.rs", "build.rs"]);
+        let library_metrics = library_metrics(
+            &folded(&metrics, &rs_files_used, &target_kinds),
+            &target_kinds,
+        )
+        .unwrap();
+        assert_eq!(
+            library_metrics.rs_paths,
+            set_of_paths(&["lib.rs", "build.rs", "unused.rs"])
+        );
+        let stats = unsafe_stats(&library_metrics, &rs_files_used);
+        assert!(stats.forbids_unsafe);
+        assert_eq!(stats.used.functions.unsafe_, 1);
+        assert_eq!(stats.unused.functions.unsafe_, 2);
+    }
+
+    #[rstest(
+        input_status,
+        input_build_failed,
+        expected_is_inactive,
+        case(ScanStatus::NoBuildData, false, true),
+        case(ScanStatus::OtherPlatform, false, true),
+        case(ScanStatus::NoBuildData, true, false),
+        case(ScanStatus::Complete, false, false),
+        case(ScanStatus::ParseErrors, false, false)
+    )]
+    fn is_inactive_test(
+        input_status: ScanStatus,
+        input_build_failed: bool,
+        expected_is_inactive: bool,
+    ) {
+        assert_eq!(
+            is_inactive(input_status, input_build_failed),
+            expected_is_inactive
+        );
+    }
+
+    #[rstest]
+    fn inconsistent_forbid_files_test() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .forbids_unsafe(true)
+                    .set_is_crate_entry_point(true)
+                    .functions(1, 0)
+                    .build(),
+            ),
+            (
+                "build.rs",
+                MetricsBuilder::default()
+                    .set_is_crate_entry_point(true)
+                    .functions(0, 1)
+                    .build(),
+            ),
+            (
+                "examples/raw.rs",
+                MetricsBuilder::default().functions(0, 2).build(),
+            ),
+        ]);
+        assert_eq!(
+            folded(&metrics, &HashSet::new(), &HashMap::new())
+                .inconsistent_forbid,
+            vec![PathBuf::from("build.rs"), PathBuf::from("examples/raw.rs")]
+        );
+
+        // Without the forbid the unsafe usage is no discrepancy.
+        let metrics = metrics_from_iter(vec![(
+            "build.rs",
+            MetricsBuilder::default()
+                .set_is_crate_entry_point(true)
+                .functions(0, 1)
+                .build(),
+        )]);
+        assert!(folded(&metrics, &HashSet::new(), &HashMap::new())
+            .inconsistent_forbid
+            .is_empty());
+    }
+
+    #[rstest]
+    fn library_metrics_of_binary_crates_is_none() {
+        let metrics = metrics_from_iter(vec![(
+            "main.rs",
+            MetricsBuilder::default().functions(1, 3).build(),
+        )]);
+        let target_kinds =
+            target_kinds_from_iter(vec![("main.rs", vec![TargetKind::Bin])]);
+        assert!(library_metrics(
+            &folded(&metrics, &HashSet::new(), &target_kinds),
+            &target_kinds
+        )
+        .is_none());
+    }
+
+    #[rstest]
+    fn build_safety_report_lists_packages_without_metrics() {
+        let package_ids = create_package_ids(&["root", "scanned", "skipped"]);
+        let graph = graph_from_edges(
+            &package_ids,
+            &[(0, 1, DepKind::Normal), (0, 2, DepKind::Normal)],
+        );
+        let rs_files_used = set_of_paths(&["root.rs", "scanned.rs"]);
+        let geiger_context = GeigerContext {
+            package_id_to_metrics: vec![
+                (
+                    package_ids[0],
+                    metrics_from_iter(vec![(
+                        "root.rs",
+                        MetricsBuilder::default().functions(1, 1).build(),
+                    )]),
+                ),
+                (
+                    package_ids[1],
+                    metrics_from_iter(vec![(
+                        "scanned.rs",
+                        MetricsBuilder::default().functions(0, 2).build(),
+                    )]),
+                ),
+            ]
+            .into_iter()
+            .map(|(package_id, metrics)| {
+                (
+                    package_id,
+                    folded(&metrics, &rs_files_used, &HashMap::new()),
+                )
+            })
+            .collect(),
+            warnings: Vec::new(),
+        };
+
+        let report = build_safety_report(
+            &geiger_context,
+            &graph,
+            package_ids[0],
+            &rs_files_used,
+        );
+
+        let root_id = from_cargo_package_id(package_ids[0]);
+        let scanned_id = from_cargo_package_id(package_ids[1]);
+        let skipped_id = from_cargo_package_id(package_ids[2]);
+        assert_eq!(
+            report.packages.keys().cloned().collect::<HashSet<_>>(),
+            vec![root_id.clone(), scanned_id.clone()]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            report.packages_without_metrics,
+            vec![skipped_id.clone()].into_iter().collect()
+        );
+        let root_entry = &report.packages[&root_id];
+        assert_eq!(
+            root_entry.package.dependencies,
+            vec![scanned_id.clone(), skipped_id].into_iter().collect()
+        );
+        assert_eq!(root_entry.unsafety.used.functions.unsafe_, 1);
+        assert_eq!(
+            report.packages[&scanned_id].unsafety.used.functions.unsafe_,
+            2
+        );
+        assert!(report.used_but_not_scanned_files.is_empty());
+        assert!(report.dependency_cycles.is_empty());
+    }
+
+    #[rstest]
+    fn build_safety_report_lists_files_used_but_not_scanned() {
+        let package_ids = create_package_ids(&["root"]);
+        let graph = graph_from_edges(&package_ids, &[]);
+        let rs_files_used = set_of_paths(&["lib.rs", "generated.rs"]);
+        let geiger_context = GeigerContext {
+            package_id_to_metrics: vec![(
+                package_ids[0],
+                folded(
+                    &metrics_from_iter(vec![(
+                        "lib.rs",
+                        MetricsBuilder::default().build(),
+                    )]),
+                    &rs_files_used,
+                    &HashMap::new(),
+                ),
+            )]
+            .into_iter()
+            .collect(),
+            warnings: Vec::new(),
+        };
+
+        let report = build_safety_report(
+            &geiger_context,
+            &graph,
+            package_ids[0],
+            &rs_files_used,
+        );
+
+        assert_eq!(
+            report.used_but_not_scanned_files,
+            set_of_paths(&["generated.rs"])
+        );
+        assert_eq!(report.packages.len(), 1);
+        assert!(report.packages_without_metrics.is_empty());
+    }
+
+    #[rstest]
+    fn report_warnings_test() {
+        let package_ids = create_package_ids(&["built", "unbuilt", "failed"])
+            .into_iter()
+            .map(from_cargo_package_id)
+            .collect::<Vec<_>>();
+        let entry = |id: &cargo_geiger_serde::PackageId, status| ReportEntry {
+            package: PackageInfo::new(id.clone()),
+            unsafety: UnsafeInfo {
+                status,
+                ..Default::default()
+            },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
+        };
+        let report = SafetyReport {
+            packages: vec![
+                (
+                    package_ids[0].clone(),
+                    entry(&package_ids[0], ScanStatus::Complete),
+                ),
+                (
+                    package_ids[1].clone(),
+                    entry(&package_ids[1], ScanStatus::NoBuildData),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            packages_without_metrics: vec![package_ids[2].clone()]
+                .into_iter()
+                .collect(),
+            used_but_not_scanned_files: set_of_paths(&["generated.rs"]),
+            ..Default::default()
+        };
+        let parse_failed = ScanWarning::ParseFailed {
+            path: PathBuf::from("broken.rs"),
+            error: String::from("expected `;`"),
+        };
+
+        let warnings = report_warnings(&report, &[parse_failed.clone()], &[]);
+
+        assert_eq!(
+            warnings,
+            vec![
+                parse_failed,
+                ScanWarning::NoBuildData {
+                    package: package_ids[1].clone()
+                },
+                ScanWarning::NoMetrics {
+                    package: package_ids[2].clone()
+                },
+                ScanWarning::NotScanned {
+                    path: PathBuf::from("generated.rs"),
+                    reason: Some(NotScannedReason::Missing),
+                },
+            ]
+        );
+        assert_eq!(
+            construct_warning_summary_lines(&warnings),
+            vec![
+                "Warnings, listed on stderr:",
+                "    1 files failed to parse",
+                "    1 files used by the build were not scanned",
+                "    1 packages have no files used by the build",
+                "    1 packages have no metrics",
+                "",
+            ]
+        );
+        assert!(construct_warning_summary_lines(&[]).is_empty());
+    }
+
+    #[rstest(
+        input_detail_level,
+        expected_details_kept,
+        case(DetailLevel::Summary, false),
+        case(DetailLevel::Detailed, true)
+    )]
+    fn detail_level_retain_test(
+        input_detail_level: DetailLevel,
+        expected_details_kept: bool,
+    ) {
+        let rs_file_metrics = RsFileMetrics {
+            counters: CounterBlock {
+                exprs: Count {
+                    safe: 0,
+                    unsafe_: 2,
+                },
+                ..Default::default()
+            },
+            unsafe_by_module: vec![(String::from("raw"), 2)]
+                .into_iter()
+                .collect(),
+            unsafe_by_feature: vec![(String::new(), 2)].into_iter().collect(),
+            transmute_lines: vec![4],
+            ..Default::default()
+        };
+
+        let retained = input_detail_level.retain(rs_file_metrics.clone());
+
+        // The counters and the breakdown by feature are always kept, the
+        // report holds them.
+        assert_eq!(retained.counters, rs_file_metrics.counters);
+        assert_eq!(
+            retained.unsafe_by_feature,
+            rs_file_metrics.unsafe_by_feature
+        );
+        assert_eq!(
+            !retained.unsafe_by_module.is_empty(),
+            expected_details_kept
+        );
+        assert_eq!(!retained.transmute_lines.is_empty(), expected_details_kept);
+    }
+
+    #[rstest]
+    fn report_warnings_test_unavailable_package() {
+        let package_ids = create_package_ids(&["unavailable", "unscanned"])
+            .into_iter()
+            .map(from_cargo_package_id)
+            .collect::<Vec<_>>();
+        let report = SafetyReport {
+            packages_without_metrics: package_ids.iter().cloned().collect(),
+            ..Default::default()
+        };
+        let unavailable = ScanWarning::Unavailable {
+            package: package_ids[0].clone(),
+            reason: String::from("failed to download"),
+        };
+
+        let warnings = report_warnings(&report, &[unavailable.clone()], &[]);
+
+        // The unavailable package has no metrics, only the reason is told.
+        assert_eq!(
+            warnings,
+            vec![
+                unavailable,
+                ScanWarning::NoMetrics {
+                    package: package_ids[1].clone()
+                },
+            ]
+        );
+        assert_eq!(
+            construct_warning_summary_lines(&warnings),
+            vec![
+                "Warnings, listed on stderr:",
+                "    1 packages have no metrics",
+                "    1 packages were unavailable",
+                "",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn proc_macro_uses_test() {
+        let package_ids = create_package_ids(&["derives", "traits", "plain"])
+            .into_iter()
+            .map(from_cargo_package_id)
+            .collect::<Vec<_>>();
+        let entry = |id: &cargo_geiger_serde::PackageId,
+                     proc_macros: &[&str]| {
+            let entry = ReportEntry {
+                package: PackageInfo::new(id.clone()),
+                unsafety: UnsafeInfo::default(),
+                features: Vec::new(),
+                platforms: Vec::new(),
+                native_linkage: None,
+                dependency_counts: None,
+                depth: None,
+                files: Vec::new(),
+                is_workspace_member: false,
+                proc_macros_applied: proc_macros
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+            };
+            (id.clone(), entry)
+        };
+        let report = SafetyReport {
+            packages: vec![
+                entry(&package_ids[0], &["async_trait", "serde_derive"]),
+                entry(&package_ids[1], &["async_trait"]),
+                entry(&package_ids[2], &[]),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            proc_macro_uses(&report),
+            vec![
+                ProcMacroUse {
+                    crate_name: String::from("async_trait"),
+                    applied_to: vec![
+                        package_ids[0].clone(),
+                        package_ids[1].clone()
+                    ],
+                },
+                ProcMacroUse {
+                    crate_name: String::from("serde_derive"),
+                    applied_to: vec![package_ids[0].clone()],
+                },
+            ]
+        );
+    }
+
+    #[rstest(
+        input_path,
+        input_exists,
+        expected_reason,
+        case("/pkg/README.md", true, NotScannedReason::NotRust),
+        case("/pkg/data", true, NotScannedReason::NotRust),
+        case(
+            "/target/debug/build/pkg-0123abcd/out/bindings.rs",
+            true,
+            NotScannedReason::OutDir
+        ),
+        case("/pkg/src/removed.rs", false, NotScannedReason::Missing),
+        case("/other/src/lib.rs", true, NotScannedReason::OutsidePackageRoots),
+        case(
+            "/pkg-old/src/lib.rs",
+            true,
+            NotScannedReason::OutsidePackageRoots
+        ),
+        case("/pkg/src/linked.rs", true, NotScannedReason::InsidePackageRoot)
+    )]
+    fn not_scanned_reason_test(
+        input_path: &str,
+        input_exists: bool,
+        expected_reason: NotScannedReason,
+    ) {
+        let package_roots = vec![PathBuf::from("/pkg"), PathBuf::from("/dep")];
+        assert_eq!(
+            not_scanned_reason(Path::new(input_path), &package_roots, |_| {
+                input_exists
+            }),
+            expected_reason
+        );
+    }
+
+    #[rstest]
+    fn warning_message_test_not_scanned() {
+        let workspace_root = Path::new("/pkg");
+        let not_scanned = |reason| ScanWarning::NotScanned {
+            path: PathBuf::from("/pkg/src/removed.rs"),
+            reason,
+        };
+
+        assert_eq!(
+            warning_message(&not_scanned(None), workspace_root, false),
+            "Dependency file was never scanned: src/removed.rs"
+        );
+        assert_eq!(
+            warning_message(
+                &not_scanned(Some(NotScannedReason::Missing)),
+                workspace_root,
+                false
+            ),
+            "Dependency file was never scanned: src/removed.rs, the file no \
+             longer exists, scan again after a fresh build"
+        );
+    }
+
+    #[rstest]
+    fn unsafe_by_feature_test() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .unsafe_in_feature("", 2)
+                    .unsafe_in_feature("simd", 1)
+                    .build(),
+            ),
+            (
+                "simd.rs",
+                MetricsBuilder::default()
+                    .unsafe_in_feature("simd", 3)
+                    .unsafe_in_feature("avx", 4)
+                    .build(),
+            ),
+        ]);
+        let by_feature = unsafe_by_feature(&folded(
+            &metrics,
+            &HashSet::new(),
+            &HashMap::new(),
+        ));
+        assert_eq!(
+            by_feature.into_iter().collect::<Vec<_>>(),
+            vec![
+                (String::from(UNCONDITIONAL_FEATURE), 2),
+                (String::from("avx"), 4),
+                (String::from("simd"), 4),
+            ]
+        );
+    }
+
+    fn target_kinds_from_iter<I, P>(
+        it: I,
+    ) -> HashMap<PathBuf, HashSet<TargetKind>>
+    where
+        I: IntoIterator<Item = (P, Vec<TargetKind>)>,
+        P: Into<PathBuf>,
+    {
+        it.into_iter()
+            .map(|(p, kinds)| (p.into(), kinds.into_iter().collect()))
+            .collect()
+    }
+
+    /// `metrics` with the metrics of its files added up like the scan does,
+    /// the build used the files in `rs_files_used` for the targets in
+    /// `target_kinds`.
+    fn folded(
+        metrics: &PackageMetrics,
+        rs_files_used: &HashSet<PathBuf>,
+        target_kinds: &HashMap<PathBuf, HashSet<TargetKind>>,
+    ) -> PackageMetrics {
+        let mut metrics = metrics.clone();
+        metrics.fold_files(
+            &FileUsage {
+                rs_files_used,
+                target_kinds,
+            },
+            DetailLevel::Summary,
+        );
+        metrics
+    }
+
+    /// The unsafe stats of the files of `metrics`, added up like the scan
+    /// does.
+    fn unsafe_stats_of_files(
+        metrics: &PackageMetrics,
+        rs_files_used: &HashSet<PathBuf>,
+    ) -> UnsafeInfo {
+        unsafe_stats(
+            &folded(metrics, rs_files_used, &HashMap::new()),
+            rs_files_used,
+        )
     }
 
     fn metrics_from_iter<I, P>(it: I) -> PackageMetrics
@@ -354,6 +2499,7 @@ mod scan_tests {
                 .into_iter()
                 .map(|(p, m)| (p.into(), m))
                 .collect(),
+            ..Default::default()
         }
     }
 
@@ -381,11 +2527,61 @@ mod scan_tests {
             self
         }
 
+        fn unsafe_in_module(mut self, module_path: &str, unsafe_: u64) -> Self {
+            self.inner
+                .metrics
+                .unsafe_by_module
+                .insert(String::from(module_path), unsafe_);
+            self
+        }
+
+        fn unsafe_in_feature(mut self, feature: &str, unsafe_: u64) -> Self {
+            self.inner
+                .metrics
+                .unsafe_by_feature
+                .insert(String::from(feature), unsafe_);
+            self
+        }
+
         fn set_is_crate_entry_point(mut self, yes: bool) -> Self {
             self.inner.is_crate_entry_point = yes;
             self
         }
 
+        fn public_functions(mut self, safe: u64, unsafe_: u64) -> Self {
+            self.inner.metrics.public_api.functions = Count { safe, unsafe_ };
+            self
+        }
+
+        fn transmutes_on_lines(mut self, lines: &[usize]) -> Self {
+            self.inner.metrics.transmute_lines = lines.to_vec();
+            self
+        }
+
+        fn watched_calls(
+            mut self,
+            pattern: &str,
+            safe: u64,
+            unsafe_: u64,
+        ) -> Self {
+            self.inner
+                .metrics
+                .watched_calls
+                .insert(pattern.to_string(), Count { safe, unsafe_ });
+            self
+        }
+
+        fn set_is_generated(mut self, yes: bool) -> Self {
+            self.inner.is_generated = yes;
+            self
+        }
+
+        fn harness_functions(mut self, safe: u64, unsafe_: u64) -> Self {
+            self.inner.metrics.verification_harness.functions =
+                Count { safe, unsafe_ };
+            self
+        }
+
         fn build(self) -> RsFileMetricsWrapper {
             self.inner
         }
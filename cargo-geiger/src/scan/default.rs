@@ -1,24 +1,54 @@
+mod dev_only;
+mod explain;
+mod external_paths;
+mod members;
+mod merged;
+mod output_dir;
+mod reuse;
 mod table;
+mod tree_json;
 
 use crate::args::Args;
 use crate::format::print_config::OutputFormat;
-use crate::graph::Graph;
+use crate::graph::{dependency_counts, package_depths, Graph};
 use crate::krates_utils::CargoMetadataParameters;
-use crate::rs_file::resolve_rs_file_deps;
+use crate::lockfile::construct_report_metadata;
+use crate::progress::ProgressEvent;
+use crate::rs_file::{resolve_rs_file_deps, RsFileDeps};
+use crate::timings::Phase;
 
-use super::find::find_unsafe;
+use super::find::{find_unsafe, DetailFilter};
 use super::{
-    list_files_used_but_not_scanned, package_metrics, unsafe_stats,
-    ScanDetails, ScanMode, ScanParameters,
+    all_package_roots, build_safety_report, from_cargo_package_id, is_inactive,
+    native_linkage, package_infos, package_root, print_warnings,
+    proc_macro_uses, report_warnings, resolved_features, scanned_files,
+    unsafe_by_feature, unsafe_by_module, unsafe_by_target, unsafe_stats,
+    DetailLevel, FileUsage, GeigerContext, ScanDetails, ScanMode,
+    ScanParameters,
 };
 
-use table::scan_to_table;
+use dev_only::dev_only_packages;
+use explain::scan_to_explanation;
+use external_paths::external_path_groups;
+use members::{members_unsafety, workspace_member_ids};
+pub use merged::MergedScan;
+use output_dir::write_output_dir;
+use reuse::{read_reusable_report, ReusableReport};
+use table::{print_table, scan_to_table};
+use tree_json::scan_to_tree_json;
 
 use cargo::core::compiler::CompileMode;
+use cargo::core::shell::Verbosity;
 use cargo::core::{PackageId, PackageSet, Workspace};
 use cargo::ops::CompileOptions;
+use cargo::util::{paths, CargoResult};
 use cargo::{CliError, CliResult, Config};
-use cargo_geiger_serde::{ReportEntry, SafetyReport};
+use cargo_geiger_serde::{
+    BuildFailure, CounterBlock, InactivePackages, ReportEntry, SafetyReport,
+};
+use petgraph::EdgeDirection;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 pub fn scan_unsafe(
     cargo_metadata_parameters: &CargoMetadataParameters,
@@ -28,11 +58,28 @@ pub fn scan_unsafe(
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
 ) -> CliResult {
+    if let Some(package_id_spec) = &scan_parameters.args.explain {
+        return scan_to_explanation(
+            cargo_metadata_parameters,
+            package_id_spec,
+            package_set,
+            scan_parameters,
+            workspace,
+        );
+    }
+
     match scan_parameters.args.output_format {
-        Some(output_format) => scan_to_report(
+        Some(OutputFormat::Json) => scan_to_report(
+            cargo_metadata_parameters,
+            graph,
+            package_set,
+            root_package_id,
+            scan_parameters,
+            workspace,
+        ),
+        Some(OutputFormat::TreeJson) => scan_to_tree_json(
             cargo_metadata_parameters,
             graph,
-            output_format,
             package_set,
             root_package_id,
             scan_parameters,
@@ -55,19 +102,10 @@ pub fn scan_unsafe(
 fn build_compile_options<'a>(
     args: &'a Args,
     config: &'a Config,
-) -> CompileOptions {
-    let features = args
-        .features
-        .as_ref()
-        .cloned()
-        .unwrap_or_else(String::new)
-        .split(' ')
-        .map(str::to_owned)
-        .collect::<Vec<String>>();
+) -> CargoResult<CompileOptions> {
     let mut compile_options =
-        CompileOptions::new(&config, CompileMode::Check { test: false })
-            .unwrap();
-    compile_options.features = features;
+        CompileOptions::new(&config, CompileMode::Check { test: false })?;
+    compile_options.features = args.features.clone();
     compile_options.all_features = args.all_features;
     compile_options.no_default_features = args.no_default_features;
 
@@ -92,99 +130,401 @@ fn build_compile_options<'a>(
     //     );
     // }
 
-    compile_options
+    Ok(compile_options)
+}
+
+/// The outputs showing where in the files the unsafe usage is need the
+/// detailed metrics, the others make do with the summary. `--explain` only
+/// needs them for the explained packages, see `DetailFilter`.
+fn detail_level(scan_parameters: &ScanParameters) -> DetailLevel {
+    if scan_parameters.args.report_modules
+        || scan_parameters.print_config.verbosity == Verbosity::Verbose
+    {
+        DetailLevel::Detailed
+    } else {
+        DetailLevel::Summary
+    }
 }
 
 fn scan(
     cargo_metadata_parameters: &CargoMetadataParameters,
     package_set: &PackageSet,
+    package_ids_to_skip: &HashSet<PackageId>,
+    detail_filter: Option<DetailFilter>,
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
 ) -> Result<ScanDetails, CliError> {
     let compile_options =
-        build_compile_options(scan_parameters.args, scan_parameters.config);
-    let rs_files_used =
-        resolve_rs_file_deps(&compile_options, workspace).unwrap();
-    let geiger_context = find_unsafe(
+        build_compile_options(scan_parameters.args, scan_parameters.config)?;
+    let RsFileDeps {
+        mut rs_files_used,
+        mut target_kinds,
+        build_failures,
+        warnings: build_warnings,
+        proc_macros,
+    } = resolve_rs_file_deps(
+        &compile_options,
+        scan_parameters.args.resolver,
+        scan_parameters.args.keep_going,
+        scan_parameters.args.reuse_build,
+        scan_parameters.timings,
+        workspace,
+    )?;
+    let mut geiger_context = find_unsafe(
         cargo_metadata_parameters,
         scan_parameters.config,
         ScanMode::Full,
+        detail_level(scan_parameters),
+        detail_filter,
+        &FileUsage {
+            rs_files_used: &rs_files_used,
+            target_kinds: &target_kinds,
+        },
         package_set,
+        package_ids_to_skip,
         scan_parameters.print_config,
+        None,
     )?;
+    scan_parameters.timings.end_phase(Phase::Scan);
+    // Whatever rustc was given before failing, none of it made it into the
+    // build.
+    for package_id in build_failures.keys() {
+        if let Some(package_metrics) =
+            geiger_context.package_id_to_metrics.get_mut(package_id)
+        {
+            for path in &package_metrics.rs_paths {
+                rs_files_used.remove(path);
+                target_kinds.remove(path);
+            }
+            package_metrics.count_as_unused();
+        }
+    }
+    let mut warnings = geiger_context.warnings.clone();
+    warnings.extend(build_warnings);
+    if scan_parameters.args.progress_json {
+        scan_done_event(&geiger_context, &rs_files_used, warnings.len()).emit();
+    }
     Ok(ScanDetails {
         rs_files_used,
+        target_kinds,
         geiger_context,
+        build_failures,
+        warnings,
+        proc_macros,
     })
 }
 
+/// The last `--progress-json` event of a workspace, with the totals of the
+/// packages scanned. Packages reused from `--reuse-report` were not scanned.
+fn scan_done_event(
+    geiger_context: &GeigerContext,
+    rs_files_used: &HashSet<PathBuf>,
+    warning_count: usize,
+) -> ProgressEvent {
+    let mut used = CounterBlock::default();
+    let mut unused = CounterBlock::default();
+    let mut files = 0;
+    for package_metrics in geiger_context.package_id_to_metrics.values() {
+        let unsafe_info = unsafe_stats(package_metrics, rs_files_used);
+        used += unsafe_info.used;
+        unused += unsafe_info.unused;
+        files += package_metrics.rs_paths.len();
+    }
+    ProgressEvent::ScanDone {
+        packages: geiger_context.package_id_to_metrics.len(),
+        files,
+        used: Box::new(used),
+        unused: Box::new(unused),
+        warnings: warning_count,
+    }
+}
+
 fn scan_to_report(
     cargo_metadata_parameters: &CargoMetadataParameters,
     graph: &Graph,
-    output_format: OutputFormat,
     package_set: &PackageSet,
     root_package_id: PackageId,
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
 ) -> CliResult {
-    let ScanDetails {
-        rs_files_used,
-        geiger_context,
-    } = scan(
+    let (report, scan_details) = scan_to_safety_report(
         cargo_metadata_parameters,
+        graph,
         package_set,
+        root_package_id,
         scan_parameters,
         workspace,
     )?;
-    let mut report = SafetyReport::default();
-    for (package, package_metrics_option) in
-        package_metrics(&geiger_context, graph, root_package_id)
+    let s = serde_json::to_string(&report).unwrap();
+    let args = scan_parameters.args;
+    if let Some(output_dir) = &args.output_dir {
+        write_output_dir(&report, output_dir, args.prune_output)?;
+    }
+    if let Some(output_path) = &args.output {
+        paths::write(output_path, s.as_bytes())?;
+    }
+    // The table is printed from the same scan as the report, so that the
+    // numbers of both agree.
+    if args.output.is_some() || args.output_dir.is_some() {
+        print_table(
+            graph,
+            package_set,
+            root_package_id,
+            &scan_details,
+            scan_parameters,
+            workspace,
+        )
+    } else {
+        println!("{}", s);
+        print_warnings(
+            &report.warnings,
+            scan_parameters.workspace_root,
+            scan_parameters.print_config.normalize_output,
+        );
+        Ok(())
+    }
+}
+
+/// Scans the dependency graph and builds its report, the scan details are
+/// returned for a table of the same scan.
+fn scan_to_safety_report(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    package_set: &PackageSet,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    workspace: &Workspace,
+) -> Result<(SafetyReport, ScanDetails), CliError> {
+    let mut metadata = construct_report_metadata(scan_parameters.resolve);
+    metadata.counting_strategy = scan_parameters.print_config.counting_strategy;
+    let mut reusable_report = match &scan_parameters.args.reuse_report {
+        Some(report_path) => read_reusable_report(report_path, &metadata)?,
+        None => ReusableReport::default(),
+    };
+    let reused_package_ids = reusable_report.package_ids(package_set);
+    if let Some(report_path) = &scan_parameters.args.reuse_report {
+        scan_parameters.config.shell().status(
+            "Reusing",
+            format!(
+                "{} unchanged packages from {}, skipped scanning them",
+                reused_package_ids.len(),
+                report_path.display()
+            ),
+        )?;
+    }
+    let scan_details = scan(
+        cargo_metadata_parameters,
+        package_set,
+        &reused_package_ids,
+        None,
+        scan_parameters,
+        workspace,
+    )?;
+    let ScanDetails {
+        rs_files_used,
+        geiger_context,
+        build_failures,
+        warnings,
+        proc_macros,
+        ..
+    } = &scan_details;
+    // Module and file paths are reported relative to the package roots.
+    let package_roots = if scan_parameters.args.report_modules
+        || scan_parameters.args.report_file_list
     {
-        let package_metrics = match package_metrics_option {
-            Some(m) => m,
-            None => {
-                report.packages_without_metrics.insert(package.id);
-                continue;
-            }
-        };
-        let unsafe_info = unsafe_stats(package_metrics, &rs_files_used);
-        let entry = ReportEntry {
-            package,
-            unsafety: unsafe_info,
+        // Unavailable packages were not scanned, they have no root.
+        package_set
+            .package_ids()
+            .filter_map(|id| {
+                let package_root = package_root(package_set, id).ok()?;
+                Some((from_cargo_package_id(id), package_root))
+            })
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+    let mut report = build_safety_report(
+        geiger_context,
+        graph,
+        root_package_id,
+        rs_files_used,
+    );
+    report.metadata = metadata;
+    // Rendering comes after the report, its time is only printed.
+    report.metadata.timings = scan_parameters.timings.phase_timings();
+    report.build_failures = build_failures
+        .iter()
+        .map(|(package_id, error)| {
+            let id = from_cargo_package_id(*package_id);
+            let error = error.clone();
+            (id.clone(), BuildFailure { id, error })
+        })
+        .collect();
+    for (package_id, package_metrics) in &geiger_context.package_id_to_metrics {
+        let id = from_cargo_package_id(*package_id);
+        let entry = match report.packages.get_mut(&id) {
+            Some(entry) => entry,
+            None => continue,
         };
-        report.packages.insert(entry.package.id.clone(), entry);
-    }
-    report.used_but_not_scanned_files =
-        list_files_used_but_not_scanned(&geiger_context, &rs_files_used)
-            .into_iter()
-            .collect();
-    let s = match output_format {
-        OutputFormat::Json => serde_json::to_string(&report).unwrap(),
+        let unsafe_info = &mut entry.unsafety;
+        unsafe_info.targets = unsafe_by_target(package_metrics);
+        unsafe_info.by_feature = unsafe_by_feature(package_metrics);
+        if let Some(package_root) = package_roots.get(&id) {
+            if scan_parameters.args.report_modules {
+                unsafe_info.modules =
+                    unsafe_by_module(package_metrics, package_root);
+            }
+            if scan_parameters.args.report_file_list {
+                entry.files =
+                    scanned_files(package_metrics, package_root, rs_files_used);
+            }
+        }
+    }
+    // The reused packages were skipped by the scan, their entries come from
+    // the previous report. Their features are taken from the current resolve
+    // like for all other entries, they can change without the package
+    // changing. The native linkage of all entries comes from the manifests,
+    // the dependency counts and the depths from the graph.
+    let package_dependency_counts = if scan_parameters.args.show_dep_counts {
+        dependency_counts(graph)
+    } else {
+        HashMap::new()
     };
-    println!("{}", s);
-    Ok(())
+    let package_depths =
+        package_depths(graph, root_package_id, EdgeDirection::Outgoing);
+    for (package_id, package) in package_infos(graph, root_package_id) {
+        let features = resolved_features(scan_parameters.resolve, package_id);
+        let dependency_counts =
+            package_dependency_counts.get(&package_id).copied();
+        let depth = package_depths.get(&package_id).copied();
+        // Unavailable packages have no manifest to tell.
+        let native_linkage = package_set
+            .get_one(package_id)
+            .ok()
+            .and_then(native_linkage);
+        let platforms = graph
+            .platforms
+            .get(&package_id)
+            .cloned()
+            .unwrap_or_default();
+        let proc_macros_applied = proc_macros
+            .get(&package_id)
+            .map(|names| names.iter().cloned().collect())
+            .unwrap_or_default();
+        if let Some(entry) = reusable_report.entries.remove(&package.id) {
+            report.packages_without_metrics.remove(&package.id);
+            report.reused_packages.insert(package.id.clone());
+            report.packages.insert(
+                package.id.clone(),
+                ReportEntry {
+                    package,
+                    unsafety: entry.unsafety,
+                    features,
+                    platforms,
+                    native_linkage,
+                    dependency_counts,
+                    depth,
+                    files: if scan_parameters.args.report_file_list {
+                        entry.files
+                    } else {
+                        Vec::new()
+                    },
+                    is_workspace_member: graph.members.contains(&package_id),
+                    proc_macros_applied,
+                },
+            );
+        } else if let Some(entry) = report.packages.get_mut(&package.id) {
+            entry.features = features;
+            entry.native_linkage = native_linkage;
+            entry.dependency_counts = dependency_counts;
+            entry.depth = depth;
+            entry.proc_macros_applied = proc_macros_applied;
+        }
+    }
+    report.proc_macros = proc_macro_uses(&report);
+    let reused_package_roots = reused_package_ids
+        .iter()
+        .map(|id| package_root(package_set, *id))
+        .collect::<CargoResult<Vec<_>>>()?;
+    report.used_but_not_scanned_files = reusable_report
+        .merge_used_but_not_scanned_files(
+            report.used_but_not_scanned_files.into_iter().collect(),
+            &reused_package_roots,
+        );
+    let reused_packages = &report.reused_packages;
+    let reused_inconsistent_forbid = reusable_report
+        .inconsistent_forbid
+        .into_iter()
+        .filter(|inconsistent_forbid| {
+            reused_packages.contains(&inconsistent_forbid.package)
+        })
+        .collect::<Vec<_>>();
+    report
+        .inconsistent_forbid
+        .extend(reused_inconsistent_forbid);
+    report
+        .inconsistent_forbid
+        .sort_by(|a, b| a.package.cmp(&b.package));
+    let mut inactive_package_ids = report
+        .packages
+        .values()
+        .filter(|entry| {
+            is_inactive(
+                entry.unsafety.status,
+                report.build_failures.contains_key(&entry.package.id),
+            )
+        })
+        .map(|entry| entry.package.id.clone())
+        .collect::<Vec<_>>();
+    if !inactive_package_ids.is_empty() {
+        inactive_package_ids.sort();
+        report.inactive = Some(InactivePackages {
+            target: graph.target.clone(),
+            packages: inactive_package_ids,
+        });
+    }
+    report.dev_only = dev_only_packages(graph, &report);
+    report.warnings =
+        report_warnings(&report, warnings, &all_package_roots(package_set));
+    if scan_parameters.args.members_only {
+        report.members =
+            members_unsafety(graph, &workspace_member_ids(workspace), &report);
+    }
+    if scan_parameters.args.split_local_paths {
+        report.external_path_groups = external_path_groups(
+            graph,
+            package_set,
+            scan_parameters.workspace_root,
+            &report,
+        )?;
+    }
+    Ok((report, scan_details))
 }
 
 #[cfg(test)]
 mod default_tests {
     use super::*;
-    use crate::format::Charset;
+    use crate::format::{Charset, MarkerPosition};
+    use crate::graph::GraphBackend;
+    use crate::rs_file::Resolver;
+    use crate::scan::UnsafeCriteria;
+    use cargo_geiger_serde::CountingStrategy;
     use rstest::*;
 
     #[rstest(
         input_features,
         expected_compile_features,
         case(
-            Some(String::from("unit test features")),
-            vec!["unit", "test", "features"],
+            vec![String::from("unit"), String::from("dep/feature")],
+            vec!["unit", "dep/feature"],
         ),
         case(
-            Some(String::from("")),
-            vec![""],
+            vec![],
+            vec![],
         )
     )]
     fn build_compile_options_test(
-        input_features: Option<String>,
+        input_features: Vec<String>,
         expected_compile_features: Vec<&str>,
     ) {
         let mut args = create_args();
@@ -193,7 +533,7 @@ mod default_tests {
         args.no_default_features = rand::random();
 
         let config = Config::default().unwrap();
-        let compile_options = build_compile_options(&args, &config);
+        let compile_options = build_compile_options(&args, &config).unwrap();
 
         assert_eq!(compile_options.all_features, args.all_features);
         assert_eq!(compile_options.features, expected_compile_features);
@@ -208,31 +548,68 @@ mod default_tests {
             all: false,
             all_deps: false,
             all_features: false,
+            all_platforms: false,
+            all_target_kinds: false,
             all_targets: false,
+            backend: GraphBackend::Resolve,
             build_deps: false,
             charset: Charset::Utf8,
+            check_lock: None,
+            cli_config: vec![],
             color: None,
+            counting_strategy: CountingStrategy::Geiger,
             dev_deps: false,
-            features: None,
+            exclude: Vec::new(),
+            explain: None,
+            features: Vec::new(),
             forbid_only: false,
             format: "".to_string(),
             frozen: false,
+            generated_markers: vec![],
+            harness_cfgs: None,
             help: false,
+            hyperlinks: false,
             include_tests: false,
             invert: false,
+            keep_going: false,
             locked: false,
-            manifest_path: None,
+            manifest_paths: Vec::new(),
+            marker_position: MarkerPosition::Left,
+            max_file_size: None,
+            members_only: false,
             no_default_features: false,
+            no_dev_deps: false,
             no_indent: false,
+            no_legend: false,
+            normalize_output: false,
             offline: false,
+            output: None,
+            output_dir: None,
             package: None,
             prefix_depth: false,
+            print_schema: false,
+            progress_json: false,
+            prune_output: false,
+            published_crate: None,
             quiet: false,
+            report_file_list: false,
+            report_modules: false,
+            resolver: Resolver::Executor,
+            reuse_build: false,
+            reuse_report: None,
+            show_dep_counts: false,
+            show_totals: false,
+            sort: None,
+            split_generated: false,
+            split_local_paths: false,
             target: None,
+            timings: false,
+            unsafe_criteria: UnsafeCriteria::default(),
             unstable_flags: vec![],
             verbose: 0,
             version: false,
             output_format: None,
+            watched_calls: vec![],
         }
     }
 }
@@ -0,0 +1,167 @@
+use crate::format::table::{table_row, CounterColumns};
+use crate::graph::Graph;
+
+use super::super::{from_cargo_package_id, UnsafeCriteria};
+
+use cargo_geiger_serde::{CounterBlock, DevOnlyPackages, SafetyReport};
+
+/// The packages of `graph.dev_only` with their unsafe usage added up from the
+/// entries of `report`. `None` without such packages.
+pub fn dev_only_packages(
+    graph: &Graph,
+    report: &SafetyReport,
+) -> Option<DevOnlyPackages> {
+    if graph.dev_only.is_empty() {
+        return None;
+    }
+    let mut packages = graph
+        .dev_only
+        .iter()
+        .map(|package_id| from_cargo_package_id(*package_id))
+        .collect::<Vec<_>>();
+    packages.sort();
+    let mut dev_only = DevOnlyPackages {
+        packages,
+        used: Default::default(),
+        unused: Default::default(),
+    };
+    for package in &dev_only.packages {
+        if let Some(entry) = report.packages.get(package) {
+            dev_only.used += entry.unsafety.used.clone();
+            dev_only.unused += entry.unsafety.unused.clone();
+        }
+    }
+    Some(dev_only)
+}
+
+/// The table of the packages only reached through dev-dependencies of
+/// dependencies, a row per package followed by their subtotal. These are left
+/// out of the totals of the main table.
+pub fn construct_dev_only_lines(
+    dev_only: &DevOnlyPackages,
+    report: &SafetyReport,
+    unsafe_criteria: &UnsafeCriteria,
+    counter_columns: &CounterColumns,
+) -> Vec<String> {
+    let mut dev_only_lines = vec![
+        String::new(),
+        String::from(
+            "Only reached through dev-dependencies of dependencies, not part \
+             of the totals",
+        ),
+        String::new(),
+        format!("{}  Dependency", counter_columns.counter_header()),
+        String::new(),
+    ];
+    for package in &dev_only.packages {
+        let (used, unused) = report
+            .packages
+            .get(package)
+            .map(|entry| {
+                (entry.unsafety.used.clone(), entry.unsafety.unused.clone())
+            })
+            .unwrap_or_else(|| {
+                (CounterBlock::default(), CounterBlock::default())
+            });
+        dev_only_lines.push(format!(
+            "{}  {} {}",
+            table_row(&used, &unused, unsafe_criteria, counter_columns),
+            package.name,
+            package.version
+        ));
+    }
+    dev_only_lines.push(format!(
+        "{}  Subtotal",
+        table_row(
+            &dev_only.used,
+            &dev_only.unused,
+            unsafe_criteria,
+            counter_columns
+        )
+    ));
+    dev_only_lines.push(String::new());
+    dev_only_lines
+}
+
+#[cfg(test)]
+mod dev_only_tests {
+    use super::*;
+
+    use cargo::core::{PackageId, SourceId};
+    use cargo_geiger_serde::{Count, PackageInfo, ReportEntry, UnsafeInfo};
+    use rstest::*;
+    use std::collections::{HashMap, HashSet};
+    use std::env;
+
+    #[rstest]
+    fn construct_dev_only_lines_test() {
+        let source_id =
+            SourceId::for_path(&env::current_dir().unwrap()).unwrap();
+        let test_helper =
+            PackageId::new("test_helper", "0.1.0", source_id).unwrap();
+        let graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            platforms: HashMap::new(),
+            target: String::new(),
+            members: HashSet::new(),
+            dev_only: vec![test_helper].into_iter().collect(),
+        };
+        let id = from_cargo_package_id(test_helper);
+        let report = SafetyReport {
+            packages: vec![(
+                id.clone(),
+                ReportEntry {
+                    package: PackageInfo::new(id),
+                    unsafety: UnsafeInfo {
+                        unused: CounterBlock {
+                            exprs: Count {
+                                safe: 0,
+                                unsafe_: 3,
+                            },
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    features: Vec::new(),
+                    platforms: Vec::new(),
+                    native_linkage: None,
+                    dependency_counts: None,
+                    depth: None,
+                    files: Vec::new(),
+                    is_workspace_member: false,
+                    proc_macros_applied: Vec::new(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let dev_only = dev_only_packages(&graph, &report).unwrap();
+        assert_eq!(dev_only.unused.exprs.unsafe_, 3);
+
+        assert_eq!(
+            construct_dev_only_lines(
+                &dev_only,
+                &report,
+                &UnsafeCriteria::default(),
+                &CounterColumns::default(),
+            ),
+            vec![
+                "",
+                "Only reached through dev-dependencies of dependencies, not \
+                 part of the totals",
+                "",
+                "Functions  Expressions  Impls  Traits  Methods  FFI  \
+                 Dependency",
+                "",
+                "0/0        0/3          0/0    0/0     0/0      0/0  \
+                 test_helper 0.1.0",
+                "0/0        0/3          0/0    0/0     0/0      0/0  \
+                 Subtotal",
+                "",
+            ]
+        );
+    }
+}
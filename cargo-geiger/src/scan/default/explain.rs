@@ -0,0 +1,289 @@
+use crate::format::table::{table_row, CounterColumns, UNSAFE_COUNTERS_HEADER};
+use crate::krates_utils::CargoMetadataParameters;
+
+use super::super::{
+    display_package_id, package_root, print_warnings, PackageMetrics,
+    ScanDetails, ScanParameters, UnsafeCriteria,
+};
+use super::scan;
+
+use cargo::core::{PackageIdSpec, PackageSet, Workspace};
+use cargo::{CliError, CliResult};
+use cargo_geiger_serde::CounterBlock;
+use geiger::RsFileMetrics;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Prints the unsafe usage of every `.rs` file of the packages matching
+/// `package_id_spec`, one section per matching package.
+pub fn scan_to_explanation(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    package_id_spec: &str,
+    package_set: &PackageSet,
+    scan_parameters: &ScanParameters,
+    workspace: &Workspace,
+) -> CliResult {
+    let package_id_spec = PackageIdSpec::parse(package_id_spec)?;
+    let mut package_ids = package_set
+        .package_ids()
+        .filter(|id| package_id_spec.matches(*id))
+        .collect::<Vec<_>>();
+    if package_ids.is_empty() {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "package ID specification `{}` matched no packages",
+                package_id_spec
+            ),
+            1,
+        ));
+    }
+    package_ids.sort();
+
+    // All packages are scanned, but only the explained ones keep the metrics
+    // of each file, the others would take up most of the memory.
+    let is_explained = |package_id| package_id_spec.matches(package_id);
+    let ScanDetails {
+        rs_files_used,
+        geiger_context,
+        warnings,
+        ..
+    } = scan(
+        cargo_metadata_parameters,
+        package_set,
+        &HashSet::new(),
+        Some(&is_explained),
+        scan_parameters,
+        workspace,
+    )?;
+
+    for package_id in package_ids {
+        let package_root = package_root(package_set, package_id)?;
+        let explanation_lines = construct_explanation_lines(
+            &display_package_id(
+                package_id,
+                scan_parameters.workspace_root,
+                scan_parameters.print_config.normalize_output,
+            ),
+            &package_root,
+            geiger_context.package_id_to_metrics.get(&package_id),
+            &rs_files_used,
+            &scan_parameters.args.unsafe_criteria,
+        );
+        for explanation_line in explanation_lines {
+            println!("{}", explanation_line);
+        }
+    }
+
+    print_warnings(
+        &warnings,
+        scan_parameters.workspace_root,
+        scan_parameters.print_config.normalize_output,
+    );
+    Ok(())
+}
+
+fn construct_explanation_lines(
+    package_heading: &str,
+    package_root: &Path,
+    package_metrics: Option<&PackageMetrics>,
+    rs_files_used: &HashSet<PathBuf>,
+    unsafe_criteria: &UnsafeCriteria,
+) -> Vec<String> {
+    let mut explanation_lines =
+        vec![String::new(), package_heading.to_string(), String::new()];
+
+    let package_metrics = match package_metrics {
+        Some(package_metrics) => package_metrics,
+        None => {
+            explanation_lines.push(String::from("No metrics found."));
+            return explanation_lines;
+        }
+    };
+
+    let mut rs_file_metrics = package_metrics
+        .rs_path_to_metrics
+        .iter()
+        .collect::<Vec<_>>();
+    rs_file_metrics.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let relative_path = |path: &Path| {
+        path.strip_prefix(package_root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+
+    explanation_lines.push(String::from("Crate roots:"));
+    for (path, rs_file_metrics_wrapper) in rs_file_metrics
+        .iter()
+        .filter(|(_, wrapper)| wrapper.is_crate_entry_point)
+    {
+        explanation_lines.push(format!(
+            "    {}: {}",
+            relative_path(path),
+            unsafe_code_lint_level(&rs_file_metrics_wrapper.metrics)
+        ));
+    }
+
+    explanation_lines.push(String::new());
+    explanation_lines.push(format!(
+        "{}{: <7} File",
+        UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1].join(" "),
+        "Build"
+    ));
+    for (path, rs_file_metrics_wrapper) in rs_file_metrics {
+        let counters = &rs_file_metrics_wrapper.metrics.counters;
+        let (row, build) = if rs_files_used.contains(path) {
+            (
+                table_row(
+                    counters,
+                    &CounterBlock::default(),
+                    unsafe_criteria,
+                    &CounterColumns::default(),
+                ),
+                "used",
+            )
+        } else {
+            (
+                table_row(
+                    &CounterBlock::default(),
+                    counters,
+                    unsafe_criteria,
+                    &CounterColumns::default(),
+                ),
+                "unused",
+            )
+        };
+        explanation_lines.push(format!(
+            "{} {: <7} {}",
+            row,
+            build,
+            relative_path(path)
+        ));
+    }
+
+    explanation_lines
+}
+
+fn unsafe_code_lint_level(rs_file_metrics: &RsFileMetrics) -> &'static str {
+    if rs_file_metrics.forbids_unsafe {
+        "#![forbid(unsafe_code)]"
+    } else if rs_file_metrics.denies_unsafe {
+        "#![deny(unsafe_code)]"
+    } else {
+        "unsafe_code allowed"
+    }
+}
+
+#[cfg(test)]
+mod explain_tests {
+    use super::*;
+
+    use crate::rs_file::RsFileMetricsWrapper;
+
+    use cargo::core::{PackageId, SourceId};
+    use cargo::Config;
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest]
+    fn construct_explanation_lines_test() {
+        let config = Config::default().unwrap();
+        let package_id = PackageId::new(
+            "explained",
+            "1.0.0",
+            SourceId::crates_io(&config).unwrap(),
+        )
+        .unwrap();
+        let package_root = Path::new("/explained");
+        let package_metrics = PackageMetrics {
+            rs_path_to_metrics: vec![
+                (
+                    package_root.join("src/unused.rs"),
+                    rs_file_metrics_wrapper(1, false, false, false),
+                ),
+                (
+                    package_root.join("src/lib.rs"),
+                    rs_file_metrics_wrapper(2, false, true, true),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let rs_files_used =
+            vec![package_root.join("src/lib.rs")].into_iter().collect();
+
+        let explanation_lines = construct_explanation_lines(
+            &package_id.to_string(),
+            package_root,
+            Some(&package_metrics),
+            &rs_files_used,
+            &UnsafeCriteria::default(),
+        );
+
+        assert_eq!(
+            explanation_lines,
+            vec![
+                "",
+                "explained v1.0.0",
+                "",
+                "Crate roots:",
+                "    src/lib.rs: #![deny(unsafe_code)]",
+                "",
+                "Functions  Expressions  Impls  Traits  Methods  FFI Build   File",
+                "0/0        2/2          0/0    0/0     0/0      0/0 used    src/lib.rs",
+                "0/0        0/1          0/0    0/0     0/0      0/0 unused  src/unused.rs",
+            ]
+        );
+    }
+
+    #[rstest(
+        input_forbids_unsafe,
+        input_denies_unsafe,
+        expected_lint_level,
+        case(true, false, "#![forbid(unsafe_code)]"),
+        case(true, true, "#![forbid(unsafe_code)]"),
+        case(false, true, "#![deny(unsafe_code)]"),
+        case(false, false, "unsafe_code allowed")
+    )]
+    fn unsafe_code_lint_level_test(
+        input_forbids_unsafe: bool,
+        input_denies_unsafe: bool,
+        expected_lint_level: &str,
+    ) {
+        let rs_file_metrics = RsFileMetrics {
+            forbids_unsafe: input_forbids_unsafe,
+            denies_unsafe: input_denies_unsafe,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            unsafe_code_lint_level(&rs_file_metrics),
+            expected_lint_level
+        );
+    }
+
+    fn rs_file_metrics_wrapper(
+        unsafe_exprs: u64,
+        forbids_unsafe: bool,
+        denies_unsafe: bool,
+        is_crate_entry_point: bool,
+    ) -> RsFileMetricsWrapper {
+        RsFileMetricsWrapper {
+            metrics: RsFileMetrics {
+                counters: CounterBlock {
+                    exprs: Count {
+                        safe: 0,
+                        unsafe_: unsafe_exprs,
+                    },
+                    ..Default::default()
+                },
+                forbids_unsafe,
+                denies_unsafe,
+                ..Default::default()
+            },
+            is_crate_entry_point,
+            is_generated: false,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::format::table::{table_row, CounterColumns};
+use crate::graph::Graph;
+
+use super::super::{from_cargo_package_id, package_root, UnsafeCriteria};
+
+use cargo::core::{PackageId, PackageSet};
+use cargo::util::CargoResult;
+use cargo_geiger_serde::{CounterBlock, ExternalPathGroup, SafetyReport};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The path dependencies in the graph with roots outside of `workspace_root`,
+/// grouped by `group_directory`, sorted by directory. The unsafe usage of a
+/// group adds up the entries of `report` of its packages.
+pub fn external_path_groups(
+    graph: &Graph,
+    package_set: &PackageSet,
+    workspace_root: &Path,
+    report: &SafetyReport,
+) -> CargoResult<Vec<ExternalPathGroup>> {
+    let mut package_ids_by_directory =
+        BTreeMap::<PathBuf, Vec<PackageId>>::new();
+    for package_id in graph.nodes.keys() {
+        if !package_id.source_id().is_path()
+            || graph.members.contains(package_id)
+        {
+            continue;
+        }
+        let root = package_root(package_set, *package_id)?;
+        if let Some(directory) = group_directory(workspace_root, &root) {
+            package_ids_by_directory
+                .entry(directory)
+                .or_insert_with(Vec::new)
+                .push(*package_id);
+        }
+    }
+    Ok(package_ids_by_directory
+        .into_iter()
+        .map(|(directory, mut package_ids)| {
+            package_ids.sort();
+            let mut group = ExternalPathGroup {
+                directory,
+                packages: Vec::new(),
+                used: Default::default(),
+                unused: Default::default(),
+            };
+            for package_id in package_ids {
+                let id = from_cargo_package_id(package_id);
+                if let Some(entry) = report.packages.get(&id) {
+                    group.used += entry.unsafety.used.clone();
+                    group.unused += entry.unsafety.unused.clone();
+                }
+                group.packages.push(id);
+            }
+            group
+        })
+        .collect())
+}
+
+/// The directory of the group of a package with its root at `package_root`,
+/// `None` for packages inside of the workspace. It is the deepest common
+/// ancestor of both roots followed by the next component towards the package,
+/// or the package root itself if the workspace is inside of the package.
+fn group_directory(
+    workspace_root: &Path,
+    package_root: &Path,
+) -> Option<PathBuf> {
+    if package_root.starts_with(workspace_root) {
+        return None;
+    }
+    // Roots on different drives have no common ancestor.
+    let common_ancestor = match workspace_root
+        .ancestors()
+        .find(|ancestor| package_root.starts_with(ancestor))
+    {
+        Some(common_ancestor) => common_ancestor,
+        None => return Some(package_root.to_path_buf()),
+    };
+    let mut directory = common_ancestor.to_path_buf();
+    if let Some(component) = package_root
+        .strip_prefix(common_ancestor)
+        .ok()
+        .and_then(|relative_path| relative_path.components().next())
+    {
+        directory.push(component);
+    }
+    Some(directory)
+}
+
+/// The tables of `--split-local-paths`, one per group with a row per package
+/// followed by the subtotal of the group.
+pub fn construct_external_path_group_lines(
+    groups: &[ExternalPathGroup],
+    report: &SafetyReport,
+    unsafe_criteria: &UnsafeCriteria,
+    counter_columns: &CounterColumns,
+) -> Vec<String> {
+    if groups.is_empty() {
+        return Vec::new();
+    }
+    let counter_header = counter_columns.counter_header();
+    let mut external_path_group_lines = Vec::new();
+    for group in groups {
+        external_path_group_lines.push(String::new());
+        external_path_group_lines.push(format!(
+            "Path dependencies in {}",
+            group.directory.display()
+        ));
+        external_path_group_lines.push(String::new());
+        external_path_group_lines
+            .push(format!("{}  Dependency", counter_header));
+        external_path_group_lines.push(String::new());
+        for package in &group.packages {
+            let (used, unused) = report
+                .packages
+                .get(package)
+                .map(|entry| {
+                    (entry.unsafety.used.clone(), entry.unsafety.unused.clone())
+                })
+                .unwrap_or_else(|| {
+                    (CounterBlock::default(), CounterBlock::default())
+                });
+            external_path_group_lines.push(format!(
+                "{}  {} {}",
+                table_row(&used, &unused, unsafe_criteria, counter_columns),
+                package.name,
+                package.version
+            ));
+        }
+        external_path_group_lines.push(format!(
+            "{}  Subtotal",
+            table_row(
+                &group.used,
+                &group.unused,
+                unsafe_criteria,
+                counter_columns
+            )
+        ));
+    }
+    external_path_group_lines.push(String::new());
+    external_path_group_lines
+}
+
+#[cfg(test)]
+mod external_paths_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_package_root,
+        expected_group_directory,
+        case("/src/product/local", None),
+        case("/src/repo_a/crate_a1", Some("/src/repo_a")),
+        case("/src/repo_a/nested/crate_a2", Some("/src/repo_a")),
+        case("/src/repo_b", Some("/src/repo_b")),
+        case("/vendor/crate_c", Some("/vendor")),
+        case("/src", Some("/src"))
+    )]
+    fn group_directory_test(
+        input_package_root: &str,
+        expected_group_directory: Option<&str>,
+    ) {
+        assert_eq!(
+            group_directory(
+                Path::new("/src/product"),
+                Path::new(input_package_root)
+            ),
+            expected_group_directory.map(PathBuf::from)
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::format::table::{table_row, CounterColumns};
+use crate::graph::Graph;
+
+use super::super::{from_cargo_package_id, UnsafeCriteria};
+
+use cargo::core::{PackageId, Workspace};
+use cargo_geiger_serde::{MemberUnsafety, SafetyReport};
+use std::collections::HashSet;
+
+pub fn workspace_member_ids(workspace: &Workspace) -> HashSet<PackageId> {
+    workspace
+        .members()
+        .map(|member| member.package_id())
+        .collect()
+}
+
+/// The unsafe usage of the members in the dependency graph, sorted by their
+/// ids. The transitive usage of a member adds up the entries of `report` of
+/// the member and of every package it reaches in the graph, each of them
+/// once. A package reached by several members is counted for each of them,
+/// the packages only reached through dev-dependencies of dependencies are not
+/// counted.
+pub fn members_unsafety(
+    graph: &Graph,
+    member_ids: &HashSet<PackageId>,
+    report: &SafetyReport,
+) -> Vec<MemberUnsafety> {
+    let mut member_ids = member_ids
+        .iter()
+        .filter(|member_id| graph.nodes.contains_key(member_id))
+        .collect::<Vec<_>>();
+    member_ids.sort();
+    member_ids
+        .into_iter()
+        .map(|member_id| {
+            let id = from_cargo_package_id(*member_id);
+            let (used, unused) = report
+                .packages
+                .get(&id)
+                .map(|entry| {
+                    (entry.unsafety.used.clone(), entry.unsafety.unused.clone())
+                })
+                .unwrap_or_default();
+            let mut member = MemberUnsafety {
+                id,
+                used,
+                unused,
+                transitive_used: Default::default(),
+                transitive_unused: Default::default(),
+            };
+            for package_id in reachable_package_ids(graph, *member_id)
+                .into_iter()
+                .filter(|package_id| !graph.dev_only.contains(package_id))
+            {
+                if let Some(entry) =
+                    report.packages.get(&from_cargo_package_id(package_id))
+                {
+                    member.transitive_used += entry.unsafety.used.clone();
+                    member.transitive_unused += entry.unsafety.unused.clone();
+                }
+            }
+            member
+        })
+        .collect()
+}
+
+/// The package and all packages it depends on in the graph, directly or not.
+fn reachable_package_ids(
+    graph: &Graph,
+    package_id: PackageId,
+) -> HashSet<PackageId> {
+    let mut visited_indices = HashSet::new();
+    let mut pending_indices = vec![graph.nodes[&package_id]];
+    while let Some(index) = pending_indices.pop() {
+        if visited_indices.insert(index) {
+            pending_indices.extend(graph.graph.neighbors(index));
+        }
+    }
+    visited_indices
+        .into_iter()
+        .map(|index| graph.graph[index].id)
+        .collect()
+}
+
+/// The table of `--members-only`, a row per member with its own unsafe usage
+/// next to the transitive one.
+pub fn construct_members_table_lines(
+    members: &[MemberUnsafety],
+    unsafe_criteria: &UnsafeCriteria,
+    counter_columns: &CounterColumns,
+) -> Vec<String> {
+    let row_width = counter_columns.widths.iter().sum::<usize>()
+        + counter_columns.widths.len()
+        - 1;
+    let counter_header = counter_columns.counter_header();
+    let mut members_table_lines = vec![
+        String::new(),
+        format!("{: <1$}  Transitive", "Own", row_width),
+        format!("{}  {}  Member", counter_header, counter_header),
+        String::new(),
+    ];
+    for member in members {
+        members_table_lines.push(format!(
+            "{}  {}  {} {}",
+            table_row(
+                &member.used,
+                &member.unused,
+                unsafe_criteria,
+                counter_columns
+            ),
+            table_row(
+                &member.transitive_used,
+                &member.transitive_unused,
+                unsafe_criteria,
+                counter_columns
+            ),
+            member.id.name,
+            member.id.version
+        ));
+    }
+    members_table_lines.push(String::new());
+    members_table_lines
+}
+
+#[cfg(test)]
+mod members_tests {
+    use super::*;
+
+    use crate::graph::Node;
+
+    use cargo::core::dependency::DepKind;
+    use cargo::core::SourceId;
+    use cargo_geiger_serde::{
+        Count, CounterBlock, PackageInfo, ReportEntry, UnsafeInfo,
+    };
+    use rstest::*;
+    use std::collections::HashMap;
+    use std::env;
+
+    #[rstest]
+    fn members_unsafety_counts_shared_dependencies_for_each_member() {
+        let source_id =
+            SourceId::for_path(&env::current_dir().unwrap()).unwrap();
+        let package_id =
+            |name: &str| PackageId::new(name, "0.1.0", source_id).unwrap();
+        let (app, member_a, member_b, shared, test_helper) = (
+            package_id("app"),
+            package_id("member_a"),
+            package_id("member_b"),
+            package_id("shared"),
+            package_id("test_helper"),
+        );
+        // app -> member_a -> shared and app -> member_b -> shared, the
+        // dev-dependency member_a -> test_helper is not counted.
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            platforms: HashMap::new(),
+            target: String::new(),
+            members: HashSet::new(),
+            dev_only: vec![test_helper].into_iter().collect(),
+        };
+        for id in &[app, member_a, member_b, shared, test_helper] {
+            graph
+                .nodes
+                .insert(*id, graph.graph.add_node(Node { id: *id }));
+        }
+        for (from, to, kind) in &[
+            (app, member_a, DepKind::Normal),
+            (app, member_b, DepKind::Normal),
+            (member_a, shared, DepKind::Normal),
+            (member_b, shared, DepKind::Normal),
+            (member_a, test_helper, DepKind::Development),
+        ] {
+            graph
+                .graph
+                .add_edge(graph.nodes[from], graph.nodes[to], *kind);
+        }
+        let report = SafetyReport {
+            packages: vec![
+                (app, 1),
+                (member_a, 2),
+                (member_b, 4),
+                (shared, 8),
+                (test_helper, 16),
+            ]
+            .into_iter()
+            .map(|(id, unsafe_exprs)| {
+                let id = from_cargo_package_id(id);
+                let entry = ReportEntry {
+                    package: PackageInfo::new(id.clone()),
+                    unsafety: UnsafeInfo {
+                        used: exprs(unsafe_exprs),
+                        ..Default::default()
+                    },
+                    features: Vec::new(),
+                    platforms: Vec::new(),
+                    native_linkage: None,
+                    dependency_counts: None,
+                    depth: None,
+                    files: Vec::new(),
+                    is_workspace_member: false,
+                    proc_macros_applied: Vec::new(),
+                };
+                (id, entry)
+            })
+            .collect(),
+            ..Default::default()
+        };
+        // shared is a dependency of the workspace, not a member.
+        let member_ids = vec![member_a, member_b, app]
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let members = members_unsafety(&graph, &member_ids, &report);
+
+        assert_eq!(
+            members
+                .iter()
+                .map(|member| (
+                    member.id.name.as_str(),
+                    member.used.exprs.unsafe_,
+                    member.transitive_used.exprs.unsafe_
+                ))
+                .collect::<Vec<_>>(),
+            vec![("app", 1, 15), ("member_a", 2, 10), ("member_b", 4, 12)]
+        );
+    }
+
+    #[rstest]
+    fn construct_members_table_lines_test() {
+        let source_id =
+            SourceId::for_path(&env::current_dir().unwrap()).unwrap();
+        let member = MemberUnsafety {
+            id: from_cargo_package_id(
+                PackageId::new("member_a", "0.1.0", source_id).unwrap(),
+            ),
+            used: exprs(2),
+            unused: Default::default(),
+            transitive_used: exprs(10),
+            transitive_unused: Default::default(),
+        };
+
+        let members_table_lines = construct_members_table_lines(
+            &[member],
+            &UnsafeCriteria::default(),
+            &CounterColumns::default(),
+        );
+
+        assert_eq!(
+            members_table_lines,
+            vec![
+                "",
+                "Own                                                  \
+                 Transitive",
+                "Functions  Expressions  Impls  Traits  Methods  FFI  \
+                 Functions  Expressions  Impls  Traits  Methods  FFI  Member",
+                "",
+                "0/0        2/2          0/0    0/0     0/0      0/0  \
+                 0/0        10/10        0/0    0/0     0/0      0/0  \
+                 member_a 0.1.0",
+                "",
+            ]
+        );
+    }
+
+    fn exprs(unsafe_exprs: u64) -> CounterBlock {
+        CounterBlock {
+            exprs: Count {
+                safe: 0,
+                unsafe_: unsafe_exprs,
+            },
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use crate::args::Args;
+use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::format::table::{table_row, CounterColumns};
+use crate::timings::{Phase, Timings};
+
+use super::super::{
+    display_path, print_warnings, ScanParameters, UnsafeCriteria,
+    WorkspaceParameters,
+};
+use super::scan_to_safety_report;
+use super::table::{print_table_lines, warnings_result};
+
+use cargo::{CliError, CliResult, Config};
+use cargo_geiger_serde::{
+    CounterBlock, MergedSafetyReport, WorkspaceSafetyReport,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::PathBuf;
+
+/// The workspaces of a run with more than one `--manifest-path`, or more than
+/// one selected workspace member. Each of them is resolved and scanned on its
+/// own, the results are merged once all of them are scanned.
+pub struct MergedScan {
+    workspaces: Vec<WorkspaceSafetyReport>,
+    /// The warnings of all tables that fail the run
+    warning_count: u64,
+}
+
+impl MergedScan {
+    pub fn new(args: &Args) -> Result<Self, CliError> {
+        if args.forbid_only
+            || args.output_format == Some(OutputFormat::TreeJson)
+            || args.explain.is_some()
+            || args.output.is_some()
+            || args.output_dir.is_some()
+            || args.reuse_report.is_some()
+            || args.check_lock.is_some()
+            || args.package.is_some()
+        {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "more than one `--manifest-path` or workspace member \
+                     can't be combined with `--forbid-only`, \
+                     `--output-format tree-json`, `--explain`, `--output`, \
+                     `--output-dir`, `--reuse-report`, `--check-lock` or \
+                     `--package`"
+                ),
+                1,
+            ));
+        }
+        Ok(MergedScan {
+            workspaces: Vec::new(),
+            warning_count: 0,
+        })
+    }
+
+    /// Scans the workspace of `manifest_path`. Its table is printed right
+    /// away, below the path of the manifest, unless the output is JSON.
+    pub fn scan_workspace(
+        &mut self,
+        args: &Args,
+        config: &Config,
+        print_config: &PrintConfig,
+        manifest_path: PathBuf,
+        timings: &Timings,
+        workspace_parameters: &WorkspaceParameters,
+    ) -> CliResult {
+        let workspace_root = workspace_parameters
+            .workspace
+            .root()
+            .canonicalize()
+            .map_err(anyhow::Error::from)?;
+        let scan_parameters = ScanParameters {
+            args,
+            config,
+            print_config,
+            resolve: workspace_parameters.resolve,
+            timings,
+            workspace_root: &workspace_root,
+        };
+        let (report, scan_details) = scan_to_safety_report(
+            workspace_parameters.cargo_metadata_parameters,
+            workspace_parameters.graph,
+            workspace_parameters.package_set,
+            workspace_parameters.root_package_id,
+            &scan_parameters,
+            workspace_parameters.workspace,
+        )?;
+        match args.output_format {
+            Some(_) => print_warnings(
+                &report.warnings,
+                scan_parameters.workspace_root,
+                print_config.normalize_output,
+            ),
+            None => {
+                let manifest_path_display = if print_config.normalize_output {
+                    display_path(
+                        &manifest_path,
+                        scan_parameters.workspace_root,
+                        print_config.normalize_output,
+                    )
+                } else {
+                    manifest_path.display().to_string()
+                };
+                println!("Manifest: {}", manifest_path_display);
+                self.warning_count += print_table_lines(
+                    workspace_parameters.graph,
+                    workspace_parameters.package_set,
+                    workspace_parameters.root_package_id,
+                    &scan_details,
+                    &scan_parameters,
+                    workspace_parameters.workspace,
+                )?;
+            }
+        }
+        timings.end_phase(Phase::Render);
+        self.workspaces.push(WorkspaceSafetyReport {
+            manifest_path,
+            report,
+        });
+        Ok(())
+    }
+
+    /// Prints the merged report, or the totals of all workspaces below their
+    /// tables. Fails like a single table does if any of the tables had
+    /// warnings.
+    pub fn finish(self, args: &Args) -> CliResult {
+        let merged_report = merge_reports(self.workspaces);
+        match args.output_format {
+            Some(_) => {
+                println!("{}", serde_json::to_string(&merged_report).unwrap())
+            }
+            None => {
+                let merged_total_lines = construct_merged_total_lines(
+                    &merged_report,
+                    &args.unsafe_criteria,
+                    args.show_totals,
+                );
+                for merged_total_line in merged_total_lines {
+                    println!("{}", merged_total_line);
+                }
+            }
+        }
+        warnings_result(self.warning_count)
+    }
+}
+
+/// Adds up the unsafe usage of the packages of all workspaces. A package used
+/// by more than one workspace, with the same name, version and source, is
+/// counted once, different versions of a crate are all counted. The packages
+/// a workspace only reaches through dev-dependencies of dependencies are not
+/// counted for it.
+fn merge_reports(workspaces: Vec<WorkspaceSafetyReport>) -> MergedSafetyReport {
+    let mut used = CounterBlock::default();
+    let mut unused = CounterBlock::default();
+    let mut counted_package_ids = HashSet::new();
+    for workspace in &workspaces {
+        let dev_only_package_ids = workspace
+            .report
+            .dev_only
+            .iter()
+            .flat_map(|dev_only| &dev_only.packages)
+            .collect::<HashSet<_>>();
+        for (package_id, entry) in &workspace.report.packages {
+            if dev_only_package_ids.contains(package_id) {
+                continue;
+            }
+            if counted_package_ids.insert(package_id) {
+                used += entry.unsafety.used.clone();
+                unused += entry.unsafety.unused.clone();
+            }
+        }
+    }
+    MergedSafetyReport {
+        workspaces,
+        used,
+        unused,
+    }
+}
+
+fn construct_merged_total_lines(
+    merged_report: &MergedSafetyReport,
+    unsafe_criteria: &UnsafeCriteria,
+    show_totals: bool,
+) -> Vec<String> {
+    let mut package_ids = HashSet::new();
+    let mut crate_versions = BTreeMap::<&str, BTreeSet<_>>::new();
+    for workspace in &merged_report.workspaces {
+        for package_id in workspace.report.packages.keys() {
+            package_ids.insert(package_id);
+            crate_versions
+                .entry(package_id.name.as_str())
+                .or_default()
+                .insert(&package_id.version);
+        }
+    }
+
+    let counter_columns = if show_totals {
+        CounterColumns::fitting(
+            &(merged_report.used.clone() + merged_report.unused.clone()),
+        )
+    } else {
+        CounterColumns::default()
+    };
+    let mut merged_total_lines = vec![
+        String::from(
+            "All manifests, packages used by more than one of them are \
+             counted once:",
+        ),
+        String::new(),
+        counter_columns.header(),
+        format!(
+            "{}     {} packages",
+            table_row(
+                &merged_report.used,
+                &merged_report.unused,
+                unsafe_criteria,
+                &counter_columns,
+            ),
+            package_ids.len()
+        ),
+        String::new(),
+    ];
+
+    let crates_with_several_versions = crate_versions
+        .into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .collect::<Vec<_>>();
+    if !crates_with_several_versions.is_empty() {
+        merged_total_lines.push(String::from(
+            "Crates used in more than one version, each version is counted:",
+        ));
+        for (name, versions) in crates_with_several_versions {
+            merged_total_lines.push(format!(
+                "    {} {}",
+                name,
+                versions
+                    .iter()
+                    .map(|version| version.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        merged_total_lines.push(String::new());
+    }
+
+    merged_total_lines
+}
+
+#[cfg(test)]
+mod merged_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{
+        Count, DevOnlyPackages, PackageId, PackageInfo, ReportEntry,
+        SafetyReport, Source, UnsafeInfo,
+    };
+    use rstest::*;
+    use url::Url;
+
+    #[rstest]
+    fn merge_reports_counts_shared_packages_once() {
+        let merged_report = merge_reports(vec![
+            workspace_report(
+                "frontend/Cargo.toml",
+                &[("frontend", "0.1.0", 1), ("rand", "0.7.3", 2)],
+            ),
+            workspace_report(
+                "backend/Cargo.toml",
+                &[
+                    ("backend", "0.1.0", 4),
+                    ("rand", "0.7.3", 2),
+                    ("rand", "0.8.5", 8),
+                ],
+            ),
+        ]);
+
+        assert_eq!(merged_report.workspaces.len(), 2);
+        assert_eq!(merged_report.used.exprs.unsafe_, 15);
+
+        let merged_total_lines = construct_merged_total_lines(
+            &merged_report,
+            &UnsafeCriteria::default(),
+            false,
+        );
+        assert_eq!(
+            merged_total_lines,
+            vec![
+                "All manifests, packages used by more than one of them are \
+                 counted once:",
+                "",
+                "Functions  Expressions  Impls  Traits  Methods  FFI  \
+                 Dependency",
+                "0/0        15/15        0/0    0/0     0/0      0/0     \
+                 4 packages",
+                "",
+                "Crates used in more than one version, each version is \
+                 counted:",
+                "    rand 0.7.3, 0.8.5",
+                "",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn merge_reports_leaves_out_dev_only_packages() {
+        let mut frontend = workspace_report(
+            "frontend/Cargo.toml",
+            &[("frontend", "0.1.0", 1), ("rand", "0.7.3", 2)],
+        );
+        let mut backend = workspace_report(
+            "backend/Cargo.toml",
+            &[("backend", "0.1.0", 4), ("rand", "0.8.5", 8)],
+        );
+        for workspace in &mut [&mut frontend, &mut backend] {
+            let packages = workspace
+                .report
+                .packages
+                .keys()
+                .filter(|id| id.name == "rand")
+                .cloned()
+                .collect();
+            workspace.report.dev_only = Some(DevOnlyPackages {
+                packages,
+                used: Default::default(),
+                unused: Default::default(),
+            });
+        }
+
+        let merged_report = merge_reports(vec![frontend, backend]);
+
+        assert_eq!(merged_report.used.exprs.unsafe_, 5);
+    }
+
+    fn workspace_report(
+        manifest_path: &str,
+        packages: &[(&str, &str, u64)],
+    ) -> WorkspaceSafetyReport {
+        let source = Source::Registry {
+            name: String::from("crates.io"),
+            url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                .unwrap(),
+        };
+        let report = SafetyReport {
+            packages: packages
+                .iter()
+                .map(|(name, version, unsafe_exprs)| {
+                    let id = PackageId {
+                        name: String::from(*name),
+                        version: version.parse().unwrap(),
+                        source: source.clone(),
+                    };
+                    let entry = ReportEntry {
+                        package: PackageInfo::new(id.clone()),
+                        unsafety: UnsafeInfo {
+                            used: CounterBlock {
+                                exprs: Count {
+                                    safe: 0,
+                                    unsafe_: *unsafe_exprs,
+                                },
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        features: Vec::new(),
+                        platforms: Vec::new(),
+                        native_linkage: None,
+                        dependency_counts: None,
+                        depth: None,
+                        files: Vec::new(),
+                        is_workspace_member: false,
+                        proc_macros_applied: Vec::new(),
+                    };
+                    (id, entry)
+                })
+                .collect(),
+            ..Default::default()
+        };
+        WorkspaceSafetyReport {
+            manifest_path: PathBuf::from(manifest_path),
+            report,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::Context;
+use cargo::util::{paths, CargoResult};
+use cargo_geiger_serde::{
+    PackageId, PackageReport, ReportIndex, ReportIndexEntry, ReportMetadata,
+    SafetyReport,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Writes the report to `output_dir` split by package, a
+/// `<name>-<version>.json` per package and an `index.json` listing them.
+/// Files whose contents didn't change are left alone, the files of unchanged
+/// packages keep their modification time. With `prune`, all other `.json`
+/// files in the directory are removed, like the ones of packages that are no
+/// longer in the graph.
+pub fn write_output_dir(
+    report: &SafetyReport,
+    output_dir: &Path,
+    prune: bool,
+) -> CargoResult<()> {
+    fs::create_dir_all(output_dir).with_context(|| {
+        format!("failed to create directory `{}`", output_dir.display())
+    })?;
+    // The timings change with every run, they would change every file.
+    let package_metadata = ReportMetadata {
+        timings: Vec::new(),
+        ..report.metadata.clone()
+    };
+    let mut package_ids = report.packages.keys().collect::<Vec<_>>();
+    package_ids.sort();
+    let file_names = package_file_names(&package_ids);
+
+    let mut index = ReportIndex {
+        metadata: report.metadata.clone(),
+        warnings: report.warnings.clone(),
+        ..Default::default()
+    };
+    for (package_id, file_name) in package_ids.into_iter().zip(file_names) {
+        let entry = &report.packages[package_id];
+        let package_report = PackageReport {
+            metadata: package_metadata.clone(),
+            entry: entry.clone(),
+        };
+        write_if_changed(
+            &output_dir.join(&file_name),
+            &serde_json::to_string(&package_report)?,
+        )?;
+        index.used += entry.unsafety.used.clone();
+        index.unused += entry.unsafety.unused.clone();
+        index.packages.push(ReportIndexEntry {
+            id: package_id.clone(),
+            file: file_name,
+            used: entry.unsafety.used.clone(),
+            unused: entry.unsafety.unused.clone(),
+        });
+    }
+    write_if_changed(
+        &output_dir.join(INDEX_FILE_NAME),
+        &serde_json::to_string(&index)?,
+    )?;
+
+    if prune {
+        let written_file_names = index
+            .packages
+            .iter()
+            .map(|index_entry| index_entry.file.as_str())
+            .chain(std::iter::once(INDEX_FILE_NAME))
+            .collect::<HashSet<_>>();
+        prune_output_dir(output_dir, &written_file_names)?;
+    }
+    Ok(())
+}
+
+/// The file name of each package, in the same order. Characters that aren't
+/// safe in file names on all platforms are replaced by `_`. Packages ending
+/// up with the same name, like a crate from two sources, get a number
+/// appended in the order of the package ids. Names are compared ignoring
+/// case, for case insensitive file systems.
+fn package_file_names(package_ids: &[&PackageId]) -> Vec<String> {
+    let mut taken_file_names = HashSet::new();
+    package_ids
+        .iter()
+        .map(|package_id| {
+            let stem = sanitize_file_name(&format!(
+                "{}-{}",
+                package_id.name, package_id.version
+            ));
+            let mut file_name = format!("{}.json", stem);
+            let mut number = 2;
+            while !taken_file_names.insert(file_name.to_lowercase()) {
+                file_name = format!("{}-{}.json", stem, number);
+                number += 1;
+            }
+            file_name
+        })
+        .collect()
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn write_if_changed(path: &Path, contents: &str) -> CargoResult<()> {
+    if fs::read_to_string(path).ok().as_deref() == Some(contents) {
+        return Ok(());
+    }
+    paths::write(path, contents.as_bytes())
+}
+
+/// Removes the `.json` files of the directory that weren't written by this
+/// run. Other files and directories are left alone.
+fn prune_output_dir(
+    output_dir: &Path,
+    written_file_names: &HashSet<&str>,
+) -> CargoResult<()> {
+    for dir_entry in fs::read_dir(output_dir)? {
+        let path = dir_entry?.path();
+        let is_stale = path.is_file()
+            && path
+                .extension()
+                .map_or(false, |extension| extension == "json")
+            && path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .map_or(true, |file_name| {
+                    !written_file_names.contains(file_name)
+                });
+        if is_stale {
+            fs::remove_file(&path).with_context(|| {
+                format!("failed to remove `{}`", path.display())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod output_dir_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Source;
+    use rstest::*;
+    use url::Url;
+
+    #[rstest(
+        input_name,
+        expected_file_name,
+        case("rand-0.8.5", "rand-0.8.5"),
+        case("ring-0.17.0+build.1", "ring-0.17.0_build.1"),
+        case("odd/name:1.0.0", "odd_name_1.0.0")
+    )]
+    fn sanitize_file_name_test(input_name: &str, expected_file_name: &str) {
+        assert_eq!(sanitize_file_name(input_name), expected_file_name);
+    }
+
+    #[rstest]
+    fn package_file_names_numbers_clashing_names() {
+        let registry = Source::Registry {
+            name: String::from("crates.io"),
+            url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                .unwrap(),
+        };
+        let git = Source::Git {
+            url: Url::parse("https://github.com/rust-random/rand").unwrap(),
+            rev: String::from("0123456789abcdef"),
+        };
+        let package_id = |name: &str, source: &Source| PackageId {
+            name: String::from(name),
+            version: "0.8.5".parse().unwrap(),
+            source: source.clone(),
+        };
+        let package_ids = vec![
+            package_id("rand", &registry),
+            package_id("rand", &git),
+            package_id("Rand", &registry),
+            package_id("rand_core", &registry),
+        ];
+
+        assert_eq!(
+            package_file_names(&package_ids.iter().collect::<Vec<_>>()),
+            vec![
+                "rand-0.8.5.json",
+                "rand-0.8.5-2.json",
+                "Rand-0.8.5-3.json",
+                "rand_core-0.8.5.json",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn write_output_dir_prunes_stale_files_only_when_asked() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let stale_path = output_dir.path().join("removed-1.0.0.json");
+        let other_path = output_dir.path().join("notes.txt");
+        fs::write(&stale_path, "{}").unwrap();
+        fs::write(&other_path, "").unwrap();
+
+        write_output_dir(&SafetyReport::default(), output_dir.path(), false)
+            .unwrap();
+        assert!(stale_path.exists());
+        assert!(output_dir.path().join(INDEX_FILE_NAME).exists());
+
+        write_output_dir(&SafetyReport::default(), output_dir.path(), true)
+            .unwrap();
+        assert!(!stale_path.exists());
+        assert!(other_path.exists());
+        assert!(output_dir.path().join(INDEX_FILE_NAME).exists());
+    }
+}
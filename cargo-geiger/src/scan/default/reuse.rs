@@ -0,0 +1,319 @@
+use crate::scan::from_cargo_package_id;
+
+use cargo::core::{PackageId, PackageSet};
+use cargo::util::{paths, CargoResult};
+use cargo_geiger_serde::{
+    InconsistentForbid, PackageId as ReportPackageId, ReportEntry,
+    ReportMetadata, SafetyReport, Source,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The parts of a previous report that are still valid for the current
+/// resolve.
+#[derive(Debug, Default)]
+pub struct ReusableReport {
+    pub entries: HashMap<ReportPackageId, ReportEntry>,
+    pub used_but_not_scanned_files: HashSet<PathBuf>,
+    /// The inconsistent forbids of the packages in `entries`
+    pub inconsistent_forbid: Vec<InconsistentForbid>,
+}
+
+pub fn read_reusable_report(
+    report_path: &Path,
+    metadata: &ReportMetadata,
+) -> CargoResult<ReusableReport> {
+    let report = paths::read(report_path)?;
+    let report = serde_json::from_str::<SafetyReport>(&report)?;
+    Ok(reusable_report(report, metadata))
+}
+
+/// Packages that are still in the current resolve with the same name,
+/// version, source and checksum can reuse their entries. Path dependencies
+/// are always scanned again since their contents are not locked, and so are
+/// packages that failed to build, the build environment may have changed.
+/// Nothing is reused from a report counted with another counting strategy.
+fn reusable_report(
+    report: SafetyReport,
+    metadata: &ReportMetadata,
+) -> ReusableReport {
+    if report.metadata.counting_strategy != metadata.counting_strategy {
+        return ReusableReport::default();
+    }
+    let previously_locked_packages = report
+        .metadata
+        .locked_packages
+        .iter()
+        .collect::<HashSet<_>>();
+    let unchanged_package_ids = metadata
+        .locked_packages
+        .iter()
+        .filter(|locked_package| match locked_package.id.source {
+            Source::Path(_) => false,
+            Source::Git { .. } | Source::Registry { .. } => {
+                previously_locked_packages.contains(locked_package)
+            }
+        })
+        .map(|locked_package| &locked_package.id)
+        .collect::<HashSet<_>>();
+    let build_failures = &report.build_failures;
+    let entries = report
+        .packages
+        .into_iter()
+        .filter(|(id, _)| {
+            unchanged_package_ids.contains(id)
+                && !build_failures.contains_key(id)
+        })
+        .collect::<HashMap<_, _>>();
+    let inconsistent_forbid = report
+        .inconsistent_forbid
+        .into_iter()
+        .filter(|inconsistent_forbid| {
+            entries.contains_key(&inconsistent_forbid.package)
+        })
+        .collect();
+    ReusableReport {
+        entries,
+        used_but_not_scanned_files: report.used_but_not_scanned_files,
+        inconsistent_forbid,
+    }
+}
+
+impl ReusableReport {
+    pub fn package_ids(&self, package_set: &PackageSet) -> HashSet<PackageId> {
+        package_set
+            .package_ids()
+            .filter(|id| self.entries.contains_key(&from_cargo_package_id(*id)))
+            .collect()
+    }
+
+    /// Files that are used by the build but weren't scanned, now that the
+    /// packages in `reused_package_roots` are no longer scanned: these are
+    /// taken from the previous report instead.
+    pub fn merge_used_but_not_scanned_files(
+        &self,
+        used_but_not_scanned_files: Vec<PathBuf>,
+        reused_package_roots: &[PathBuf],
+    ) -> HashSet<PathBuf> {
+        let is_in_reused_package = |path: &PathBuf| {
+            reused_package_roots
+                .iter()
+                .any(|root| path.starts_with(root))
+        };
+        used_but_not_scanned_files
+            .into_iter()
+            .filter(|path| !is_in_reused_package(path))
+            .chain(
+                self.used_but_not_scanned_files
+                    .iter()
+                    .filter(|path| is_in_reused_package(path))
+                    .cloned(),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod reuse_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{
+        BuildFailure, CountingStrategy, LockedPackage, PackageInfo, UnsafeInfo,
+        SCHEMA_VERSION,
+    };
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest]
+    fn reusable_report_test() {
+        let previous_packages = vec![
+            locked_package(registry_id("a", "1.0.0"), Some("aaaa")),
+            locked_package(registry_id("b", "1.0.0"), Some("bbbb")),
+            locked_package(registry_id("c", "1.0.0"), Some("cccc")),
+            locked_package(path_id("d"), None),
+        ];
+        let current_packages = vec![
+            locked_package(registry_id("a", "1.0.0"), Some("aaaa")),
+            locked_package(registry_id("b", "1.0.0"), Some("changed")),
+            locked_package(registry_id("c", "1.1.0"), Some("cccc")),
+            locked_package(path_id("d"), None),
+        ];
+        let report = SafetyReport {
+            packages: previous_packages
+                .iter()
+                .map(|locked_package| {
+                    (
+                        locked_package.id.clone(),
+                        report_entry(locked_package.id.clone()),
+                    )
+                })
+                .collect(),
+            metadata: report_metadata(previous_packages),
+            ..Default::default()
+        };
+
+        let reusable_report =
+            reusable_report(report, &report_metadata(current_packages));
+
+        assert_eq!(
+            reusable_report.entries.keys().collect::<Vec<_>>(),
+            vec![&registry_id("a", "1.0.0")]
+        );
+    }
+
+    #[rstest]
+    fn reusable_report_test_build_failure() {
+        let locked_packages = vec![
+            locked_package(registry_id("a", "1.0.0"), Some("aaaa")),
+            locked_package(registry_id("b", "1.0.0"), Some("bbbb")),
+        ];
+        let failed_id = registry_id("b", "1.0.0");
+        let report = SafetyReport {
+            packages: locked_packages
+                .iter()
+                .map(|locked_package| {
+                    (
+                        locked_package.id.clone(),
+                        report_entry(locked_package.id.clone()),
+                    )
+                })
+                .collect(),
+            metadata: report_metadata(locked_packages.clone()),
+            build_failures: vec![(
+                failed_id.clone(),
+                BuildFailure {
+                    id: failed_id,
+                    error: String::from("error: linking failed"),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let reusable_report =
+            reusable_report(report, &report_metadata(locked_packages));
+
+        assert_eq!(
+            reusable_report.entries.keys().collect::<Vec<_>>(),
+            vec![&registry_id("a", "1.0.0")]
+        );
+    }
+
+    #[rstest]
+    fn reusable_report_test_counting_strategy() {
+        let locked_packages =
+            vec![locked_package(registry_id("a", "1.0.0"), Some("aaaa"))];
+        let report = SafetyReport {
+            packages: locked_packages
+                .iter()
+                .map(|locked_package| {
+                    (
+                        locked_package.id.clone(),
+                        report_entry(locked_package.id.clone()),
+                    )
+                })
+                .collect(),
+            metadata: report_metadata(locked_packages.clone()),
+            ..Default::default()
+        };
+        let metadata = ReportMetadata {
+            counting_strategy: CountingStrategy::AllNodes,
+            ..report_metadata(locked_packages)
+        };
+
+        let reusable_report = reusable_report(report, &metadata);
+
+        assert!(reusable_report.entries.is_empty());
+    }
+
+    #[rstest]
+    fn merge_used_but_not_scanned_files_test() {
+        let reusable_report = ReusableReport {
+            used_but_not_scanned_files: vec![
+                PathBuf::from("/reused/a/src/generated.rs"),
+                PathBuf::from("/changed/b/src/generated.rs"),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let merged = reusable_report.merge_used_but_not_scanned_files(
+            vec![
+                PathBuf::from("/reused/a/src/lib.rs"),
+                PathBuf::from("/changed/b/src/other.rs"),
+            ],
+            &[PathBuf::from("/reused/a")],
+        );
+
+        assert_eq!(
+            merged,
+            vec![
+                PathBuf::from("/reused/a/src/generated.rs"),
+                PathBuf::from("/changed/b/src/other.rs"),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+    }
+
+    fn locked_package(
+        id: ReportPackageId,
+        checksum: Option<&str>,
+    ) -> LockedPackage {
+        LockedPackage {
+            id,
+            checksum: checksum.map(String::from),
+        }
+    }
+
+    fn path_id(name: &str) -> ReportPackageId {
+        ReportPackageId {
+            name: name.into(),
+            version: Version::new(0, 1, 0),
+            source: Source::Path(
+                Url::from_file_path(Path::new("/workspace").join(name))
+                    .unwrap(),
+            ),
+        }
+    }
+
+    fn registry_id(name: &str, version: &str) -> ReportPackageId {
+        ReportPackageId {
+            name: name.into(),
+            version: Version::parse(version).unwrap(),
+            source: Source::Registry {
+                name: "crates.io".into(),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        }
+    }
+
+    fn report_entry(id: ReportPackageId) -> ReportEntry {
+        ReportEntry {
+            package: PackageInfo::new(id),
+            unsafety: UnsafeInfo::default(),
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
+        }
+    }
+
+    fn report_metadata(locked_packages: Vec<LockedPackage>) -> ReportMetadata {
+        ReportMetadata {
+            schema_version: SCHEMA_VERSION,
+            lock_fingerprint: String::new(),
+            locked_packages,
+            counting_strategy: CountingStrategy::Geiger,
+            timings: Vec::new(),
+        }
+    }
+}
@@ -1,24 +1,45 @@
 use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::print_config::colorize;
 use crate::format::table::{
-    create_table_from_text_tree_lines, TableParameters, UNSAFE_COUNTERS_HEADER,
+    create_table_from_text_tree_lines, CounterColumns, TableParameters,
 };
-use crate::format::SymbolKind;
-use crate::graph::Graph;
+use crate::format::{pad_to_display_width, CrateDetectionStatus, SymbolKind};
+use crate::graph::{dependency_counts, Graph};
 use crate::tree::traversal::walk_dependency_tree;
 
 use super::super::{
-    construct_rs_files_used_lines, list_files_used_but_not_scanned,
-    ScanDetails, ScanParameters,
+    all_package_roots, build_safety_report, construct_rs_files_used_lines,
+    construct_warning_summary_lines, display_package_id, from_cargo_package_id,
+    list_files_used_but_not_scanned, not_scanned_warnings, package_root,
+    print_warnings, transmute_locations, unavailable_packages,
+    unsafe_by_feature, unsafe_by_module, unsafe_stats, GeigerContext,
+    ScanDetails, ScanParameters, UNCONDITIONAL_FEATURE,
+};
+use super::dev_only::{construct_dev_only_lines, dev_only_packages};
+use super::external_paths::{
+    construct_external_path_group_lines, external_path_groups,
+};
+use super::members::{
+    construct_members_table_lines, members_unsafety, workspace_member_ids,
 };
 use super::scan;
 
 use crate::krates_utils::CargoMetadataParameters;
 use cargo::core::shell::Verbosity;
 use cargo::core::{PackageId, PackageSet, Workspace};
+use cargo::util::CargoResult;
 use cargo::{CliError, CliResult};
+use cargo_geiger_serde::{
+    ApiCounterBlock, CounterBlock, ModuleUnsafety, ScanWarning,
+};
 use colored::Colorize;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The number of modules listed per package in verbose output.
+const UNSAFE_MODULES_SHOWN: usize = 5;
 
 pub fn scan_to_table(
     cargo_metadata_parameters: &CargoMetadataParameters,
@@ -28,61 +49,275 @@ pub fn scan_to_table(
     scan_parameters: &ScanParameters,
     workspace: &Workspace,
 ) -> CliResult {
-    let mut scan_output_lines = Vec::<String>::new();
-
-    let ScanDetails {
-        rs_files_used,
-        geiger_context,
-    } = scan(
+    let scan_details = scan(
         cargo_metadata_parameters,
         package_set,
+        &HashSet::new(),
+        None,
+        scan_parameters,
+        workspace,
+    )?;
+    print_table(
+        graph,
+        package_set,
+        root_package_id,
+        &scan_details,
+        scan_parameters,
+        workspace,
+    )
+}
+
+/// Prints the table of an already finished scan, with the warnings of the scan
+/// on stderr.
+pub fn print_table(
+    graph: &Graph,
+    package_set: &PackageSet,
+    root_package_id: PackageId,
+    scan_details: &ScanDetails,
+    scan_parameters: &ScanParameters,
+    workspace: &Workspace,
+) -> CliResult {
+    let warning_count = print_table_lines(
+        graph,
+        package_set,
+        root_package_id,
+        scan_details,
         scan_parameters,
         workspace,
     )?;
+    warnings_result(warning_count)
+}
+
+/// Like `print_table`, but returns the number of warnings that fail the run
+/// instead of failing it.
+pub fn print_table_lines(
+    graph: &Graph,
+    package_set: &PackageSet,
+    root_package_id: PackageId,
+    scan_details: &ScanDetails,
+    scan_parameters: &ScanParameters,
+    workspace: &Workspace,
+) -> Result<u64, CliError> {
+    let mut scan_output_lines = Vec::<String>::new();
+
+    let ScanDetails {
+        rs_files_used,
+        target_kinds,
+        geiger_context,
+        build_failures,
+        warnings: scan_warnings,
+        ..
+    } = scan_details;
 
     if scan_parameters.print_config.verbosity == Verbosity::Verbose {
-        let mut rs_files_used_lines =
-            construct_rs_files_used_lines(&rs_files_used);
+        let mut rs_files_used_lines = construct_rs_files_used_lines(
+            rs_files_used,
+            scan_parameters.workspace_root,
+            scan_parameters.print_config.normalize_output,
+        );
         scan_output_lines.append(&mut rs_files_used_lines);
     }
 
-    let emoji_symbols = EmojiSymbols::new(scan_parameters.print_config.charset);
-    let mut output_key_lines = construct_key_lines(&emoji_symbols);
-    scan_output_lines.append(&mut output_key_lines);
+    let mut counter_columns = if scan_parameters.args.show_totals {
+        CounterColumns::with_totals(geiger_context)
+    } else {
+        CounterColumns::default()
+    };
+    counter_columns.show_dep_counts = scan_parameters.args.show_dep_counts;
+    counter_columns.marker_position = scan_parameters.args.marker_position;
+    let table_warnings = if scan_parameters.args.members_only {
+        // The members are counted like in the report, from all targets.
+        let report = build_safety_report(
+            geiger_context,
+            graph,
+            root_package_id,
+            rs_files_used,
+        );
+        let members =
+            members_unsafety(graph, &workspace_member_ids(workspace), &report);
+        scan_output_lines.append(&mut construct_members_table_lines(
+            &members,
+            &scan_parameters.args.unsafe_criteria,
+            &counter_columns,
+        ));
+        let unavailable = unavailable_packages(scan_warnings);
+        let mut packages_without_metrics = report
+            .packages_without_metrics
+            .iter()
+            .filter(|package| !unavailable.contains(package))
+            .cloned()
+            .collect::<Vec<_>>();
+        packages_without_metrics.sort();
+        packages_without_metrics
+            .into_iter()
+            .map(|package| ScanWarning::NoMetrics { package })
+            .collect()
+    } else {
+        let emoji_symbols =
+            EmojiSymbols::new(scan_parameters.print_config.charset);
+        let mut output_key_lines = construct_key_lines(
+            &emoji_symbols,
+            &counter_columns,
+            !scan_parameters.args.no_legend,
+        );
+        scan_output_lines.append(&mut output_key_lines);
 
-    let text_tree_lines = walk_dependency_tree(
-        root_package_id,
-        &graph,
-        &scan_parameters.print_config,
-    );
-    let table_parameters = TableParameters {
-        geiger_context: &geiger_context,
-        print_config: &scan_parameters.print_config,
-        rs_files_used: &rs_files_used,
+        let text_tree_lines = walk_dependency_tree(
+            root_package_id,
+            &graph,
+            &scan_parameters.print_config,
+        );
+        let dependency_counts = if counter_columns.show_dep_counts {
+            dependency_counts(graph)
+        } else {
+            HashMap::new()
+        };
+        let table_parameters = TableParameters {
+            geiger_context,
+            print_config: &scan_parameters.print_config,
+            rs_files_used,
+            target_kinds,
+            all_target_kinds: scan_parameters.args.all_target_kinds,
+            unsafe_criteria: &scan_parameters.args.unsafe_criteria,
+            feature_resolve: if scan_parameters.args.verbose > 1 {
+                Some(scan_parameters.resolve)
+            } else {
+                None
+            },
+            platforms: &graph.platforms,
+            split_generated: scan_parameters.args.split_generated,
+            counter_columns,
+            target: &graph.target,
+            build_failures,
+            dependency_counts: &dependency_counts,
+            members: &graph.members,
+            dev_only: &graph.dev_only,
+        };
+
+        let (mut table_lines, table_warnings) =
+            create_table_from_text_tree_lines(
+                package_set,
+                &table_parameters,
+                text_tree_lines,
+            );
+        scan_output_lines.append(&mut table_lines);
+        table_warnings
     };
 
-    let (mut table_lines, mut warning_count) =
-        create_table_from_text_tree_lines(
+    if scan_parameters.args.split_local_paths {
+        let report = build_safety_report(
+            geiger_context,
+            graph,
+            root_package_id,
+            rs_files_used,
+        );
+        let groups = external_path_groups(
+            graph,
             package_set,
-            &table_parameters,
-            text_tree_lines,
+            scan_parameters.workspace_root,
+            &report,
+        )?;
+        scan_output_lines.append(&mut construct_external_path_group_lines(
+            &groups,
+            &report,
+            &scan_parameters.args.unsafe_criteria,
+            &counter_columns,
+        ));
+    }
+
+    if !graph.dev_only.is_empty() {
+        let report = build_safety_report(
+            geiger_context,
+            graph,
+            root_package_id,
+            rs_files_used,
+        );
+        if let Some(dev_only) = dev_only_packages(graph, &report) {
+            scan_output_lines.append(&mut construct_dev_only_lines(
+                &dev_only,
+                &report,
+                &scan_parameters.args.unsafe_criteria,
+                &counter_columns,
+            ));
+        }
+    }
+
+    if scan_parameters.print_config.verbosity == Verbosity::Verbose {
+        let workspace_root = scan_parameters.workspace_root;
+        let normalize_output = scan_parameters.print_config.normalize_output;
+        let mut unsafe_by_module_lines = construct_unsafe_by_module_lines(
+            geiger_context,
+            package_set,
+            workspace_root,
+            normalize_output,
+        )?;
+        scan_output_lines.append(&mut unsafe_by_module_lines);
+        let mut unsafe_by_feature_lines = construct_unsafe_by_feature_lines(
+            geiger_context,
+            workspace_root,
+            normalize_output,
         );
-    scan_output_lines.append(&mut table_lines);
+        scan_output_lines.append(&mut unsafe_by_feature_lines);
+        let mut public_api_lines = construct_public_api_lines(
+            geiger_context,
+            workspace_root,
+            normalize_output,
+        );
+        scan_output_lines.append(&mut public_api_lines);
+        let mut transmute_lines = construct_transmute_lines(
+            geiger_context,
+            package_set,
+            rs_files_used,
+            workspace_root,
+            normalize_output,
+        )?;
+        scan_output_lines.append(&mut transmute_lines);
+        if !scan_parameters.print_config.watched_calls.is_empty() {
+            let mut watched_call_lines = construct_watched_call_lines(
+                geiger_context,
+                rs_files_used,
+                workspace_root,
+                normalize_output,
+            );
+            scan_output_lines.append(&mut watched_call_lines);
+        }
+    }
+
+    let mut failed_package_ids = build_failures.keys().collect::<Vec<_>>();
+    failed_package_ids.sort();
+    // Parse failures and missing paths are reported, but only the warnings
+    // about incomplete results fail the run.
+    let result_warnings = table_warnings
+        .into_iter()
+        .chain(not_scanned_warnings(
+            &list_files_used_but_not_scanned(geiger_context, rs_files_used),
+            &all_package_roots(package_set),
+        ))
+        .chain(failed_package_ids.into_iter().map(|package_id| {
+            ScanWarning::BuildFailed {
+                package: from_cargo_package_id(*package_id),
+            }
+        }))
+        .collect::<Vec<_>>();
+    let warning_count = result_warnings.len() as u64;
+    let mut warnings = scan_warnings.clone();
+    warnings.extend(result_warnings);
 
+    scan_output_lines.append(&mut construct_warning_summary_lines(&warnings));
     for scan_output_line in scan_output_lines {
         println!("{}", scan_output_line);
     }
+    print_warnings(
+        &warnings,
+        scan_parameters.workspace_root,
+        scan_parameters.print_config.normalize_output,
+    );
 
-    let used_but_not_scanned =
-        list_files_used_but_not_scanned(&geiger_context, &rs_files_used);
-    warning_count += used_but_not_scanned.len() as u64;
-    for path in &used_but_not_scanned {
-        eprintln!(
-            "WARNING: Dependency file was never scanned: {}",
-            path.display()
-        );
-    }
+    Ok(warning_count)
+}
 
+/// Fails with the number of warnings, if there are any.
+pub fn warnings_result(warning_count: u64) -> CliResult {
     if warning_count > 0 {
         Err(CliError::new(
             anyhow::Error::new(FoundWarningsError { warning_count }),
@@ -107,25 +342,279 @@ impl fmt::Display for FoundWarningsError {
     }
 }
 
-fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
+/// Lists the modules with the most unsafe usage of every package that has
+/// any.
+fn construct_unsafe_by_module_lines(
+    geiger_context: &GeigerContext,
+    package_set: &PackageSet,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> CargoResult<Vec<String>> {
+    let mut package_ids = geiger_context
+        .package_id_to_metrics
+        .keys()
+        .collect::<Vec<_>>();
+    package_ids.sort();
+
+    let mut unsafe_by_module_lines = vec![format!(
+        "Unsafe usage by module (top {} per package):",
+        UNSAFE_MODULES_SHOWN
+    )];
+    for package_id in package_ids {
+        let modules = unsafe_by_module(
+            &geiger_context.package_id_to_metrics[package_id],
+            &package_root(package_set, *package_id)?,
+        );
+        if modules.is_empty() {
+            continue;
+        }
+        unsafe_by_module_lines.push(String::new());
+        unsafe_by_module_lines.push(display_package_id(
+            *package_id,
+            workspace_root,
+            normalize_output,
+        ));
+        unsafe_by_module_lines.extend(
+            modules.into_iter().take(UNSAFE_MODULES_SHOWN).map(
+                |ModuleUnsafety { module, unsafe_ }| {
+                    format!("    {: <6} {}", unsafe_, module)
+                },
+            ),
+        );
+    }
+    unsafe_by_module_lines.push(String::new());
+
+    Ok(unsafe_by_module_lines)
+}
+
+/// Lists the unsafe usage per feature of every package that has any feature
+/// gated unsafe usage.
+fn construct_unsafe_by_feature_lines(
+    geiger_context: &GeigerContext,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> Vec<String> {
+    let mut package_ids = geiger_context
+        .package_id_to_metrics
+        .keys()
+        .collect::<Vec<_>>();
+    package_ids.sort();
+
+    let mut unsafe_by_feature_lines =
+        vec![String::from("Unsafe usage by feature:")];
+    for package_id in package_ids {
+        let by_feature = unsafe_by_feature(
+            &geiger_context.package_id_to_metrics[package_id],
+        );
+        if by_feature.keys().all(|f| f == UNCONDITIONAL_FEATURE) {
+            continue;
+        }
+        unsafe_by_feature_lines.push(String::new());
+        unsafe_by_feature_lines.push(display_package_id(
+            *package_id,
+            workspace_root,
+            normalize_output,
+        ));
+        unsafe_by_feature_lines.extend(by_feature.into_iter().map(
+            |(feature, unsafe_)| format!("    {: <6} {}", unsafe_, feature),
+        ));
+    }
+    unsafe_by_feature_lines.push(String::new());
+
+    unsafe_by_feature_lines
+}
+
+/// Lists the unsafe functions, methods and traits of every package that has
+/// any, split into the ones in its public API and the internal ones.
+fn construct_public_api_lines(
+    geiger_context: &GeigerContext,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> Vec<String> {
+    let mut package_ids = geiger_context
+        .package_id_to_metrics
+        .keys()
+        .collect::<Vec<_>>();
+    package_ids.sort();
+
+    let mut public_api_lines =
+        vec![String::from("Unsafe items, public API/internal:")];
+    for package_id in package_ids {
+        let mut all = CounterBlock::default();
+        let mut public = ApiCounterBlock::default();
+        for metrics in geiger_context.package_id_to_metrics[package_id]
+            .file_groups
+            .values()
+        {
+            all += metrics.counters.clone();
+            public += metrics.public_api.clone();
+        }
+        let rows = [
+            ("functions", all.functions, public.functions),
+            ("methods", all.methods, public.methods),
+            ("traits", all.item_traits, public.item_traits),
+        ];
+        if rows.iter().all(|(_, all, _)| all.unsafe_ == 0) {
+            continue;
+        }
+        public_api_lines.push(String::new());
+        public_api_lines.push(display_package_id(
+            *package_id,
+            workspace_root,
+            normalize_output,
+        ));
+        public_api_lines.extend(rows.iter().map(|(name, all, public)| {
+            format!(
+                "    {: <10} {}/{}",
+                name,
+                public.unsafe_,
+                all.unsafe_ - public.unsafe_
+            )
+        }));
+    }
+    public_api_lines.push(String::new());
+
+    public_api_lines
+}
+
+/// Lists where every package with `transmute` calls makes them.
+fn construct_transmute_lines(
+    geiger_context: &GeigerContext,
+    package_set: &PackageSet,
+    rs_files_used: &HashSet<PathBuf>,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> CargoResult<Vec<String>> {
+    let mut package_ids = geiger_context
+        .package_id_to_metrics
+        .keys()
+        .collect::<Vec<_>>();
+    package_ids.sort();
+
+    let mut transmute_lines = vec![String::from("Transmutes:")];
+    for package_id in package_ids {
+        let locations = transmute_locations(
+            &geiger_context.package_id_to_metrics[package_id],
+            &package_root(package_set, *package_id)?,
+            rs_files_used,
+        );
+        if locations.is_empty() {
+            continue;
+        }
+        transmute_lines.push(String::new());
+        transmute_lines.push(display_package_id(
+            *package_id,
+            workspace_root,
+            normalize_output,
+        ));
+        transmute_lines.extend(
+            locations
+                .into_iter()
+                .map(|location| format!("    {}", location)),
+        );
+    }
+    transmute_lines.push(String::new());
+
+    Ok(transmute_lines)
+}
+
+/// Lists the number of calls matching each watched call pattern for every
+/// package that makes any, in code used and not used by the build.
+fn construct_watched_call_lines(
+    geiger_context: &GeigerContext,
+    rs_files_used: &HashSet<PathBuf>,
+    workspace_root: &Path,
+    normalize_output: bool,
+) -> Vec<String> {
+    let mut package_ids = geiger_context
+        .package_id_to_metrics
+        .keys()
+        .collect::<Vec<_>>();
+    package_ids.sort();
+
+    let mut watched_call_lines =
+        vec![String::from("Watched calls, used/unused by the build:")];
+    for package_id in package_ids {
+        let unsafe_info = unsafe_stats(
+            &geiger_context.package_id_to_metrics[package_id],
+            rs_files_used,
+        );
+        if unsafe_info.watched_calls.is_empty() {
+            continue;
+        }
+        let width = unsafe_info
+            .watched_calls
+            .keys()
+            .map(|pattern| pattern.len())
+            .max()
+            .unwrap_or(0);
+        watched_call_lines.push(String::new());
+        watched_call_lines.push(display_package_id(
+            *package_id,
+            workspace_root,
+            normalize_output,
+        ));
+        watched_call_lines.extend(unsafe_info.watched_calls.iter().map(
+            |(pattern, count)| {
+                format!(
+                    "    {: <width$} {}/{}",
+                    pattern,
+                    count.used.safe + count.used.unsafe_,
+                    count.unused.safe + count.unused.unsafe_,
+                    width = width
+                )
+            },
+        ));
+    }
+    watched_call_lines.push(String::new());
+
+    watched_call_lines
+}
+
+fn construct_key_lines(
+    emoji_symbols: &EmojiSymbols,
+    counter_columns: &CounterColumns,
+    show_legend: bool,
+) -> Vec<String> {
     let mut output_key_lines = Vec::<String>::new();
 
     output_key_lines.push(String::new());
-    output_key_lines.push(String::from("Metric output format: x/y"));
-    output_key_lines
-        .push(String::from("    x = unsafe code used by the build"));
+    if counter_columns.show_totals {
+        output_key_lines.push(String::from("Metric output format: x/y (of N)"));
+    } else {
+        output_key_lines.push(String::from("Metric output format: x/y"));
+    }
+    output_key_lines.push(String::from(
+        "    x = unsafe code used by the build, ? if the crate was not built",
+    ));
     output_key_lines
         .push(String::from("    y = total unsafe code found in the crate"));
+    if counter_columns.show_totals {
+        output_key_lines.push(String::from(
+            "    N = total code found in the crate, safe and unsafe",
+        ));
+    }
+    if counter_columns.show_dep_counts {
+        output_key_lines.push(String::new());
+        output_key_lines.push(String::from("Deps output format: d/t"));
+        output_key_lines
+            .push(String::from("    d = direct dependencies of the crate"));
+        output_key_lines.push(String::from(
+            "    t = all dependencies of the crate, each counted once",
+        ));
+    }
     output_key_lines.push(String::new());
     output_key_lines.push(String::from("Symbols: "));
 
     let forbids = "No `unsafe` usage found, declares #![forbid(unsafe_code)]";
     let unknown = "No `unsafe` usage found, missing #![forbid(unsafe_code)]";
+    let unused = "`unsafe` usage found only in code not used by the build";
     let guilty = "`unsafe` usage found";
 
     let shift_sequence = if emoji_symbols.will_output_emoji() {
-        "\r\x1B[7C" // The radiation icon's Unicode width is 2,
-                    // but by most terminals it seems to be rendered at width 1.
+        // Terminals don't agree on the width of the radiation icon, move the
+        // cursor to the end of the two column icon slot.
+        "\r\x1B[6C"
     } else {
         ""
     };
@@ -133,6 +622,7 @@ fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
     let symbol_kinds_to_string_values = vec![
         (SymbolKind::Lock, "", forbids),
         (SymbolKind::QuestionMark, "", unknown),
+        (SymbolKind::HollowRads, "", unused),
         (SymbolKind::Rads, shift_sequence, guilty),
     ];
 
@@ -140,24 +630,109 @@ fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
         symbol_kinds_to_string_values
     {
         output_key_lines.push(format!(
-            "    {: <2}{} = {}",
-            emoji_symbols.emoji(symbol_kind),
+            "    {}{} = {}",
+            pad_to_display_width(
+                &emoji_symbols.emoji(symbol_kind).to_string(),
+                2
+            ),
             shift_sequence,
             string_values
         ));
     }
 
     output_key_lines.push(String::new());
-    output_key_lines.push(format!(
-        "{}",
-        UNSAFE_COUNTERS_HEADER
-            .iter()
-            .map(|s| s.to_owned())
-            .collect::<Vec<_>>()
-            .join(" ")
-            .bold()
-    ));
+    output_key_lines.push(format!("{}", counter_columns.header().bold()));
+    if show_legend {
+        output_key_lines.extend(construct_legend_lines(emoji_symbols));
+    }
     output_key_lines.push(String::new());
 
     output_key_lines
 }
+
+/// The short form of the key, right below the header where it is seen by
+/// readers skipping to the table. Each symbol is followed by its meaning in
+/// the color of the rows it marks, the symbols are the ones of the charset in
+/// use. Both lines fit in 80 columns.
+fn construct_legend_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
+    let symbol = |symbol_kind| emoji_symbols.emoji(symbol_kind).to_string();
+    let meaning = |text: &str, crate_detection_status| {
+        colorize(String::from(text), &crate_detection_status)
+    };
+    vec![
+        String::from(
+            "Legend: x/y = unsafe code used by the build/found in the crate, \
+             ? if not built",
+        ),
+        format!(
+            "        {} {}, {} {}, {} {}, {} {}, {} native lib",
+            symbol(SymbolKind::Lock),
+            meaning(
+                "forbids unsafe",
+                CrateDetectionStatus::NoneDetectedForbidsUnsafe
+            ),
+            symbol(SymbolKind::QuestionMark),
+            meaning(
+                "none found",
+                CrateDetectionStatus::NoneDetectedAllowsUnsafe
+            ),
+            symbol(SymbolKind::HollowRads),
+            meaning("only unused", CrateDetectionStatus::UnusedUnsafeDetected),
+            symbol(SymbolKind::Rads),
+            meaning("used", CrateDetectionStatus::UnsafeDetected),
+            symbol(SymbolKind::Gear)
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod table_tests {
+    use super::*;
+
+    use crate::format::Charset;
+    use insta::assert_snapshot;
+    use rstest::*;
+
+    #[rstest(
+        input_emoji_symbols,
+        expected_snapshot,
+        case(EmojiSymbols::new(Charset::Ascii), "legend_ascii"),
+        case(EmojiSymbols::for_emoji_terminal(Charset::Utf8), "legend_utf8")
+    )]
+    fn construct_legend_lines_test(
+        input_emoji_symbols: EmojiSymbols,
+        expected_snapshot: &str,
+    ) {
+        let legend_lines = construct_legend_lines(&input_emoji_symbols);
+        assert_eq!(legend_lines.len(), 2);
+        for legend_line in &legend_lines {
+            assert!(console::measure_text_width(legend_line) <= 80);
+        }
+
+        let legend =
+            console::strip_ansi_codes(&legend_lines.join("\n")).into_owned();
+        assert_snapshot!(expected_snapshot, legend);
+    }
+
+    #[rstest]
+    fn construct_key_lines_no_legend_test() {
+        let emoji_symbols = EmojiSymbols::new(Charset::Ascii);
+        let counter_columns = CounterColumns::default();
+
+        let with_legend =
+            construct_key_lines(&emoji_symbols, &counter_columns, true);
+        let without_legend =
+            construct_key_lines(&emoji_symbols, &counter_columns, false);
+
+        // The key is printed either way, `--no-legend` only leaves out the two
+        // lines below the header.
+        assert!(with_legend.contains(&String::from("Symbols: ")));
+        let legend_index = with_legend
+            .iter()
+            .position(|line| line.starts_with("Legend: "))
+            .unwrap();
+        let mut legend_left_out = with_legend.clone();
+        legend_left_out.drain(legend_index..legend_index + 2);
+        assert_eq!(legend_left_out, without_legend);
+    }
+}
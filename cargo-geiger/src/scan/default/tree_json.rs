@@ -0,0 +1,72 @@
+use crate::format::table::{CounterColumns, TableParameters};
+use crate::format::tree_json::create_tree_lines_from_text_tree_lines;
+use crate::graph::Graph;
+use crate::krates_utils::CargoMetadataParameters;
+use crate::tree::traversal::walk_dependency_tree;
+
+use super::super::{print_warnings, ScanDetails, ScanParameters};
+use super::scan;
+
+use cargo::core::{PackageId, PackageSet, Workspace};
+use cargo::CliResult;
+use std::collections::{HashMap, HashSet};
+
+/// Prints the lines of the tree the table would show as a JSON array, for
+/// rendering the tree elsewhere.
+pub fn scan_to_tree_json(
+    cargo_metadata_parameters: &CargoMetadataParameters,
+    graph: &Graph,
+    package_set: &PackageSet,
+    root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
+    workspace: &Workspace,
+) -> CliResult {
+    let ScanDetails {
+        rs_files_used,
+        target_kinds,
+        geiger_context,
+        build_failures,
+        warnings,
+        ..
+    } = scan(
+        cargo_metadata_parameters,
+        package_set,
+        &HashSet::new(),
+        None,
+        scan_parameters,
+        workspace,
+    )?;
+    let text_tree_lines = walk_dependency_tree(
+        root_package_id,
+        &graph,
+        &scan_parameters.print_config,
+    );
+    let table_parameters = TableParameters {
+        geiger_context: &geiger_context,
+        print_config: &scan_parameters.print_config,
+        rs_files_used: &rs_files_used,
+        target_kinds: &target_kinds,
+        all_target_kinds: scan_parameters.args.all_target_kinds,
+        unsafe_criteria: &scan_parameters.args.unsafe_criteria,
+        feature_resolve: None,
+        platforms: &graph.platforms,
+        split_generated: false,
+        counter_columns: CounterColumns::default(),
+        target: &graph.target,
+        build_failures: &build_failures,
+        dependency_counts: &HashMap::new(),
+        members: &graph.members,
+        dev_only: &graph.dev_only,
+    };
+    let tree_lines = create_tree_lines_from_text_tree_lines(
+        &table_parameters,
+        text_tree_lines,
+    );
+    println!("{}", serde_json::to_string(&tree_lines).unwrap());
+    print_warnings(
+        &warnings,
+        scan_parameters.workspace_root,
+        scan_parameters.print_config.normalize_output,
+    );
+    Ok(())
+}
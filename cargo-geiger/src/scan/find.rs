@@ -1,133 +1,493 @@
+use crate::cli::get_path_overrides;
 use crate::format::print_config::PrintConfig;
 use crate::krates_utils::{
-    CargoMetadataParameters, GetRoot, ToCargoMetadataPackage, ToPackageId,
+    CargoMetadataParameters, ToCargoMetadataPackage, ToPackageId,
 };
+use crate::progress::ProgressEvent;
 use crate::rs_file::{
     into_is_entry_point_and_path_buf, into_rs_code_file, into_target_kind,
-    is_file_with_ext, RsFile, RsFileMetricsWrapper,
+    is_file_with_ext, is_generated_file, RsFile, RsFileMetricsWrapper,
+};
+use crate::scan::{
+    canonical_package_root, from_cargo_package_id, PackageMetrics,
 };
-use crate::scan::PackageMetrics;
 
-use super::{GeigerContext, ScanMode};
+use super::{DetailLevel, FileUsage, GeigerContext, ScanMode};
 
 use cargo::core::package::PackageSet;
 use cargo::core::PackageId;
 use cargo::util::CargoResult;
 use cargo::{CliError, Config};
-use geiger::{find_unsafe_in_file, IncludeTests, RsFileMetrics, ScanFileError};
-use std::collections::HashMap;
+use cargo_geiger_serde::ScanWarning;
+use geiger::{find_unsafe_in_file_with_options, RsFileMetrics, ScanFileError};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
 use std::path::Path;
 use std::path::PathBuf;
 use walkdir::WalkDir;
 
+/// Consulted for every file and directory that would be scanned, with its
+/// path, only the accepted ones are. The files and subdirectories of a
+/// rejected directory are never read.
+pub type ScanFilter<'a> = &'a dyn Fn(&Path) -> bool;
+
+/// Consulted for every scanned package, the metrics of the accepted ones are
+/// kept at `DetailLevel::Detailed` whatever the detail level of the others.
+pub type DetailFilter<'a> = &'a dyn Fn(PackageId) -> bool;
+
 pub fn find_unsafe(
     cargo_metadata_parameters: &CargoMetadataParameters,
     config: &Config,
     mode: ScanMode,
+    detail_level: DetailLevel,
+    detail_filter: Option<DetailFilter>,
+    file_usage: &FileUsage,
     package_set: &PackageSet,
+    package_ids_to_skip: &HashSet<PackageId>,
     print_config: &PrintConfig,
+    scan_filter: Option<ScanFilter>,
 ) -> Result<GeigerContext, CliError> {
+    report_path_overrides(config, package_set)?;
     let mut progress = cargo::util::Progress::new("Scanning", config);
-    let geiger_context = find_unsafe_in_packages(
-        print_config.allow_partial_results,
+    let (geiger_context, shared_files) = find_unsafe_in_packages(
         cargo_metadata_parameters,
-        print_config.include_tests,
         mode,
+        detail_level,
+        detail_filter,
+        file_usage,
         package_set,
-        |i, count| -> CargoResult<()> { progress.tick(i, count) },
-    );
+        package_ids_to_skip,
+        print_config,
+        scan_filter,
+        |scan_progress| -> CargoResult<()> {
+            if let (true, Some((package, files))) =
+                (print_config.progress_json, scan_progress.package_scanned)
+            {
+                ProgressEvent::PackageScanned {
+                    package: format!("{} {}", package.name, package.version),
+                    files,
+                }
+                .emit();
+            }
+            progress.tick(scan_progress.index, scan_progress.count)
+        },
+    )?;
     progress.clear();
+    report_shared_files(config, &shared_files)?;
     config.shell().status("Scanning", "done")?;
     Ok(geiger_context)
 }
 
+/// Passed to the progress callback of the scan after each file.
+struct ScanProgress<'a> {
+    index: usize,
+    count: usize,
+    /// The package and its number of files, after the last of them.
+    package_scanned: Option<(&'a cargo_metadata::Package, usize)>,
+}
+
+/// The metrics of the files are added up once all packages are scanned, the
+/// files in more than one package and the `#[path]` modules are sorted out
+/// with the metrics of each file.
 fn find_unsafe_in_packages<F>(
-    allow_partial_results: bool,
     cargo_metadata_parameters: &CargoMetadataParameters,
-    include_tests: IncludeTests,
     mode: ScanMode,
+    detail_level: DetailLevel,
+    detail_filter: Option<DetailFilter>,
+    file_usage: &FileUsage,
     package_set: &PackageSet,
+    package_ids_to_skip: &HashSet<PackageId>,
+    print_config: &PrintConfig,
+    scan_filter: Option<ScanFilter>,
     mut progress_step: F,
-) -> GeigerContext
+) -> CargoResult<(GeigerContext, Vec<SharedFile>)>
 where
-    F: FnMut(usize, usize) -> CargoResult<()>,
+    F: FnMut(ScanProgress) -> CargoResult<()>,
 {
     let mut package_id_to_metrics = HashMap::new();
-    let packages = package_set
-        .get_many(package_set.package_ids())
-        .unwrap()
+    let mut warnings = Vec::new();
+    // The package root is taken from the cargo package since that one is
+    // loaded from the effective source, after path overrides and source
+    // replacement have been applied. It is canonicalized once, the paths
+    // found below it then match the ones from the rustc dep-info files.
+    // A package whose sources can't be downloaded or read is left out, the
+    // others are still scanned. The packages are downloaded together, one by
+    // one only to find out which of them failed.
+    let package_ids = package_set
+        .package_ids()
+        .filter(|id| !package_ids_to_skip.contains(id))
+        .collect::<Vec<_>>();
+    let available_packages =
+        match package_set.get_many(package_ids.iter().cloned()) {
+            Ok(packages) => packages,
+            Err(_) => package_ids
+                .into_iter()
+                .filter_map(|id| match package_set.get_one(id) {
+                    Ok(package) => Some(package),
+                    Err(e) => {
+                        warnings.push(ScanWarning::Unavailable {
+                            package: from_cargo_package_id(id),
+                            reason: format!("{:#}", e),
+                        });
+                        None
+                    }
+                })
+                .collect(),
+        };
+    let packages = available_packages
         .iter()
         .map(|p| {
-            p.to_cargo_metadata_package(cargo_metadata_parameters.metadata)
+            if p.root().canonicalize().is_err() {
+                warnings.push(ScanWarning::NotCanonicalizable {
+                    path: p.root().to_path_buf(),
+                });
+            }
+            (
+                p.to_cargo_metadata_package(cargo_metadata_parameters.metadata),
+                canonical_package_root(p),
+            )
+        })
+        .collect::<Vec<(cargo_metadata::Package, PathBuf)>>();
+    let detail_levels = available_packages
+        .iter()
+        .zip(&packages)
+        .map(|(package, (cargo_metadata_package, _))| {
+            let detail_level = match detail_filter {
+                Some(detail_filter) if detail_filter(package.package_id()) => {
+                    DetailLevel::Detailed
+                }
+                _ => detail_level,
+            };
+            (cargo_metadata_package.id.clone(), detail_level)
         })
-        .collect::<Vec<cargo_metadata::Package>>();
+        .collect::<HashMap<_, _>>();
     let package_code_files: Vec<_> =
-        find_rs_files_in_packages(&packages).collect();
+        find_rs_files_in_packages(&packages, &mode, scan_filter, &mut warnings)
+            .into_iter()
+            .filter(|(_, rs_code_file)| {
+                !matches!(
+                    (rs_code_file, &mode),
+                    (RsFile::Other(_), ScanMode::EntryPointsOnly)
+                )
+            })
+            .collect();
     let package_code_file_count = package_code_files.len();
+    let scan_options = print_config.scan_options();
+    // The files of a package come one after another, a package is scanned
+    // once none of its files are left.
+    let mut package_file_counts = HashMap::<_, usize>::new();
+    for (package_id, _) in &package_code_files {
+        *package_file_counts.entry(package_id.clone()).or_default() += 1;
+    }
+    let mut package_files_left = package_file_counts.clone();
     for (i, (package_id, rs_code_file)) in
         package_code_files.into_iter().enumerate()
     {
+        let files_left = package_files_left.get_mut(&package_id).unwrap();
+        *files_left -= 1;
+        let package_scanned = if *files_left == 0 {
+            packages
+                .iter()
+                .find(|(package, _)| package.id == package_id)
+                .map(|(package, _)| (package, package_file_counts[&package_id]))
+        } else {
+            None
+        };
         let (is_entry_point, path_buf) =
             into_is_entry_point_and_path_buf(rs_code_file);
-        if let (false, ScanMode::EntryPointsOnly) = (is_entry_point, &mode) {
-            continue;
-        }
-        match find_unsafe_in_file(&path_buf, include_tests) {
+        match find_unsafe_in_file_with_options(&path_buf, &scan_options) {
             Err(error) => {
-                handle_unsafe_in_file_error(
-                    allow_partial_results,
+                package_id_to_metrics
+                    .entry(package_id)
+                    .or_insert_with(PackageMetrics::default)
+                    .add_scan_failure(&error, path_buf.clone());
+                warnings.push(handle_unsafe_in_file_error(
+                    print_config.allow_partial_results,
                     error,
                     &path_buf,
-                );
+                ));
             }
             Ok(rs_file_metrics) => {
+                let is_generated = is_generated_file(
+                    &path_buf,
+                    &print_config.generated_markers,
+                );
+                let detail_level = detail_levels[&package_id];
                 update_package_id_to_metrics_with_rs_file_metrics(
                     is_entry_point,
+                    is_generated,
                     package_id,
                     &mut package_id_to_metrics,
                     path_buf,
-                    rs_file_metrics,
+                    detail_level.retain(rs_file_metrics),
                 );
             }
         }
-        let _ = progress_step(i, package_code_file_count);
+        let _ = progress_step(ScanProgress {
+            index: i,
+            count: package_code_file_count,
+            package_scanned,
+        });
+    }
+    if let ScanMode::Full = mode {
+        find_unsafe_in_path_modules(
+            print_config,
+            &detail_levels,
+            scan_filter,
+            &mut package_id_to_metrics,
+            &mut warnings,
+        );
     }
+    let shared_files =
+        attribute_shared_files(&packages, &mut package_id_to_metrics);
 
     let cargo_core_package_metrics = package_id_to_metrics
-        .iter()
-        .map(|(cargo_metadata_package_id, package_metrics)| {
+        .into_iter()
+        .map(|(cargo_metadata_package_id, mut package_metrics)| {
+            package_metrics.fold_files(
+                file_usage,
+                detail_levels[&cargo_metadata_package_id],
+            );
             (
-                cargo_metadata_package_id.clone().to_package_id(
+                cargo_metadata_package_id.to_package_id(
                     cargo_metadata_parameters.krates,
                     package_set,
                 ),
-                package_metrics.clone(),
+                package_metrics,
             )
         })
         .collect::<HashMap<PackageId, PackageMetrics>>();
 
-    GeigerContext {
+    let geiger_context = GeigerContext {
         package_id_to_metrics: cargo_core_package_metrics,
+        warnings,
+    };
+    Ok((geiger_context, shared_files))
+}
+
+/// A file that was found in more than one package, like the files of a
+/// package whose root is inside the directory of another package.
+#[derive(Debug)]
+struct SharedFile {
+    path: PathBuf,
+    /// The package the file is counted for.
+    owner: cargo_metadata::Package,
+    /// The packages the file was removed from.
+    others: Vec<cargo_metadata::Package>,
+}
+
+/// Keeps each file in one package only, the totals would count a file once
+/// for every package it was found in otherwise. A file is kept in the
+/// package it is a crate entry point of, then in the package whose root is
+/// the longest prefix of its path, then in the package with the lowest id.
+fn attribute_shared_files(
+    packages: &[(cargo_metadata::Package, PathBuf)],
+    package_id_to_metrics: &mut HashMap<
+        cargo_metadata::PackageId,
+        PackageMetrics,
+    >,
+) -> Vec<SharedFile> {
+    let mut path_to_package_ids =
+        HashMap::<PathBuf, Vec<cargo_metadata::PackageId>>::new();
+    for (package_id, package_metrics) in package_id_to_metrics.iter() {
+        for path_buf in package_metrics
+            .rs_path_to_metrics
+            .keys()
+            .chain(&package_metrics.parse_failures)
+        {
+            path_to_package_ids
+                .entry(path_buf.clone())
+                .or_default()
+                .push(package_id.clone());
+        }
+    }
+    let package_of = |package_id: &cargo_metadata::PackageId| {
+        packages
+            .iter()
+            .find(|(package, _)| &package.id == package_id)
+            .expect("Scanned package is missing")
+    };
+
+    let mut shared_files = Vec::new();
+    for (path_buf, mut package_ids) in path_to_package_ids {
+        if package_ids.len() < 2 {
+            continue;
+        }
+        package_ids.sort_by_cached_key(|package_id| {
+            let is_entry_point = package_id_to_metrics[package_id]
+                .rs_path_to_metrics
+                .get(&path_buf)
+                .map_or(false, |wrapper| wrapper.is_crate_entry_point);
+            let (_, package_root) = package_of(package_id);
+            let root_length = if path_buf.starts_with(package_root) {
+                package_root.components().count()
+            } else {
+                0
+            };
+            (
+                Reverse(is_entry_point),
+                Reverse(root_length),
+                package_id.clone(),
+            )
+        });
+        let owner = package_ids.remove(0);
+        for package_id in &package_ids {
+            let package_metrics =
+                package_id_to_metrics.get_mut(package_id).unwrap();
+            package_metrics.rs_path_to_metrics.remove(&path_buf);
+            package_metrics
+                .parse_failures
+                .retain(|failure| failure != &path_buf);
+            package_metrics.approximated.remove(&path_buf);
+            package_metrics.oversized.remove(&path_buf);
+        }
+        shared_files.push(SharedFile {
+            path: path_buf,
+            owner: package_of(&owner).0.clone(),
+            others: package_ids
+                .iter()
+                .map(|package_id| package_of(package_id).0.clone())
+                .collect(),
+        });
+    }
+    shared_files.sort_by(|a, b| a.path.cmp(&b.path));
+    shared_files
+}
+
+/// Scans the files of `#[path]` modules that the walk of the package
+/// directories did not reach, like files outside of the package root. They
+/// are added to the package that declares the module, files that declare
+/// more `#[path]` modules are followed too.
+fn find_unsafe_in_path_modules(
+    print_config: &PrintConfig,
+    detail_levels: &HashMap<cargo_metadata::PackageId, DetailLevel>,
+    scan_filter: Option<ScanFilter>,
+    package_id_to_metrics: &mut HashMap<
+        cargo_metadata::PackageId,
+        PackageMetrics,
+    >,
+    warnings: &mut Vec<ScanWarning>,
+) {
+    let mut pending = Vec::new();
+    for (package_id, package_metrics) in package_id_to_metrics.iter() {
+        for (path_buf, rs_file_metrics_wrapper) in
+            &package_metrics.rs_path_to_metrics
+        {
+            // Files loaded through `#[path]` own their directory too, but
+            // nothing tells which of the walked files were.
+            let owns_dir = rs_file_metrics_wrapper.is_crate_entry_point
+                || path_buf.file_name() == Some(OsStr::new("mod.rs"));
+            for path_module in &rs_file_metrics_wrapper.metrics.path_modules {
+                pending.push((
+                    package_id.clone(),
+                    path_module.resolve(path_buf, owns_dir),
+                ));
+            }
+        }
+    }
+
+    let scan_options = print_config.scan_options();
+    while let Some((package_id, path_buf)) = pending.pop() {
+        // Missing files are usually behind a `#[cfg]` that is not enabled.
+        let path_buf = match path_buf.canonicalize() {
+            Ok(path_buf) => path_buf,
+            Err(_) => continue,
+        };
+        if !accepts(scan_filter, &path_buf) {
+            continue;
+        }
+        let package_metrics =
+            package_id_to_metrics.entry(package_id.clone()).or_default();
+        if package_metrics.rs_path_to_metrics.contains_key(&path_buf)
+            || package_metrics.parse_failures.contains(&path_buf)
+        {
+            continue;
+        }
+        match find_unsafe_in_file_with_options(&path_buf, &scan_options) {
+            Err(error) => {
+                package_metrics.add_scan_failure(&error, path_buf.clone());
+                warnings.push(handle_unsafe_in_file_error(
+                    print_config.allow_partial_results,
+                    error,
+                    &path_buf,
+                ));
+            }
+            Ok(rs_file_metrics) => {
+                for path_module in &rs_file_metrics.path_modules {
+                    pending.push((
+                        package_id.clone(),
+                        path_module.resolve(&path_buf, true),
+                    ));
+                }
+                let is_generated = is_generated_file(
+                    &path_buf,
+                    &print_config.generated_markers,
+                );
+                let detail_level = detail_levels[&package_id];
+                update_package_id_to_metrics_with_rs_file_metrics(
+                    false,
+                    is_generated,
+                    package_id,
+                    package_id_to_metrics,
+                    path_buf,
+                    detail_level.retain(rs_file_metrics),
+                );
+            }
+        }
     }
 }
 
-fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
-    let walker = WalkDir::new(dir).into_iter();
-    walker.filter_map(|entry| {
-        let entry = entry.expect("walkdir error."); // TODO: Return result.
+fn accepts(scan_filter: Option<ScanFilter>, path: &Path) -> bool {
+    scan_filter.map_or(true, |scan_filter| scan_filter(path))
+}
+
+/// The `.rs` files below `dir`, canonicalized. Directories rejected by the
+/// `scan_filter` are skipped without reading them, `dir` itself is not
+/// passed to it. Entries that can't be read or canonicalized are returned as
+/// `ScanWarning::Unreadable`, the walk goes on past them.
+pub fn find_rs_files_in_dir<'a>(
+    dir: &Path,
+    scan_filter: Option<ScanFilter<'a>>,
+) -> impl Iterator<Item = Result<PathBuf, ScanWarning>> + 'a {
+    let dir = dir.to_path_buf();
+    let walker = WalkDir::new(&dir).into_iter().filter_entry(move |entry| {
+        entry.depth() == 0 || accepts(scan_filter, entry.path())
+    });
+    walker.filter_map(move |entry| {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                let path_buf =
+                    error.path().map_or_else(|| dir.clone(), Path::to_path_buf);
+                return Some(Err(ScanWarning::Unreadable {
+                    path: path_buf,
+                    error: error.to_string(),
+                }));
+            }
+        };
         if !is_file_with_ext(&entry, "rs") {
             return None;
         }
-        Some(
-            entry
-                .path()
-                .canonicalize()
-                .expect("Error converting to canonical path"),
-        ) // TODO: Return result.
+        Some(entry.path().canonicalize().map_err(|error| {
+            ScanWarning::Unreadable {
+                path: entry.path().to_path_buf(),
+                error: error.to_string(),
+            }
+        }))
     })
 }
 
-fn find_rs_files_in_package(package: &cargo_metadata::Package) -> Vec<RsFile> {
+/// The other `.rs` files of the package directory are only searched for in
+/// `ScanMode::Full`, the entry points are all that is read otherwise. Files
+/// that can't be read or canonicalized are left out with a warning.
+fn find_rs_files_in_package(
+    package: &cargo_metadata::Package,
+    package_root: &Path,
+    mode: &ScanMode,
+    scan_filter: Option<ScanFilter>,
+    warnings: &mut Vec<ScanWarning>,
+) -> Vec<RsFile> {
     // Find all build target entry point source files.
     let mut canon_targets = HashMap::new();
     for target in &package.targets {
@@ -141,17 +501,33 @@ fn find_rs_files_in_package(package: &cargo_metadata::Package) -> Vec<RsFile> {
             // everything. We have to skip this build target.
             continue;
         }
-        let canon = path
-            .canonicalize() // will Err on non-existing paths.
-            .expect("canonicalize for build target path failed."); // FIXME
+        if !accepts(scan_filter, path) {
+            continue;
+        }
+        let canon = match path.canonicalize() {
+            Ok(canon) => canon,
+            Err(error) => {
+                warnings.push(ScanWarning::Unreadable {
+                    path: path.to_path_buf(),
+                    error: error.to_string(),
+                });
+                continue;
+            }
+        };
         let targets = canon_targets.entry(canon).or_insert_with(Vec::new);
         targets.push(target);
     }
     let mut rs_files = Vec::new();
-    for path_bufs in find_rs_files_in_dir(package.clone().get_root().as_path())
-    {
-        if !canon_targets.contains_key(&path_bufs) {
-            rs_files.push(RsFile::Other(path_bufs));
+    if let ScanMode::Full = mode {
+        for path_buf in find_rs_files_in_dir(package_root, scan_filter) {
+            match path_buf {
+                Ok(path_buf) => {
+                    if !canon_targets.contains_key(&path_buf) {
+                        rs_files.push(RsFile::Other(path_buf));
+                    }
+                }
+                Err(warning) => warnings.push(warning),
+            }
         }
     }
     for (path_buf, targets) in canon_targets.into_iter() {
@@ -164,29 +540,131 @@ fn find_rs_files_in_package(package: &cargo_metadata::Package) -> Vec<RsFile> {
 }
 
 fn find_rs_files_in_packages(
-    packages: &[cargo_metadata::Package],
-) -> impl Iterator<Item = (cargo_metadata::PackageId, RsFile)> + '_ {
-    packages.iter().flat_map(|package| {
-        find_rs_files_in_package(package)
+    packages: &[(cargo_metadata::Package, PathBuf)],
+    mode: &ScanMode,
+    scan_filter: Option<ScanFilter>,
+    warnings: &mut Vec<ScanWarning>,
+) -> Vec<(cargo_metadata::PackageId, RsFile)> {
+    let mut rs_files = Vec::new();
+    for (package, package_root) in packages {
+        rs_files.extend(
+            find_rs_files_in_package(
+                package,
+                package_root,
+                mode,
+                scan_filter,
+                warnings,
+            )
             .into_iter()
-            .map(move |p| (package.id.clone(), p))
-    })
+            .map(|p| (package.id.clone(), p)),
+        );
+    }
+    rs_files
 }
 
+/// Shows which packages are read from a path override in `.cargo/config`
+/// instead of their original source. Only printed with `--verbose`.
+fn report_path_overrides(
+    config: &Config,
+    package_set: &PackageSet,
+) -> CargoResult<()> {
+    let path_overrides = get_path_overrides(config)?;
+    if path_overrides.is_empty() {
+        return Ok(());
+    }
+    // Unavailable packages are warned about by the scan.
+    for package in package_set
+        .package_ids()
+        .filter_map(|id| package_set.get_one(id).ok())
+    {
+        let overriding = path_overrides
+            .iter()
+            .find(|(path, _)| package.root().starts_with(path));
+        if let Some((path, definition)) = overriding {
+            config.shell().verbose(|shell| {
+                shell.status(
+                    "Overriding",
+                    format!(
+                        "{} with path `{}` (defined in `{}`)",
+                        package.package_id(),
+                        path.display(),
+                        definition
+                    ),
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Shows which package each file that was found in several packages is
+/// counted for. Only printed with `--verbose`.
+fn report_shared_files(
+    config: &Config,
+    shared_files: &[SharedFile],
+) -> CargoResult<()> {
+    for shared_file in shared_files {
+        let others = shared_file
+            .others
+            .iter()
+            .map(|package| format!("{} {}", package.name, package.version))
+            .collect::<Vec<_>>()
+            .join(", ");
+        config.shell().verbose(|shell| {
+            shell.status(
+                "Attributing",
+                format!(
+                    "`{}` to {} {}, not counted for {}",
+                    shared_file.path.display(),
+                    shared_file.owner.name,
+                    shared_file.owner.version,
+                    others
+                ),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Files that can't be read, like ones without read permission, and files
+/// larger than `--max-file-size` are only warned about. Without
+/// `allow_partial_results` the files that can't be decoded or parsed stop the
+/// scan.
 fn handle_unsafe_in_file_error(
     allow_partial_results: bool,
     error: ScanFileError,
     path_buf: &PathBuf,
-) {
-    if allow_partial_results {
-        eprintln!("Failed to parse file: {}, {:?} ", path_buf.display(), error);
-    } else {
+) -> ScanWarning {
+    if let ScanFileError::Io(e, _) = &error {
+        return ScanWarning::Unreadable {
+            path: path_buf.clone(),
+            error: e.to_string(),
+        };
+    }
+    if let ScanFileError::TooLarge(size, _) = &error {
+        return ScanWarning::Oversized {
+            path: path_buf.clone(),
+            size: *size,
+        };
+    }
+    if !allow_partial_results {
         panic!("Failed to parse file: {}, {:?} ", path_buf.display(), error);
     }
+    let error = match error {
+        ScanFileError::Io(e, _) => e.to_string(),
+        ScanFileError::Utf8(e, _) => e.to_string(),
+        ScanFileError::Syn(e, _) => e.to_string(),
+        ScanFileError::TooLarge(size, _) => format!("{} bytes", size),
+    };
+    ScanWarning::ParseFailed {
+        path: path_buf.clone(),
+        error,
+    }
 }
 
 fn update_package_id_to_metrics_with_rs_file_metrics(
     is_entry_point: bool,
+    is_generated: bool,
     package_id: cargo_metadata::PackageId,
     package_id_to_metrics: &mut HashMap<
         cargo_metadata::PackageId,
@@ -204,13 +682,25 @@ fn update_package_id_to_metrics_with_rs_file_metrics(
         .or_insert_with(RsFileMetricsWrapper::default);
     wrapper.metrics = rs_file_metrics;
     wrapper.is_crate_entry_point = is_entry_point;
+    wrapper.is_generated = is_generated;
 }
 
 #[cfg(test)]
 mod find_tests {
     use super::*;
 
+    use crate::cli::{get_registry, get_workspace};
+    use crate::format::pattern::Pattern;
+    use crate::format::print_config::Prefix;
+    use crate::format::Charset;
+    use crate::scan::{unsafe_by_target, unsafe_stats};
+
+    use cargo::core::Verbosity;
+    use cargo_geiger_serde::{CountingStrategy, TargetKind};
     use cargo_metadata::{CargoOpt, MetadataCommand};
+    use geiger::{IncludeTests, ScanOptions, DEFAULT_MAX_FILE_SIZE};
+    use krates::Builder;
+    use petgraph::EdgeDirection;
     use rstest::*;
     use std::fs::File;
     use std::io;
@@ -237,11 +727,11 @@ mod find_tests {
             File::create(file_path).unwrap();
         }
 
-        let actual_rs_files = find_rs_files_in_dir(temp_dir.path());
+        let actual_rs_files = find_rs_files_in_dir(temp_dir.path(), None);
 
         let mut actual_rs_file_names = actual_rs_files
-            .into_iter()
             .map(|f| {
+                let f = f.unwrap();
                 String::from(f.as_path().file_name().unwrap().to_str().unwrap())
             })
             .collect::<Vec<String>>();
@@ -252,10 +742,70 @@ mod find_tests {
         assert_eq!(actual_rs_file_names, rs_file_names);
     }
 
+    #[rstest]
+    fn find_rs_files_in_dir_never_reads_rejected_dirs() {
+        let temp_dir = tempdir().unwrap();
+        let vendor_dir = temp_dir.path().join("vendor");
+        std::fs::create_dir_all(vendor_dir.join("nested")).unwrap();
+        File::create(temp_dir.path().join("lib.rs")).unwrap();
+        File::create(temp_dir.path().join("generated.rs")).unwrap();
+        File::create(vendor_dir.join("vendored.rs")).unwrap();
+        File::create(vendor_dir.join("nested").join("deep.rs")).unwrap();
+
+        let consulted_paths = std::cell::RefCell::new(Vec::new());
+        let scan_filter = |path: &Path| {
+            consulted_paths.borrow_mut().push(path.to_path_buf());
+            path.file_name() != Some(OsStr::new("vendor"))
+                && path.file_name() != Some(OsStr::new("generated.rs"))
+        };
+        let actual_rs_file_names =
+            find_rs_files_in_dir(temp_dir.path(), Some(&scan_filter))
+                .map(|path_buf| {
+                    String::from(
+                        path_buf
+                            .unwrap()
+                            .file_name()
+                            .unwrap()
+                            .to_str()
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+        assert_eq!(actual_rs_file_names, vec!["lib.rs"]);
+        // The directory itself is consulted, nothing below it.
+        let consulted_paths = consulted_paths.into_inner();
+        assert!(consulted_paths.contains(&vendor_dir));
+        assert!(consulted_paths
+            .iter()
+            .all(|path| path == &vendor_dir || !path.starts_with(&vendor_dir)));
+    }
+
+    #[rstest]
+    fn find_rs_files_in_dir_warns_about_unreadable_entries() {
+        let temp_dir = tempdir().unwrap();
+        let missing_dir = temp_dir.path().join("missing");
+
+        let actual_rs_files =
+            find_rs_files_in_dir(&missing_dir, None).collect::<Vec<_>>();
+
+        assert_eq!(actual_rs_files.len(), 1);
+        assert!(matches!(
+            &actual_rs_files[0],
+            Err(ScanWarning::Unreadable { path, .. }) if path == &missing_dir
+        ));
+    }
+
     #[rstest]
     fn find_rs_file_in_package() {
         let package = get_current_workspace_package();
-        let rs_files_in_package = find_rs_files_in_package(&package);
+        let rs_files_in_package = find_rs_files_in_package(
+            &package,
+            &get_package_root(&package),
+            &ScanMode::Full,
+            None,
+            &mut Vec::new(),
+        );
 
         let path_bufs_in_package = rs_files_in_package
             .iter()
@@ -272,18 +822,53 @@ mod find_tests {
         }
     }
 
+    #[rstest]
+    fn find_rs_file_in_package_with_entry_points_only() {
+        let package = get_current_workspace_package();
+        let rs_files_in_package = find_rs_files_in_package(
+            &package,
+            &get_package_root(&package),
+            &ScanMode::EntryPointsOnly,
+            None,
+            &mut Vec::new(),
+        );
+
+        // The roots of tests, benches and examples are `RsFile::Other`.
+        let target_roots = package
+            .targets
+            .iter()
+            .map(|target| target.src_path.canonicalize().unwrap())
+            .collect::<HashSet<_>>();
+        assert!(!rs_files_in_package.is_empty());
+        assert!(rs_files_in_package.iter().all(|f| match f {
+            RsFile::BinRoot(path_buf)
+            | RsFile::CustomBuildRoot(path_buf)
+            | RsFile::LibRoot(path_buf)
+            | RsFile::Other(path_buf) => target_roots.contains(path_buf),
+        }));
+    }
+
     #[rstest]
     fn handle_unsafe_in_file_error_doesnt_panic_when_allow_partial_results_is_true(
     ) {
         let path_buf = PathBuf::from("test_path");
-        handle_unsafe_in_file_error(
+        let warning = handle_unsafe_in_file_error(
             true,
-            ScanFileError::Io(
-                io::Error::new(ErrorKind::Other, "test"),
+            ScanFileError::Utf8(
+                String::from_utf8(vec![0xff]).unwrap_err(),
                 path_buf.clone(),
             ),
             &path_buf,
         );
+        assert_eq!(
+            warning,
+            ScanWarning::ParseFailed {
+                path: path_buf,
+                error: String::from(
+                    "invalid utf-8 sequence of 1 bytes from index 0"
+                )
+            }
+        );
     }
 
     #[rstest]
@@ -292,13 +877,50 @@ mod find_tests {
     {
         let path_buf = PathBuf::from("test_path");
         handle_unsafe_in_file_error(
+            false,
+            ScanFileError::Utf8(
+                String::from_utf8(vec![0xff]).unwrap_err(),
+                path_buf.clone(),
+            ),
+            &path_buf,
+        );
+    }
+
+    #[rstest]
+    fn handle_unsafe_in_file_error_doesnt_panic_on_unreadable_files() {
+        let path_buf = PathBuf::from("test_path");
+        let warning = handle_unsafe_in_file_error(
             false,
             ScanFileError::Io(
-                io::Error::new(ErrorKind::Other, "test"),
+                io::Error::new(ErrorKind::PermissionDenied, "test"),
                 path_buf.clone(),
             ),
             &path_buf,
         );
+        assert_eq!(
+            warning,
+            ScanWarning::Unreadable {
+                path: path_buf,
+                error: String::from("test")
+            }
+        );
+    }
+
+    #[rstest]
+    fn handle_unsafe_in_file_error_doesnt_panic_on_oversized_files() {
+        let path_buf = PathBuf::from("test_path");
+        let warning = handle_unsafe_in_file_error(
+            false,
+            ScanFileError::TooLarge(2048, path_buf.clone()),
+            &path_buf,
+        );
+        assert_eq!(
+            warning,
+            ScanWarning::Oversized {
+                path: path_buf,
+                size: 2048
+            }
+        );
     }
 
     #[rstest(
@@ -316,15 +938,25 @@ mod find_tests {
         let mut package_id_to_metrics =
             HashMap::<cargo_metadata::PackageId, PackageMetrics>::new();
 
-        let mut rs_files_in_package = find_rs_files_in_package(&package);
+        let mut rs_files_in_package = find_rs_files_in_package(
+            &package,
+            &get_package_root(&package),
+            &ScanMode::Full,
+            None,
+            &mut Vec::new(),
+        );
         let rs_file = rs_files_in_package.pop().unwrap();
         let (_, path_buf) = into_is_entry_point_and_path_buf(rs_file);
 
-        let rs_file_metrics =
-            find_unsafe_in_file(path_buf.as_path(), IncludeTests::Yes).unwrap();
+        let rs_file_metrics = find_unsafe_in_file_with_options(
+            path_buf.as_path(),
+            &ScanOptions::default().include_tests(IncludeTests::Yes),
+        )
+        .unwrap();
 
         update_package_id_to_metrics_with_rs_file_metrics(
             input_is_entry_point,
+            false,
             package.id.clone(),
             &mut package_id_to_metrics,
             package.manifest_path.clone(),
@@ -341,6 +973,202 @@ mod find_tests {
 
         assert_eq!(wrapper.metrics, rs_file_metrics);
         assert_eq!(wrapper.is_crate_entry_point, expected_is_crate_entry_point);
+        assert!(!wrapper.is_generated);
+    }
+
+    #[rstest]
+    fn attribute_shared_files_keeps_a_file_in_the_innermost_package() {
+        let metadata = MetadataCommand::new()
+            .manifest_path("./Cargo.toml")
+            .exec()
+            .unwrap();
+        let outer = metadata.root_package().unwrap().clone();
+        let inner = metadata
+            .packages
+            .iter()
+            .find(|package| package.name == "geiger")
+            .unwrap()
+            .clone();
+        let packages = vec![
+            (outer.clone(), PathBuf::from("/outer")),
+            (inner.clone(), PathBuf::from("/outer/inner")),
+        ];
+        let outer_path = PathBuf::from("/outer/src/lib.rs");
+        let shared_path = PathBuf::from("/outer/inner/src/lib.rs");
+
+        let mut package_id_to_metrics = HashMap::new();
+        for (package_id, path_buf) in vec![
+            (&outer.id, &outer_path),
+            (&outer.id, &shared_path),
+            (&inner.id, &shared_path),
+        ] {
+            update_package_id_to_metrics_with_rs_file_metrics(
+                false,
+                false,
+                package_id.clone(),
+                &mut package_id_to_metrics,
+                path_buf.clone(),
+                RsFileMetrics::default(),
+            );
+        }
+
+        let shared_files =
+            attribute_shared_files(&packages, &mut package_id_to_metrics);
+
+        assert_eq!(shared_files.len(), 1);
+        assert_eq!(shared_files[0].path, shared_path);
+        assert_eq!(shared_files[0].owner.name, "geiger");
+        assert_eq!(
+            shared_files[0]
+                .others
+                .iter()
+                .map(|package| package.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cargo-geiger"]
+        );
+        assert_eq!(
+            package_id_to_metrics[&outer.id]
+                .rs_path_to_metrics
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![&outer_path]
+        );
+        assert!(package_id_to_metrics[&inner.id]
+            .rs_path_to_metrics
+            .contains_key(&shared_path));
+    }
+
+    #[rstest]
+    fn find_unsafe_in_packages_keeps_the_metrics_of_each_file_only_when_detailed(
+    ) {
+        let config = Config::default().unwrap();
+        let manifest_path = config
+            .cwd()
+            .parent()
+            .unwrap()
+            .join("test_crates/test17_package_with_transmutes/Cargo.toml");
+        let workspace =
+            get_workspace(&config, Some(manifest_path.clone())).unwrap();
+        let package = workspace.current().unwrap();
+        let registry = get_registry(&config, package, &workspace).unwrap();
+        let package_set = registry.get(&[package.package_id()]).unwrap();
+        let metadata = MetadataCommand::new()
+            .manifest_path(&manifest_path)
+            .exec()
+            .unwrap();
+        let krates = Builder::new()
+            .build_with_metadata(metadata.clone(), |_| ())
+            .unwrap();
+        let cargo_metadata_parameters = CargoMetadataParameters {
+            krates: &krates,
+            metadata: &metadata,
+        };
+        let package_root = canonical_package_root(package);
+        let lib_path = package_root.join("src/lib.rs");
+        let rs_files_used = vec![lib_path.clone()].into_iter().collect();
+        let target_kinds = vec![(
+            lib_path.clone(),
+            vec![TargetKind::Lib].into_iter().collect(),
+        )]
+        .into_iter()
+        .collect();
+        let print_config = create_print_config();
+        let scan = |detail_level, detail_filter: Option<DetailFilter>| {
+            let (geiger_context, _) = find_unsafe_in_packages(
+                &cargo_metadata_parameters,
+                ScanMode::Full,
+                detail_level,
+                detail_filter,
+                &FileUsage {
+                    rs_files_used: &rs_files_used,
+                    target_kinds: &target_kinds,
+                },
+                &package_set,
+                &HashSet::new(),
+                &print_config,
+                None,
+                |_| Ok(()),
+            )
+            .unwrap();
+            geiger_context.package_id_to_metrics[&package.package_id()].clone()
+        };
+
+        let summary = scan(DetailLevel::Summary, None);
+        let detailed = scan(DetailLevel::Detailed, None);
+        let is_scanned_package =
+            |package_id| package_id == package.package_id();
+        let filtered = scan(DetailLevel::Summary, Some(&is_scanned_package));
+        let filtered_out = scan(DetailLevel::Summary, Some(&|_| false));
+
+        let rs_paths =
+            vec![lib_path.clone(), package_root.join("src/unused.rs")]
+                .into_iter()
+                .collect::<HashSet<_>>();
+        assert!(summary.rs_path_to_metrics.is_empty());
+        assert_eq!(summary.rs_paths, rs_paths);
+        assert_eq!(
+            detailed
+                .rs_path_to_metrics
+                .keys()
+                .cloned()
+                .collect::<HashSet<_>>(),
+            rs_paths
+        );
+        assert!(!detailed.rs_path_to_metrics[&lib_path]
+            .metrics
+            .transmute_lines
+            .is_empty());
+        assert_eq!(summary.file_groups, detailed.file_groups);
+        assert_eq!(summary.file_groups.len(), 2);
+        // Only the packages accepted by the detail filter are detailed.
+        assert_eq!(
+            filtered
+                .rs_path_to_metrics
+                .keys()
+                .cloned()
+                .collect::<HashSet<_>>(),
+            rs_paths
+        );
+        assert!(!filtered.rs_path_to_metrics[&lib_path]
+            .metrics
+            .transmute_lines
+            .is_empty());
+        assert!(filtered_out.rs_path_to_metrics.is_empty());
+        let unsafe_info = unsafe_stats(&summary, &rs_files_used);
+        assert!(unsafe_info.used.has_unsafe());
+        assert!(unsafe_info.unused.has_unsafe());
+        assert_eq!(
+            unsafe_by_target(&summary).keys().collect::<Vec<_>>(),
+            vec![&TargetKind::Lib]
+        );
+        assert_eq!(unsafe_info, unsafe_stats(&detailed, &rs_files_used));
+    }
+
+    fn create_print_config() -> PrintConfig {
+        PrintConfig {
+            all: false,
+            allow_partial_results: false,
+            charset: Charset::Ascii,
+            counting_strategy: CountingStrategy::Geiger,
+            direction: EdgeDirection::Outgoing,
+            format: Pattern(vec![]),
+            generated_markers: Vec::new(),
+            harness_cfgs: Vec::new(),
+            hyperlinks: false,
+            include_tests: IncludeTests::Yes,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            normalize_output: false,
+            prefix: Prefix::None,
+            output_format: None,
+            progress_json: false,
+            sort: None,
+            watched_calls: Vec::new(),
+            verbosity: Verbosity::Normal,
+        }
+    }
+
+    fn get_package_root(package: &cargo_metadata::Package) -> PathBuf {
+        package.manifest_path.parent().unwrap().to_path_buf()
     }
 
     #[fixture]
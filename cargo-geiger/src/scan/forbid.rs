@@ -1,17 +1,23 @@
 mod table;
 
-use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::format::print_config::OutputFormat;
 use crate::graph::Graph;
+use crate::lockfile::construct_report_metadata;
+use crate::timings::Phase;
 
 use super::find::find_unsafe;
-use super::{package_metrics, ScanMode, ScanParameters};
+use super::{
+    dependency_cycles, package_metrics, DetailLevel, FileUsage, ScanMode,
+    ScanParameters,
+};
 
 use table::scan_forbid_to_table;
 
 use crate::krates_utils::CargoMetadataParameters;
 use cargo::core::{PackageId, PackageSet};
-use cargo::{CliResult, Config};
+use cargo::CliResult;
 use cargo_geiger_serde::{QuickReportEntry, QuickSafetyReport};
+use std::collections::{HashMap, HashSet};
 
 pub fn scan_forbid_unsafe(
     cargo_metadata_parameters: &CargoMetadataParameters,
@@ -21,15 +27,17 @@ pub fn scan_forbid_unsafe(
     scan_parameters: &ScanParameters,
 ) -> CliResult {
     match scan_parameters.args.output_format {
-        Some(output_format) => scan_forbid_to_report(
+        Some(OutputFormat::Json) => scan_forbid_to_report(
             cargo_metadata_parameters,
-            scan_parameters.config,
             graph,
-            output_format,
             package_set,
-            scan_parameters.print_config,
             root_package_id,
+            scan_parameters,
         ),
+        // Rejected by `scan`, there are no counts to put on the lines.
+        Some(OutputFormat::TreeJson) => {
+            unreachable!("`--output-format tree-json` with `--forbid-only`")
+        }
         None => scan_forbid_to_table(
             cargo_metadata_parameters,
             scan_parameters.config,
@@ -37,27 +45,40 @@ pub fn scan_forbid_unsafe(
             package_set,
             scan_parameters.print_config,
             root_package_id,
+            scan_parameters.timings,
         ),
     }
 }
 
 fn scan_forbid_to_report(
     cargo_metadata_parameters: &CargoMetadataParameters,
-    config: &Config,
     graph: &Graph,
-    output_format: OutputFormat,
     package_set: &PackageSet,
-    print_config: &PrintConfig,
     root_package_id: PackageId,
+    scan_parameters: &ScanParameters,
 ) -> CliResult {
     let geiger_context = find_unsafe(
         cargo_metadata_parameters,
-        config,
+        scan_parameters.config,
         ScanMode::EntryPointsOnly,
+        DetailLevel::Summary,
+        None,
+        &FileUsage {
+            rs_files_used: &HashSet::new(),
+            target_kinds: &HashMap::new(),
+        },
         package_set,
-        print_config,
+        &HashSet::new(),
+        scan_parameters.print_config,
+        None,
     )?;
-    let mut report = QuickSafetyReport::default();
+    scan_parameters.timings.end_phase(Phase::Scan);
+    let mut report = QuickSafetyReport {
+        dependency_cycles: dependency_cycles(graph),
+        metadata: construct_report_metadata(scan_parameters.resolve),
+        ..Default::default()
+    };
+    report.metadata.timings = scan_parameters.timings.phase_timings();
     for (package, package_metrics) in
         package_metrics(&geiger_context, graph, root_package_id)
     {
@@ -68,20 +89,17 @@ fn scan_forbid_to_report(
                 continue;
             }
         };
-        let forbids_unsafe = pack_metrics.rs_path_to_metrics.iter().all(
-            |(_, rs_file_metrics_wrapper)| {
-                rs_file_metrics_wrapper.metrics.forbids_unsafe
-            },
-        );
+        let forbids_unsafe = pack_metrics
+            .file_groups
+            .keys()
+            .all(|file_group| file_group.forbids_unsafe);
         let entry = QuickReportEntry {
             package,
             forbids_unsafe,
         };
         report.packages.insert(entry.package.id.clone(), entry);
     }
-    let s = match output_format {
-        OutputFormat::Json => serde_json::to_string(&report).unwrap(),
-    };
+    let s = serde_json::to_string(&report).unwrap();
     println!("{}", s);
     Ok(())
 }
@@ -1,19 +1,24 @@
 use crate::format::emoji_symbols::EmojiSymbols;
 use crate::format::pattern::Pattern;
 use crate::format::print_config::PrintConfig;
-use crate::format::{get_kind_group_name, SymbolKind};
+use crate::format::{
+    get_kind_group_name, pad_to_display_width, unavailable_package_name,
+    SymbolKind,
+};
 use crate::graph::Graph;
 use crate::krates_utils::CargoMetadataParameters;
+use crate::timings::{Phase, Timings};
 use crate::tree::traversal::walk_dependency_tree;
 use crate::tree::TextTreeLine;
 
 use super::super::find::find_unsafe;
-use super::super::ScanMode;
+use super::super::{DetailLevel, FileUsage, ScanMode};
 
 use crate::scan::GeigerContext;
 use cargo::core::{Package, PackageId, PackageSet};
 use cargo::{CliResult, Config};
 use colored::Colorize;
+use std::collections::{HashMap, HashSet};
 
 pub fn scan_forbid_to_table(
     cargo_metadata_parameters: &CargoMetadataParameters,
@@ -22,6 +27,7 @@ pub fn scan_forbid_to_table(
     package_set: &PackageSet,
     print_config: &PrintConfig,
     root_package_id: PackageId,
+    timings: &Timings,
 ) -> CliResult {
     let mut scan_output_lines = Vec::<String>::new();
     let emoji_symbols = EmojiSymbols::new(print_config.charset);
@@ -29,11 +35,46 @@ pub fn scan_forbid_to_table(
     let mut output_key_lines = construct_key_lines(&emoji_symbols);
     scan_output_lines.append(&mut output_key_lines);
 
+    let geiger_ctx = find_unsafe(
+        cargo_metadata_parameters,
+        config,
+        ScanMode::EntryPointsOnly,
+        DetailLevel::Summary,
+        None,
+        &FileUsage {
+            rs_files_used: &HashSet::new(),
+            target_kinds: &HashMap::new(),
+        },
+        package_set,
+        &HashSet::new(),
+        print_config,
+        None,
+    )?;
+    timings.end_phase(Phase::Scan);
+
     let tree_lines =
         walk_dependency_tree(root_package_id, &graph, &print_config);
     for tree_line in tree_lines {
         match tree_line {
-            TextTreeLine::ExtraDepsGroup { kind, tree_vines } => {
+            TextTreeLine::Cycle {
+                id: package_id,
+                tree_vines,
+                ..
+            } => {
+                let name = match package_set.get_one(package_id) {
+                    Ok(package) => format_package_name(
+                        package,
+                        &print_config.format,
+                        print_config.hyperlinks,
+                    ),
+                    Err(_) => unavailable_package_name(package_id),
+                };
+                scan_output_lines
+                    .push(format!("  {}{} (cycle)", tree_vines, name));
+            }
+            TextTreeLine::ExtraDepsGroup {
+                kind, tree_vines, ..
+            } => {
                 let name = get_kind_group_name(kind);
                 if name.is_none() {
                     continue;
@@ -45,15 +86,8 @@ pub fn scan_forbid_to_table(
             TextTreeLine::Package {
                 id: package_id,
                 tree_vines,
+                ..
             } => {
-                let geiger_ctx = find_unsafe(
-                    cargo_metadata_parameters,
-                    config,
-                    ScanMode::EntryPointsOnly,
-                    package_set,
-                    print_config,
-                )?;
-
                 handle_package_text_tree_line(
                     &emoji_symbols,
                     &geiger_ctx,
@@ -90,8 +124,11 @@ fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
 
     for (symbol_kind, string_values) in symbol_kinds_to_string_values {
         output_key_lines.push(format!(
-            "    {: <2} = {}",
-            emoji_symbols.emoji(symbol_kind),
+            "    {} = {}",
+            pad_to_display_width(
+                &emoji_symbols.emoji(symbol_kind).to_string(),
+                2
+            ),
             string_values
         ));
     }
@@ -100,10 +137,18 @@ fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
     output_key_lines
 }
 
-fn format_package_name(package: &Package, pattern: &Pattern) -> String {
+fn format_package_name(
+    package: &Package,
+    pattern: &Pattern,
+    hyperlinks: bool,
+) -> String {
     format!(
         "{}",
-        pattern.display(&package.package_id(), package.manifest().metadata())
+        pattern.display(
+            &package.package_id(),
+            package.manifest().metadata(),
+            hyperlinks
+        )
     )
 }
 
@@ -119,16 +164,30 @@ fn handle_package_text_tree_line(
     let sym_lock = emoji_symbols.emoji(SymbolKind::Lock);
     let sym_qmark = emoji_symbols.emoji(SymbolKind::QuestionMark);
 
-    let package = package_set.get_one(package_id).unwrap(); // FIXME
-    let name = format_package_name(package, &print_config.format);
+    let package = match package_set.get_one(package_id) {
+        Ok(package) => package,
+        Err(_) => {
+            // Not scanned, the scan warns about it.
+            scan_output_lines.push(format!(
+                "  {}{}",
+                tree_vines,
+                unavailable_package_name(package_id)
+            ));
+            return Ok(());
+        }
+    };
+    let name = format_package_name(
+        package,
+        &print_config.format,
+        print_config.hyperlinks,
+    );
     let package_metrics = geiger_ctx.package_id_to_metrics.get(&package_id);
     let package_forbids_unsafe = match package_metrics {
         None => false, // no metrics available, .rs parsing failed?
-        Some(package_metric) => package_metric.rs_path_to_metrics.iter().all(
-            |(_k, rs_file_metrics_wrapper)| {
-                rs_file_metrics_wrapper.metrics.forbids_unsafe
-            },
-        ),
+        Some(package_metric) => package_metric
+            .file_groups
+            .keys()
+            .all(|file_group| file_group.forbids_unsafe),
     };
     let (symbol, name) = if package_forbids_unsafe {
         (&sym_lock, name.green())
@@ -171,7 +230,8 @@ mod forbid_tests {
 
         let package = workspace.current().unwrap();
 
-        let formatted_package_name = format_package_name(&package, &pattern);
+        let formatted_package_name =
+            format_package_name(&package, &pattern, false);
 
         assert_eq!(formatted_package_name, "cargo-geiger 0.10.2");
     }
@@ -0,0 +1,372 @@
+use crate::args::Args;
+use crate::format::print_config::PrintConfig;
+use crate::format::table::{
+    table_row_without_build_data, CounterColumns, UNSAFE_COUNTERS_HEADER,
+};
+use crate::rs_file::{is_generated_file, RsFileMetricsWrapper};
+
+use super::find::find_rs_files_in_dir;
+use super::{
+    unsafe_stats, DetailLevel, FileUsage, PackageMetrics, UnsafeCriteria,
+};
+
+use cargo::core::package::PackageSet;
+use cargo::core::{
+    Dependency, Package, PackageId, Source, SourceId, SourceMap,
+};
+use cargo::sources::SourceConfigMap;
+use cargo::util::CargoResult;
+use cargo::{CliError, CliResult, Config};
+use geiger::find_unsafe_in_file_with_options;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Fetches a crate from crates.io by `name@version`, or the latest version
+/// that isn't yanked without the `@version`, and prints its unsafe usage.
+/// Nothing is built, every file of the crate is scanned and counted as not
+/// used by the build.
+pub fn scan_published_crate(
+    args: &Args,
+    config: &Config,
+    print_config: &PrintConfig,
+    crate_spec: &str,
+) -> CliResult {
+    if args.forbid_only || args.output_format.is_some() || args.progress_json {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "`--crate` can't be combined with `--forbid-only`, \
+                 `--progress-json` or an output format"
+            ),
+            1,
+        ));
+    }
+    let (name, version) = parse_crate_spec(crate_spec);
+    let package = download_published_crate(config, name, version)?;
+    let package_root =
+        package.root().canonicalize().map_err(anyhow::Error::from)?;
+    let package_metrics =
+        find_unsafe_in_published_crate(config, &package, print_config)?;
+
+    let published_crate_lines = construct_published_crate_lines(
+        package.package_id(),
+        &package_root,
+        &package_metrics,
+        &args.unsafe_criteria,
+    );
+    for published_crate_line in published_crate_lines {
+        println!("{}", published_crate_line);
+    }
+
+    Ok(())
+}
+
+/// Splits `name@version` into its name and version, the version is optional.
+fn parse_crate_spec(crate_spec: &str) -> (&str, Option<&str>) {
+    let mut parts = crate_spec.splitn(2, '@');
+    let name = parts.next().unwrap_or_default();
+    let version = parts.next().filter(|version| !version.is_empty());
+    (name, version)
+}
+
+/// Downloads the `.crate` file of the package, unless it's in the local
+/// registry cache already, and unpacks it. With `--offline` the index is
+/// not updated and only cached crates can be scanned.
+fn download_published_crate(
+    config: &Config,
+    name: &str,
+    version: Option<&str>,
+) -> CargoResult<Package> {
+    let source_id = SourceId::crates_io(config)?;
+    // An exact version is scanned even if it was yanked, only the search for
+    // the latest version skips yanked versions.
+    let yanked_whitelist = match version {
+        Some(version) => vec![PackageId::new(name, version, source_id)?]
+            .into_iter()
+            .collect(),
+        None => HashSet::new(),
+    };
+    let version_req = version.map(|version| format!("={}", version));
+    let dependency = Dependency::parse_no_deprecated(
+        name,
+        version_req.as_deref(),
+        source_id,
+    )?;
+
+    let _lock = config.acquire_package_cache_lock()?;
+    let mut source =
+        SourceConfigMap::new(config)?.load(source_id, &yanked_whitelist)?;
+    source.update()?;
+    let package_id = source
+        .query_vec(&dependency)?
+        .iter()
+        .map(|summary| summary.package_id())
+        .max_by(|a, b| a.version().cmp(b.version()))
+        .ok_or_else(|| match version {
+            Some(version) => {
+                anyhow::anyhow!("could not find `{}@{}`", name, version)
+            }
+            None => anyhow::anyhow!(
+                "could not find a version of `{}` that isn't yanked",
+                name
+            ),
+        })?;
+
+    let mut sources = SourceMap::new();
+    sources.insert(source);
+    let package_set = PackageSet::new(&[package_id], sources, config)?;
+    let package = package_set.get_one(package_id)?.clone();
+    Ok(package)
+}
+
+fn find_unsafe_in_published_crate(
+    config: &Config,
+    package: &Package,
+    print_config: &PrintConfig,
+) -> CargoResult<PackageMetrics> {
+    let crate_roots = package
+        .targets()
+        .iter()
+        .filter_map(|target| target.src_path().path())
+        .filter_map(|path| path.canonicalize().ok())
+        .collect::<HashSet<_>>();
+
+    let scan_options = print_config.scan_options();
+    let mut package_metrics = PackageMetrics::default();
+    for path_buf in find_rs_files_in_dir(package.root(), None) {
+        let path_buf = match path_buf {
+            Ok(path_buf) => path_buf,
+            Err(warning) => {
+                config.shell().warn(format!("{:?}", warning))?;
+                continue;
+            }
+        };
+        match find_unsafe_in_file_with_options(&path_buf, &scan_options) {
+            Err(error) => {
+                package_metrics.add_scan_failure(&error, path_buf);
+                config.shell().warn(error)?;
+            }
+            Ok(metrics) => {
+                let rs_file_metrics_wrapper = RsFileMetricsWrapper {
+                    metrics,
+                    is_crate_entry_point: crate_roots.contains(&path_buf),
+                    is_generated: is_generated_file(
+                        &path_buf,
+                        &print_config.generated_markers,
+                    ),
+                };
+                package_metrics
+                    .rs_path_to_metrics
+                    .insert(path_buf, rs_file_metrics_wrapper);
+            }
+        }
+    }
+    // The files are listed one by one.
+    package_metrics.fold_files(
+        &FileUsage {
+            rs_files_used: &HashSet::new(),
+            target_kinds: &HashMap::new(),
+        },
+        DetailLevel::Detailed,
+    );
+
+    Ok(package_metrics)
+}
+
+fn construct_published_crate_lines(
+    package_id: PackageId,
+    package_root: &Path,
+    package_metrics: &PackageMetrics,
+    unsafe_criteria: &UnsafeCriteria,
+) -> Vec<String> {
+    let unsafe_info = unsafe_stats(package_metrics, &HashSet::new());
+    let mut published_crate_lines = vec![
+        String::new(),
+        package_id.to_string(),
+        String::new(),
+        format!(
+            "Forbids unsafe code: {}",
+            if unsafe_info.forbids_unsafe {
+                "yes"
+            } else {
+                "no"
+            }
+        ),
+        String::from("Nothing was built, the used counts are unknown."),
+        String::new(),
+        format!(
+            "{}File",
+            UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+                .join(" ")
+        ),
+    ];
+
+    let mut rs_file_metrics = package_metrics
+        .rs_path_to_metrics
+        .iter()
+        .collect::<Vec<(&PathBuf, _)>>();
+    rs_file_metrics.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (path, rs_file_metrics_wrapper) in rs_file_metrics {
+        published_crate_lines.push(format!(
+            "{} {}",
+            table_row_without_build_data(
+                &rs_file_metrics_wrapper.metrics.counters,
+                unsafe_criteria,
+                &CounterColumns::default(),
+            ),
+            path.strip_prefix(package_root).unwrap_or(path).display()
+        ));
+    }
+    published_crate_lines.push(format!(
+        "{} Total",
+        table_row_without_build_data(
+            &unsafe_info.unused,
+            unsafe_criteria,
+            &CounterColumns::default(),
+        )
+    ));
+    for path in &package_metrics.parse_failures {
+        let approximated = match package_metrics.approximated.get(path) {
+            Some(unsafe_tokens) => {
+                format!(", ~{} unsafe counted from the tokens", unsafe_tokens)
+            }
+            None => String::new(),
+        };
+        published_crate_lines.push(format!(
+            "Failed to parse: {}{}",
+            path.strip_prefix(package_root).unwrap_or(path).display(),
+            approximated
+        ));
+    }
+    for oversized_file in &unsafe_info.skipped_oversized {
+        published_crate_lines.push(format!(
+            "Too large to scan: {}, {} bytes",
+            oversized_file
+                .path
+                .strip_prefix(package_root)
+                .unwrap_or(&oversized_file.path)
+                .display(),
+            oversized_file.size
+        ));
+    }
+
+    published_crate_lines
+}
+
+#[cfg(test)]
+mod published_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{Count, CounterBlock};
+    use geiger::RsFileMetrics;
+    use rstest::*;
+
+    #[rstest(
+        input_crate_spec,
+        expected_name,
+        expected_version,
+        case("rkyv@0.7.42", "rkyv", Some("0.7.42")),
+        case("rkyv", "rkyv", None),
+        case("rkyv@", "rkyv", None)
+    )]
+    fn parse_crate_spec_test(
+        input_crate_spec: &str,
+        expected_name: &str,
+        expected_version: Option<&str>,
+    ) {
+        assert_eq!(
+            parse_crate_spec(input_crate_spec),
+            (expected_name, expected_version)
+        );
+    }
+
+    #[rstest]
+    fn construct_published_crate_lines_test() {
+        let config = Config::default().unwrap();
+        let package_id = PackageId::new(
+            "published",
+            "1.0.0",
+            SourceId::crates_io(&config).unwrap(),
+        )
+        .unwrap();
+        let package_root = Path::new("/published");
+        let mut package_metrics = PackageMetrics {
+            rs_path_to_metrics: vec![
+                (
+                    package_root.join("src/raw.rs"),
+                    rs_file_metrics_wrapper(3, false),
+                ),
+                (
+                    package_root.join("src/lib.rs"),
+                    rs_file_metrics_wrapper(1, true),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            parse_failures: vec![
+                package_root.join("src/broken.rs"),
+                package_root.join("src/unlexable.rs"),
+            ],
+            approximated: vec![(package_root.join("src/broken.rs"), 2)]
+                .into_iter()
+                .collect(),
+            oversized: vec![(package_root.join("src/generated.rs"), 20_000)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        package_metrics.fold_files(
+            &FileUsage {
+                rs_files_used: &HashSet::new(),
+                target_kinds: &HashMap::new(),
+            },
+            DetailLevel::Detailed,
+        );
+
+        let published_crate_lines = construct_published_crate_lines(
+            package_id,
+            package_root,
+            &package_metrics,
+            &UnsafeCriteria::default(),
+        );
+
+        assert_eq!(
+            published_crate_lines,
+            vec![
+                "",
+                "published v1.0.0",
+                "",
+                "Forbids unsafe code: no",
+                "Nothing was built, the used counts are unknown.",
+                "",
+                "Functions  Expressions  Impls  Traits  Methods  FFI File",
+                "?/0        ?/1          ?/0    ?/0     ?/0      ?/0 src/lib.rs",
+                "?/0        ?/3          ?/0    ?/0     ?/0      ?/0 src/raw.rs",
+                "?/0        ?/4          ?/0    ?/0     ?/0      ?/0 Total",
+                "Failed to parse: src/broken.rs, ~2 unsafe counted from the \
+                 tokens",
+                "Failed to parse: src/unlexable.rs",
+                "Too large to scan: src/generated.rs, 20000 bytes",
+            ]
+        );
+    }
+
+    fn rs_file_metrics_wrapper(
+        unsafe_exprs: u64,
+        is_crate_entry_point: bool,
+    ) -> RsFileMetricsWrapper {
+        RsFileMetricsWrapper {
+            metrics: RsFileMetrics {
+                counters: CounterBlock {
+                    exprs: Count {
+                        safe: 0,
+                        unsafe_: unsafe_exprs,
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            is_crate_entry_point,
+            is_generated: false,
+        }
+    }
+}
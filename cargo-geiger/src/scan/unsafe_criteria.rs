@@ -0,0 +1,330 @@
+use cargo_geiger_serde::{ApproximateUnsafety, CounterBlock, UnsafeInfo};
+use std::str::FromStr;
+
+/// Decides which unsafe usage makes a crate count as using `unsafe`. By
+/// default every category counts, both in code used by the build and in code
+/// that is not.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsafeCriteria {
+    /// The categories that count in code used by the build.
+    pub used: UnsafeCategories,
+    /// The categories that count in code not used by the build.
+    pub unused: UnsafeCategories,
+}
+
+/// The unsafe usage categories, one for each column of the table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsafeCategories {
+    pub functions: bool,
+    pub exprs: bool,
+    pub item_impls: bool,
+    pub item_traits: bool,
+    pub methods: bool,
+    pub foreign_items: bool,
+}
+
+impl UnsafeCriteria {
+    /// Whether unsafe code that counts is used by the build. The category of
+    /// the `unsafe` keywords counted in files that failed to parse is
+    /// unknown, they always count.
+    pub fn has_used_unsafe(&self, unsafe_info: &UnsafeInfo) -> bool {
+        self.used.any_unsafe(&unsafe_info.used)
+            || approximate_count(unsafe_info, |approximate| approximate.used)
+                > 0
+    }
+
+    /// Whether unsafe code that counts is found, but only in code not used
+    /// by the build.
+    pub fn has_only_unused_unsafe(&self, unsafe_info: &UnsafeInfo) -> bool {
+        !self.has_used_unsafe(unsafe_info)
+            && (self.unused.any_unsafe(&unsafe_info.unused)
+                || approximate_count(unsafe_info, |approximate| {
+                    approximate.unused
+                }) > 0)
+    }
+
+    /// Whether declarations in `extern` blocks count at all.
+    pub fn counts_foreign_items(&self) -> bool {
+        self.used.foreign_items || self.unused.foreign_items
+    }
+
+    /// Stops declarations in `extern` blocks from counting, for crates that
+    /// are mostly bindings.
+    pub fn without_foreign_items(mut self) -> Self {
+        self.used.foreign_items = false;
+        self.unused.foreign_items = false;
+        self
+    }
+}
+
+fn approximate_count(
+    unsafe_info: &UnsafeInfo,
+    count: fn(&ApproximateUnsafety) -> u64,
+) -> u64 {
+    unsafe_info.approximate.as_ref().map_or(0, count)
+}
+
+impl Default for UnsafeCriteria {
+    fn default() -> Self {
+        UnsafeCriteria {
+            used: UnsafeCategories::all(),
+            unused: UnsafeCategories::all(),
+        }
+    }
+}
+
+/// Parses a comma separated list of categories: `functions`, `exprs`,
+/// `impls`, `traits`, `methods` and `ffi`. Categories prefixed with `used-` only
+/// count in code used by the build.
+impl FromStr for UnsafeCriteria {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<UnsafeCriteria, String> {
+        let mut criteria = UnsafeCriteria {
+            used: UnsafeCategories::none(),
+            unused: UnsafeCategories::none(),
+        };
+        for value in s.split(',').map(str::trim) {
+            let (category, used_only) = match value.strip_prefix("used-") {
+                Some(category) => (category, true),
+                None => (value, false),
+            };
+            criteria.used.set(category, value)?;
+            if !used_only {
+                criteria.unused.set(category, value)?;
+            }
+        }
+        Ok(criteria)
+    }
+}
+
+impl UnsafeCategories {
+    fn all() -> Self {
+        UnsafeCategories {
+            functions: true,
+            exprs: true,
+            item_impls: true,
+            item_traits: true,
+            methods: true,
+            foreign_items: true,
+        }
+    }
+
+    fn none() -> Self {
+        UnsafeCategories {
+            functions: false,
+            exprs: false,
+            item_impls: false,
+            item_traits: false,
+            methods: false,
+            foreign_items: false,
+        }
+    }
+
+    fn any_unsafe(&self, counters: &CounterBlock) -> bool {
+        (self.functions && counters.functions.unsafe_ > 0)
+            || (self.exprs && counters.exprs.unsafe_ > 0)
+            || (self.item_impls && counters.item_impls.unsafe_ > 0)
+            || (self.item_traits && counters.item_traits.unsafe_ > 0)
+            || (self.methods && counters.methods.unsafe_ > 0)
+            || (self.foreign_items && counters.foreign_items.unsafe_ > 0)
+    }
+
+    fn set(&mut self, category: &str, value: &str) -> Result<(), String> {
+        match category {
+            "functions" => self.functions = true,
+            "exprs" => self.exprs = true,
+            "impls" => self.item_impls = true,
+            "traits" => self.item_traits = true,
+            "methods" => self.methods = true,
+            "ffi" => self.foreign_items = true,
+            _ => {
+                return Err(format!(
+                    "invalid unsafe category `{}`, expected one of \
+                     functions, exprs, impls, traits, methods, ffi, optionally \
+                     prefixed with `used-`",
+                    value
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod unsafe_criteria_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest(
+        input_criteria,
+        expected_used,
+        expected_unused,
+        case(
+            "functions,used-exprs",
+            [true, true, false, false, false, false],
+            [true, false, false, false, false, false]
+        ),
+        case(
+            "used-functions, used-exprs, used-impls, used-traits, used-methods",
+            [true, true, true, true, true, false],
+            [false, false, false, false, false, false]
+        ),
+        case(
+            "impls,traits,methods",
+            [false, false, true, true, true, false],
+            [false, false, true, true, true, false]
+        ),
+        case(
+            "used-ffi,exprs",
+            [false, true, false, false, false, true],
+            [false, true, false, false, false, false]
+        )
+    )]
+    fn unsafe_criteria_from_str_test(
+        input_criteria: &str,
+        expected_used: [bool; 6],
+        expected_unused: [bool; 6],
+    ) {
+        let criteria = UnsafeCriteria::from_str(input_criteria).unwrap();
+        assert_eq!(criteria.used, categories(expected_used));
+        assert_eq!(criteria.unused, categories(expected_unused));
+    }
+
+    #[rstest(input_criteria, case(""), case("exprs,"), case("used-unsafe"))]
+    fn unsafe_criteria_from_str_invalid_test(input_criteria: &str) {
+        assert!(UnsafeCriteria::from_str(input_criteria).is_err());
+    }
+
+    #[rstest(
+        input_criteria,
+        input_used,
+        input_unused,
+        expected_has_used_unsafe,
+        expected_has_only_unused_unsafe,
+        case(UnsafeCriteria::default(), 1, 0, true, false),
+        case(UnsafeCriteria::default(), 0, 1, false, true),
+        case(UnsafeCriteria::default(), 0, 0, false, false),
+        // A single unsafe trait definition doesn't count when only the
+        // expressions do.
+        case(
+            UnsafeCriteria::from_str("exprs").unwrap(),
+            1,
+            1,
+            false,
+            false
+        ),
+        case(
+            UnsafeCriteria::from_str("traits,used-exprs").unwrap(),
+            0,
+            1,
+            false,
+            true
+        ),
+        case(
+            UnsafeCriteria::from_str("used-traits").unwrap(),
+            0,
+            1,
+            false,
+            false
+        )
+    )]
+    fn unsafe_criteria_has_unsafe_test(
+        input_criteria: UnsafeCriteria,
+        input_used: u64,
+        input_unused: u64,
+        expected_has_used_unsafe: bool,
+        expected_has_only_unused_unsafe: bool,
+    ) {
+        let unsafe_info = UnsafeInfo {
+            used: unsafe_traits(input_used),
+            unused: unsafe_traits(input_unused),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            input_criteria.has_used_unsafe(&unsafe_info),
+            expected_has_used_unsafe
+        );
+        assert_eq!(
+            input_criteria.has_only_unused_unsafe(&unsafe_info),
+            expected_has_only_unused_unsafe
+        );
+    }
+
+    #[rstest(
+        input_used,
+        input_unused,
+        expected_has_used_unsafe,
+        expected_has_only_unused_unsafe,
+        case(1, 0, true, false),
+        case(0, 1, false, true),
+        case(0, 0, false, false)
+    )]
+    fn unsafe_criteria_has_unsafe_with_approximate_test(
+        input_used: u64,
+        input_unused: u64,
+        expected_has_used_unsafe: bool,
+        expected_has_only_unused_unsafe: bool,
+    ) {
+        let unsafe_info = UnsafeInfo {
+            approximate: Some(ApproximateUnsafety {
+                used: input_used,
+                unused: input_unused,
+            }),
+            ..Default::default()
+        };
+        let criteria = UnsafeCriteria::from_str("used-exprs").unwrap();
+
+        assert_eq!(
+            criteria.has_used_unsafe(&unsafe_info),
+            expected_has_used_unsafe
+        );
+        assert_eq!(
+            criteria.has_only_unused_unsafe(&unsafe_info),
+            expected_has_only_unused_unsafe
+        );
+    }
+
+    #[rstest]
+    fn unsafe_criteria_without_foreign_items_test() {
+        let unsafe_info = UnsafeInfo {
+            used: CounterBlock {
+                foreign_items: Count {
+                    safe: 0,
+                    unsafe_: 2,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let criteria = UnsafeCriteria::default();
+        assert!(criteria.counts_foreign_items());
+        assert!(criteria.has_used_unsafe(&unsafe_info));
+
+        let criteria = criteria.without_foreign_items();
+        assert!(!criteria.counts_foreign_items());
+        assert!(!criteria.has_used_unsafe(&unsafe_info));
+    }
+
+    fn categories(values: [bool; 6]) -> UnsafeCategories {
+        UnsafeCategories {
+            functions: values[0],
+            exprs: values[1],
+            item_impls: values[2],
+            item_traits: values[3],
+            methods: values[4],
+            foreign_items: values[5],
+        }
+    }
+
+    fn unsafe_traits(unsafe_: u64) -> CounterBlock {
+        CounterBlock {
+            item_traits: Count { safe: 0, unsafe_ },
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use cargo_geiger_serde::PhaseTiming;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// The phases of a run, in the order they happen.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Phase {
+    /// Loading the workspace, resolving the dependencies and building the
+    /// dependency graph
+    Resolve,
+    /// `cargo clean` before the check build, skipped with `--reuse-build`
+    Clean,
+    /// The check build that records the files passed to rustc
+    Build,
+    /// Reading the files used by the build from the dep-info files
+    DepInfo,
+    /// Parsing the `.rs` files of all packages
+    Scan,
+    /// Building and printing the table, tree or report
+    Render,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Resolve => "resolve",
+            Phase::Clean => "clean",
+            Phase::Build => "build",
+            Phase::DepInfo => "dep-info",
+            Phase::Scan => "scan",
+            Phase::Render => "render",
+        }
+    }
+}
+
+/// The time spent in each phase of a run, see `--timings`. A phase is ended
+/// instead of started, it's the time since the end of the previous phase.
+/// This way the phases always add up to the whole run, phases that happen
+/// more than once add up too.
+pub struct Timings {
+    enabled: bool,
+    phase_start: Cell<Instant>,
+    durations: RefCell<BTreeMap<Phase, Duration>>,
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Self {
+        Timings {
+            enabled,
+            phase_start: Cell::new(Instant::now()),
+            durations: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn end_phase(&self, phase: Phase) {
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        *self.durations.borrow_mut().entry(phase).or_default() +=
+            now - self.phase_start.replace(now);
+    }
+
+    /// The phases ended so far, for the report metadata. Empty without
+    /// `--timings`.
+    pub fn phase_timings(&self) -> Vec<PhaseTiming> {
+        self.durations
+            .borrow()
+            .iter()
+            .map(|(phase, duration)| PhaseTiming {
+                phase: String::from(phase.name()),
+                seconds: duration.as_secs_f64(),
+            })
+            .collect()
+    }
+
+    /// Prints the phases ended so far on stderr, with `--timings`.
+    pub fn print(&self) {
+        if !self.enabled {
+            return;
+        }
+        for timings_line in construct_timings_lines(&self.durations.borrow()) {
+            eprintln!("{}", timings_line);
+        }
+    }
+}
+
+fn construct_timings_lines(
+    durations: &BTreeMap<Phase, Duration>,
+) -> Vec<String> {
+    let mut timings_lines = vec![String::new(), String::from("Timings:")];
+    for (phase, duration) in durations {
+        timings_lines.push(format!(
+            "    {: <9} {:>8.2}s",
+            phase.name(),
+            duration.as_secs_f64()
+        ));
+    }
+    timings_lines.push(format!(
+        "    {: <9} {:>8.2}s",
+        "total",
+        durations.values().sum::<Duration>().as_secs_f64()
+    ));
+    timings_lines
+}
+
+#[cfg(test)]
+mod timings_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest]
+    fn construct_timings_lines_test() {
+        let durations = vec![
+            (Phase::Scan, Duration::from_millis(1250)),
+            (Phase::Resolve, Duration::from_millis(300)),
+            (Phase::Build, Duration::from_secs(12)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            construct_timings_lines(&durations),
+            vec![
+                "",
+                "Timings:",
+                "    resolve       0.30s",
+                "    build        12.00s",
+                "    scan          1.25s",
+                "    total        13.55s",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn end_phase_adds_up_repeated_phases() {
+        let timings = Timings::new(true);
+        timings.end_phase(Phase::Scan);
+        timings.end_phase(Phase::Render);
+        timings.end_phase(Phase::Scan);
+
+        let phase_timings = timings.phase_timings();
+        assert_eq!(
+            phase_timings
+                .iter()
+                .map(|phase_timing| phase_timing.phase.as_str())
+                .collect::<Vec<_>>(),
+            vec!["scan", "render"]
+        );
+    }
+
+    #[rstest]
+    fn end_phase_without_timings_records_nothing() {
+        let timings = Timings::new(false);
+        timings.end_phase(Phase::Resolve);
+
+        assert!(timings.phase_timings().is_empty());
+    }
+}
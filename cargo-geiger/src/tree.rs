@@ -1,7 +1,7 @@
 pub mod traversal;
 
 use crate::format::print_config::{Prefix, PrintConfig};
-use crate::format::Charset;
+use crate::format::{pad_to_display_width, Charset};
 
 use cargo::core::dependency::DepKind;
 use cargo::core::PackageId;
@@ -9,14 +9,35 @@ use cargo::core::PackageId;
 /// A step towards decoupling some parts of the table-tree printing from the
 /// dependency graph traversal.
 #[derive(Debug, PartialEq)]
+///
+/// The `depth` of a line is the tree level of its package, the root package is
+/// at depth 0. A group header is at the depth of the dependencies below it.
 pub enum TextTreeLine {
     /// A text line for a package
-    Package { id: PackageId, tree_vines: String },
+    Package {
+        id: PackageId,
+        depth: usize,
+        tree_vines: String,
+    },
     /// There are extra dependencies coming and we should print a group header,
     /// eg. "[build-dependencies]".
-    ExtraDepsGroup { kind: DepKind, tree_vines: String },
+    ExtraDepsGroup {
+        kind: DepKind,
+        depth: usize,
+        tree_vines: String,
+    },
+    /// A text line for a package that is already on the path from the root,
+    /// its dependencies are not walked again.
+    Cycle {
+        id: PackageId,
+        depth: usize,
+        tree_vines: String,
+    },
 }
 
+/// The number of terminal columns taken up by each tree level.
+pub const TREE_VINE_WIDTH: usize = 4;
+
 #[derive(Debug, PartialEq)]
 pub struct TreeSymbols {
     pub down: &'static str,
@@ -39,14 +60,17 @@ fn construct_tree_vines_string(
             {
                 for &continues in rest {
                     let c = if continues { tree_symbols.down } else { " " };
-                    buffer.push_str(&format!("{}   ", c));
+                    buffer.push_str(&pad_to_display_width(c, TREE_VINE_WIDTH));
                 }
                 let c = if last_continues {
                     tree_symbols.tee
                 } else {
                     tree_symbols.ell
                 };
-                buffer.push_str(&format!("{0}{1}{1} ", c, tree_symbols.right));
+                buffer.push_str(&pad_to_display_width(
+                    &format!("{0}{1}{1}", c, tree_symbols.right),
+                    TREE_VINE_WIDTH,
+                ));
             }
             buffer
         }
@@ -83,7 +107,8 @@ mod tree_tests {
     use crate::format::Charset;
 
     use cargo::core::shell::Verbosity;
-    use geiger::IncludeTests;
+    use cargo_geiger_serde::CountingStrategy;
+    use geiger::{IncludeTests, DEFAULT_MAX_FILE_SIZE};
     use petgraph::EdgeDirection;
     use rstest::*;
 
@@ -128,10 +153,19 @@ mod tree_tests {
             direction: EdgeDirection::Outgoing,
             prefix,
             format: pattern,
+            generated_markers: Vec::new(),
+            harness_cfgs: Vec::new(),
+            hyperlinks: false,
             charset: Charset::Ascii,
+            counting_strategy: CountingStrategy::Geiger,
             allow_partial_results: false,
             include_tests: IncludeTests::Yes,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            normalize_output: false,
             output_format: None,
+            progress_json: false,
+            sort: None,
+            watched_calls: Vec::new(),
         }
     }
 }
@@ -1,16 +1,58 @@
 mod dependency_kind;
 mod dependency_node;
 
-use crate::format::print_config::PrintConfig;
-use crate::graph::Graph;
+use crate::format::print_config::{PrintConfig, SortKey};
+use crate::graph::{package_depths, Graph, Node};
 use crate::tree::TextTreeLine;
 
 use super::construct_tree_vines_string;
 use dependency_kind::walk_dependency_kind;
 use dependency_node::walk_dependency_node;
 
+use cargo::core::dependency::DepKind;
 use cargo::core::PackageId;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+/// Pending work of the tree traversal. Dependency chains can be thousands of
+/// packages deep, so the traversal keeps an explicit stack of these instead
+/// of recursing once per tree level.
+pub enum WalkStep<'a> {
+    /// Emit the line for a package and queue its dependencies.
+    Node(&'a Node),
+    /// Emit the group header for a kind of dependencies and queue them.
+    DependencyKind(DepKind, Vec<&'a Node>),
+    /// Enter a tree level, `true` if more siblings follow on this level.
+    PushLevel(bool),
+    /// Leave the current tree level.
+    PopLevel,
+}
+
+/// The packages on the path from the root to the package being walked, used
+/// to detect dependency cycles. These are possible through dev-dependencies.
+#[derive(Default)]
+struct TraversalPath {
+    package_ids: Vec<PackageId>,
+    package_id_set: HashSet<PackageId>,
+}
+
+impl TraversalPath {
+    fn contains(&self, package_id: PackageId) -> bool {
+        self.package_id_set.contains(&package_id)
+    }
+
+    fn push(&mut self, package_id: PackageId) {
+        self.package_ids.push(package_id);
+        self.package_id_set.insert(package_id);
+    }
+
+    fn truncate(&mut self, len: usize) {
+        while self.package_ids.len() > len {
+            if let Some(package_id) = self.package_ids.pop() {
+                self.package_id_set.remove(&package_id);
+            }
+        }
+    }
+}
 
 /// Printing the returned TextTreeLines in order is expected to produce a nice
 /// looking tree structure.
@@ -25,12 +67,372 @@ pub fn walk_dependency_tree(
 ) -> Vec<TextTreeLine> {
     let mut visited_deps = HashSet::new();
     let mut levels_continue = vec![];
+    let mut text_tree_lines = vec![];
+    let mut traversal_path = TraversalPath::default();
     let node = &graph.graph[graph.nodes[&root_package_id]];
-    walk_dependency_node(
-        node,
-        graph,
-        &mut visited_deps,
-        &mut levels_continue,
-        print_config,
-    )
+    let mut walk_steps = vec![WalkStep::Node(node)];
+    while let Some(walk_step) = walk_steps.pop() {
+        match walk_step {
+            WalkStep::Node(node) => {
+                // Everything deeper on the path was left since the last
+                // package on this tree level was walked.
+                traversal_path.truncate(levels_continue.len());
+                if traversal_path.contains(node.id) {
+                    // Stop here even with `--all`, the walk wouldn't end.
+                    text_tree_lines.push(TextTreeLine::Cycle {
+                        id: node.id,
+                        depth: levels_continue.len(),
+                        tree_vines: construct_tree_vines_string(
+                            &mut levels_continue,
+                            print_config,
+                        ),
+                    });
+                    continue;
+                }
+                traversal_path.push(node.id);
+                walk_dependency_node(
+                    node,
+                    graph,
+                    &mut visited_deps,
+                    &mut levels_continue,
+                    print_config,
+                    &mut text_tree_lines,
+                    &mut walk_steps,
+                )
+            }
+            WalkStep::DependencyKind(dep_kind, deps) => walk_dependency_kind(
+                dep_kind,
+                deps,
+                &levels_continue,
+                print_config,
+                &mut text_tree_lines,
+                &mut walk_steps,
+            ),
+            WalkStep::PushLevel(continues) => levels_continue.push(continues),
+            WalkStep::PopLevel => {
+                levels_continue.pop();
+            }
+        }
+    }
+    match print_config.sort {
+        Some(SortKey::Depth) => sort_by_depth(
+            text_tree_lines,
+            &package_depths(graph, root_package_id, print_config.direction),
+            print_config,
+        ),
+        None => text_tree_lines,
+    }
+}
+
+/// The packages of the flat output each on one line, at the minimum depth
+/// they are found at in the graph. Packages at the same depth are ordered by
+/// package id, like the siblings in the tree. Group headers and cycles have
+/// no place in a list without the tree and are left out.
+fn sort_by_depth(
+    text_tree_lines: Vec<TextTreeLine>,
+    depths: &HashMap<PackageId, u64>,
+    print_config: &PrintConfig,
+) -> Vec<TextTreeLine> {
+    let mut package_ids = text_tree_lines
+        .into_iter()
+        .filter_map(|text_tree_line| match text_tree_line {
+            TextTreeLine::Package { id, .. } => Some(id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    package_ids.sort_by_key(|id| (depths[id], *id));
+    package_ids.dedup();
+    package_ids
+        .into_iter()
+        .map(|id| {
+            let depth = depths[&id] as usize;
+            TextTreeLine::Package {
+                id,
+                depth,
+                tree_vines: construct_tree_vines_string(
+                    &mut vec![false; depth],
+                    print_config,
+                ),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod traversal_tests {
+    use super::*;
+
+    use crate::format::pattern::Pattern;
+    use crate::format::print_config::Prefix;
+    use crate::format::Charset;
+
+    use cargo::core::{SourceId, Verbosity};
+    use cargo_geiger_serde::CountingStrategy;
+    use geiger::{IncludeTests, DEFAULT_MAX_FILE_SIZE};
+    use petgraph::EdgeDirection;
+    use rstest::*;
+    use std::env;
+
+    #[rstest]
+    fn walk_dependency_tree_test() {
+        let package_ids = create_package_id_vec(3);
+        let graph = create_graph(
+            &package_ids,
+            &[
+                (0, 1, DepKind::Normal),
+                (0, 2, DepKind::Normal),
+                (1, 2, DepKind::Normal),
+                (2, 0, DepKind::Development),
+            ],
+        );
+
+        let text_tree_lines = walk_dependency_tree(
+            package_ids[0],
+            &graph,
+            &create_print_config(Prefix::Indent),
+        );
+
+        assert_eq!(
+            text_tree_lines,
+            vec![
+                TextTreeLine::Package {
+                    id: package_ids[0],
+                    depth: 0,
+                    tree_vines: String::from(""),
+                },
+                TextTreeLine::Package {
+                    id: package_ids[1],
+                    depth: 1,
+                    tree_vines: String::from("|-- "),
+                },
+                TextTreeLine::Package {
+                    id: package_ids[2],
+                    depth: 2,
+                    tree_vines: String::from("|   `-- "),
+                },
+                TextTreeLine::ExtraDepsGroup {
+                    kind: DepKind::Development,
+                    depth: 3,
+                    tree_vines: String::from("|       "),
+                },
+                TextTreeLine::Cycle {
+                    id: package_ids[0],
+                    depth: 3,
+                    tree_vines: String::from("|       `-- "),
+                },
+                TextTreeLine::Package {
+                    id: package_ids[2],
+                    depth: 1,
+                    tree_vines: String::from("`-- "),
+                },
+            ]
+        );
+    }
+
+    #[rstest(input_all, case(false), case(true))]
+    fn walk_dependency_tree_cycle_test(input_all: bool) {
+        let package_ids = create_package_id_vec(2);
+        let graph = create_graph(
+            &package_ids,
+            &[(0, 1, DepKind::Development), (1, 0, DepKind::Normal)],
+        );
+        let mut print_config = create_print_config(Prefix::Depth);
+        print_config.all = input_all;
+
+        let text_tree_lines =
+            walk_dependency_tree(package_ids[0], &graph, &print_config);
+
+        assert_eq!(
+            text_tree_lines,
+            vec![
+                TextTreeLine::Package {
+                    id: package_ids[0],
+                    depth: 0,
+                    tree_vines: String::from("0 "),
+                },
+                TextTreeLine::Package {
+                    id: package_ids[1],
+                    depth: 1,
+                    tree_vines: String::from("1 "),
+                },
+                TextTreeLine::Cycle {
+                    id: package_ids[0],
+                    depth: 2,
+                    tree_vines: String::from("2 "),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn walk_dependency_tree_deep_chain_test() {
+        let depth = 10_000;
+        let package_ids = create_package_id_vec(depth);
+        let edges = (1..depth)
+            .map(|i| (i - 1, i, DepKind::Normal))
+            .collect::<Vec<_>>();
+        let graph = create_graph(&package_ids, &edges);
+
+        let text_tree_lines = walk_dependency_tree(
+            package_ids[0],
+            &graph,
+            &create_print_config(Prefix::Depth),
+        );
+
+        assert_eq!(text_tree_lines.len(), depth);
+        assert_eq!(
+            text_tree_lines.last(),
+            Some(&TextTreeLine::Package {
+                id: package_ids[depth - 1],
+                depth: depth - 1,
+                tree_vines: format!("{} ", depth - 1),
+            })
+        );
+    }
+
+    #[rstest(
+        input_prefix,
+        input_direction,
+        expected_lines,
+        case(
+            Prefix::Depth,
+            EdgeDirection::Outgoing,
+            vec![
+                (0, 0, "0 "),
+                (1, 1, "1 "),
+                (4, 1, "1 "),
+                (2, 2, "2 "),
+                (3, 2, "2 ")
+            ]
+        ),
+        case(
+            Prefix::None,
+            EdgeDirection::Outgoing,
+            vec![(0, 0, ""), (1, 1, ""), (4, 1, ""), (2, 2, ""), (3, 2, "")]
+        ),
+        case(
+            Prefix::Depth,
+            EdgeDirection::Incoming,
+            vec![
+                (3, 0, "0 "),
+                (2, 1, "1 "),
+                (4, 1, "1 "),
+                (0, 2, "2 "),
+                (1, 2, "2 ")
+            ]
+        )
+    )]
+    fn walk_dependency_tree_sort_by_depth_test(
+        input_prefix: Prefix,
+        input_direction: EdgeDirection,
+        expected_lines: Vec<(usize, usize, &str)>,
+    ) {
+        // A diamond with a longer left side, 0 -> 1 -> 2 -> 3 and 0 -> 4 -> 3,
+        // and a build dependency of 2 on 4.
+        let package_ids = create_package_id_vec(5);
+        let graph = create_graph(
+            &package_ids,
+            &[
+                (0, 1, DepKind::Normal),
+                (1, 2, DepKind::Normal),
+                (2, 3, DepKind::Normal),
+                (2, 4, DepKind::Build),
+                (0, 4, DepKind::Normal),
+                (4, 3, DepKind::Normal),
+            ],
+        );
+        let mut print_config = create_print_config(input_prefix);
+        print_config.all = true;
+        print_config.direction = input_direction;
+        print_config.sort = Some(SortKey::Depth);
+        let root_index = match input_direction {
+            EdgeDirection::Outgoing => 0,
+            EdgeDirection::Incoming => 3,
+        };
+
+        let text_tree_lines = walk_dependency_tree(
+            package_ids[root_index],
+            &graph,
+            &print_config,
+        );
+
+        assert_eq!(
+            text_tree_lines,
+            expected_lines
+                .into_iter()
+                .map(|(index, depth, tree_vines)| TextTreeLine::Package {
+                    id: package_ids[index],
+                    depth,
+                    tree_vines: String::from(tree_vines),
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn create_graph(
+        package_ids: &[PackageId],
+        directed_edges: &[(usize, usize, DepKind)],
+    ) -> Graph {
+        let mut inner_graph = petgraph::Graph::<Node, DepKind>::new();
+        let mut nodes = HashMap::new();
+        for package_id in package_ids {
+            nodes.insert(
+                *package_id,
+                inner_graph.add_node(Node { id: *package_id }),
+            );
+        }
+        for (source_index, target_index, dep_kind) in directed_edges {
+            inner_graph.add_edge(
+                nodes[&package_ids[*source_index]],
+                nodes[&package_ids[*target_index]],
+                *dep_kind,
+            );
+        }
+        Graph {
+            graph: inner_graph,
+            nodes,
+            platforms: HashMap::new(),
+            target: String::new(),
+            members: HashSet::new(),
+            dev_only: HashSet::new(),
+        }
+    }
+
+    fn create_package_id_vec(count: usize) -> Vec<PackageId> {
+        let source_id =
+            SourceId::for_path(&env::current_dir().unwrap()).unwrap();
+        (0..count)
+            .map(|i| {
+                PackageId::new(
+                    format!("test_name_{:05}", i),
+                    "1.0.0",
+                    source_id,
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn create_print_config(prefix: Prefix) -> PrintConfig {
+        PrintConfig {
+            all: false,
+            allow_partial_results: false,
+            charset: Charset::Ascii,
+            counting_strategy: CountingStrategy::Geiger,
+            direction: EdgeDirection::Outgoing,
+            format: Pattern(vec![]),
+            generated_markers: Vec::new(),
+            harness_cfgs: Vec::new(),
+            hyperlinks: false,
+            include_tests: IncludeTests::Yes,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            normalize_output: false,
+            prefix,
+            output_format: None,
+            progress_json: false,
+            sort: None,
+            watched_calls: Vec::new(),
+            verbosity: Verbosity::Normal,
+        }
+    }
 }
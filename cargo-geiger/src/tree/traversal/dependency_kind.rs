@@ -1,74 +1,47 @@
+use crate::format::pad_to_display_width;
 use crate::format::print_config::{Prefix, PrintConfig};
-use crate::graph::{Graph, Node};
-use crate::tree::{get_tree_symbols, TextTreeLine, TreeSymbols};
+use crate::graph::Node;
+use crate::tree::{
+    get_tree_symbols, TextTreeLine, TreeSymbols, TREE_VINE_WIDTH,
+};
 
-use super::dependency_node::walk_dependency_node;
+use super::WalkStep;
 
 use cargo::core::dependency::DepKind;
-use cargo::core::PackageId;
-use std::collections::HashSet;
-use std::iter::Peekable;
-use std::slice::Iter;
 
-pub fn walk_dependency_kind(
+pub fn walk_dependency_kind<'a>(
     dep_kind: DepKind,
-    deps: &mut Vec<&Node>,
-    graph: &Graph,
-    visited_deps: &mut HashSet<PackageId>,
-    levels_continue: &mut Vec<bool>,
+    mut deps: Vec<&'a Node>,
+    levels_continue: &[bool],
     print_config: &PrintConfig,
-) -> Vec<TextTreeLine> {
+    text_tree_lines: &mut Vec<TextTreeLine>,
+    walk_steps: &mut Vec<WalkStep<'a>>,
+) {
     if deps.is_empty() {
-        return Vec::new();
+        return;
     }
 
     // Resolve uses Hash data types internally but we want consistent output ordering
     deps.sort_by_key(|n| n.id);
 
     let tree_symbols = get_tree_symbols(print_config.charset);
-    let mut text_tree_lines = Vec::new();
     if let Prefix::Indent = print_config.prefix {
         push_extra_deps_group_text_tree_line_for_non_normal_dependencies(
             dep_kind,
             levels_continue,
             &tree_symbols,
-            &mut text_tree_lines,
+            text_tree_lines,
         )
     }
 
-    let mut node_iterator = deps.iter().peekable();
-    while let Some(dependency) = node_iterator.next() {
-        handle_walk_dependency_node(
-            dependency,
-            graph,
-            levels_continue,
-            &mut node_iterator,
-            print_config,
-            &mut text_tree_lines,
-            visited_deps,
-        );
+    // Pushed in reverse, so that the first dependency is walked first. Each
+    // dependency is walked one tree level deeper.
+    let dependency_count = deps.len();
+    for (index, dependency) in deps.into_iter().enumerate().rev() {
+        walk_steps.push(WalkStep::PopLevel);
+        walk_steps.push(WalkStep::Node(dependency));
+        walk_steps.push(WalkStep::PushLevel(index + 1 < dependency_count));
     }
-    text_tree_lines
-}
-
-fn handle_walk_dependency_node(
-    dependency: &Node,
-    graph: &Graph,
-    levels_continue: &mut Vec<bool>,
-    node_iterator: &mut Peekable<Iter<&Node>>,
-    print_config: &PrintConfig,
-    text_tree_lines: &mut Vec<TextTreeLine>,
-    visited_deps: &mut HashSet<PackageId>,
-) {
-    levels_continue.push(node_iterator.peek().is_some());
-    text_tree_lines.append(&mut walk_dependency_node(
-        dependency,
-        graph,
-        visited_deps,
-        levels_continue,
-        print_config,
-    ));
-    levels_continue.pop();
 }
 
 fn push_extra_deps_group_text_tree_line_for_non_normal_dependencies(
@@ -83,10 +56,11 @@ fn push_extra_deps_group_text_tree_line_for_non_normal_dependencies(
             let mut tree_vines = String::new();
             for &continues in &*levels_continue {
                 let c = if continues { tree_symbols.down } else { " " };
-                tree_vines.push_str(&format!("{}   ", c))
+                tree_vines.push_str(&pad_to_display_width(c, TREE_VINE_WIDTH))
             }
             text_tree_lines.push(TextTreeLine::ExtraDepsGroup {
                 kind: dep_kind,
+                depth: levels_continue.len() + 1,
                 tree_vines,
             });
         }
@@ -112,6 +86,7 @@ mod traversal_tests {
             vec![
                 ExtraDepsGroup {
                     kind: DepKind::Build,
+                    depth: 1,
                     tree_vines: String::from("")
                 }
             ]
@@ -122,6 +97,7 @@ mod traversal_tests {
             vec![
                 ExtraDepsGroup {
                     kind: DepKind::Build,
+                    depth: 3,
                     tree_vines: format!(
                     "    {}   ",
                     get_tree_symbols(Charset::Utf8).down
@@ -135,6 +111,7 @@ mod traversal_tests {
             vec![
                 ExtraDepsGroup {
                     kind: DepKind::Development,
+                    depth: 2,
                     tree_vines: format!(
                     "{}   ",
                     get_tree_symbols(Charset::Utf8).down
@@ -148,6 +125,7 @@ mod traversal_tests {
             vec![
                 ExtraDepsGroup {
                     kind: DepKind::Development,
+                    depth: 2,
                     tree_vines: String::from("    ")
                 }
             ]
@@ -3,7 +3,7 @@ use crate::graph::{Graph, Node};
 use crate::tree::TextTreeLine;
 
 use super::construct_tree_vines_string;
-use super::walk_dependency_kind;
+use super::WalkStep;
 
 use cargo::core::dependency::DepKind;
 use cargo::core::PackageId;
@@ -11,42 +11,38 @@ use petgraph::visit::EdgeRef;
 use petgraph::EdgeDirection;
 use std::collections::{HashMap, HashSet};
 
-pub fn walk_dependency_node(
+pub fn walk_dependency_node<'a>(
     package: &Node,
-    graph: &Graph,
+    graph: &'a Graph,
     visited_deps: &mut HashSet<PackageId>,
     levels_continue: &mut Vec<bool>,
     print_config: &PrintConfig,
-) -> Vec<TextTreeLine> {
+    text_tree_lines: &mut Vec<TextTreeLine>,
+    walk_steps: &mut Vec<WalkStep<'a>>,
+) {
     let new = print_config.all || visited_deps.insert(package.id);
     let tree_vines = construct_tree_vines_string(levels_continue, print_config);
 
-    let mut all_out_text_tree_lines = vec![TextTreeLine::Package {
+    text_tree_lines.push(TextTreeLine::Package {
         id: package.id,
+        depth: levels_continue.len(),
         tree_vines,
-    }];
+    });
 
     if !new {
-        return all_out_text_tree_lines;
+        return;
     }
 
-    let mut dependency_type_nodes =
+    let dependency_type_nodes =
         construct_dependency_type_nodes_hashmap(graph, package, print_config);
 
-    for (dep_kind, nodes) in dependency_type_nodes.iter_mut() {
-        let mut dep_kind_out = walk_dependency_kind(
-            *dep_kind,
-            nodes,
-            graph,
-            visited_deps,
-            levels_continue,
-            print_config,
-        );
-
-        all_out_text_tree_lines.append(&mut dep_kind_out);
-    }
-
-    all_out_text_tree_lines
+    // The steps are popped in reverse order, push them reversed to walk the
+    // dependency kinds in iteration order.
+    let dependency_kind_steps = dependency_type_nodes
+        .into_iter()
+        .map(|(dep_kind, nodes)| WalkStep::DependencyKind(dep_kind, nodes))
+        .collect::<Vec<_>>();
+    walk_steps.extend(dependency_kind_steps.into_iter().rev());
 }
 
 fn construct_dependency_type_nodes_hashmap<'a>(
@@ -92,7 +88,8 @@ mod dependency_node_tests {
 
     use cargo::core::Verbosity;
     use cargo::Config;
-    use geiger::IncludeTests;
+    use cargo_geiger_serde::CountingStrategy;
+    use geiger::{IncludeTests, DEFAULT_MAX_FILE_SIZE};
     use petgraph::graph::NodeIndex;
     use rstest::*;
     use std::env;
@@ -162,6 +159,10 @@ mod dependency_node_tests {
         let graph = Graph {
             graph: inner_graph,
             nodes,
+            platforms: HashMap::new(),
+            target: String::new(),
+            members: HashSet::new(),
+            dev_only: HashSet::new(),
         };
 
         let dependency_type_nodes_hashmap =
@@ -229,11 +230,20 @@ mod dependency_node_tests {
             all: false,
             allow_partial_results: false,
             charset: Charset::Ascii,
+            counting_strategy: CountingStrategy::Geiger,
             direction: edge_direction,
             format: Pattern(vec![]),
+            generated_markers: Vec::new(),
+            harness_cfgs: Vec::new(),
+            hyperlinks: false,
             include_tests: IncludeTests::Yes,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            normalize_output: false,
             prefix: Prefix::Depth,
             output_format: None,
+            progress_json: false,
+            sort: None,
+            watched_calls: Vec::new(),
             verbosity: Verbosity::Verbose,
         }
     }
@@ -3,17 +3,22 @@
 
 use assert_cmd::prelude::*;
 use cargo_geiger_serde::{
-    Count, CounterBlock, PackageId, PackageInfo, QuickReportEntry,
-    QuickSafetyReport, ReportEntry, SafetyReport, Source, UnsafeInfo,
+    ApproximateUnsafety, Count, CounterBlock, DependencyKind,
+    MergedSafetyReport, ModuleUnsafety, NativeLinkage, OversizedFile,
+    PackageId, PackageInfo, PackageReport, QuickReportEntry, QuickSafetyReport,
+    ReportEntry, ReportIndex, SafetyReport, ScanStatus, ScanWarning, Source,
+    TargetKind, TreeLine, UnsafeInfo, WatchedCallCount,
 };
 use insta::assert_snapshot;
 use rstest::rstest;
 use semver::Version;
+use serde_json::Value;
 use tempfile::TempDir;
 use url::Url;
 
 use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fs;
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
@@ -26,7 +31,9 @@ use std::process::{Command, Output};
     case("test4_workspace_with_top_level_package"),
     case("test5_workspace_with_virtual_manifest"),
     case("test6_cargo_lock_out_of_date"),
-    case("test7_package_with_patched_dep")
+    case("test7_package_with_patched_dep"),
+    case("test11_package_with_ffi_bindings"),
+    case("test12_package_with_unsafe_bin")
 )]
 fn test_package(name: &str) {
     better_panic::install();
@@ -53,34 +60,2164 @@ fn test_package(name: &str) {
     }
 }
 
+#[test]
+fn test_package_ignoring_ffi() {
+    let name = "test11_package_with_ffi_bindings";
+    let (output, _cx) = run_geiger_with(name, &["--ignore-ffi"]);
+    assert!(output.status.success(), "`cargo-geiger` failed");
+
+    let stdout_filename = format!("{}_ignore_ffi.stdout", name);
+    let stdout = String::from_utf8(output.stdout)
+        .expect("output should have been valid utf-8");
+    assert_snapshot!(stdout_filename, stdout);
+}
+
+#[test]
+fn test_package_with_all_target_kinds() {
+    let name = "test12_package_with_unsafe_bin";
+    let (output, _cx) = run_geiger_with(name, &["--all-target-kinds"]);
+    assert!(output.status.success(), "`cargo-geiger` failed");
+
+    let stdout_filename = format!("{}_all_target_kinds.stdout", name);
+    let stdout = String::from_utf8(output.stdout)
+        .expect("output should have been valid utf-8");
+    assert_snapshot!(stdout_filename, stdout);
+}
+
+#[rstest(position, case("left"), case("right"), case("none"))]
+fn test_package_with_marker_position(position: &str) {
+    let name = "test2_package_with_shallow_deps";
+    let (output, _cx) = run_geiger_with(name, &["--marker-position", position]);
+    assert!(output.status.success(), "`cargo-geiger` failed");
+
+    let stdout_filename = format!("{}_marker_{}.stdout", name, position);
+    let stdout = String::from_utf8(output.stdout)
+        .expect("output should have been valid utf-8");
+    assert_snapshot!(stdout_filename, stdout);
+}
+
+#[test]
+fn report_splits_unsafe_by_target_kind() {
+    let (output, _cx) = run_geiger_json("test12_package_with_unsafe_bin");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = &report.packages.values().next().unwrap().unsafety;
+    // Unlike the table, the used counts of the report cover all targets.
+    assert!(unsafety.used.has_unsafe());
+    assert!(!unsafety.targets[&TargetKind::Lib].has_unsafe());
+    assert!(unsafety.targets[&TargetKind::Bin].has_unsafe());
+}
+
 #[test]
 fn serialize_test1_report() {
     Test1.run();
 }
 
 #[test]
-fn serialize_test2_report() {
-    Test2.run();
+fn serialize_test2_report() {
+    Test2.run();
+}
+
+#[test]
+fn serialize_test3_report() {
+    Test3.run();
+}
+
+#[test]
+fn serialize_test4_report() {
+    Test4.run();
+}
+
+#[test]
+fn serialize_test6_report() {
+    Test6.run();
+}
+
+#[test]
+fn serialize_test7_report() {
+    Test7.run();
+}
+
+#[test]
+fn serialize_test8_report() {
+    Test8.run();
+}
+
+#[test]
+fn serialize_test3_report_with_redirected_target_dir() {
+    let cx = Context::new();
+    let target_dir = TempDir::new().unwrap();
+    let output = geiger_command(&cx, Test3::NAME)
+        .arg("--json")
+        .env("CARGO_TARGET_DIR", target_dir.path())
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let actual =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    assert_eq!(
+        without_breakdowns(without_metadata(actual)),
+        Test3.expected_report(&cx)
+    );
+}
+
+#[test]
+fn check_lock_of_test3_report() {
+    let (output, cx) = run_geiger_json(Test3::NAME);
+    assert!(output.status.success());
+    let report_path = cx.crate_dir(Test3::NAME).join("geiger-report.json");
+    fs::write(&report_path, &output.stdout).unwrap();
+    let output = geiger_command(&cx, Test3::NAME)
+        .arg("--check-lock")
+        .arg(&report_path)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn check_lock_of_report_without_metadata() {
+    let cx = Context::new();
+    let report_path = cx.crate_dir(Test1::NAME).join("geiger-report.json");
+    fs::write(
+        &report_path,
+        serde_json::to_string(&SafetyReport::default()).unwrap(),
+    )
+    .unwrap();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--check-lock")
+        .arg(&report_path)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "+ test1_package_with_no_deps 0.1.0 path -\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(
+        "the report was made from a different Cargo.lock, 1 package line \
+         changed"
+    ));
+}
+
+#[test]
+fn reuse_test3_report() {
+    let (output, cx) = run_geiger_json(Test3::NAME);
+    assert!(output.status.success());
+    let expected =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let report_path = cx.crate_dir(Test3::NAME).join("geiger-report.json");
+    fs::write(&report_path, &output.stdout).unwrap();
+    let output = geiger_command(&cx, Test3::NAME)
+        .arg("--json")
+        .arg("--reuse-report")
+        .arg(&report_path)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let actual =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    assert!(!actual.reused_packages.is_empty());
+    assert!(actual
+        .reused_packages
+        .iter()
+        .all(|id| !matches!(id.source, Source::Path(_))));
+    let actual = SafetyReport {
+        reused_packages: Default::default(),
+        ..actual
+    };
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn invalid_option_values_are_reported_without_panicking() {
+    let dir = TempDir::new().unwrap();
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .arg("geiger")
+        .arg("--resolver")
+        .arg("x")
+        .current_dir(dir.path())
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid resolver"));
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn report_matches_the_printed_schema() {
+    // No workspace is needed to print the schema.
+    let schema_dir = TempDir::new().unwrap();
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .arg("geiger")
+        .arg("--print-schema")
+        .current_dir(schema_dir.path())
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let schema = serde_json::from_slice::<Value>(&output.stdout).unwrap();
+
+    let (output, _cx) = run_geiger_with(
+        Test3::NAME,
+        &[
+            "--json",
+            "--report-modules",
+            "--report-file-list",
+            "--show-dep-counts",
+        ],
+    );
+    assert!(output.status.success());
+    let report = serde_json::from_slice::<Value>(&output.stdout).unwrap();
+    assert_eq!(
+        schema["schema_version"],
+        report["metadata"]["schema_version"]
+    );
+    let errors = schema_errors(&report, &schema, &schema, "report");
+    assert!(errors.is_empty(), "{:#?}", errors);
+}
+
+#[test]
+fn report_to_file_with_table() {
+    let cx = Context::new();
+    let report_path = cx.crate_dir(Test2::NAME).join("geiger-report.json");
+    let output = geiger_command(&cx, Test2::NAME)
+        .arg("--json")
+        .arg("--output")
+        .arg(&report_path)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    // The same table and report as separate runs.
+    let stdout = String::from_utf8(output.stdout)
+        .expect("output should have been valid utf-8");
+    assert_snapshot!(format!("{}.stdout", Test2::NAME), stdout);
+    let report = fs::read(&report_path).unwrap();
+    let report = serde_json::from_slice::<SafetyReport>(&report).unwrap();
+    assert_eq!(
+        without_breakdowns(without_metadata(report)),
+        Test2.expected_report(&cx)
+    );
+}
+
+#[test]
+fn report_split_by_package_to_output_dir() {
+    let cx = Context::new();
+    let output_dir = cx.crate_dir(Test2::NAME).join("geiger-reports");
+    fs::create_dir(&output_dir).unwrap();
+    let stale_path = output_dir.join("removed-1.0.0.json");
+    fs::write(&stale_path, "{}").unwrap();
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, Test2::NAME)
+            .arg("--json")
+            .arg("--output-dir")
+            .arg(&output_dir)
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+    };
+
+    run(&[]);
+    let index = fs::read(output_dir.join("index.json")).unwrap();
+    let index = serde_json::from_slice::<ReportIndex>(&index).unwrap();
+    assert_eq!(
+        index
+            .packages
+            .iter()
+            .map(|index_entry| index_entry.file.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "ref_slice-1.1.1.json",
+            "test1_package_with_no_deps-0.1.0.json",
+            "test2_package_with_shallow_deps-0.1.0.json",
+        ]
+    );
+    for index_entry in &index.packages {
+        let package_report =
+            fs::read(output_dir.join(&index_entry.file)).unwrap();
+        let package_report =
+            serde_json::from_slice::<PackageReport>(&package_report).unwrap();
+        assert_eq!(package_report.entry.package.id, index_entry.id);
+        assert_eq!(package_report.entry.unsafety.used, index_entry.used);
+    }
+    // Files of other packages are only removed with `--prune-output`.
+    assert!(stale_path.exists());
+    run(&["--prune-output"]);
+    assert!(!stale_path.exists());
+    assert!(output_dir.join("ref_slice-1.1.1.json").exists());
+}
+
+#[test]
+fn explain_test1() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--explain")
+        .arg(Test1::NAME)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("    src/lib.rs: unsafe_code allowed\n"));
+    assert!(stdout.contains(
+        "1/1        2/2          0/0    0/0     0/0      0/0 used    src/lib.rs\n"
+    ));
+}
+
+#[test]
+fn explain_with_unmatched_spec() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--explain")
+        .arg("not_a_dependency")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn scan_published_crate() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--crate")
+        .arg("ref_slice@1.1.1")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\nref_slice v1.1.1\n"));
+    assert!(stdout.contains("Forbids unsafe code: no\n"));
+    assert!(stdout.contains(
+        "?/0        ?/2          ?/0    ?/0     ?/0      ?/0 Total\n"
+    ));
+}
+
+#[test]
+fn scan_published_crate_with_output_format() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--crate")
+        .arg("ref_slice@1.1.1")
+        .arg("--json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn verbose_output_lists_unsafe_by_module() {
+    let cx = Context::new();
+    // `geiger_command` passes `--quiet`, which can't be combined with
+    // `--verbose`.
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .arg("geiger")
+        .arg("--color=never")
+        .arg("--charset=ascii")
+        .arg("--verbose")
+        .current_dir(cx.crate_dir(Test1::NAME))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Unsafe usage by module (top 5 per package):\n"));
+    assert!(stdout.contains("\n    3      src/lib.rs\n"));
+}
+
+#[test]
+fn no_legend_leaves_out_the_legend_below_the_header() {
+    let (output, _cx) = run_geiger_with(Test1::NAME, &["--no-legend"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Functions  Expressions"));
+    assert!(!stdout.contains("Legend: "));
+    assert!(stdout.contains("Metric output format: x/y\n"));
+    assert!(stdout.contains("Symbols: \n"));
+}
+
+#[test]
+fn report_modules() {
+    let cx = Context::new();
+    let report_with = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, Test1::NAME)
+            .arg("--json")
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        let report =
+            serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+        report
+            .packages
+            .into_iter()
+            .next()
+            .unwrap()
+            .1
+            .unsafety
+            .modules
+    };
+
+    assert!(report_with(&[]).is_empty());
+    assert_eq!(
+        report_with(&["--report-modules"]),
+        vec![ModuleUnsafety {
+            module: String::from("src/lib.rs"),
+            unsafe_: 3,
+        }]
+    );
+}
+
+#[test]
+fn report_file_list() {
+    let file_list_with = |extra_args: &[&str]| {
+        let mut args = vec!["--json"];
+        args.extend(extra_args);
+        let (output, _cx) = run_geiger_with(INCONSISTENT_FORBID, &args);
+        assert!(output.status.success());
+        let report =
+            serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+        report
+            .packages
+            .into_iter()
+            .next()
+            .unwrap()
+            .1
+            .files
+            .into_iter()
+            .map(|file| (file.path, file.used))
+            .collect::<Vec<_>>()
+    };
+
+    assert!(file_list_with(&[]).is_empty());
+    assert_eq!(
+        file_list_with(&["--report-file-list"]),
+        vec![
+            (String::from("build.rs"), true),
+            (String::from("src/lib.rs"), true)
+        ]
+    );
+}
+
+const FEATURE_GATED_UNSAFE: &str = "test13_package_with_feature_gated_unsafe";
+
+#[test]
+fn report_unsafe_by_feature() {
+    let (output, _cx) = run_geiger_json(FEATURE_GATED_UNSAFE);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let by_feature =
+        &report.packages.values().next().unwrap().unsafety.by_feature;
+    assert_eq!(
+        by_feature.iter().collect::<Vec<_>>(),
+        vec![
+            (&String::from("(unconditional)"), &1),
+            (&String::from("avx"), &1),
+            (&String::from("simd"), &2),
+        ]
+    );
+}
+
+const DEPENDENCY_FEATURE: &str = "test20_package_with_dependency_feature";
+
+#[test]
+fn features_of_dependencies_are_activated() {
+    let cx = Context::new();
+    let gated_used = |extra_args: &[&str]| {
+        let output = Command::cargo_bin("cargo-geiger")
+            .unwrap()
+            .arg("geiger")
+            .arg("--quiet")
+            .arg("--json")
+            .args(extra_args)
+            .current_dir(cx.crate_dir(DEPENDENCY_FEATURE))
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        let report =
+            serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == "gated")
+            .unwrap()
+            .unsafety
+            .used
+            .clone()
+    };
+
+    // The unsafe module of the dependency is only built with its feature.
+    assert!(!gated_used(&[]).has_unsafe());
+    assert!(gated_used(&["--features", "gated/raw"]).has_unsafe());
+    assert!(gated_used(&["--features", "extra,gated/raw"]).has_unsafe());
+}
+
+#[test]
+fn verbose_output_lists_unsafe_by_feature() {
+    let cx = Context::new();
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .arg("geiger")
+        .arg("--color=never")
+        .arg("--charset=ascii")
+        .arg("--verbose")
+        .current_dir(cx.crate_dir(FEATURE_GATED_UNSAFE))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Unsafe usage by feature:\n"));
+    assert!(stdout.contains("\n    2      simd\n"));
+}
+
+#[test]
+fn report_unsafe_in_public_api() {
+    let (output, cx) = run_geiger_json(Test1::NAME);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let public_api =
+        &report.packages.values().next().unwrap().unsafety.public_api;
+    // `pub unsafe fn f` and `pub fn g`.
+    assert_eq!(
+        public_api.used.functions,
+        Count {
+            safe: 1,
+            unsafe_: 1
+        }
+    );
+
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .arg("geiger")
+        .arg("--color=never")
+        .arg("--charset=ascii")
+        .arg("--verbose")
+        .current_dir(cx.crate_dir(Test1::NAME))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Unsafe items, public API/internal:\n"));
+    assert!(stdout.contains("\n    functions  1/0\n"));
+}
+
+const CONST_UNSAFE: &str = "test25_package_with_const_unsafe";
+
+#[test]
+fn report_const_unsafe_separately() {
+    let (output, _cx) = run_geiger_json(CONST_UNSAFE);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = &report.packages.values().next().unwrap().unsafety;
+    // The static initializer and the const fn, not the runtime function.
+    assert_eq!(unsafety.used.const_unsafe.unsafe_, 2);
+    assert_eq!(unsafety.used.exprs.unsafe_, 3);
+}
+
+const TRANSMUTES: &str = "test17_package_with_transmutes";
+
+#[test]
+fn report_transmutes() {
+    let (output, cx) = run_geiger_json(TRANSMUTES);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = &report.packages.values().next().unwrap().unsafety;
+    // Qualified with `std::mem` and `core::mem`, and imported.
+    assert_eq!(unsafety.used.transmutes.unsafe_, 3);
+    assert_eq!(unsafety.unused.transmutes.unsafe_, 1);
+
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .arg("geiger")
+        .arg("--color=never")
+        .arg("--charset=ascii")
+        .arg("--verbose")
+        .current_dir(cx.crate_dir(TRANSMUTES))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(
+        "\n    src/lib.rs:4\n    src/lib.rs:8\n    src/lib.rs:12\n    \
+         src/unused.rs:4 (not used by the build)\n"
+    ));
+}
+
+const WATCHED_CALLS: &str = "test18_package_with_watched_calls";
+const WATCHED_CALL_PATTERNS: &str =
+    "slice::get_unchecked,ptr::copy_nonoverlapping,MaybeUninit::assume_init";
+
+#[test]
+fn report_watched_calls() {
+    let (output, cx) = run_geiger_with(
+        WATCHED_CALLS,
+        &["--json", "--watch-calls", WATCHED_CALL_PATTERNS],
+    );
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = &report.packages.values().next().unwrap().unsafety;
+    let count = |unsafe_| Count { safe: 0, unsafe_ };
+    // A method call and a type-qualified call.
+    assert_eq!(
+        unsafety.watched_calls["slice::get_unchecked"],
+        WatchedCallCount {
+            used: count(2),
+            unused: count(0),
+        }
+    );
+    assert_eq!(
+        unsafety.watched_calls["ptr::copy_nonoverlapping"].used,
+        count(1)
+    );
+    assert_eq!(
+        unsafety.watched_calls["MaybeUninit::assume_init"].unused,
+        count(1)
+    );
+
+    let output = Command::cargo_bin("cargo-geiger")
+        .unwrap()
+        .arg("geiger")
+        .arg("--color=never")
+        .arg("--charset=ascii")
+        .arg("--verbose")
+        .arg("--watch-calls")
+        .arg(WATCHED_CALL_PATTERNS)
+        .current_dir(cx.crate_dir(WATCHED_CALLS))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Watched calls, used/unused by the build:"));
+    assert!(stdout.contains(
+        "\n    MaybeUninit::assume_init 0/1\n    \
+         ptr::copy_nonoverlapping 1/0\n    \
+         slice::get_unchecked     2/0\n"
+    ));
+}
+
+const UNIFIED_FEATURES: &str = "test15_workspace_with_unified_features";
+
+#[test]
+fn report_features_unified_across_the_graph() {
+    let cx = Context::new();
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, UNIFIED_FEATURES)
+            .current_dir(cx.workspace_crate_dir(UNIFIED_FEATURES, "app"))
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let report =
+        serde_json::from_str::<SafetyReport>(&run(&["--json"])).unwrap();
+    let entry = |name: &str| {
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .clone()
+    };
+    // Only parent_a asks for the feature, it is enabled for parent_b too.
+    let shared = entry("shared");
+    assert_eq!(shared.features, vec![String::from("unchecked")]);
+    assert!(shared.unsafety.used.has_unsafe());
+    assert!(entry("parent_b").features.is_empty());
+
+    let stdout = run(&["-vv"]);
+    assert!(stdout.contains("shared 0.1.0 (features: unchecked)"));
+    assert!(!stdout.contains("parent_b 0.1.0 (features"));
+}
+
+#[test]
+fn exclude_leaves_out_workspace_members() {
+    let (output, _cx) =
+        run_geiger_with(UNIFIED_FEATURES, &["--json", "--exclude", "app"]);
+    assert!(output.status.success());
+    let merged_report =
+        serde_json::from_slice::<MergedSafetyReport>(&output.stdout).unwrap();
+    // Each of the other members is scanned on its own, nothing pulls in app.
+    assert_eq!(merged_report.workspaces.len(), 3);
+    assert!(merged_report.workspaces.iter().all(|workspace| {
+        workspace
+            .report
+            .packages
+            .keys()
+            .all(|package_id| package_id.name != "app")
+    }));
+
+    let (output, _cx) =
+        run_geiger_with(UNIFIED_FEATURES, &["--exclude", "not-a-member"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(
+        "`--exclude` doesn't match any workspace member: `not-a-member`"
+    ));
+}
+
+#[test]
+fn paths_are_relative_to_the_workspace_root_from_a_subdirectory() {
+    let cx = Context::new();
+    let workspace_dir = cx.crate_dir(UNIFIED_FEATURES);
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, UNIFIED_FEATURES)
+            .current_dir(
+                cx.workspace_crate_dir(UNIFIED_FEATURES, "app").join("src"),
+            )
+            .arg("--verbose")
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // The member of the directory is found from below its manifest, and a
+    // relative `--manifest-path` is taken from the current directory.
+    for extra_args in &[&[][..], &["--manifest-path", "../Cargo.toml"][..]] {
+        let used_by_build_lines = run(extra_args)
+            .lines()
+            .filter(|line| line.starts_with("Used by build (sorted): "))
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert!(used_by_build_lines
+            .contains(&String::from("Used by build (sorted): app/src/lib.rs")));
+        assert!(used_by_build_lines.contains(&String::from(
+            "Used by build (sorted): shared/src/lib.rs"
+        )));
+        assert!(used_by_build_lines
+            .iter()
+            .all(|line| !line.contains(workspace_dir.to_str().unwrap())));
+    }
+}
+
+const DEFAULT_MEMBERS: &str = "test19_workspace_with_default_members";
+
+#[test]
+fn default_members_are_scanned() {
+    let (output, _cx) = run_geiger_json(DEFAULT_MEMBERS);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let names = report
+        .packages
+        .keys()
+        .map(|package_id| package_id.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["app"]);
+
+    // Excluding the only default member leaves nothing to scan.
+    let (output, _cx) = run_geiger_with(DEFAULT_MEMBERS, &["--exclude", "app"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("all default members of the workspace at"));
+}
+
+const GENERATED_BINDINGS: &str = "test16_package_with_generated_bindings";
+
+#[test]
+fn generated_files_are_counted_separately() {
+    let (output, _cx) = run_geiger_json(GENERATED_BINDINGS);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = &report.packages.values().next().unwrap().unsafety;
+    let generated = unsafety.generated.as_ref().unwrap();
+    assert_eq!(generated.used.foreign_items.unsafe_, 3);
+    assert_eq!(generated.used.exprs.unsafe_, 0);
+    assert_eq!(unsafety.used.foreign_items.unsafe_, 3);
+    assert!(unsafety.used.exprs.unsafe_ > 0);
+
+    // The cells are functions, exprs, impls, traits, methods and FFI.
+    let ffi_cell = |stdout: &str, suffix: &str| {
+        let line = stdout
+            .lines()
+            .find(|line| line.ends_with(suffix))
+            .unwrap()
+            .to_string();
+        line.split_whitespace().nth(5).unwrap().to_string()
+    };
+    let package_line = format!("{} 0.1.0", GENERATED_BINDINGS);
+    let generated_line = format!("{} 0.1.0 (generated)", GENERATED_BINDINGS);
+
+    let (output, _cx) = run_geiger_with(GENERATED_BINDINGS, &[] as &[&str]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(ffi_cell(&stdout, &package_line), "3/3");
+    assert!(!stdout.contains("(generated)"));
+
+    let (output, _cx) =
+        run_geiger_with(GENERATED_BINDINGS, &["--split-generated"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(ffi_cell(&stdout, &package_line), "0/0");
+    assert_eq!(ffi_cell(&stdout, &generated_line), "3/3");
+}
+
+#[test]
+fn manifest_path_can_be_a_directory() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test2::NAME)
+        .arg("--json")
+        .arg("--manifest-path")
+        .arg(cx.crate_dir(Test1::NAME))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    assert_eq!(
+        without_breakdowns(without_metadata(report)),
+        Test1.expected_report(&cx)
+    );
+
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--manifest-path")
+        .arg(cx.crate_dir(Test1::NAME).join("src"))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("is a directory without a `Cargo.toml`"));
+    assert!(stderr.contains("hint: pass the directory of a package"));
+}
+
+#[test]
+fn invalid_manifest_is_reported_with_its_line() {
+    let cx = Context::new();
+    let manifest_path = cx.crate_dir(Test1::NAME).join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path).unwrap();
+    fs::write(&manifest_path, manifest.replacen("name =", "name", 1)).unwrap();
+
+    let output = geiger_command(&cx, Test1::NAME)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!(
+        "the manifest `{}` is not valid TOML, line 2: ",
+        manifest_path.display()
+    )));
+}
+
+#[test]
+fn package_of_a_virtual_manifest_points_to_the_members() {
+    let output = run_geiger_with(
+        "test5_workspace_with_virtual_manifest",
+        &["--package", "member1"],
+    )
+    .0;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("is the virtual manifest of a workspace"));
+    assert!(stderr.contains("hint: leave out `--package`"));
+}
+
+#[test]
+fn scan_merges_several_manifest_paths() {
+    let cx = Context::new();
+    let manifest_path =
+        |name: &str| cx.crate_dir(name).join("Cargo.toml").into_os_string();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--json")
+        .arg("--manifest-path")
+        .arg(manifest_path(Test1::NAME))
+        .arg("--manifest-path")
+        .arg(manifest_path(Test2::NAME))
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let merged_report =
+        serde_json::from_slice::<MergedSafetyReport>(&output.stdout).unwrap();
+
+    let mut workspaces = merged_report.workspaces.into_iter();
+    let test1_workspace = workspaces.next().unwrap();
+    let test2_workspace = workspaces.next().unwrap();
+    assert!(workspaces.next().is_none());
+    assert_eq!(
+        without_breakdowns(without_metadata(test1_workspace.report)),
+        Test1.expected_report(&cx)
+    );
+    assert_eq!(
+        without_breakdowns(without_metadata(test2_workspace.report)),
+        Test2.expected_report(&cx)
+    );
+
+    // The first workspace is a dependency of the second one, it's counted
+    // once.
+    let expected_used = Test2
+        .expected_report(&cx)
+        .packages
+        .values()
+        .fold(CounterBlock::default(), |used, entry| {
+            used + entry.unsafety.used.clone()
+        });
+    assert_eq!(merged_report.used, expected_used);
+}
+
+#[test]
+fn show_totals_adds_item_counts_to_cells() {
+    let (output, _cx) = run_geiger_with(Test1::NAME, &["--show-totals"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Metric output format: x/y (of N)"));
+    let package_line = stdout
+        .lines()
+        .find(|line| line.ends_with(&format!("{} 0.1.0", Test1::NAME)))
+        .unwrap();
+    // The expressions column keeps the width of its header.
+    assert!(package_line.starts_with("1/1 (of 2) 2/2 (of 6)   0/0 (of 0) "));
+}
+
+#[test]
+fn timings_are_printed_and_stored_in_the_report() {
+    let (output, _cx) = run_geiger_with(Test1::NAME, &["--timings", "--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let phases = report
+        .metadata
+        .timings
+        .iter()
+        .map(|phase_timing| phase_timing.phase.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        phases,
+        vec!["resolve", "clean", "build", "dep-info", "scan"]
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Timings:"));
+    assert!(stderr.contains("    render "));
+}
+
+#[test]
+fn progress_json_events_are_printed_on_stderr() {
+    let (output, _cx) =
+        run_geiger_with(Test1::NAME, &["--progress-json", "--json"]);
+    assert!(output.status.success());
+    // The report on stdout is left as it is.
+    serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let events = stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        events.first().unwrap(),
+        &serde_json::json!({"event": "resolve_done", "packages": 1})
+    );
+    assert!(events.contains(&serde_json::json!({
+        "event": "package_scanned",
+        "package": "test1_package_with_no_deps 0.1.0",
+        "files": 1
+    })));
+    let scan_done = events.last().unwrap();
+    assert_eq!(scan_done["event"], "scan_done");
+    assert_eq!(scan_done["packages"], 1);
+    assert_eq!(scan_done["used"]["exprs"]["unsafe_"], 2);
+}
+
+const PATH_MODULES: &str = "test21_package_with_path_modules";
+
+#[test]
+fn path_modules_outside_of_the_package_are_scanned() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, PATH_MODULES)
+        .current_dir(cx.workspace_crate_dir(PATH_MODULES, "package"))
+        .arg("--json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = &report.packages.values().next().unwrap().unsafety;
+    // The unsafe function is only in the file outside of the package root.
+    assert_eq!(
+        unsafety.used.functions,
+        Count {
+            safe: 2,
+            unsafe_: 1
+        }
+    );
+    assert_eq!(unsafety.used.exprs.unsafe_, 3);
+    assert_eq!(unsafety.unused, CounterBlock::default());
+}
+
+const PLATFORM_DEPS: &str = "test22_package_with_platform_deps";
+
+#[test]
+fn all_platforms_classifies_dependencies_of_other_platforms() {
+    let (output, _cx) =
+        run_geiger_with(PLATFORM_DEPS, &["--all-platforms", "--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let entry = |name: &str| {
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .clone()
+    };
+
+    let host_only = entry("host_only");
+    assert_eq!(host_only.unsafety.status, ScanStatus::Complete);
+    assert!(host_only.platforms.is_empty());
+    // The dependency of the other platform's package is of that platform
+    // too, neither of them was built.
+    for name in &["redox_only", "redox_sys"] {
+        let other_platform = entry(name);
+        assert_eq!(other_platform.unsafety.status, ScanStatus::OtherPlatform);
+        assert_eq!(
+            other_platform.platforms,
+            vec![String::from(r#"cfg(target_os = "redox")"#)]
+        );
+        assert!(other_platform.unsafety.unused.has_unsafe());
+    }
+    assert!(!report
+        .warnings
+        .iter()
+        .any(|warning| matches!(warning, ScanWarning::NoBuildData { .. })));
+
+    let (output, _cx) = run_geiger_with(PLATFORM_DEPS, &["--all-platforms"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout
+        .contains(r#"redox_only 0.1.0 (platform: cfg(target_os = "redox"))"#));
+}
+
+const WINDOWS_DEPENDENCY: &str = "test27_package_with_windows_dependency";
+
+#[cfg(not(windows))]
+#[test]
+fn packages_of_other_platforms_are_inactive() {
+    let (output, _cx) =
+        run_geiger_with(WINDOWS_DEPENDENCY, &["--all-platforms", "--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let inactive = report.inactive.unwrap();
+    assert!(!inactive.target.is_empty());
+    assert_eq!(
+        inactive
+            .packages
+            .iter()
+            .map(|id| id.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["windows_only"]
+    );
+
+    let (output, _cx) =
+        run_geiger_with(WINDOWS_DEPENDENCY, &["--all-platforms"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!(
+        "windows_only 0.1.0 (platform: cfg(windows)) (inactive on {})",
+        inactive.target
+    )));
+    assert!(stdout.contains(&format!(
+        "Inactive on {}, nothing of them was built: 1 package",
+        inactive.target
+    )));
+}
+
+const NESTED_PACKAGE: &str = "test28_package_with_nested_package";
+
+#[test]
+fn files_of_a_nested_package_are_counted_once() {
+    let (output, _cx) = run_geiger_json(NESTED_PACKAGE);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = |name: &str| {
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .unsafety
+            .clone()
+    };
+    // The files of `nested` are below the root of the outer package too.
+    let outer = unsafety(NESTED_PACKAGE);
+    assert!(!outer.used.has_unsafe());
+    assert!(!outer.unused.has_unsafe());
+    assert!(unsafety("nested").used.has_unsafe());
+
+    let (output, _cx) = run_geiger_with(NESTED_PACKAGE, &["--verbose"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!(
+        "to nested 0.1.0, not counted for {} 0.1.0",
+        NESTED_PACKAGE
+    )));
+}
+
+const DIAMOND_DEPENDENCIES: &str = "test29_package_with_diamond_dependencies";
+
+#[test]
+fn dependency_counts_count_shared_dependencies_once() {
+    let (output, _cx) =
+        run_geiger_with(DIAMOND_DEPENDENCIES, &["--show-dep-counts", "--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let dependency_counts = |name: &str| {
+        let counts = report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .dependency_counts
+            .unwrap();
+        (counts.direct, counts.transitive)
+    };
+    assert_eq!(dependency_counts(DIAMOND_DEPENDENCIES), (2, 3));
+    assert_eq!(dependency_counts("left"), (1, 1));
+    assert_eq!(dependency_counts("right"), (1, 1));
+    assert_eq!(dependency_counts("bottom"), (0, 0));
+
+    let (output, _cx) = run_geiger_with(DIAMOND_DEPENDENCIES, &["--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    assert!(report
+        .packages
+        .values()
+        .all(|entry| entry.dependency_counts.is_none()));
+
+    let (output, _cx) =
+        run_geiger_with(DIAMOND_DEPENDENCIES, &["--show-dep-counts"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("FFI Deps       Dependency"));
+    assert!(stdout.contains(" 2/3       "));
+}
+
+#[test]
+fn metadata_backend_prints_the_same_tree_as_the_resolve_backend() {
+    let cx = Context::new();
+    let tree_lines = |backend: &str| {
+        let output = geiger_command(&cx, DIAMOND_DEPENDENCIES)
+            .arg("--backend")
+            .arg(backend)
+            .arg("--output-format")
+            .arg("tree-json")
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        serde_json::from_slice::<Vec<TreeLine>>(&output.stdout).unwrap()
+    };
+    assert_eq!(tree_lines("metadata"), tree_lines("resolve"));
+}
+
+#[test]
+fn packages_are_listed_once_at_their_minimum_depth() {
+    let (output, _cx) = run_geiger_with(DIAMOND_DEPENDENCIES, &["--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let depth = |name: &str| {
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .depth
+    };
+    assert_eq!(depth(DIAMOND_DEPENDENCIES), Some(0));
+    assert_eq!(depth("left"), Some(1));
+    assert_eq!(depth("right"), Some(1));
+    assert_eq!(depth("bottom"), Some(2));
+
+    let (output, _cx) = run_geiger_with(
+        DIAMOND_DEPENDENCIES,
+        &["--prefix-depth", "--sort", "depth", "--charset", "ascii"],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let bottom_lines = stdout
+        .lines()
+        .filter(|line| line.contains("bottom 0.1.0"))
+        .collect::<Vec<_>>();
+    assert_eq!(bottom_lines.len(), 1);
+    assert!(bottom_lines[0].contains("2 bottom 0.1.0"));
+
+    let (output, _cx) =
+        run_geiger_with(DIAMOND_DEPENDENCIES, &["--sort", "depth"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("`--sort` requires `--prefix-depth` or `--no-indent`"));
+}
+
+const WORKSPACE_WITH_REGISTRY_DEP: &str = "test30_workspace_with_registry_dep";
+
+#[test]
+fn workspace_members_are_marked_and_totaled_apart() {
+    let (output, _cx) =
+        run_geiger_with(WORKSPACE_WITH_REGISTRY_DEP, &["--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let is_workspace_member = |name: &str| {
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .is_workspace_member
+    };
+    assert!(is_workspace_member(WORKSPACE_WITH_REGISTRY_DEP));
+    assert!(!is_workspace_member("ref_slice"));
+
+    let (output, _cx) =
+        run_geiger_with(WORKSPACE_WITH_REGISTRY_DEP, &["--show-totals"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let totals_line = |label: &str| {
+        stdout
+            .lines()
+            .find(|line| line.ends_with(&format!("     {}", label)))
+            .unwrap()
+            .to_string()
+    };
+    assert!(totals_line("our code").starts_with("0/0 (of 1) 0/0 (of "));
+    assert!(totals_line("dependencies").starts_with("0/0 (of 4) 2/2 (of 12) "));
+
+    // The split only comes with the totals.
+    let output = run_geiger(WORKSPACE_WITH_REGISTRY_DEP);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("our code"));
+}
+
+const PROC_MACRO: &str = "test31_package_with_proc_macro";
+
+#[test]
+fn proc_macros_are_attributed_to_the_packages_they_are_applied_to() {
+    let (output, _cx) = run_geiger_json(PROC_MACRO);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let proc_macros_applied = |name: &str| {
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .proc_macros_applied
+            .clone()
+    };
+    assert_eq!(proc_macros_applied(PROC_MACRO), vec!["derive_nothing"]);
+    assert!(proc_macros_applied("derive_nothing").is_empty());
+
+    assert_eq!(report.proc_macros.len(), 1);
+    let proc_macro_use = &report.proc_macros[0];
+    assert_eq!(proc_macro_use.crate_name, "derive_nothing");
+    assert_eq!(
+        proc_macro_use
+            .applied_to
+            .iter()
+            .map(|id| id.name.as_str())
+            .collect::<Vec<_>>(),
+        vec![PROC_MACRO]
+    );
+}
+
+const NATIVE_LINKAGE: &str = "test23_package_with_native_linkage";
+
+#[test]
+fn native_linkage_is_reported_and_marked_in_tree() {
+    let (output, _cx) = run_geiger_json(NATIVE_LINKAGE);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let native_linkage = |name: &str| {
+        report
+            .packages
+            .values()
+            .find(|entry| entry.package.id.name == name)
+            .unwrap()
+            .native_linkage
+            .clone()
+    };
+    assert_eq!(
+        native_linkage("fake_sys"),
+        Some(NativeLinkage {
+            links: String::from("fake"),
+            build_script: true,
+        })
+    );
+    assert_eq!(native_linkage(NATIVE_LINKAGE), None);
+
+    let output = run_geiger(NATIVE_LINKAGE);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("fake_sys 0.1.0 L (links: fake)"));
+    assert!(stdout.contains(
+        "L = links to a native library whose code isn't scanned: 1 package"
+    ));
+}
+
+const SHARED_DEPENDENCY: &str = "test24_workspace_with_shared_dependency";
+
+#[test]
+fn members_only_folds_in_the_dependencies_of_each_member() {
+    let (output, _cx) =
+        run_geiger_with(SHARED_DEPENDENCY, &["--members-only", "--json"]);
+    assert!(output.status.success());
+    let merged_report =
+        serde_json::from_slice::<MergedSafetyReport>(&output.stdout).unwrap();
+    // Each member is a workspace of its own in the merged report, the shared
+    // dependency is counted for both of them.
+    assert_eq!(merged_report.workspaces.len(), 2);
+    for workspace in &merged_report.workspaces {
+        let report = &workspace.report;
+        let entry = |name: &str| {
+            report
+                .packages
+                .values()
+                .find(|entry| entry.package.id.name == name)
+                .unwrap()
+        };
+        assert_eq!(report.members.len(), 1);
+        let member = &report.members[0];
+        assert_eq!(member.used, entry(&member.id.name).unsafety.used);
+        assert_eq!(
+            member.transitive_used,
+            member.used.clone() + entry("shared").unsafety.used.clone()
+        );
+        assert!(member.transitive_used.has_unsafe());
+    }
+
+    let (output, _cx) = run_geiger_with(SHARED_DEPENDENCY, &["--members-only"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout
+        .lines()
+        .any(|line| line.ends_with("  member_a 0.1.0")));
+    assert!(stdout
+        .lines()
+        .any(|line| line.ends_with("  member_b 0.1.0")));
+    assert!(!stdout.contains("shared 0.1.0"));
+}
+
+const EXTERNAL_PATH_DEPS: &str = "test33_multi_repo_with_external_path_deps";
+
+#[test]
+fn path_dependencies_outside_of_the_workspace_are_grouped_by_directory() {
+    let product = format!("{}/product", EXTERNAL_PATH_DEPS);
+    let (output, cx) =
+        run_geiger_with(&product, &["--split-local-paths", "--json"]);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let repos_dir = cx.crate_dir(EXTERNAL_PATH_DEPS);
+    // local is a path dependency inside of the workspace directory.
+    assert_eq!(
+        report
+            .external_path_groups
+            .iter()
+            .map(|group| (
+                group.directory.clone(),
+                group
+                    .packages
+                    .iter()
+                    .map(|package| package.name.as_str())
+                    .collect::<Vec<_>>()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (repos_dir.join("repo_a"), vec!["crate_a1", "crate_a2"]),
+            (repos_dir.join("repo_b"), vec!["crate_b"]),
+        ]
+    );
+    for group in &report.external_path_groups {
+        let used = group
+            .packages
+            .iter()
+            .map(|package| report.packages[package].unsafety.used.clone())
+            .fold(CounterBlock::default(), |used, package_used| {
+                used + package_used
+            });
+        assert_eq!(group.used, used);
+    }
+    let repo_a = &report.external_path_groups[0];
+    assert_eq!(repo_a.used.exprs.unsafe_, 2);
+    assert_eq!(repo_a.used.functions.unsafe_, 1);
+
+    let (output, cx) = run_geiger_with(&product, &["--split-local-paths"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let repos_dir = cx.crate_dir(EXTERNAL_PATH_DEPS);
+    assert!(stdout.contains(&format!(
+        "Path dependencies in {}",
+        repos_dir.join("repo_a").display()
+    )));
+    assert_eq!(
+        stdout
+            .lines()
+            .filter(|line| line.ends_with("  Subtotal"))
+            .count(),
+        2
+    );
+}
+
+const EMPTY_AND_UNREADABLE_FILES: &str =
+    "test34_package_with_empty_and_unreadable_files";
+
+#[test]
+fn empty_files_are_safe_and_unreadable_files_are_warned_about() {
+    let cx = Context::new();
+    let secret = cx
+        .crate_dir(EMPTY_AND_UNREADABLE_FILES)
+        .join("src")
+        .join("secret.rs");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o000))
+            .unwrap();
+    }
+    let output = geiger_command(&cx, EMPTY_AND_UNREADABLE_FILES)
+        .arg("--json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let entry = report
+        .packages
+        .values()
+        .find(|entry| entry.package.id.name == EMPTY_AND_UNREADABLE_FILES)
+        .unwrap();
+    // Only the unsafe block of lib.rs, the empty placeholder.rs is no parse
+    // failure.
+    assert_eq!(
+        entry.unsafety.used.exprs,
+        Count {
+            safe: 0,
+            unsafe_: 2
+        }
+    );
+    assert!(!report
+        .warnings
+        .iter()
+        .any(|warning| matches!(warning, ScanWarning::ParseFailed { .. })));
+
+    // Permissions don't keep root, or other platforms, from reading the file.
+    if fs::read(&secret).is_ok() {
+        return;
+    }
+    assert_eq!(entry.unsafety.status, ScanStatus::ParseErrors);
+    assert!(report.warnings.iter().any(|warning| matches!(
+        warning,
+        ScanWarning::Unreadable { path, .. } if path == &secret
+    )));
+}
+
+const OVERSIZED_FILE: &str = "test35_package_with_oversized_file";
+
+#[test]
+fn files_larger_than_the_max_file_size_are_reported_apart() {
+    let cx = Context::new();
+    let tables = cx.crate_dir(OVERSIZED_FILE).join("src").join("tables.rs");
+    let generated_source = (0..4096)
+        .map(|i| format!("pub const TABLE_{}: u8 = {};\n", i, i % 256))
+        .collect::<String>();
+    fs::write(&tables, &generated_source).unwrap();
+    let size = generated_source.len() as u64;
+
+    let output = geiger_command(&cx, OVERSIZED_FILE)
+        .args(&["--max-file-size", "4096", "--json"])
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let entry = report
+        .packages
+        .values()
+        .find(|entry| entry.package.id.name == OVERSIZED_FILE)
+        .unwrap();
+    assert_eq!(
+        entry.unsafety.skipped_oversized,
+        vec![OversizedFile {
+            path: tables.clone(),
+            size
+        }]
+    );
+    assert_eq!(entry.unsafety.status, ScanStatus::ParseErrors);
+    assert_eq!(
+        entry.unsafety.used.exprs,
+        Count {
+            safe: 0,
+            unsafe_: 2
+        }
+    );
+    assert!(report
+        .warnings
+        .contains(&ScanWarning::Oversized { path: tables, size }));
+
+    let output = geiger_command(&cx, OVERSIZED_FILE)
+        .args(&["--max-file-size", "4096"])
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("{} 0.1.0 (>)", OVERSIZED_FILE)));
+    assert!(stdout.contains(&format!(
+        "    {} v0.1.0: src/tables.rs ({} bytes)",
+        OVERSIZED_FILE, size
+    )));
+
+    let output = geiger_command(&cx, OVERSIZED_FILE)
+        .arg("--json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    assert!(report
+        .packages
+        .values()
+        .all(|entry| entry.unsafety.skipped_oversized.is_empty()));
+}
+
+const DEV_ONLY_DEPENDENCY: &str = "test36_package_with_dev_only_dependency";
+
+#[test]
+fn dev_dependencies_of_dependencies_are_left_out_of_the_totals() {
+    let (output, _cx) =
+        run_geiger_with(DEV_ONLY_DEPENDENCY, &["--dev-dependencies", "--json"]);
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let dev_only = report.dev_only.unwrap();
+    assert_eq!(
+        dev_only
+            .packages
+            .iter()
+            .map(|id| id.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["fuzz_helper"]
+    );
+    assert_eq!(dev_only.unused.functions.unsafe_, 1);
+    assert!(report
+        .packages
+        .values()
+        .any(|entry| entry.package.id.name == "fuzz_helper"));
+
+    let (output, _cx) =
+        run_geiger_with(DEV_ONLY_DEPENDENCY, &["--dev-dependencies"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("fuzz_helper 0.1.0 (dev-only)"));
+    assert!(stdout.contains(
+        "Only reached through dev-dependencies of dependencies, not part of \
+         the totals"
+    ));
+
+    let (output, _cx) = run_geiger_json(DEV_ONLY_DEPENDENCY);
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    assert_eq!(report.dev_only, None);
+}
+
+#[test]
+fn normalized_output_is_the_same_for_copies_in_different_directories() {
+    let args = ["--dev-dependencies", "--verbose", "--normalize-output"];
+    let (first_output, first_cx) = run_geiger_with(DEV_ONLY_DEPENDENCY, &args);
+    let (second_output, second_cx) =
+        run_geiger_with(DEV_ONLY_DEPENDENCY, &args);
+    assert_ne!(first_cx.path, second_cx.path);
+    assert!(first_output.status.success());
+    assert!(second_output.status.success());
+
+    let stdout = String::from_utf8(first_output.stdout).unwrap();
+    assert_eq!(stdout, String::from_utf8(second_output.stdout).unwrap());
+    assert!(stdout.contains("Used by build (sorted): [ROOT]/src/lib.rs"));
+    assert!(stdout.contains("fuzz_helper v0.1.0 ([ROOT]/fuzz_helper)"));
+    assert!(!stdout.contains(&first_cx.path.display().to_string()));
+}
+
+const INCONSISTENT_FORBID: &str = "test26_package_with_inconsistent_forbid";
+
+#[test]
+fn forbid_with_unsafe_in_build_script_is_flagged() {
+    let (output, _cx) = run_geiger_json(INCONSISTENT_FORBID);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    assert_eq!(report.inconsistent_forbid.len(), 1);
+    let inconsistent_forbid = &report.inconsistent_forbid[0];
+    assert_eq!(inconsistent_forbid.package.name, INCONSISTENT_FORBID);
+    assert_eq!(inconsistent_forbid.files.len(), 1);
+    assert!(inconsistent_forbid.files[0].ends_with("build.rs"));
+
+    let output = run_geiger(INCONSISTENT_FORBID);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("{} 0.1.0 (!)", INCONSISTENT_FORBID)));
+    assert!(stdout
+        .contains(&format!("    {} v0.1.0: build.rs", INCONSISTENT_FORBID)));
+}
+
+const UNPARSABLE_FILE: &str = "test32_package_with_unparsable_file";
+
+#[test]
+fn unsafe_keywords_of_unparsable_files_are_counted_from_the_tokens() {
+    let (output, _cx) = run_geiger_json(UNPARSABLE_FILE);
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let unsafety = &report.packages.values().next().unwrap().unsafety;
+    assert_eq!(unsafety.status, ScanStatus::ParseErrors);
+    assert_eq!(
+        unsafety.approximate,
+        Some(ApproximateUnsafety { used: 0, unused: 3 })
+    );
+    assert!(!unsafety.used.has_unsafe());
+    assert!(!unsafety.unused.has_unsafe());
+
+    let output = run_geiger(UNPARSABLE_FILE);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("{} 0.1.0 (~)", UNPARSABLE_FILE)));
+    assert!(stdout.contains(&format!("    {} v0.1.0: 0/3", UNPARSABLE_FILE)));
+}
+
+#[test]
+fn remapped_dep_info_paths_are_mapped_back() {
+    let cx = Context::new();
+    // The module file is only found through the dep-info file, which lists
+    // it below the remapped prefix.
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, GENERATED_BINDINGS)
+            .env("RUSTFLAGS", "--remap-path-prefix=src=/remapped/src")
+            .arg("--json")
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        let report =
+            serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+        let unsafety =
+            report.packages.values().next().unwrap().unsafety.clone();
+        assert_eq!(unsafety.generated.unwrap().used.foreign_items.unsafe_, 3);
+    };
+    run(&[]);
+    run(&["--reuse-build"]);
+}
+
+#[test]
+fn config_override_without_unstable_options() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--config")
+        .arg("net.offline=true")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`--config` flag is unstable"));
+}
+
+#[test]
+fn unstable_flag_on_nightly() {
+    if !rustc_is_nightly() {
+        return;
+    }
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("-Z")
+        .arg("no-index-update")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+}
+
+#[test]
+fn unstable_flag_on_stable() {
+    if rustc_is_nightly() {
+        return;
+    }
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("-Z")
+        .arg("no-index-update")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("`-Z no-index-update` requires a nightly toolchain")
+    );
+}
+
+fn rustc_is_nightly() -> bool {
+    if env::var("RUSTC_BOOTSTRAP").map_or(false, |v| v == "1") {
+        return true;
+    }
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let output = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .expect("failed to run `rustc`");
+    let version = String::from_utf8(output.stdout).unwrap();
+    version.contains("-nightly") || version.contains("-dev")
+}
+
+#[test]
+fn no_hyperlinks_when_piped() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, Test3::NAME)
+        .env("FORCE_HYPERLINK", "1")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("itertools"));
+    assert!(!stdout.contains("\x1B]8;"));
 }
 
+const BROKEN_DEP: &str = "test10_package_with_broken_dep";
+
 #[test]
-fn serialize_test3_report() {
-    Test3.run();
+fn broken_dep_fails_the_scan() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, BROKEN_DEP)
+        .arg("--json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    // A proper error with the cause of the failure, not a panic.
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("could not compile `broken_dep`"));
+    assert!(!stderr.contains("panicked"));
 }
 
 #[test]
-fn serialize_test4_report() {
-    Test4.run();
+fn broken_dep_with_keep_going() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, BROKEN_DEP)
+        .arg("--json")
+        .arg("--keep-going")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let member_id = |name: &str| PackageId {
+        name: name.into(),
+        version: Version::new(0, 1, 0),
+        source: make_workspace_source(&cx, BROKEN_DEP, name),
+    };
+    let broken_dep_id = member_id("broken_dep");
+    let working_dep_id = member_id("working_dep");
+
+    // The root package fails too, its dependency is missing.
+    assert_eq!(
+        to_set(report.build_failures.keys().cloned()),
+        to_set(vec![
+            broken_dep_id.clone(),
+            make_package_id(&cx, BROKEN_DEP)
+        ])
+    );
+    assert!(report.build_failures[&broken_dep_id]
+        .error
+        .contains("broken_dep doesn't build"));
+
+    let broken_dep_unsafety = &report.packages[&broken_dep_id].unsafety;
+    assert_eq!(broken_dep_unsafety.status, ScanStatus::NoBuildData);
+    assert!(!broken_dep_unsafety.used.has_unsafe());
+    assert!(broken_dep_unsafety.unused.has_unsafe());
+    let working_dep_unsafety = &report.packages[&working_dep_id].unsafety;
+    assert_eq!(working_dep_unsafety.status, ScanStatus::Complete);
+    assert!(working_dep_unsafety.used.has_unsafe());
+
+    assert!(report.warnings.contains(&ScanWarning::BuildFailed {
+        package: broken_dep_id.clone(),
+    }));
+    assert!(report.warnings.contains(&ScanWarning::NoBuildData {
+        package: broken_dep_id,
+    }));
 }
 
 #[test]
-fn serialize_test6_report() {
-    Test6.run();
+fn reuse_build_matches_forced_rebuild() {
+    let cx = Context::new();
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, Test2::NAME)
+            .arg("--json")
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap()
+    };
+    let rebuilt = run(&[]);
+    // Everything is up to date now, nothing is passed to rustc again.
+    let reused = run(&["--reuse-build"]);
+    assert_eq!(reused, rebuilt);
+    assert_eq!(
+        without_breakdowns(without_metadata(reused)),
+        Test2.expected_report(&cx)
+    );
+}
+
+#[rstest(
+    name,
+    case("test1_package_with_no_deps"),
+    case("test2_package_with_shallow_deps")
+)]
+fn unit_graph_resolver_matches_executor(name: &str) {
+    let cx = Context::new();
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, name)
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap()
+    };
+    let from_executor = run(&["--json"]);
+    let from_unit_graph = run(&["--json", "--resolver", "unit-graph"]);
+    assert_eq!(from_unit_graph, from_executor);
+}
+
+/// A package depending on `vendored_dep` from crates.io, replaced by a
+/// `vendor` directory the way `cargo vendor` sets it up. The empty checksum
+/// file skips the verification of the vendored files.
+fn generate_vendored_package(dir: &Path) {
+    let vendored_dep_dir = dir.join("vendor").join("vendored_dep");
+    fs::create_dir_all(vendored_dep_dir.join("src")).unwrap();
+    fs::write(
+        vendored_dep_dir.join("Cargo.toml"),
+        "[package]\nname = \"vendored_dep\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        vendored_dep_dir.join("src").join("lib.rs"),
+        "pub fn first(v: &[u8]) -> u8 {\n    unsafe { *v.get_unchecked(0) }\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        vendored_dep_dir.join(".cargo-checksum.json"),
+        r#"{"files":{},"package":null}"#,
+    )
+    .unwrap();
+
+    fs::create_dir_all(dir.join(".cargo")).unwrap();
+    fs::write(
+        dir.join(".cargo").join("config"),
+        "[source.crates-io]\nreplace-with = \"vendored-sources\"\n\n\
+         [source.vendored-sources]\ndirectory = \"vendor\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"vendored_package\"\nversion = \"0.1.0\"\n\n\
+         [dependencies]\nvendored_dep = \"0.1.0\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src").join("lib.rs"),
+        "pub fn first(v: &[u8]) -> u8 {\n    vendored_dep::first(v)\n}\n",
+    )
+    .unwrap();
 }
 
+#[cfg(unix)]
 #[test]
-fn serialize_test7_report() {
-    Test7.run();
+fn vendored_dependency_is_used_through_symlinked_checkout() {
+    let cx = Context::new();
+    let package_dir = cx.crate_dir("vendored_package");
+    generate_vendored_package(&package_dir);
+    // The dep-info files have the paths below the symlink, the scanned
+    // files are found below the package root it points to.
+    let checkout_dir = cx.crate_dir("vendored_checkout");
+    std::os::unix::fs::symlink(&package_dir, &checkout_dir).unwrap();
+
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, "vendored_package")
+            .current_dir(&checkout_dir)
+            .arg("--json")
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap()
+    };
+    let rebuilt = run(&[]);
+    let reused = run(&["--reuse-build"]);
+    assert_eq!(reused, rebuilt);
+
+    let vendored_dep = rebuilt
+        .packages
+        .values()
+        .find(|entry| entry.package.id.name == "vendored_dep")
+        .expect("the vendored dependency should be in the report");
+    assert_eq!(vendored_dep.unsafety.status, ScanStatus::Complete);
+    assert!(vendored_dep.unsafety.used.has_unsafe());
+    assert!(!vendored_dep.unsafety.unused.has_unsafe());
+    assert!(rebuilt.used_but_not_scanned_files.is_empty());
+}
+
+const DEV_DEPENDENCY_CYCLE: &str = "test9_workspace_with_dev_dependency_cycle";
+
+#[test]
+fn dev_dependency_cycle_is_marked_in_tree() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, DEV_DEPENDENCY_CYCLE)
+        .current_dir(cx.workspace_crate_dir(DEV_DEPENDENCY_CYCLE, "member1"))
+        .arg("--all")
+        .arg("--dev-dependencies")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("(cycle)").count(), 1);
+}
+
+#[test]
+fn tree_json_lists_the_lines_of_the_tree() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, DEV_DEPENDENCY_CYCLE)
+        .current_dir(cx.workspace_crate_dir(DEV_DEPENDENCY_CYCLE, "member1"))
+        .arg("--dev-dependencies")
+        .arg("--output-format")
+        .arg("tree-json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let tree_lines =
+        serde_json::from_slice::<Vec<TreeLine>>(&output.stdout).unwrap();
+    let member_id = |name: &str| PackageId {
+        name: name.into(),
+        version: Version::new(0, 1, 0),
+        source: make_workspace_source(&cx, DEV_DEPENDENCY_CYCLE, name),
+    };
+    let unsafety = |line: &TreeLine| match line {
+        TreeLine::Package { unsafety, .. } => unsafety.clone(),
+        _ => None,
+    };
+    assert_eq!(tree_lines.len(), 4);
+    assert_eq!(
+        tree_lines[0],
+        TreeLine::Package {
+            id: member_id("member1"),
+            depth: 0,
+            tree_vines: String::new(),
+            unsafety: unsafety(&tree_lines[0]),
+        }
+    );
+    assert!(unsafety(&tree_lines[0]).is_some());
+    assert_eq!(
+        tree_lines[1],
+        TreeLine::ExtraDepsGroup {
+            kind: DependencyKind::Development,
+            depth: 1,
+            tree_vines: String::new(),
+        }
+    );
+    assert_eq!(
+        tree_lines[2],
+        TreeLine::Package {
+            id: member_id("member2"),
+            depth: 1,
+            tree_vines: String::from("`-- "),
+            unsafety: unsafety(&tree_lines[2]),
+        }
+    );
+    assert!(unsafety(&tree_lines[2]).is_some());
+    assert_eq!(
+        tree_lines[3],
+        TreeLine::Cycle {
+            id: member_id("member1"),
+            depth: 2,
+            tree_vines: String::from("    `-- "),
+        }
+    );
+}
+
+#[test]
+fn dev_dependency_cycle_is_listed_in_report() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, DEV_DEPENDENCY_CYCLE)
+        .current_dir(cx.workspace_crate_dir(DEV_DEPENDENCY_CYCLE, "member1"))
+        .arg("--dev-dependencies")
+        .arg("--json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let member_id = |name: &str| PackageId {
+        name: name.into(),
+        version: Version::new(0, 1, 0),
+        source: make_workspace_source(&cx, DEV_DEPENDENCY_CYCLE, name),
+    };
+    assert_eq!(
+        report.dependency_cycles,
+        vec![vec![member_id("member1"), member_id("member2")]]
+    );
+}
+
+// Dev-dependencies are in the tree with `--dev-dependencies`, but not in the
+// build, which only checks the library.
+#[test]
+fn dev_dependency_without_build_data_is_marked_in_tree() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, DEV_DEPENDENCY_CYCLE)
+        .current_dir(cx.workspace_crate_dir(DEV_DEPENDENCY_CYCLE, "member1"))
+        .arg("--dev-dependencies")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let member2_line = stdout
+        .lines()
+        .find(|line| line.ends_with("member2 0.1.0"))
+        .expect("member2 should be in the tree");
+    assert!(member2_line.starts_with("?/0        ?/0 "));
+}
+
+#[test]
+fn dev_dependency_without_build_data_is_listed_in_report() {
+    let cx = Context::new();
+    let output = geiger_command(&cx, DEV_DEPENDENCY_CYCLE)
+        .current_dir(cx.workspace_crate_dir(DEV_DEPENDENCY_CYCLE, "member1"))
+        .arg("--dev-dependencies")
+        .arg("--json")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
+    let report =
+        serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
+    let status = |name: &str| {
+        let id = PackageId {
+            name: name.into(),
+            version: Version::new(0, 1, 0),
+            source: make_workspace_source(&cx, DEV_DEPENDENCY_CYCLE, name),
+        };
+        report.packages[&id].unsafety.status
+    };
+    assert_eq!(status("member1"), ScanStatus::Complete);
+    assert_eq!(status("member2"), ScanStatus::NoBuildData);
+}
+
+#[test]
+fn no_dev_deps_leaves_out_dev_dependencies() {
+    let cx = Context::new();
+    let run = |extra_args: &[&str]| {
+        let output = geiger_command(&cx, DEV_DEPENDENCY_CYCLE)
+            .current_dir(
+                cx.workspace_crate_dir(DEV_DEPENDENCY_CYCLE, "member1"),
+            )
+            .arg("--all-dependencies")
+            .arg("--no-dev-deps")
+            .args(extra_args)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(output.status.success());
+        output.stdout
+    };
+
+    let stdout = String::from_utf8(run(&[])).unwrap();
+    assert!(stdout.contains("member1 0.1.0"));
+    assert!(!stdout.contains("member2"));
+
+    let report =
+        serde_json::from_slice::<SafetyReport>(&run(&["--json"])).unwrap();
+    let member2_id = PackageId {
+        name: "member2".into(),
+        version: Version::new(0, 1, 0),
+        source: make_workspace_source(&cx, DEV_DEPENDENCY_CYCLE, "member2"),
+    };
+    assert_eq!(report.packages.len(), 1);
+    assert!(!report.packages.contains_key(&member2_id));
+    assert!(!report.packages_without_metrics.contains(&member2_id));
+}
+
+const STALE_LOCK: &str = "test14_package_with_stale_lock";
+
+#[test]
+fn locked_fails_before_resolving_without_lock_file() {
+    let cx = Context::new();
+    let lock_path = cx.crate_dir(Test1::NAME).join("Cargo.lock");
+    fs::remove_file(&lock_path).unwrap();
+
+    for flag in &["--locked", "--frozen"] {
+        let output = geiger_command(&cx, Test1::NAME)
+            .arg(flag)
+            .output()
+            .expect("failed to run `cargo-geiger`");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(&format!(
+            "the lock file `{}` does not exist",
+            lock_path.display()
+        )));
+        assert!(stderr.contains("hint: run `cargo generate-lockfile`"));
+        assert!(!lock_path.exists());
+    }
+}
+
+#[test]
+fn locked_fails_before_resolving_with_unreadable_lock_file() {
+    let cx = Context::new();
+    let lock_path = cx.crate_dir(Test1::NAME).join("Cargo.lock");
+    // A directory can't be read as a file, not even with root privileges.
+    fs::remove_file(&lock_path).unwrap();
+    fs::create_dir(&lock_path).unwrap();
+
+    let output = geiger_command(&cx, Test1::NAME)
+        .arg("--locked")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!(
+        "failed to read the lock file `{}`",
+        lock_path.display()
+    )));
+}
+
+#[test]
+fn locked_fails_when_resolve_differs_from_lock_file() {
+    let cx = Context::new();
+    let lock_path = cx.crate_dir(STALE_LOCK).join("Cargo.lock");
+    let lock_before = std::fs::read_to_string(&lock_path).unwrap();
+
+    let output = geiger_command(&cx, STALE_LOCK)
+        .arg("--locked")
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(
+        "the resolved dependencies differ from the lock file, but --locked \
+         or --frozen was passed:"
+    ));
+    assert!(stderr.contains("\n    - test1_package_with_no_deps 0.0.1"));
+    assert!(stderr.contains("\n    + test1_package_with_no_deps 0.1.0"));
+    assert_eq!(std::fs::read_to_string(&lock_path).unwrap(), lock_before);
+
+    let output = geiger_command(&cx, STALE_LOCK)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    assert!(output.status.success());
 }
 
 #[test]
@@ -113,6 +2250,18 @@ fn serialize_test7_quick_report() {
     Test7.run_quick();
 }
 
+#[test]
+fn serialize_test8_quick_report() {
+    Test8.run_quick();
+}
+
+#[rstest(args, case(&["--forbid-only"]), case(&["--forbid-only", "--json"]))]
+fn forbid_only_does_not_build(args: &[&str]) {
+    let (output, cx) = run_geiger_with(Test3::NAME, args);
+    assert!(output.status.success());
+    assert!(!cx.crate_dir(Test3::NAME).join("target").exists());
+}
+
 trait Test {
     const NAME: &'static str;
 
@@ -128,7 +2277,10 @@ trait Test {
         assert!(output.status.success());
         let actual =
             serde_json::from_slice::<SafetyReport>(&output.stdout).unwrap();
-        assert_eq!(actual, self.expected_report(&cx));
+        assert_eq!(
+            without_breakdowns(without_metadata(actual)),
+            self.expected_report(&cx)
+        );
     }
 
     fn run_quick(&self) {
@@ -137,6 +2289,11 @@ trait Test {
         let actual =
             serde_json::from_slice::<QuickSafetyReport>(&output.stdout)
                 .unwrap();
+        assert!(!actual.metadata.lock_fingerprint.is_empty());
+        let actual = QuickSafetyReport {
+            metadata: Default::default(),
+            ..actual
+        };
         assert_eq!(actual, self.expected_quick_report(&cx));
     }
 }
@@ -167,6 +2324,14 @@ impl Test for Test1 {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: true,
+            proc_macros_applied: Vec::new(),
         }
     }
 }
@@ -207,6 +2372,14 @@ impl Test for Test2 {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: true,
+            proc_macros_applied: Vec::new(),
         }
     }
 }
@@ -249,6 +2422,14 @@ impl Test for Test3 {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: true,
+            proc_macros_applied: Vec::new(),
         }
     }
 }
@@ -296,6 +2477,14 @@ impl Test for Test4 {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: true,
+            proc_macros_applied: Vec::new(),
         }
     }
 }
@@ -340,6 +2529,14 @@ impl Test for Test6 {
                 forbids_unsafe: true,
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: true,
+            proc_macros_applied: Vec::new(),
         }
     }
 }
@@ -377,6 +2574,64 @@ impl Test for Test7 {
                 forbids_unsafe: true,
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: true,
+            proc_macros_applied: Vec::new(),
+        }
+    }
+}
+
+struct Test8;
+
+impl Test for Test8 {
+    const NAME: &'static str = "test8_package_with_path_override";
+
+    fn expected_report(&self, cx: &Context) -> SafetyReport {
+        let mut report =
+            single_entry_safety_report(self.expected_report_entry(cx));
+        merge_test_reports(
+            &mut report,
+            external::ref_slice_override_safety_report(cx),
+        );
+        report
+    }
+
+    fn expected_report_entry(&self, cx: &Context) -> ReportEntry {
+        ReportEntry {
+            package: PackageInfo {
+                dependencies: to_set(vec![
+                    external::ref_slice_override_package_id(cx),
+                ]),
+                ..PackageInfo::new(make_package_id(cx, Self::NAME))
+            },
+            unsafety: UnsafeInfo {
+                used: CounterBlock {
+                    functions: Count {
+                        safe: 1,
+                        unsafe_: 0,
+                    },
+                    exprs: Count {
+                        safe: 3,
+                        unsafe_: 0,
+                    },
+                    ..Default::default()
+                },
+                forbids_unsafe: true,
+                ..Default::default()
+            },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: true,
+            proc_macros_applied: Vec::new(),
         }
     }
 }
@@ -385,6 +2640,47 @@ fn run_geiger(test_name: &str) -> Output {
     run_geiger_with(test_name, None::<&str>).0
 }
 
+/// The lock metadata depends on the registry state at the time of the test
+/// run, so only its presence is checked.
+fn without_metadata(report: SafetyReport) -> SafetyReport {
+    assert!(!report.metadata.lock_fingerprint.is_empty());
+    assert!(!report.metadata.locked_packages.is_empty());
+    SafetyReport {
+        metadata: Default::default(),
+        ..report
+    }
+}
+
+/// The expected reports only list the combined counters, the splits by target
+/// kind and by feature, the public API counters, the enabled features and the
+/// depths are checked separately.
+fn without_breakdowns(report: SafetyReport) -> SafetyReport {
+    SafetyReport {
+        packages: report
+            .packages
+            .into_iter()
+            .map(|(id, entry)| {
+                let unsafety = UnsafeInfo {
+                    targets: Default::default(),
+                    by_feature: Default::default(),
+                    public_api: Default::default(),
+                    ..entry.unsafety
+                };
+                (
+                    id,
+                    ReportEntry {
+                        unsafety,
+                        features: Vec::new(),
+                        depth: None,
+                        ..entry
+                    },
+                )
+            })
+            .collect(),
+        ..report
+    }
+}
+
 fn run_geiger_json(test_name: &str) -> (Output, Context) {
     run_geiger_with(test_name, &["--json"])
 }
@@ -399,19 +2695,131 @@ where
     I::Item: AsRef<std::ffi::OsStr>,
 {
     let cx = Context::new();
-    let output = Command::cargo_bin("cargo-geiger")
-        .unwrap()
+    let output = geiger_command(&cx, test_name)
+        .args(extra_args)
+        .output()
+        .expect("failed to run `cargo-geiger`");
+    (output, cx)
+}
+
+fn geiger_command(cx: &Context, test_name: &str) -> Command {
+    let mut command = Command::cargo_bin("cargo-geiger").unwrap();
+    command
         .arg("geiger")
         .arg("--color=never")
         .arg("--quiet")
         .arg("--charset=ascii")
         .arg("--all-targets")
         .arg("--all-features")
-        .args(extra_args)
-        .current_dir(cx.crate_dir(test_name))
-        .output()
-        .expect("failed to run `cargo-geiger`");
-    (output, cx)
+        .current_dir(cx.crate_dir(test_name));
+    command
+}
+
+/// Checks `value` against `schema`, for the keywords the schema of the report
+/// uses. `root` holds the definitions that `$ref`s point to.
+fn schema_errors(
+    value: &Value,
+    schema: &Value,
+    root: &Value,
+    path: &str,
+) -> Vec<String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/definitions/");
+        return schema_errors(value, &root["definitions"][name], root, path);
+    }
+    let matches =
+        |schema: &Value| schema_errors(value, schema, root, path).is_empty();
+    let mut errors = Vec::new();
+    if let Some(types) = schema.get("type") {
+        let types = match types {
+            Value::Array(types) => types.clone(),
+            single => vec![single.clone()],
+        };
+        if !types
+            .iter()
+            .any(|name| has_schema_type(value, name.as_str().unwrap()))
+        {
+            errors.push(format!(
+                "{}: {} is not of type {:?}",
+                path, value, types
+            ));
+            return errors;
+        }
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            errors.push(format!(
+                "{}: {} is not one of {:?}",
+                path, value, values
+            ));
+        }
+    }
+    for all_of in schema["allOf"].as_array().into_iter().flatten() {
+        errors.extend(schema_errors(value, all_of, root, path));
+    }
+    if let Some(any_of) = schema["anyOf"].as_array() {
+        if !any_of.iter().any(matches) {
+            errors.push(format!("{}: {} matches none of anyOf", path, value));
+        }
+    }
+    if let Some(one_of) = schema["oneOf"].as_array() {
+        if one_of.iter().filter(|schema| matches(schema)).count() != 1 {
+            errors.push(format!(
+                "{}: {} matches not exactly one of oneOf",
+                path, value
+            ));
+        }
+    }
+    if let Value::Object(object) = value {
+        for name in schema["required"].as_array().into_iter().flatten() {
+            if !object.contains_key(name.as_str().unwrap()) {
+                errors.push(format!("{}: missing {}", path, name));
+            }
+        }
+        for (name, field) in object {
+            let field_path = format!("{}.{}", path, name);
+            match (
+                schema["properties"].get(name),
+                &schema["additionalProperties"],
+            ) {
+                (Some(property), _) => errors.extend(schema_errors(
+                    field,
+                    property,
+                    root,
+                    &field_path,
+                )),
+                (None, Value::Bool(false)) => {
+                    errors.push(format!("{}: unexpected field", field_path))
+                }
+                (None, additional @ Value::Object(_)) => errors.extend(
+                    schema_errors(field, additional, root, &field_path),
+                ),
+                (None, _) => {}
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) =
+        (value, schema.get("items"))
+    {
+        for (index, item) in items.iter().enumerate() {
+            let item_path = format!("{}[{}]", path, index);
+            errors.extend(schema_errors(item, item_schema, root, &item_path));
+        }
+    }
+    errors
+}
+
+fn has_schema_type(value: &Value, name: &str) -> bool {
+    match name {
+        "array" => value.is_array(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "object" => value.is_object(),
+        "string" => value.is_string(),
+        _ => panic!("unknown schema type `{}`", name),
+    }
 }
 
 fn make_source(cx: &Context, name: &str) -> Source {
@@ -498,8 +2906,19 @@ where
 
 // This function does not handle all merges but works well enough to avoid repetition in these
 // tests.
+/// The packages of `other` are dependencies of the package of `report`, none
+/// of them is a member of its workspace.
 fn merge_test_reports(report: &mut SafetyReport, other: SafetyReport) {
-    report.packages.extend(other.packages);
+    report
+        .packages
+        .extend(other.packages.into_iter().map(|(id, entry)| {
+            let entry = ReportEntry {
+                is_workspace_member: false,
+                proc_macros_applied: Vec::new(),
+                ..entry
+            };
+            (id, entry)
+        }));
     report
         .packages_without_metrics
         .extend(other.packages_without_metrics);
@@ -523,6 +2942,8 @@ fn to_quick_report(report: SafetyReport) -> QuickSafetyReport {
     QuickSafetyReport {
         packages: entries,
         packages_without_metrics: report.packages_without_metrics,
+        dependency_cycles: report.dependency_cycles,
+        metadata: report.metadata,
     }
 }
 
@@ -577,6 +2998,14 @@ mod external {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         single_entry_safety_report(entry)
     }
@@ -614,6 +3043,14 @@ mod external {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         single_entry_safety_report(entry)
     }
@@ -643,6 +3080,14 @@ mod external {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         single_entry_safety_report(entry)
     }
@@ -689,6 +3134,7 @@ mod external {
                         safe: 180,
                         unsafe_: 0,
                     },
+                    ..Default::default()
                 },
                 unused: CounterBlock {
                     functions: Count {
@@ -711,9 +3157,18 @@ mod external {
                         safe: 29,
                         unsafe_: 3,
                     },
+                    ..Default::default()
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         let mut report = single_entry_safety_report(entry);
         merge_test_reports(&mut report, either_safety_report());
@@ -732,6 +3187,14 @@ mod external {
         let entry = ReportEntry {
             package: PackageInfo::new(cfg_if_package_id()),
             unsafety: Default::default(),
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         single_entry_safety_report(entry)
     }
@@ -786,7 +3249,16 @@ mod external {
                     ..Default::default()
                 },
                 forbids_unsafe: true,
+                ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         let mut report = single_entry_safety_report(entry);
         merge_test_reports(&mut report, cfg_if_safety_report());
@@ -836,6 +3308,14 @@ mod external {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         let mut report = single_entry_safety_report(entry);
         merge_test_reports(&mut report, matches_safety_report());
@@ -856,6 +3336,14 @@ mod external {
         let entry = ReportEntry {
             package: PackageInfo::new(matches_package_id()),
             unsafety: Default::default(),
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         single_entry_safety_report(entry)
     }
@@ -893,6 +3381,7 @@ mod external {
                         safe: 92,
                         unsafe_: 13,
                     },
+                    ..Default::default()
                 },
                 unused: CounterBlock {
                     functions: Count {
@@ -915,9 +3404,18 @@ mod external {
                         safe: 14,
                         unsafe_: 0,
                     },
+                    ..Default::default()
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         single_entry_safety_report(entry)
     }
@@ -959,6 +3457,14 @@ mod external {
                 forbids_unsafe: true,
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         let mut report = single_entry_safety_report(entry);
         merge_test_reports(&mut report, matches_safety_report());
@@ -1001,6 +3507,7 @@ mod external {
                         safe: 21,
                         unsafe_: 0,
                     },
+                    ..Default::default()
                 },
                 unused: CounterBlock {
                     functions: Count {
@@ -1015,6 +3522,14 @@ mod external {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         let mut report = single_entry_safety_report(entry);
         merge_test_reports(&mut report, smallvec_safety_report());
@@ -1029,6 +3544,47 @@ mod external {
         }
     }
 
+    /// The modified copy of `ref_slice` that `test8` points to through a
+    /// `paths` override in its `.cargo/config`.
+    pub(super) fn ref_slice_override_package_id(cx: &Context) -> PackageId {
+        PackageId {
+            name: "ref_slice".into(),
+            version: Version::new(1, 1, 1),
+            source: super::make_workspace_source(cx, "support", "ref_slice"),
+        }
+    }
+
+    pub(super) fn ref_slice_override_safety_report(
+        cx: &Context,
+    ) -> SafetyReport {
+        let entry = ReportEntry {
+            package: PackageInfo::new(ref_slice_override_package_id(cx)),
+            unsafety: UnsafeInfo {
+                used: CounterBlock {
+                    functions: Count {
+                        safe: 4,
+                        unsafe_: 1,
+                    },
+                    exprs: Count {
+                        safe: 10,
+                        unsafe_: 3,
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
+        };
+        single_entry_safety_report(entry)
+    }
+
     pub(super) fn num_cpus_safety_report(cx: &Context) -> SafetyReport {
         let entry = ReportEntry {
             package: PackageInfo {
@@ -1048,6 +3604,14 @@ mod external {
                 },
                 ..Default::default()
             },
+            features: Vec::new(),
+            platforms: Vec::new(),
+            native_linkage: None,
+            dependency_counts: None,
+            depth: None,
+            files: Vec::new(),
+            is_workspace_member: false,
+            proc_macros_applied: Vec::new(),
         };
         let mut report = single_entry_safety_report(entry);
         merge_test_reports(&mut report, super::Test1.expected_report(cx));
@@ -7,7 +7,19 @@
 #![forbid(unsafe_code)]
 #![forbid(warnings)]
 
-use cargo_geiger_serde::CounterBlock;
+mod module_files;
+mod scan_options;
+
+pub use module_files::{find_module_files, PathModule};
+pub use scan_options::ScanOptions;
+
+use module_files::path_attribute;
+
+use cargo_geiger_serde::{
+    ApiCounterBlock, Count, CounterBlock, CountingStrategy,
+};
+use proc_macro2::{TokenStream, TokenTree};
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
@@ -16,13 +28,21 @@ use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
 use std::string::FromUtf8Error;
-use syn::{visit, Expr, ImplItemMethod, ItemFn, ItemImpl, ItemMod, ItemTrait};
+use syn::spanned::Spanned;
+use syn::{
+    visit, Attribute, Expr, ForeignItemFn, ForeignItemStatic, ImplItemConst,
+    ImplItemMethod, Item, ItemConst, ItemFn, ItemForeignMod, ItemImpl, ItemMod,
+    ItemStatic, ItemTrait, Local, TraitItemConst, TraitItemMethod, Visibility,
+};
 
 #[derive(Debug)]
 pub enum ScanFileError {
     Io(io::Error, PathBuf),
     Utf8(FromUtf8Error, PathBuf),
     Syn(syn::Error, PathBuf),
+    /// The file is larger than `ScanOptions::max_file_size`, with its size in
+    /// bytes. It was not read.
+    TooLarge(u64, PathBuf),
 }
 
 impl Error for ScanFileError {}
@@ -35,14 +55,58 @@ impl fmt::Display for ScanFileError {
     }
 }
 
+/// The `cfg` names of fuzzing, model checking and other verification
+/// harnesses, see `ScanOptions::harness_cfgs`. Not used unless given.
+pub const DEFAULT_HARNESS_CFGS: [&str; 4] = ["fuzzing", "kani", "loom", "miri"];
+
+/// A limit for `ScanOptions::max_file_size` that hand written code stays well
+/// below, 16 MiB. Not used unless given.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
 /// Scan result for a single `.rs` file.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct RsFileMetrics {
     /// Metrics storage.
     pub counters: CounterBlock,
 
+    /// The part of the functions, methods and traits in `counters` that is
+    /// declared `pub`. Restricted visibility like `pub(crate)` is not public.
+    pub public_api: ApiCounterBlock,
+
     /// This file is decorated with `#![forbid(unsafe_code)]`
     pub forbids_unsafe: bool,
+
+    /// This file is decorated with `#![deny(unsafe_code)]`
+    pub denies_unsafe: bool,
+
+    /// Unsafe usage per inline module, keyed by the path of the module
+    /// within this file, e.g. `raw::sys`. Unsafe usage outside of inline
+    /// modules is keyed by the empty string. Modules without unsafe usage
+    /// are left out.
+    pub unsafe_by_module: HashMap<String, u64>,
+
+    /// Unsafe usage per cargo feature, keyed by the feature named in the
+    /// innermost `#[cfg(feature = "...")]` attribute enclosing it. Unsafe
+    /// usage outside of feature gates is keyed by the empty string. This is
+    /// read from the attributes alone, regardless of the enabled features.
+    pub unsafe_by_feature: HashMap<String, u64>,
+
+    /// The lines of the `transmute` calls counted as unsafe, in the order
+    /// they appear in the file.
+    pub transmute_lines: Vec<usize>,
+
+    /// Calls matching the watched call patterns, keyed by the pattern as it
+    /// was given. Patterns without matching calls are left out.
+    pub watched_calls: HashMap<String, Count>,
+
+    /// The modules declared with a `#[path]` attribute. Their files can be
+    /// anywhere, even outside of the package.
+    pub path_modules: Vec<PathModule>,
+
+    /// Metrics of the code gated by one of the harness cfgs of the scan, like
+    /// `#[cfg(fuzzing)]`. Counted here instead of in `counters`, and left out
+    /// of all other metrics.
+    pub verification_harness: CounterBlock,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -66,14 +130,151 @@ struct GeigerSynVisitor {
     /// This is needed since unsafe scopes can be nested and we need to know
     /// when we leave the outmost unsafe scope and get back into a safe scope.
     unsafe_scopes: u32,
+
+    /// The `unsafe_scopes` of the items enclosing the one the visitor is
+    /// currently in, outermost first. Unsafe scopes don't reach into nested
+    /// items, an item declared in the body of an unsafe function or in an
+    /// unsafe block starts out safe.
+    enclosing_unsafe_scopes: Vec<u32>,
+
+    /// Whether the visitor is in code that is evaluated at compile time, the
+    /// initializer of a const or static or the body of a `const fn`.
+    const_context: bool,
+
+    /// The names of the inline modules that the visitor is currently in.
+    module_path: Vec<String>,
+
+    /// The inline modules that the visitor is currently in, with their
+    /// `#[path]` attributes, see `PathModule`.
+    inline_modules: Vec<(String, Option<String>)>,
+
+    /// The features of the `cfg(feature = "...")` gates that the visitor is
+    /// currently in, innermost last.
+    cfg_features: Vec<String>,
+
+    /// The call patterns to count, split into path segments.
+    watched_calls: Vec<(String, Vec<String>)>,
+
+    /// The `cfg` names that gate verification harnesses.
+    harness_cfgs: Vec<String>,
+
+    /// The number of nested harness cfg gates that the visitor is currently
+    /// in, see `RsFileMetrics::verification_harness`.
+    harness_scopes: u32,
+
+    /// Whether paths and literals are counted as expressions.
+    counting_strategy: CountingStrategy,
+}
+
+/// The `cfg` gates of an item or expression entered by `enter_cfg`, to be
+/// passed to `exit_cfg`.
+#[derive(Clone, Copy)]
+struct EnteredCfg {
+    feature: bool,
+    harness: bool,
 }
 
 impl GeigerSynVisitor {
-    fn new(include_tests: IncludeTests) -> Self {
+    fn new(scan_options: &ScanOptions) -> Self {
         GeigerSynVisitor {
-            include_tests,
+            include_tests: scan_options.include_tests,
             metrics: Default::default(),
             unsafe_scopes: 0,
+            enclosing_unsafe_scopes: Vec::new(),
+            const_context: false,
+            module_path: Vec::new(),
+            inline_modules: Vec::new(),
+            cfg_features: Vec::new(),
+            watched_calls: scan_options
+                .watched_calls
+                .iter()
+                .map(|pattern| (pattern.clone(), path_segments(pattern)))
+                .collect(),
+            harness_cfgs: scan_options.harness_cfgs.clone(),
+            harness_scopes: 0,
+            counting_strategy: scan_options.counting_strategy,
+        }
+    }
+
+    /// The counters of the code the visitor is in, the ones of the
+    /// verification harnesses inside a harness cfg gate.
+    fn counters(&mut self) -> &mut CounterBlock {
+        if self.in_harness() {
+            &mut self.metrics.verification_harness
+        } else {
+            &mut self.metrics.counters
+        }
+    }
+
+    fn in_harness(&self) -> bool {
+        self.harness_scopes > 0
+    }
+
+    /// Counts a call for every watched pattern that `segments` matches.
+    /// With `partial`, the call only spells out the end of its path, as in
+    /// method calls and `<T>::f(..)`, and the pattern segments before it are
+    /// not compared.
+    fn count_watched_calls(&mut self, segments: &[String], partial: bool) {
+        if self.in_harness() {
+            return;
+        }
+        let is_unsafe = self.unsafe_scopes > 0;
+        for (pattern, pattern_segments) in &self.watched_calls {
+            if path_matches(pattern_segments, segments, partial) {
+                self.metrics
+                    .watched_calls
+                    .entry(pattern.clone())
+                    .or_default()
+                    .count(is_unsafe);
+            }
+        }
+    }
+
+    /// Attributes unsafe usage to the current module and feature.
+    fn count_in_module(&mut self, is_unsafe: bool) {
+        if is_unsafe && !self.in_harness() {
+            *self
+                .metrics
+                .unsafe_by_module
+                .entry(self.module_path.join("::"))
+                .or_insert(0) += 1;
+            *self
+                .metrics
+                .unsafe_by_feature
+                .entry(self.cfg_features.last().cloned().unwrap_or_default())
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Without `IncludeTests::Yes` the items gated by `#[cfg(test)]` are
+    /// skipped along with everything in them.
+    fn skips_test_item(&self, attrs: &[Attribute]) -> bool {
+        IncludeTests::No == self.include_tests && is_cfg_test(attrs)
+    }
+
+    /// Enters the feature gate and the harness cfg gate of an item or
+    /// expression, the ones it has.
+    fn enter_cfg(&mut self, attrs: &[Attribute]) -> EnteredCfg {
+        let feature = match cfg_feature(attrs) {
+            Some(feature) => {
+                self.cfg_features.push(feature);
+                true
+            }
+            None => false,
+        };
+        let harness = is_cfg_harness(attrs, &self.harness_cfgs);
+        if harness {
+            self.harness_scopes += 1;
+        }
+        EnteredCfg { feature, harness }
+    }
+
+    fn exit_cfg(&mut self, entered: EnteredCfg) {
+        if entered.feature {
+            self.cfg_features.pop();
+        }
+        if entered.harness {
+            self.harness_scopes -= 1;
         }
     }
 
@@ -84,19 +285,42 @@ impl GeigerSynVisitor {
     fn exit_unsafe_scope(&mut self) {
         self.unsafe_scopes -= 1;
     }
+
+    /// Enters an item, at the top level of a file or module or nested in a
+    /// function body, leaving the unsafe scopes of the enclosing code.
+    fn enter_item(&mut self) {
+        self.enclosing_unsafe_scopes.push(self.unsafe_scopes);
+        self.unsafe_scopes = 0;
+    }
+
+    fn exit_item(&mut self) {
+        self.unsafe_scopes = self.enclosing_unsafe_scopes.pop().unwrap();
+    }
+
+    /// Enters a const initializer or a function body, which is a const
+    /// context for a `const fn` only. A function nested in a const context
+    /// is not one itself. Returns the context of the enclosing code, to be
+    /// passed to `exit_const_context`.
+    fn enter_const_context(&mut self, is_const: bool) -> bool {
+        std::mem::replace(&mut self.const_context, is_const)
+    }
+
+    fn exit_const_context(&mut self, const_context: bool) {
+        self.const_context = const_context;
+    }
 }
 
-/// Will return true for #[cfg(test)] decodated modules.
+/// Will return true for #[cfg(test)] decorated items, like modules, impls and
+/// helper functions.
 ///
 /// This function is a somewhat of a hack and will probably misinterpret more
 /// advanced cfg expressions. A better way to do this would be to let rustc emit
 /// every single source file path and span within each source file and use that
 /// as a general filter for included code.
 /// TODO: Investigate if the needed information can be emitted by rustc today.
-fn is_test_mod(i: &ItemMod) -> bool {
-    use syn::Attribute;
+fn is_cfg_test(attrs: &[Attribute]) -> bool {
     use syn::Meta;
-    i.attrs
+    attrs
         .iter()
         .flat_map(Attribute::parse_meta)
         .any(|m| match m {
@@ -139,8 +363,152 @@ fn meta_is_word_test(m: &syn::Meta) -> bool {
     }
 }
 
+/// The feature named in a `#[cfg(...)]` attribute, also when it is combined
+/// with other conditions through `all` or `any`. Features under `not` are
+/// skipped since they gate code that is compiled without the feature.
+fn cfg_feature(attrs: &[Attribute]) -> Option<String> {
+    use syn::Meta;
+    attrs
+        .iter()
+        .flat_map(Attribute::parse_meta)
+        .find_map(|m| match m {
+            Meta::List(ml) if ml.path.is_ident("cfg") => {
+                ml.nested.iter().find_map(nested_meta_feature)
+            }
+            _ => None,
+        })
+}
+
+/// Whether a `#[cfg(...)]` attribute names one of the harness cfgs, also when
+/// it is combined with other conditions through `all` or `any`. Names under
+/// `not` are skipped, like for features.
+fn is_cfg_harness(attrs: &[Attribute], harness_cfgs: &[String]) -> bool {
+    use syn::Meta;
+    if harness_cfgs.is_empty() {
+        return false;
+    }
+    attrs
+        .iter()
+        .flat_map(Attribute::parse_meta)
+        .any(|m| match m {
+            Meta::List(ml) if ml.path.is_ident("cfg") => ml
+                .nested
+                .iter()
+                .any(|n| nested_meta_is_harness(n, harness_cfgs)),
+            _ => false,
+        })
+}
+
+fn nested_meta_is_harness(
+    n: &syn::NestedMeta,
+    harness_cfgs: &[String],
+) -> bool {
+    use syn::Meta;
+    use syn::NestedMeta;
+    match n {
+        NestedMeta::Meta(Meta::Path(p)) => harness_cfgs
+            .iter()
+            .any(|harness_cfg| p.is_ident(harness_cfg)),
+        NestedMeta::Meta(Meta::List(ml))
+            if ml.path.is_ident("all") || ml.path.is_ident("any") =>
+        {
+            ml.nested
+                .iter()
+                .any(|n| nested_meta_is_harness(n, harness_cfgs))
+        }
+        _ => false,
+    }
+}
+
+fn nested_meta_feature(n: &syn::NestedMeta) -> Option<String> {
+    use syn::Lit;
+    use syn::Meta;
+    use syn::NestedMeta;
+    match n {
+        NestedMeta::Meta(Meta::NameValue(nv))
+            if nv.path.is_ident("feature") =>
+        {
+            match &nv.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            }
+        }
+        NestedMeta::Meta(Meta::List(ml))
+            if ml.path.is_ident("all") || ml.path.is_ident("any") =>
+        {
+            ml.nested.iter().find_map(nested_meta_feature)
+        }
+        _ => None,
+    }
+}
+
+/// The attributes of the expressions that can be feature gated as a
+/// statement, other expressions have none.
+fn expr_attrs(i: &Expr) -> &[Attribute] {
+    match i {
+        Expr::Assign(e) => &e.attrs,
+        Expr::Block(e) => &e.attrs,
+        Expr::Call(e) => &e.attrs,
+        Expr::ForLoop(e) => &e.attrs,
+        Expr::If(e) => &e.attrs,
+        Expr::Loop(e) => &e.attrs,
+        Expr::Macro(e) => &e.attrs,
+        Expr::Match(e) => &e.attrs,
+        Expr::MethodCall(e) => &e.attrs,
+        Expr::Unsafe(e) => &e.attrs,
+        Expr::While(e) => &e.attrs,
+        _ => &[],
+    }
+}
+
+/// Only plain `pub` makes an item part of the public API, `pub(crate)`,
+/// `pub(super)`, `pub(in path)` and `crate` keep it internal.
+fn is_public(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+/// Calls to a path ending in `transmute` or `transmute_copy`. This covers
+/// `std::mem::transmute`, `core::mem::transmute` and a bare `transmute` after
+/// a `use`, but also any other function that happens to have the same name.
+fn is_transmute_call(i: &syn::ExprCall) -> bool {
+    match &*i.func {
+        Expr::Path(p) => p.path.segments.last().map_or(false, |segment| {
+            segment.ident == "transmute" || segment.ident == "transmute_copy"
+        }),
+        _ => false,
+    }
+}
+
+/// Splits a call pattern like `std::ptr::copy_nonoverlapping` into its path
+/// segments. A leading `::` is ignored.
+fn path_segments(pattern: &str) -> Vec<String> {
+    pattern
+        .split("::")
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Whether `pattern` is a suffix of the path `segments`, or with `partial`,
+/// whether `segments` is a suffix of `pattern` when the path is the shorter
+/// one.
+fn path_matches(
+    pattern: &[String],
+    segments: &[String],
+    partial: bool,
+) -> bool {
+    if pattern.is_empty() {
+        return false;
+    }
+    if segments.len() < pattern.len() {
+        partial && pattern.ends_with(segments)
+    } else {
+        segments.ends_with(pattern)
+    }
+}
+
 fn is_test_fn(i: &ItemFn) -> bool {
-    use syn::Attribute;
     i.attrs
         .iter()
         .flat_map(Attribute::parse_meta)
@@ -148,6 +516,15 @@ fn is_test_fn(i: &ItemFn) -> bool {
 }
 
 fn file_forbids_unsafe(f: &syn::File) -> bool {
+    file_sets_unsafe_code_lint(f, "forbid")
+}
+
+fn file_denies_unsafe(f: &syn::File) -> bool {
+    file_sets_unsafe_code_lint(f, "deny")
+}
+
+/// Checks for an inner attribute like `#![<level>(unsafe_code)]`.
+fn file_sets_unsafe_code_lint(f: &syn::File, level: &str) -> bool {
     use syn::AttrStyle;
     use syn::Meta;
     use syn::MetaList;
@@ -162,7 +539,7 @@ fn file_forbids_unsafe(f: &syn::File) -> bool {
                 paren_token: _paren,
                 nested,
             }) => {
-                if !path.is_ident("forbid") {
+                if !path.is_ident(level) {
                     return false;
                 }
                 nested.iter().any(|n| match n {
@@ -181,28 +558,46 @@ fn file_forbids_unsafe(f: &syn::File) -> bool {
 impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
     fn visit_file(&mut self, i: &'ast syn::File) {
         self.metrics.forbids_unsafe = file_forbids_unsafe(i);
+        self.metrics.denies_unsafe = file_denies_unsafe(i);
         syn::visit::visit_file(self, i);
     }
 
+    fn visit_item(&mut self, i: &Item) {
+        self.enter_item();
+        visit::visit_item(self, i);
+        self.exit_item();
+    }
+
     /// Free-standing functions
     fn visit_item_fn(&mut self, i: &ItemFn) {
-        if IncludeTests::No == self.include_tests && is_test_fn(i) {
+        if (IncludeTests::No == self.include_tests && is_test_fn(i))
+            || self.skips_test_item(&i.attrs)
+        {
             return;
         }
+        let cfg = self.enter_cfg(&i.attrs);
         if i.sig.unsafety.is_some() {
             self.enter_unsafe_scope()
         }
-        self.metrics
-            .counters
-            .functions
-            .count(i.sig.unsafety.is_some());
+        self.counters().functions.count(i.sig.unsafety.is_some());
+        if is_public(&i.vis) && !self.in_harness() {
+            self.metrics
+                .public_api
+                .functions
+                .count(i.sig.unsafety.is_some());
+        }
+        self.count_in_module(i.sig.unsafety.is_some());
+        let const_context = self.enter_const_context(i.sig.constness.is_some());
         visit::visit_item_fn(self, i);
+        self.exit_const_context(const_context);
         if i.sig.unsafety.is_some() {
             self.exit_unsafe_scope()
         }
+        self.exit_cfg(cfg);
     }
 
     fn visit_expr(&mut self, i: &Expr) {
+        let cfg = self.enter_cfg(expr_attrs(i));
         // Total number of expressions of any type
         match i {
             Expr::Unsafe(i) => {
@@ -210,7 +605,9 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
                 visit::visit_expr_unsafe(self, i);
                 self.exit_unsafe_scope();
             }
-            Expr::Path(_) | Expr::Lit(_) => {
+            Expr::Path(_) | Expr::Lit(_)
+                if self.counting_strategy == CountingStrategy::Geiger =>
+            {
                 // Do not count. The expression `f(x)` should count as one
                 // expression, not three.
             }
@@ -220,46 +617,215 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
                 // if self.verbosity == Verbosity::Verbose && self.unsafe_scopes > 0 {
                 //     println!("{:#?}", other);
                 // }
-                self.metrics.counters.exprs.count(self.unsafe_scopes > 0);
+                let is_unsafe = self.unsafe_scopes > 0;
+                self.counters().exprs.count(is_unsafe);
+                if self.const_context {
+                    self.counters().const_unsafe.count(is_unsafe);
+                }
+                self.count_in_module(is_unsafe);
+                match other {
+                    Expr::Call(call) => {
+                        if is_transmute_call(call) {
+                            self.counters().transmutes.count(is_unsafe);
+                            if is_unsafe && !self.in_harness() {
+                                self.metrics
+                                    .transmute_lines
+                                    .push(call.span().start().line);
+                            }
+                        }
+                        if let Expr::Path(p) = &*call.func {
+                            let segments = p
+                                .path
+                                .segments
+                                .iter()
+                                .map(|segment| segment.ident.to_string())
+                                .collect::<Vec<_>>();
+                            self.count_watched_calls(
+                                &segments,
+                                p.qself.is_some(),
+                            );
+                        }
+                    }
+                    Expr::MethodCall(call) => {
+                        self.count_watched_calls(
+                            &[call.method.to_string()],
+                            true,
+                        );
+                    }
+                    _ => {}
+                }
                 visit::visit_expr(self, other);
             }
         }
+        self.exit_cfg(cfg);
+    }
+
+    fn visit_local(&mut self, i: &Local) {
+        let cfg = self.enter_cfg(&i.attrs);
+        visit::visit_local(self, i);
+        self.exit_cfg(cfg);
+    }
+
+    fn visit_item_const(&mut self, i: &ItemConst) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
+        let const_context = self.enter_const_context(true);
+        visit::visit_item_const(self, i);
+        self.exit_const_context(const_context);
+        self.exit_cfg(cfg);
+    }
+
+    fn visit_item_static(&mut self, i: &ItemStatic) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
+        let const_context = self.enter_const_context(true);
+        visit::visit_item_static(self, i);
+        self.exit_const_context(const_context);
+        self.exit_cfg(cfg);
+    }
+
+    /// Associated consts of impls
+    fn visit_impl_item_const(&mut self, i: &ImplItemConst) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
+        let const_context = self.enter_const_context(true);
+        visit::visit_impl_item_const(self, i);
+        self.exit_const_context(const_context);
+        self.exit_cfg(cfg);
+    }
+
+    /// Associated consts of traits, with their default value
+    fn visit_trait_item_const(&mut self, i: &TraitItemConst) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
+        let const_context = self.enter_const_context(true);
+        visit::visit_trait_item_const(self, i);
+        self.exit_const_context(const_context);
+        self.exit_cfg(cfg);
+    }
+
+    /// Methods of traits, with their default body
+    fn visit_trait_item_method(&mut self, i: &TraitItemMethod) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
+        visit::visit_trait_item_method(self, i);
+        self.exit_cfg(cfg);
     }
 
     fn visit_item_mod(&mut self, i: &ItemMod) {
-        if IncludeTests::No == self.include_tests && is_test_mod(i) {
+        if self.skips_test_item(&i.attrs) {
             return;
         }
+        let cfg = self.enter_cfg(&i.attrs);
+        let path_attribute = path_attribute(i);
+        if i.content.is_none() {
+            if let Some(path) = path_attribute.clone() {
+                self.metrics.path_modules.push(PathModule {
+                    inline_modules: self.inline_modules.clone(),
+                    path,
+                });
+            }
+        }
+        self.module_path.push(i.ident.to_string());
+        self.inline_modules
+            .push((i.ident.to_string(), path_attribute));
         visit::visit_item_mod(self, i);
+        self.inline_modules.pop();
+        self.module_path.pop();
+        self.exit_cfg(cfg);
     }
 
     fn visit_item_impl(&mut self, i: &ItemImpl) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
         // unsafe trait impl's
-        self.metrics.counters.item_impls.count(i.unsafety.is_some());
+        self.counters().item_impls.count(i.unsafety.is_some());
+        self.count_in_module(i.unsafety.is_some());
         visit::visit_item_impl(self, i);
+        self.exit_cfg(cfg);
     }
 
     fn visit_item_trait(&mut self, i: &ItemTrait) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
         // Unsafe traits
-        self.metrics
-            .counters
-            .item_traits
-            .count(i.unsafety.is_some());
+        self.counters().item_traits.count(i.unsafety.is_some());
+        if is_public(&i.vis) && !self.in_harness() {
+            self.metrics
+                .public_api
+                .item_traits
+                .count(i.unsafety.is_some());
+        }
+        self.count_in_module(i.unsafety.is_some());
         visit::visit_item_trait(self, i);
+        self.exit_cfg(cfg);
     }
 
     fn visit_impl_item_method(&mut self, i: &ImplItemMethod) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
         if i.sig.unsafety.is_some() {
             self.enter_unsafe_scope()
         }
-        self.metrics
-            .counters
-            .methods
-            .count(i.sig.unsafety.is_some());
+        self.counters().methods.count(i.sig.unsafety.is_some());
+        if is_public(&i.vis) && !self.in_harness() {
+            self.metrics
+                .public_api
+                .methods
+                .count(i.sig.unsafety.is_some());
+        }
+        self.count_in_module(i.sig.unsafety.is_some());
+        let const_context = self.enter_const_context(i.sig.constness.is_some());
         visit::visit_impl_item_method(self, i);
+        self.exit_const_context(const_context);
         if i.sig.unsafety.is_some() {
             self.exit_unsafe_scope()
         }
+        self.exit_cfg(cfg);
+    }
+
+    /// `extern` blocks
+    fn visit_item_foreign_mod(&mut self, i: &ItemForeignMod) {
+        if self.skips_test_item(&i.attrs) {
+            return;
+        }
+        let cfg = self.enter_cfg(&i.attrs);
+        visit::visit_item_foreign_mod(self, i);
+        self.exit_cfg(cfg);
+    }
+
+    /// Functions declared in `extern` blocks
+    fn visit_foreign_item_fn(&mut self, i: &ForeignItemFn) {
+        let cfg = self.enter_cfg(&i.attrs);
+        self.counters().foreign_items.count(true);
+        self.count_in_module(true);
+        visit::visit_foreign_item_fn(self, i);
+        self.exit_cfg(cfg);
+    }
+
+    /// Statics declared in `extern` blocks
+    fn visit_foreign_item_static(&mut self, i: &ForeignItemStatic) {
+        let cfg = self.enter_cfg(&i.attrs);
+        self.counters().foreign_items.count(true);
+        self.count_in_module(true);
+        visit::visit_foreign_item_static(self, i);
+        self.exit_cfg(cfg);
     }
 
     // TODO: Visit macros.
@@ -268,29 +834,704 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
     // implemented here.
 }
 
-pub fn find_unsafe_in_string(
+/// Scan source code for `unsafe` usage. Calls matching one of the watched
+/// call path patterns of the options, like `ptr::copy_nonoverlapping`, are
+/// counted per pattern. Method calls are matched by the last segment of the
+/// pattern alone, the type of the receiver is not known. Empty source code,
+/// like a placeholder file, is safe without being parsed.
+pub fn find_unsafe_in_string_with_options(
     src: &str,
-    include_tests: IncludeTests,
+    scan_options: &ScanOptions,
 ) -> Result<RsFileMetrics, syn::Error> {
     use syn::visit::Visit;
+    if src.is_empty() {
+        return Ok(RsFileMetrics::default());
+    }
     let syntax = syn::parse_file(&src)?;
-    let mut vis = GeigerSynVisitor::new(include_tests);
+    let mut vis = GeigerSynVisitor::new(scan_options);
     vis.visit_file(&syntax);
     Ok(vis.metrics)
 }
 
 /// Scan a single file for `unsafe` usage.
+pub fn find_unsafe_in_file_with_options(
+    p: &Path,
+    scan_options: &ScanOptions,
+) -> Result<RsFileMetrics, ScanFileError> {
+    if let Some(max_file_size) = scan_options.max_file_size {
+        let size = p
+            .metadata()
+            .map_err(|e| ScanFileError::Io(e, p.to_path_buf()))?
+            .len();
+        if size > max_file_size {
+            return Err(ScanFileError::TooLarge(size, p.to_path_buf()));
+        }
+    }
+    let src = read_source_file(p)?;
+    find_unsafe_in_string_with_options(&src, scan_options)
+        .map_err(|e| ScanFileError::Syn(e, p.to_path_buf()))
+}
+
+/// Scan source code for `unsafe` usage.
+#[deprecated(note = "use `find_unsafe_in_string_with_options` instead")]
+pub fn find_unsafe_in_string(
+    src: &str,
+    include_tests: IncludeTests,
+) -> Result<RsFileMetrics, syn::Error> {
+    find_unsafe_in_string_with_options(
+        src,
+        &ScanOptions::default().include_tests(include_tests),
+    )
+}
+
+/// Scan a single file for `unsafe` usage.
+#[deprecated(note = "use `find_unsafe_in_file_with_options` instead")]
 pub fn find_unsafe_in_file(
     p: &Path,
     include_tests: IncludeTests,
 ) -> Result<RsFileMetrics, ScanFileError> {
+    find_unsafe_in_file_with_options(
+        p,
+        &ScanOptions::default().include_tests(include_tests),
+    )
+}
+
+/// Counts the `unsafe` keywords of source code that syn can't parse, like
+/// code using newer syntax than this version of syn knows. The code is only
+/// lexed, nothing is known about what each keyword applies to and tests are
+/// counted too. Keywords in string literals and comments are not tokens of
+/// their own and are not counted. `None` if the code can't be lexed either,
+/// like with unbalanced delimiters.
+pub fn count_unsafe_tokens_in_string(src: &str) -> Option<u64> {
+    src.parse::<TokenStream>().ok().map(count_unsafe_tokens)
+}
+
+/// Like `count_unsafe_tokens_in_string`, `None` if the file can't be read.
+pub fn count_unsafe_tokens_in_file(p: &Path) -> Option<u64> {
+    read_source_file(p)
+        .ok()
+        .and_then(|src| count_unsafe_tokens_in_string(&src))
+}
+
+fn count_unsafe_tokens(token_stream: TokenStream) -> u64 {
+    token_stream
+        .into_iter()
+        .map(|token_tree| match token_tree {
+            TokenTree::Ident(ident) if ident == "unsafe" => 1,
+            TokenTree::Group(group) => count_unsafe_tokens(group.stream()),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn read_source_file(p: &Path) -> Result<String, ScanFileError> {
     let mut file =
         File::open(p).map_err(|e| ScanFileError::Io(e, p.to_path_buf()))?;
     let mut src = vec![];
     file.read_to_end(&mut src)
         .map_err(|e| ScanFileError::Io(e, p.to_path_buf()))?;
-    let src = String::from_utf8(src)
-        .map_err(|e| ScanFileError::Utf8(e, p.to_path_buf()))?;
-    find_unsafe_in_string(&src, include_tests)
-        .map_err(|e| ScanFileError::Syn(e, p.to_path_buf()))
+    String::from_utf8(src).map_err(|e| ScanFileError::Utf8(e, p.to_path_buf()))
+}
+
+#[cfg(test)]
+mod geiger_tests {
+    use super::*;
+
+    #[test]
+    fn unsafe_tokens_are_counted_in_unparsable_code() {
+        // `become` is reserved, syn doesn't parse it as an expression.
+        let src = r#"
+            /// Calls `unsafe` code.
+            pub unsafe fn call(f: fn()) {
+                unsafe { become f() }
+            }
+
+            // unsafe impl Send for Call {}
+            unsafe impl Sync for Call {}
+
+            const NOTE: &str = "unsafe { }";
+            const RAW: &str = r"unsafe";
+            macro_rules! twice {
+                ($e:expr) => { unsafe { $e; $e } };
+            }
+        "#;
+
+        assert!(find_unsafe_in_string_with_options(
+            src,
+            &ScanOptions::default()
+        )
+        .is_err());
+        assert_eq!(count_unsafe_tokens_in_string(src), Some(4));
+        assert_eq!(count_unsafe_tokens_in_string("fn f() { unsafe {"), None);
+    }
+
+    #[test]
+    fn tests_are_counted_with_include_tests() {
+        let src = "
+            #[test]
+            fn test() {
+                unsafe { std::ptr::null::<u8>().read() };
+            }
+
+            #[cfg(test)]
+            mod tests {
+                unsafe fn helper() {}
+            }
+        ";
+        let count_functions = |scan_options: &ScanOptions| {
+            find_unsafe_in_string_with_options(src, scan_options)
+                .unwrap()
+                .counters
+                .functions
+        };
+
+        assert_eq!(count_functions(&ScanOptions::default()), Count::default());
+        assert_eq!(
+            count_functions(
+                &ScanOptions::default().include_tests(IncludeTests::Yes)
+            ),
+            Count {
+                safe: 1,
+                unsafe_: 1
+            }
+        );
+    }
+
+    #[test]
+    fn cfg_test_items_are_counted_with_include_tests() {
+        let src = "
+            struct Buffer(Vec<u8>);
+
+            #[cfg(test)]
+            impl Buffer {
+                unsafe fn first_unchecked(&self) -> u8 {
+                    *self.0.get_unchecked(0)
+                }
+            }
+
+            #[cfg(test)]
+            unsafe impl Send for Buffer {}
+
+            #[cfg(test)]
+            unsafe trait TestOnly {}
+
+            trait Peek {
+                #[cfg(test)]
+                fn peek(bytes: &[u8]) -> u8 {
+                    unsafe { *bytes.get_unchecked(0) }
+                }
+            }
+
+            #[cfg(test)]
+            fn helper(bytes: &[u8]) -> u8 {
+                unsafe { *bytes.get_unchecked(0) }
+            }
+
+            #[cfg(test)]
+            static FIRST: u8 = unsafe { *[1u8].as_ptr() };
+        ";
+        let counters = |scan_options: &ScanOptions| {
+            find_unsafe_in_string_with_options(src, scan_options)
+                .unwrap()
+                .counters
+        };
+
+        let production = counters(&ScanOptions::default());
+        assert!(!production.has_unsafe());
+        assert_eq!(production.item_impls, Count::default());
+        assert_eq!(production.methods, Count::default());
+
+        let all =
+            counters(&ScanOptions::default().include_tests(IncludeTests::Yes));
+        assert_eq!(all.methods.unsafe_, 1);
+        assert_eq!(all.item_impls.unsafe_, 1);
+        assert_eq!(all.item_traits.unsafe_, 1);
+        assert_eq!(all.functions.safe, 1);
+        assert!(all.exprs.unsafe_ > 0);
+    }
+
+    #[test]
+    fn items_nested_in_test_functions_are_counted_with_include_tests() {
+        let src = "
+            #[test]
+            fn test() {
+                unsafe fn inner() {}
+                struct S;
+                impl S {
+                    unsafe fn m(&self) {}
+                }
+            }
+
+            fn outer() {
+                #[cfg(test)]
+                unsafe fn helper() {}
+            }
+        ";
+        let counters = |scan_options: &ScanOptions| {
+            find_unsafe_in_string_with_options(src, scan_options)
+                .unwrap()
+                .counters
+        };
+
+        let production = counters(&ScanOptions::default());
+        assert_eq!(
+            production.functions,
+            Count {
+                safe: 1,
+                unsafe_: 0
+            }
+        );
+        assert_eq!(production.methods, Count::default());
+
+        let all =
+            counters(&ScanOptions::default().include_tests(IncludeTests::Yes));
+        assert_eq!(
+            all.functions,
+            Count {
+                safe: 2,
+                unsafe_: 2
+            }
+        );
+        assert_eq!(
+            all.methods,
+            Count {
+                safe: 0,
+                unsafe_: 1
+            }
+        );
+    }
+
+    #[test]
+    fn items_nested_in_unsafe_code_start_out_safe() {
+        let src = "
+            unsafe fn outer(p: *const u8) -> u8 {
+                fn inner(x: u8) -> u8 {
+                    x + 1
+                }
+                struct S;
+                impl S {
+                    fn m(&self) -> u8 {
+                        2 + 2
+                    }
+                }
+                inner(*p)
+            }
+
+            fn safe() {
+                unsafe {
+                    fn nested() -> u8 {
+                        1 + 1
+                    }
+                }
+            }
+        ";
+        let counters =
+            find_unsafe_in_string_with_options(src, &ScanOptions::default())
+                .unwrap()
+                .counters;
+
+        // Only the call of `inner` and the dereference in the body of
+        // `outer` itself are unsafe.
+        assert_eq!(
+            counters.exprs,
+            Count {
+                safe: 3,
+                unsafe_: 2
+            }
+        );
+        assert_eq!(
+            counters.functions,
+            Count {
+                safe: 3,
+                unsafe_: 1
+            }
+        );
+        assert_eq!(
+            counters.methods,
+            Count {
+                safe: 1,
+                unsafe_: 0
+            }
+        );
+    }
+
+    #[test]
+    fn transmute_calls_are_counted_by_name() {
+        let src = "
+            use std::mem::transmute;
+
+            fn f(x: u32) -> f32 {
+                unsafe { std::mem::transmute(x) }
+            }
+
+            fn g(x: u32) -> f32 {
+                let y: u32 = unsafe { core::mem::transmute_copy(&x) };
+                unsafe { transmute(y) }
+            }
+
+            fn transmute_me(x: u32) -> u32 {
+                x.transmute()
+            }
+        ";
+        let metrics =
+            find_unsafe_in_string_with_options(src, &ScanOptions::default())
+                .unwrap();
+
+        assert_eq!(
+            metrics.counters.transmutes,
+            Count {
+                safe: 0,
+                unsafe_: 3
+            }
+        );
+        assert_eq!(metrics.transmute_lines, vec![5, 9, 10]);
+    }
+
+    #[test]
+    fn expressions_in_const_contexts_are_counted() {
+        let src = "
+            static TABLE: &[u8] = unsafe { std::slice::from_raw_parts(PTR, 4) };
+
+            const fn first(bytes: &[u8]) -> u8 {
+                unsafe { *bytes.as_ptr() }
+            }
+
+            struct S;
+
+            impl S {
+                const ZERO: u32 = unsafe { std::mem::transmute(0f32) };
+            }
+
+            fn runtime(bytes: &[u8]) -> u8 {
+                const fn nested() -> u8 {
+                    1 + 1
+                }
+                unsafe { *bytes.as_ptr() }
+            }
+        ";
+        let metrics =
+            find_unsafe_in_string_with_options(src, &ScanOptions::default())
+                .unwrap();
+
+        // The expressions of `runtime` itself are not counted, the ones of
+        // the const fn nested in it are.
+        assert_eq!(
+            metrics.counters.const_unsafe,
+            Count {
+                safe: 1,
+                unsafe_: 4
+            }
+        );
+        assert_eq!(
+            metrics.counters.exprs,
+            Count {
+                safe: 1,
+                unsafe_: 6
+            }
+        );
+    }
+
+    #[test]
+    fn watched_calls_are_counted_per_pattern() {
+        let src = "
+            use std::ptr::copy_nonoverlapping;
+
+            unsafe fn copy(src: &[u8], dst: &mut [u8]) {
+                std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), 1);
+                core::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), 1);
+                copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), 1);
+            }
+
+            fn get(s: &[u8]) -> u8 {
+                let a = unsafe { *s.get_unchecked(0) };
+                let b = unsafe { *<[u8]>::get_unchecked(s, 1) };
+                let c = unsafe { *slice::get_unchecked(s, 2) };
+                let d = s.get(3).copied().unwrap_or_default();
+                a + b + c + d
+            }
+        ";
+        let scan_options = ScanOptions::default().watched_calls(vec![
+            String::from("ptr::copy_nonoverlapping"),
+            String::from("slice::get_unchecked"),
+            String::from("MaybeUninit::assume_init"),
+        ]);
+        let metrics =
+            find_unsafe_in_string_with_options(src, &scan_options).unwrap();
+
+        // The bare `copy_nonoverlapping` does not spell out `ptr`.
+        assert_eq!(
+            metrics.watched_calls["ptr::copy_nonoverlapping"],
+            Count {
+                safe: 0,
+                unsafe_: 2
+            }
+        );
+        // Method call, type-qualified and path call.
+        assert_eq!(
+            metrics.watched_calls["slice::get_unchecked"],
+            Count {
+                safe: 0,
+                unsafe_: 3
+            }
+        );
+        assert!(!metrics
+            .watched_calls
+            .contains_key("MaybeUninit::assume_init"));
+    }
+
+    #[test]
+    fn harness_cfg_code_is_counted_apart() {
+        let src = "
+            unsafe fn library() {}
+
+            #[cfg(fuzzing)]
+            mod fuzz {
+                pub unsafe fn target(data: &[u8]) -> u8 {
+                    unsafe { *data.get_unchecked(0) }
+                }
+
+                mod nested {
+                    unsafe impl Send for super::Input {}
+                }
+            }
+
+            mod verify {
+                #[cfg(all(kani, target_os = \"linux\"))]
+                unsafe fn proof() {}
+
+                #[cfg(not(kani))]
+                unsafe fn not_a_proof() {}
+            }
+        ";
+        let harness_cfgs = DEFAULT_HARNESS_CFGS
+            .iter()
+            .map(|harness_cfg| harness_cfg.to_string())
+            .collect::<Vec<_>>();
+        let count = |scan_options: &ScanOptions| {
+            find_unsafe_in_string_with_options(src, scan_options).unwrap()
+        };
+
+        let metrics = count(&ScanOptions::default());
+        assert_eq!(metrics.counters.functions.unsafe_, 4);
+        assert_eq!(metrics.verification_harness, CounterBlock::default());
+
+        let metrics = count(&ScanOptions::default().harness_cfgs(harness_cfgs));
+        assert_eq!(metrics.counters.functions.unsafe_, 2);
+        assert_eq!(metrics.counters.item_impls.unsafe_, 0);
+        assert_eq!(metrics.counters.exprs.unsafe_, 0);
+        assert_eq!(metrics.verification_harness.functions.unsafe_, 2);
+        assert_eq!(metrics.verification_harness.item_impls.unsafe_, 1);
+        assert_eq!(metrics.verification_harness.exprs.unsafe_, 2);
+        // Only `library` and `not_a_proof` are left in the other metrics.
+        assert_eq!(metrics.public_api.functions.unsafe_, 0);
+        assert_eq!(metrics.unsafe_by_module[""], 1);
+        assert_eq!(metrics.unsafe_by_module["verify"], 1);
+        assert!(!metrics.unsafe_by_module.contains_key("fuzz"));
+    }
+
+    #[test]
+    fn public_api_counts_plain_pub_items_only() {
+        let src = "
+            pub unsafe fn public() {}
+            pub(crate) unsafe fn crate_visible() {}
+            pub(super) unsafe fn parent_visible() {}
+            pub(in crate::a) unsafe fn path_visible() {}
+            crate unsafe fn crate_shorthand() {}
+            unsafe fn private() {}
+            pub fn safe_public() {}
+        ";
+        let metrics =
+            find_unsafe_in_string_with_options(src, &ScanOptions::default())
+                .unwrap();
+
+        assert_eq!(
+            metrics.counters.functions,
+            Count {
+                safe: 1,
+                unsafe_: 6
+            }
+        );
+        assert_eq!(
+            metrics.public_api.functions,
+            Count {
+                safe: 1,
+                unsafe_: 1
+            }
+        );
+    }
+
+    #[test]
+    fn public_api_counts_methods_and_traits() {
+        let src = "
+            pub struct S;
+            impl S {
+                pub unsafe fn public(&self) {}
+                pub(crate) unsafe fn crate_visible(&self) {}
+                unsafe fn private(&self) {}
+            }
+            pub unsafe trait PublicTrait {}
+            pub(crate) unsafe trait CrateTrait {}
+            unsafe trait PrivateTrait {}
+            pub trait SafeTrait {}
+        ";
+        let metrics =
+            find_unsafe_in_string_with_options(src, &ScanOptions::default())
+                .unwrap();
+
+        assert_eq!(
+            metrics.public_api,
+            ApiCounterBlock {
+                functions: Count::default(),
+                methods: Count {
+                    safe: 0,
+                    unsafe_: 1
+                },
+                item_traits: Count {
+                    safe: 1,
+                    unsafe_: 1
+                },
+            }
+        );
+        assert_eq!(metrics.counters.methods.unsafe_, 3);
+        assert_eq!(metrics.counters.item_traits.unsafe_, 3);
+    }
+
+    #[test]
+    fn path_modules_are_resolved_like_rustc() {
+        let src = r#"
+            #[path = "../generated/impls.rs"]
+            mod impls;
+
+            mod sys {
+                #[path = "unix.rs"]
+                mod unix;
+            }
+
+            #[path = "platform"]
+            mod os {
+                #[path = "linux.rs"]
+                mod linux;
+            }
+        "#;
+        let metrics =
+            find_unsafe_in_string_with_options(src, &ScanOptions::default())
+                .unwrap();
+
+        let path_modules = metrics
+            .path_modules
+            .iter()
+            .map(|path_module| {
+                (
+                    path_module.resolve(Path::new("/p/src/lib.rs"), true),
+                    path_module.resolve(Path::new("/p/src/io.rs"), false),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            path_modules,
+            vec![
+                (
+                    PathBuf::from("/p/src/../generated/impls.rs"),
+                    PathBuf::from("/p/src/../generated/impls.rs"),
+                ),
+                (
+                    PathBuf::from("/p/src/sys/unix.rs"),
+                    PathBuf::from("/p/src/io/sys/unix.rs"),
+                ),
+                (
+                    PathBuf::from("/p/src/platform/linux.rs"),
+                    PathBuf::from("/p/src/platform/linux.rs"),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn counting_strategies_count_paths_and_literals_or_not() {
+        let src = "
+            fn f(x: u8) -> u8 {
+                x
+            }
+
+            unsafe fn g(p: *const u8) -> u8 {
+                *p
+            }
+
+            fn h(p: *const u8) -> u8 {
+                f(1);
+                unsafe { g(p) }
+            }
+        ";
+        let exprs = |counting_strategy: CountingStrategy| {
+            find_unsafe_in_string_with_options(
+                src,
+                &ScanOptions::default().counting_strategy(counting_strategy),
+            )
+            .unwrap()
+            .counters
+            .exprs
+        };
+
+        // The calls and the dereference.
+        assert_eq!(
+            exprs(CountingStrategy::Geiger),
+            Count {
+                safe: 1,
+                unsafe_: 2
+            }
+        );
+        // Also `x`, `p` twice, `f`, `1`, `g` and the argument `p`.
+        assert_eq!(
+            exprs(CountingStrategy::AllNodes),
+            Count {
+                safe: 4,
+                unsafe_: 5
+            }
+        );
+    }
+
+    #[test]
+    fn empty_source_code_is_safe() {
+        let metrics =
+            find_unsafe_in_string_with_options("", &ScanOptions::default())
+                .unwrap();
+
+        assert_eq!(metrics, RsFileMetrics::default());
+    }
+
+    #[test]
+    fn files_larger_than_the_max_file_size_are_not_read() {
+        let path = std::env::temp_dir()
+            .join(format!("geiger_max_file_size_{}.rs", std::process::id()));
+        std::fs::write(&path, "fn f() {}\n").unwrap();
+
+        let too_large = find_unsafe_in_file_with_options(
+            &path,
+            &ScanOptions::default().max_file_size(8),
+        );
+        let small_enough = find_unsafe_in_file_with_options(
+            &path,
+            &ScanOptions::default().max_file_size(10),
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            too_large,
+            Err(ScanFileError::TooLarge(10, ref too_large_path))
+                if too_large_path == &path
+        ));
+        assert_eq!(small_enough.unwrap().counters.functions.safe, 1);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn the_functions_of_0_4_5_scan_like_the_default_options() {
+        let src = "unsafe fn f() {}";
+
+        assert_eq!(
+            find_unsafe_in_string(src, IncludeTests::No).unwrap(),
+            find_unsafe_in_string_with_options(src, &ScanOptions::default())
+                .unwrap()
+        );
+    }
 }
@@ -0,0 +1,143 @@
+use crate::{is_cfg_test, read_source_file, ScanFileError};
+
+use std::path::{Path, PathBuf};
+use syn::{Item, ItemMod, Lit, Meta};
+
+/// Lists the files of a crate by following the `mod name;` declarations from
+/// the crate root, the same way rustc looks them up. The crate root itself is
+/// the first file in the list.
+///
+/// This is an approximation of what rustc reads. Modules behind `#[cfg]`
+/// attributes are included, except for `#[cfg(test)]` modules, and files
+/// pulled in with `include!` are not found. Declared module files that don't
+/// exist are skipped, these are usually excluded by a `#[cfg]`.
+pub fn find_module_files(
+    crate_root: &Path,
+) -> Result<Vec<PathBuf>, ScanFileError> {
+    let mut module_files = vec![];
+    // The crate root owns its directory like a `mod.rs` file.
+    let mut pending = vec![(crate_root.to_path_buf(), true)];
+    while let Some((path, is_mod_rs)) = pending.pop() {
+        let src = read_source_file(&path)?;
+        let syntax = syn::parse_file(&src)
+            .map_err(|e| ScanFileError::Syn(e, path.clone()))?;
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let child_dir = match (is_mod_rs, path.file_stem()) {
+            (false, Some(stem)) => dir.join(stem),
+            _ => dir.clone(),
+        };
+        add_declared_module_files(
+            &syntax.items,
+            &dir,
+            &child_dir,
+            &mut pending,
+        );
+        module_files.push(path);
+    }
+    Ok(module_files)
+}
+
+/// `path_dir` is the directory that `#[path]` attributes are relative to and
+/// `child_dir` the one that holds the files of the child modules. They only
+/// differ outside of inline modules in files that are not `mod.rs` files.
+fn add_declared_module_files(
+    items: &[Item],
+    path_dir: &Path,
+    child_dir: &Path,
+    pending: &mut Vec<(PathBuf, bool)>,
+) {
+    for item_mod in items.iter().filter_map(|item| match item {
+        Item::Mod(item_mod) => Some(item_mod),
+        _ => None,
+    }) {
+        // The scan builds don't build tests.
+        if is_cfg_test(&item_mod.attrs) {
+            continue;
+        }
+        let path_attribute = path_attribute(item_mod);
+        match &item_mod.content {
+            Some((_, items)) => {
+                let inline_dir = match path_attribute {
+                    Some(path) => path_dir.join(path),
+                    None => child_dir.join(item_mod.ident.to_string()),
+                };
+                add_declared_module_files(
+                    items,
+                    &inline_dir,
+                    &inline_dir,
+                    pending,
+                );
+            }
+            None => {
+                if let Some(path) = path_attribute {
+                    // Files loaded through `#[path]` own their directory.
+                    pending.push((path_dir.join(path), true));
+                    continue;
+                }
+                let name = item_mod.ident.to_string();
+                let candidates = vec![
+                    (child_dir.join(format!("{}.rs", name)), false),
+                    (child_dir.join(&name).join("mod.rs"), true),
+                ];
+                if let Some(candidate) =
+                    candidates.into_iter().find(|(path, _)| path.is_file())
+                {
+                    pending.push(candidate);
+                }
+            }
+        }
+    }
+}
+
+/// A `#[path = "..."] mod name;` declaration, found while scanning a file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathModule {
+    /// The inline modules the declaration is in, outermost first, with the
+    /// value of their own `#[path]` attribute.
+    pub inline_modules: Vec<(String, Option<String>)>,
+
+    /// The value of the `#[path]` attribute.
+    pub path: String,
+}
+
+impl PathModule {
+    /// The file of the module, looked up like `find_module_files` does.
+    /// `owns_dir` is true for crate roots, `mod.rs` files and files loaded
+    /// through `#[path]`, the files of their child modules are next to them.
+    pub fn resolve(&self, declaring_file: &Path, owns_dir: bool) -> PathBuf {
+        let dir = declaring_file
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut path_dir = dir.clone();
+        let mut child_dir = match (owns_dir, declaring_file.file_stem()) {
+            (false, Some(stem)) => dir.join(stem),
+            _ => dir,
+        };
+        for (name, path_attribute) in &self.inline_modules {
+            let inline_dir = match path_attribute {
+                Some(path) => path_dir.join(path),
+                None => child_dir.join(name),
+            };
+            path_dir = inline_dir.clone();
+            child_dir = inline_dir;
+        }
+        path_dir.join(&self.path)
+    }
+}
+
+pub(crate) fn path_attribute(item_mod: &ItemMod) -> Option<String> {
+    item_mod
+        .attrs
+        .iter()
+        .filter_map(|a| a.parse_meta().ok())
+        .find_map(|meta| match meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident("path") => {
+                match name_value.lit {
+                    Lit::Str(path) => Some(path.value()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+}
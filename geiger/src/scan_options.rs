@@ -0,0 +1,117 @@
+use crate::IncludeTests;
+
+use cargo_geiger_serde::CountingStrategy;
+
+/// The options of a scan, passed to `find_unsafe_in_file_with_options` and
+/// `find_unsafe_in_string_with_options`. Start from the defaults and change
+/// the options that matter:
+///
+/// ```
+/// use geiger::{IncludeTests, ScanOptions};
+///
+/// let scan_options = ScanOptions::default()
+///     .include_tests(IncludeTests::Yes)
+///     .watched_calls(vec![String::from("ptr::copy_nonoverlapping")]);
+/// ```
+///
+/// Each option defaults to the behavior of a scan without it, new options
+/// don't change the scans of existing callers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanOptions {
+    pub(crate) include_tests: IncludeTests,
+    pub(crate) watched_calls: Vec<String>,
+    pub(crate) harness_cfgs: Vec<String>,
+    pub(crate) counting_strategy: CountingStrategy,
+    pub(crate) max_file_size: Option<u64>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            include_tests: IncludeTests::No,
+            watched_calls: Vec::new(),
+            harness_cfgs: Vec::new(),
+            counting_strategy: CountingStrategy::Geiger,
+            max_file_size: None,
+        }
+    }
+}
+
+impl ScanOptions {
+    /// Whether the unsafe usage in `#[test]` functions and `#[cfg(test)]`
+    /// modules is counted. Not counted by default.
+    pub fn include_tests(mut self, include_tests: IncludeTests) -> Self {
+        self.include_tests = include_tests;
+        self
+    }
+
+    /// Call path patterns like `ptr::copy_nonoverlapping` whose calls are
+    /// counted per pattern, see `RsFileMetrics::watched_calls`. None by
+    /// default.
+    pub fn watched_calls(mut self, watched_calls: Vec<String>) -> Self {
+        self.watched_calls = watched_calls;
+        self
+    }
+
+    /// The `cfg` names gating verification harnesses, like `fuzzing` or
+    /// `kani`, see `DEFAULT_HARNESS_CFGS`. Their code is counted in
+    /// `RsFileMetrics::verification_harness` instead of with the rest. None
+    /// by default.
+    pub fn harness_cfgs(mut self, harness_cfgs: Vec<String>) -> Self {
+        self.harness_cfgs = harness_cfgs;
+        self
+    }
+
+    /// Which expressions are counted, see `CountingStrategy`. Paths and
+    /// literals are left out with the default `CountingStrategy::Geiger`.
+    pub fn counting_strategy(
+        mut self,
+        counting_strategy: CountingStrategy,
+    ) -> Self {
+        self.counting_strategy = counting_strategy;
+        self
+    }
+
+    /// Files larger than this many bytes are not read, scanning them fails
+    /// with `ScanFileError::TooLarge`. The size is taken from the file
+    /// metadata. No limit by default, see `DEFAULT_MAX_FILE_SIZE`.
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+}
+
+#[cfg(test)]
+mod scan_options_tests {
+    use super::*;
+
+    #[test]
+    fn default_scan_options_leave_out_tests_and_watch_no_calls() {
+        let scan_options = ScanOptions::default();
+
+        assert_eq!(scan_options.include_tests, IncludeTests::No);
+        assert!(scan_options.watched_calls.is_empty());
+        assert!(scan_options.harness_cfgs.is_empty());
+        assert_eq!(scan_options.counting_strategy, CountingStrategy::Geiger);
+        assert_eq!(scan_options.max_file_size, None);
+    }
+
+    #[test]
+    fn scan_options_builder_sets_each_option() {
+        let scan_options = ScanOptions::default()
+            .include_tests(IncludeTests::Yes)
+            .watched_calls(vec![String::from("slice::get_unchecked")])
+            .harness_cfgs(vec![String::from("kani")])
+            .counting_strategy(CountingStrategy::AllNodes)
+            .max_file_size(1024);
+
+        assert_eq!(scan_options.include_tests, IncludeTests::Yes);
+        assert_eq!(
+            scan_options.watched_calls,
+            vec![String::from("slice::get_unchecked")]
+        );
+        assert_eq!(scan_options.harness_cfgs, vec![String::from("kani")]);
+        assert_eq!(scan_options.counting_strategy, CountingStrategy::AllNodes);
+        assert_eq!(scan_options.max_file_size, Some(1024));
+    }
+}
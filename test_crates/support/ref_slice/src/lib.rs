@@ -0,0 +1,44 @@
+#![no_std]
+
+pub use self::ref_slice_mut as mut_ref_slice;
+pub use self::opt_slice_mut as mut_opt_slice;
+
+/// Converts a reference to `A` into a slice of length 1 (without copying).
+pub fn ref_slice<A>(s: &A) -> &[A] {
+    unsafe {
+        core::slice::from_raw_parts(s, 1)
+    }
+}
+
+/// Converts a reference to `A` into a slice of length 1 (without copying).
+pub fn ref_slice_mut<A>(s: &mut A) -> &mut [A] {
+    unsafe {
+        core::slice::from_raw_parts_mut(s, 1)
+    }
+}
+
+/// Converts a raw pointer to `A` into a slice of length 1 (without copying).
+///
+/// Not part of the published crate, only exists to make the override visible
+/// in the scan results.
+pub unsafe fn ptr_slice<'a, A>(p: *const A) -> &'a [A] {
+    core::slice::from_raw_parts(p, 1)
+}
+
+/// Converts a reference to `Option<A>` into a slice of length 0 or 1 (without copying).
+pub fn opt_slice<A>(opt: &Option<A>) -> &[A]
+{
+    match *opt {
+        Some(ref val) => ref_slice(val),
+        None => &[],
+    }
+}
+
+/// Converts a reference to `Option<A>` into a slice of length 0 or 1 (without copying).
+pub fn opt_slice_mut<A>(opt: &mut Option<A>) -> &mut [A]
+{
+    match *opt {
+        Some(ref mut val) => mut_ref_slice(val),
+        None => &mut [],
+    }
+}
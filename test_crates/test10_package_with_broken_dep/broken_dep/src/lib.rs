@@ -0,0 +1,5 @@
+compile_error!("broken_dep doesn't build");
+
+pub fn one() -> u32 {
+    unsafe { std::mem::transmute(1u32) }
+}
@@ -0,0 +1,3 @@
+pub fn sum() -> u32 {
+    broken_dep::one() + working_dep::two()
+}
@@ -0,0 +1,3 @@
+pub fn two() -> u32 {
+    unsafe { std::mem::transmute(2u32) }
+}
@@ -0,0 +1,11 @@
+//! Bindings to a few functions of the C standard library, without any
+//! `unsafe` code of its own.
+
+#![forbid(unsafe_code)]
+
+use std::os::raw::{c_char, c_int};
+
+extern "C" {
+    pub fn abs(input: c_int) -> c_int;
+    pub fn strlen(s: *const c_char) -> usize;
+}
@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+pub fn greeting() -> &'static [u8] {
+    b"hello"
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let bytes = test12_package_with_unsafe_bin::greeting();
+    let text = unsafe { std::str::from_utf8_unchecked(bytes) };
+    println!("{}", text);
+}
@@ -0,0 +1,12 @@
+pub unsafe fn fallback() {}
+
+#[cfg(feature = "simd")]
+pub unsafe fn fast() {}
+
+#[cfg(feature = "simd")]
+pub mod simd {
+    pub unsafe fn sse() {}
+
+    #[cfg(feature = "avx")]
+    pub unsafe fn avx() {}
+}
@@ -0,0 +1,3 @@
+#![forbid(unsafe_code)]
+
+pub use test1_package_with_no_deps::*;
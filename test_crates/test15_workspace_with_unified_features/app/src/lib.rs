@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+pub fn both(bytes: &[u8]) -> (u8, u8) {
+    (parent_a::first(bytes), parent_b::first(bytes))
+}
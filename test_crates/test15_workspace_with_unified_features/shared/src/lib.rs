@@ -0,0 +1,9 @@
+#[cfg(feature = "unchecked")]
+pub fn first(bytes: &[u8]) -> u8 {
+    unsafe { *bytes.get_unchecked(0) }
+}
+
+#[cfg(not(feature = "unchecked"))]
+pub fn first(bytes: &[u8]) -> u8 {
+    bytes[0]
+}
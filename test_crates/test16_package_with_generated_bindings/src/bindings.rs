@@ -0,0 +1,13 @@
+/* automatically generated by rust-bindgen 0.55.1 */
+
+pub type size_t = usize;
+
+extern "C" {
+    pub fn abs(input: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn labs(input: ::std::os::raw::c_long) -> ::std::os::raw::c_long;
+}
+extern "C" {
+    pub fn strlen(s: *const ::std::os::raw::c_char) -> size_t;
+}
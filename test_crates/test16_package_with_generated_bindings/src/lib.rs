@@ -0,0 +1,10 @@
+//! Generated bindings to a few functions of the C standard library and a
+//! hand-written wrapper around them.
+
+mod bindings;
+
+use std::os::raw::c_int;
+
+pub fn abs(input: i32) -> i32 {
+    unsafe { bindings::abs(input as c_int) as i32 }
+}
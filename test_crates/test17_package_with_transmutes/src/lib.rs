@@ -0,0 +1,13 @@
+use std::mem::transmute;
+
+pub fn qualified(x: u32) -> f32 {
+    unsafe { std::mem::transmute(x) }
+}
+
+pub fn core_qualified(x: &u32) -> [u8; 4] {
+    unsafe { core::mem::transmute_copy(x) }
+}
+
+pub fn imported(x: f32) -> u32 {
+    unsafe { transmute(x) }
+}
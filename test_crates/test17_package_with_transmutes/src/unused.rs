@@ -0,0 +1,5 @@
+//! Not declared as a module, only scanned.
+
+pub fn unused(x: u32) -> i32 {
+    unsafe { std::mem::transmute(x) }
+}
@@ -0,0 +1,14 @@
+pub fn first(s: &[u8]) -> u8 {
+    unsafe { *s.get_unchecked(0) }
+}
+
+pub fn second(s: &[u8]) -> u8 {
+    unsafe { *<[u8]>::get_unchecked(s, 1) }
+}
+
+pub fn copy(src: &[u8], dst: &mut [u8]) {
+    assert!(src.len() <= dst.len());
+    unsafe {
+        std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), src.len())
+    }
+}
@@ -0,0 +1,8 @@
+//! Not declared as a module, only scanned.
+
+use std::mem::MaybeUninit;
+
+pub fn init() -> u32 {
+    let value = MaybeUninit::new(1);
+    unsafe { value.assume_init() }
+}
@@ -0,0 +1,5 @@
+pub fn fuzz_first(bytes: &[u8]) -> u8 {
+    let ptr = bytes.as_ptr();
+    unsafe { *ptr.add(bytes.len() / 2) };
+    app::first(bytes)
+}
@@ -0,0 +1,10 @@
+#[cfg(feature = "raw")]
+mod raw;
+
+#[cfg(feature = "raw")]
+pub use raw::first;
+
+#[cfg(not(feature = "raw"))]
+pub fn first(bytes: &[u8]) -> u8 {
+    bytes[0]
+}
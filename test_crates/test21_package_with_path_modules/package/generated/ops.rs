@@ -0,0 +1,3 @@
+pub fn checked_first(bytes: &[u8]) -> Option<u8> {
+    bytes.first().copied()
+}
@@ -0,0 +1,9 @@
+#[path = "../generated/ops.rs"]
+mod ops;
+
+#[path = "../../shared/raw.rs"]
+mod raw;
+
+pub fn first(bytes: &[u8]) -> u8 {
+    ops::checked_first(bytes).unwrap_or_else(|| unsafe { raw::first(bytes) })
+}
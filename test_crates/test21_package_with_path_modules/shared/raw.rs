@@ -0,0 +1,3 @@
+pub unsafe fn first(bytes: &[u8]) -> u8 {
+    *bytes.get_unchecked(0)
+}
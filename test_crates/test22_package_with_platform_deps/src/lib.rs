@@ -0,0 +1,5 @@
+#[cfg(not(target_os = "redox"))]
+pub use host_only::first;
+
+#[cfg(target_os = "redox")]
+pub use redox_only::first;
@@ -0,0 +1,3 @@
+// The native library is only declared, the crates are checked and never
+// linked.
+fn main() {}
@@ -0,0 +1,3 @@
+extern "C" {
+    pub fn fake_answer() -> u32;
+}
@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+pub fn first(bytes: &[u8]) -> u8 {
+    shared::first(bytes)
+}
@@ -0,0 +1,3 @@
+pub fn second(bytes: &[u8]) -> u8 {
+    shared::first(bytes) + unsafe { *bytes.get_unchecked(1) }
+}
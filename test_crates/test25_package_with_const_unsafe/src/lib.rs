@@ -0,0 +1,9 @@
+pub static ONE_BITS: u32 = unsafe { std::mem::transmute(1f32) };
+
+pub const fn const_bits(x: f32) -> u32 {
+    unsafe { std::mem::transmute(x) }
+}
+
+pub fn runtime_bits(x: f32) -> u32 {
+    unsafe { std::mem::transmute(x) }
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let bytes = [1u8, 2, 3, 4];
+    let first = unsafe { *bytes.as_ptr() };
+    println!("cargo:rustc-env=FIRST_BYTE={}", first);
+}
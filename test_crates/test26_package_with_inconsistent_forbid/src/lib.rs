@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+pub fn first_byte() -> u8 {
+    env!("FIRST_BYTE").parse().unwrap()
+}
@@ -0,0 +1,7 @@
+#[cfg(windows)]
+pub use windows_only::first;
+
+#[cfg(not(windows))]
+pub fn first(bytes: &[u8]) -> u8 {
+    bytes[0]
+}
@@ -0,0 +1,3 @@
+pub fn first(bytes: &[u8]) -> Option<u8> {
+    nested::first(bytes)
+}
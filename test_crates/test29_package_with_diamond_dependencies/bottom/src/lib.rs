@@ -0,0 +1,7 @@
+pub fn get(values: &[u32], index: usize) -> u32 {
+    if index < values.len() {
+        unsafe { *values.get_unchecked(index) }
+    } else {
+        0
+    }
+}
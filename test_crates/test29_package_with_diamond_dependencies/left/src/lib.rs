@@ -0,0 +1,3 @@
+pub fn first(values: &[u32]) -> u32 {
+    bottom::get(values, 0)
+}
@@ -0,0 +1,3 @@
+pub fn last(values: &[u32]) -> u32 {
+    bottom::get(values, values.len().saturating_sub(1))
+}
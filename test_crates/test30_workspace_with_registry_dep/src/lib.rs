@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+pub fn as_slice(value: &u32) -> &[u32] {
+    ref_slice::ref_slice(value)
+}
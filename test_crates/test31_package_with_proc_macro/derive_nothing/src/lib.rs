@@ -0,0 +1,9 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+
+/// Generates nothing, the derive is only there to be attributed.
+#[proc_macro_derive(Nothing)]
+pub fn derive_nothing(_input: TokenStream) -> TokenStream {
+    TokenStream::new()
+}
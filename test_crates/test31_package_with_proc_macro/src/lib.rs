@@ -0,0 +1,4 @@
+use derive_nothing::Nothing;
+
+#[derive(Nothing)]
+pub struct Marked;
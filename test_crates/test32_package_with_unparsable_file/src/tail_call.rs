@@ -0,0 +1,8 @@
+// Not a module of the crate. `become` is reserved, but syn can't parse it.
+// The unsafe in this comment and in the string below is not counted.
+
+pub unsafe fn call(f: unsafe fn()) {
+    unsafe { become f() }
+}
+
+pub const NOTE: &str = "unsafe { }";
@@ -0,0 +1,3 @@
+pub fn sum(bytes: &[u8]) -> u8 {
+    crate_a1::first(bytes) + crate_b::last(bytes) + local::second(bytes)
+}
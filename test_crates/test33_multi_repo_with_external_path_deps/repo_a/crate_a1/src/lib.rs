@@ -0,0 +1,4 @@
+pub fn first(bytes: &[u8]) -> u8 {
+    crate_a2::checked(bytes);
+    unsafe { *bytes.get_unchecked(0) }
+}
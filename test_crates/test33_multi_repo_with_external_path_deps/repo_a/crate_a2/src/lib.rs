@@ -0,0 +1,8 @@
+pub fn checked(bytes: &[u8]) {
+    assert!(bytes.len() > 1);
+    unsafe { assert_len(bytes) }
+}
+
+unsafe fn assert_len(bytes: &[u8]) {
+    debug_assert!(!bytes.is_empty());
+}
@@ -0,0 +1,3 @@
+pub fn last(bytes: &[u8]) -> u8 {
+    unsafe { *bytes.get_unchecked(bytes.len() - 1) }
+}
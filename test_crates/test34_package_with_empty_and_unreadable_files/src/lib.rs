@@ -0,0 +1,5 @@
+mod placeholder;
+
+pub fn first(bytes: &[u8]) -> u8 {
+    unsafe { *bytes.get_unchecked(0) }
+}
@@ -0,0 +1,7 @@
+mod tables;
+
+pub use tables::*;
+
+pub fn first(bytes: &[u8]) -> u8 {
+    unsafe { *bytes.get_unchecked(0) }
+}
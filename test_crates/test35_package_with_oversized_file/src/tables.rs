@@ -0,0 +1,2 @@
+// The tests replace this file with a large one.
+pub const TABLE_0: u8 = 0;
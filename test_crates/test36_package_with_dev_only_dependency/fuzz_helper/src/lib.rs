@@ -0,0 +1,9 @@
+pub fn inputs() -> Vec<String> {
+    (0..=255u8)
+        .map(|byte| unsafe { String::from_utf8_unchecked(vec![byte]) })
+        .collect()
+}
+
+pub unsafe fn first_unchecked(bytes: &[u8]) -> u8 {
+    *bytes.get_unchecked(0)
+}
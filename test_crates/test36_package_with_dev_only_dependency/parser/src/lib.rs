@@ -0,0 +1,13 @@
+pub fn parse(input: &str) -> Vec<&str> {
+    input.split_whitespace().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_handles_arbitrary_bytes() {
+        for input in fuzz_helper::inputs() {
+            super::parse(&input);
+        }
+    }
+}
@@ -0,0 +1 @@
+pub use parser::parse;
@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+pub fn f() -> usize {
+    ref_slice::ref_slice(&1).len()
+}
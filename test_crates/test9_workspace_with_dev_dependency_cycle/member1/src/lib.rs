@@ -0,0 +1,4 @@
+pub fn first() -> u8 {
+    let bytes = b"string";
+    unsafe { *bytes.as_ptr() }
+}
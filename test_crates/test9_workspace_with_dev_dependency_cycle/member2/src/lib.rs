@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+pub fn first_twice() -> u16 {
+    2 * u16::from(member1::first())
+}